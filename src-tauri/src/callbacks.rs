@@ -9,10 +9,11 @@ use std::{
 
 use anyhow::Result;
 use jj_lib::{git::RemoteCallbacks, repo::MutableRepo};
-use tauri::{Emitter, Manager, Window};
+use tauri::{Manager, Window};
 
 use crate::{
-    messages::{InputField, InputRequest},
+    events::EventSink,
+    messages::{CheckoutPolicy, Event, InputField, InputRequest, ProgressEvent, RepoConfig},
     worker::WorkerCallbacks,
     AppState,
 };
@@ -32,6 +33,7 @@ impl WorkerCallbacks for FrontendCallbacks {
 
         let get_password = &mut |url: &str, username: &str| {
             self.request_input(
+                "Git Login",
                 format!("Please enter a password for {} at {}", username, url),
                 ["Password"],
             )
@@ -41,6 +43,7 @@ impl WorkerCallbacks for FrontendCallbacks {
 
         let get_username_password = &mut |url: &str| {
             self.request_input(
+                "Git Login",
                 format!("Please enter a username and password for {}", url),
                 ["Username", "Password"],
             )
@@ -59,6 +62,7 @@ impl WorkerCallbacks for FrontendCallbacks {
 
     fn select_remote(&self, choices: &[&str]) -> Option<String> {
         let response = self.request_input(
+            "Git Login",
             format!("Select a remote"),
             [InputField {
                 label: "Select Remote".into(),
@@ -68,11 +72,54 @@ impl WorkerCallbacks for FrontendCallbacks {
 
         response.and_then(|mut fields| fields.remove("Select Remote").to_owned())
     }
+
+    fn select_checkout_policy(&self, change: &str) -> Option<CheckoutPolicy> {
+        let response = self.request_input(
+            "Undescribed Changes",
+            format!("The working copy {change} has changes but no description."),
+            [InputField {
+                label: "Keep Changes".into(),
+                choices: vec![
+                    "As a revision".into(),
+                    "With a wip/ bookmark".into(),
+                    "By checking out a new child".into(),
+                ],
+            }],
+        );
+
+        response.and_then(
+            |mut fields| match fields.remove("Keep Changes").as_deref() {
+                Some("As a revision") => Some(CheckoutPolicy::Keep),
+                Some("With a wip/ bookmark") => Some(CheckoutPolicy::Bookmark),
+                Some("By checking out a new child") => Some(CheckoutPolicy::NewChild),
+                _ => None,
+            },
+        )
+    }
+
+    fn workspace_missing(&self, absolute_path: &Path) {
+        if let Err(err) = self.0.set_title("GG - Gui for JJ") {
+            log::error!("workspace missing: set_title failed: {err}");
+        }
+        let event = Event::RepoConfig(RepoConfig::WorkspaceMissing {
+            absolute_path: absolute_path.into(),
+        });
+        if let Err(err) = self.0.send_event(event) {
+            log::error!("workspace missing: emit failed: {err}");
+        }
+    }
+
+    fn report_progress(&self, event: ProgressEvent) {
+        if let Err(err) = self.0.send_event(Event::Progress(event)) {
+            log::error!("report progress: emit failed: {err}");
+        }
+    }
 }
 
 impl FrontendCallbacks {
     fn request_input<T: IntoIterator<Item = U>, U: Into<InputField>>(
         &self,
+        title: &str,
         detail: String,
         fields: T,
     ) -> Option<HashMap<String, String>> {
@@ -83,14 +130,11 @@ impl FrontendCallbacks {
         self.0.state::<AppState>().set_input(self.0.label(), tx);
 
         // send the request
-        match self.0.emit(
-            "gg://input",
-            InputRequest {
-                title: String::from("Git Login"),
-                detail,
-                fields: fields.into_iter().map(|field| field.into()).collect(),
-            },
-        ) {
+        match self.0.send_event(Event::Input(InputRequest {
+            title: title.to_owned(),
+            detail,
+            fields: fields.into_iter().map(|field| field.into()).collect(),
+        })) {
             Ok(_) => (),
             Err(err) => {
                 log::error!("input request failed: emit failed: {err}");
@@ -1,22 +1,200 @@
-use std::path::Path;
+use std::{collections::BTreeMap, path::Path};
 
 use anyhow::{anyhow, Result};
-use config::{Config, ConfigError};
+use config::{Config, ConfigError, Value};
+use encoding_rs::Encoding;
 use itertools::Itertools;
-use jj_cli::config::LayeredConfigs;
+use jj_cli::config::{ConfigSource, LayeredConfigs};
 use jj_lib::{
     revset::RevsetAliasesMap,
     settings::{ConfigResultExt, UserSettings},
 };
+use regex::Regex;
+
+use crate::messages::{CheckoutPolicy, ConfigDiagnostic};
+
+pub const REVSET_ALIASES_KEY: &str = "revset-aliases";
+pub const TRUNK_ALIAS: &str = "trunk()";
+pub const CHECKPOINT_TAG: &str = "gg.checkpoint";
+pub const TOOL_TAG: &str = "gg.tool";
+pub const MUTATION_TAG: &str = "gg.mutation";
+pub const WINDOW_TAG: &str = "gg.window";
+pub const DEFAULT_QUERY_KEY: &str = "gg.queries.default";
+
+/// The shape of a gg.* setting, as accepted by the GGSettings accessors
+enum SettingKind {
+    Bool,
+    Int { min: i64 },
+    String,
+    Choice(&'static [&'static str]),
+    Encoding,
+    Regex,
+    StringList,
+    IntList { len: usize },
+    TableList,
+    StringTable,
+    Renamed(&'static str),
+}
+
+const SETTINGS_SCHEMA: &[(&str, SettingKind)] = &[
+    (DEFAULT_QUERY_KEY, SettingKind::String),
+    ("gg.queries.log-page-size", SettingKind::Int { min: 1 }),
+    ("gg.queries.changes-page-size", SettingKind::Int { min: 1 }),
+    (
+        "gg.queries.large-repo-heuristic",
+        SettingKind::Int { min: 0 },
+    ),
+    ("gg.queries.large-repo-narrowing", SettingKind::String),
+    ("gg.queries.auto-snapshot", SettingKind::Bool),
+    ("gg.queries.parallel-reads", SettingKind::Bool),
+    ("gg.queries.fallback-encoding", SettingKind::Encoding),
+    ("gg.queries.age-buckets", SettingKind::IntList { len: 3 }),
+    ("gg.operations.annotate", SettingKind::Bool),
+    (
+        "gg.operations.checkout-policy",
+        SettingKind::Choice(&["keep", "bookmark", "new-child", "prompt"]),
+    ),
+    ("gg.operations.rewrite-limit", SettingKind::Int { min: 1 }),
+    ("gg.git.protected-bookmarks", SettingKind::StringList),
+    ("gg.git.env", SettingKind::StringTable),
+    ("gg.git.proxy", SettingKind::String),
+    (
+        "gg.descriptions.subject-max-length",
+        SettingKind::Int { min: 0 },
+    ),
+    (
+        "gg.descriptions.body-max-length",
+        SettingKind::Int { min: 0 },
+    ),
+    ("gg.descriptions.require-blank-line", SettingKind::Bool),
+    ("gg.ui.recent-workspaces", SettingKind::StringList),
+    ("gg.ui.workspace-states", SettingKind::TableList),
+    ("gg.ui.mark-unpushed-bookmarks", SettingKind::Bool),
+    (
+        "gg.ui.mark-unpushed-branches",
+        SettingKind::Renamed("gg.ui.mark-unpushed-bookmarks"),
+    ),
+    ("gg.ui.description-command", SettingKind::StringList),
+    ("gg.ui.new-commit-description", SettingKind::String),
+    ("gg.ui.new-commit-ticket-pattern", SettingKind::Regex),
+    ("gg.ui.check-updates", SettingKind::Bool),
+    ("gg.ui.confirm-destructive", SettingKind::Bool),
+    (
+        "gg.ui.theme-override",
+        SettingKind::Choice(&["light", "dark"]),
+    ),
+    ("gg.commands.jj-path", SettingKind::String),
+    ("gg.commands.allowed-subcommands", SettingKind::StringList),
+    ("gg.debug.trace-size", SettingKind::Int { min: 0 }),
+];
+
+impl SettingKind {
+    /// explains why a value would be ignored, mirroring the conversions the accessors use
+    fn check(&self, value: Value) -> Option<String> {
+        match self {
+            SettingKind::Bool => value
+                .into_bool()
+                .err()
+                .map(|_| "expected true or false".to_owned()),
+            SettingKind::Int { min } => match value.into_int() {
+                Ok(n) if n < *min => Some(format!("expected at least {min}, found {n}")),
+                Ok(_) => None,
+                Err(_) => Some("expected a whole number".to_owned()),
+            },
+            SettingKind::String => value
+                .into_string()
+                .err()
+                .map(|_| "expected a string".to_owned()),
+            SettingKind::Choice(choices) => match value.into_string() {
+                Ok(choice) if choices.contains(&choice.as_str()) => None,
+                Ok(choice) => Some(format!(
+                    "\"{choice}\" is not one of {}",
+                    choices.iter().map(|c| format!("\"{c}\"")).join(", ")
+                )),
+                Err(_) => Some("expected a string".to_owned()),
+            },
+            SettingKind::Encoding => match value.into_string() {
+                Ok(label) if Encoding::for_label(label.as_bytes()).is_some() => None,
+                Ok(label) => Some(format!("\"{label}\" is not a known encoding label")),
+                Err(_) => Some("expected a string".to_owned()),
+            },
+            SettingKind::Regex => match value.into_string() {
+                Ok(pattern) => Regex::new(&pattern).err().map(|err| err.to_string()),
+                Err(_) => Some("expected a string".to_owned()),
+            },
+            SettingKind::StringList => match value.into_array() {
+                Ok(values) if values.iter().all(|v| v.clone().into_string().is_ok()) => None,
+                _ => Some("expected a list of strings".to_owned()),
+            },
+            SettingKind::IntList { len } => match parse_int_list(value, *len) {
+                Some(_) => None,
+                None => Some(format!("expected {len} increasing whole numbers")),
+            },
+            SettingKind::TableList => match value.into_array() {
+                Ok(values) if values.iter().all(|v| v.clone().into_table().is_ok()) => None,
+                _ => Some("expected a list of tables".to_owned()),
+            },
+            SettingKind::StringTable => match value.into_table() {
+                Ok(values) if values.values().all(|v| v.clone().into_string().is_ok()) => None,
+                _ => Some("expected a table of strings".to_owned()),
+            },
+            SettingKind::Renamed(new_key) => Some(format!("deprecated; use {new_key} instead")),
+        }
+    }
+}
+
+// a list which can't be used as a whole is ignored, rather than partially applied
+fn parse_int_list(value: Value, len: usize) -> Option<Vec<i64>> {
+    let values: Vec<i64> = value
+        .into_array()
+        .ok()?
+        .into_iter()
+        .map(|value| value.into_int().ok())
+        .collect::<Option<_>>()?;
+    let is_increasing = values.windows(2).all(|pair| pair[0] < pair[1]);
+    (values.len() == len && is_increasing && values.iter().all(|n| *n > 0)).then_some(values)
+}
+
+/// UI state remembered for a workspace between sessions
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SavedWorkspace {
+    pub path: String,
+    pub query: Option<String>,
+    pub selection: Option<String>,
+}
 
 pub trait GGSettings {
     fn query_log_page_size(&self) -> usize;
+    fn query_changes_page_size(&self) -> usize;
     fn query_large_repo_heuristic(&self) -> i64;
+    fn query_large_repo_narrowing(&self) -> Option<String>;
     fn query_auto_snapshot(&self) -> Option<bool>;
+    fn query_parallel_reads(&self) -> bool;
+    fn query_fallback_encoding(&self) -> &'static Encoding;
+    fn query_default(&self) -> String;
+    fn query_age_buckets(&self) -> [i64; 3];
+    fn operations_annotate(&self) -> bool;
+    fn operations_checkout_policy(&self) -> CheckoutPolicy;
+    fn operations_rewrite_limit(&self) -> usize;
+    fn git_protected_bookmarks(&self) -> Vec<String>;
+    fn git_env(&self) -> Vec<(String, String)>;
+    fn git_proxy(&self) -> Option<String>;
+    fn descriptions_subject_max_length(&self) -> usize;
+    fn descriptions_body_max_length(&self) -> usize;
+    fn descriptions_require_blank_line(&self) -> bool;
     fn ui_theme_override(&self) -> Option<String>;
     fn ui_mark_unpushed_bookmarks(&self) -> bool;
+    fn ui_description_command(&self) -> Option<Vec<String>>;
+    fn ui_new_commit_description(&self) -> Option<String>;
+    fn ui_new_commit_ticket_pattern(&self) -> Option<Regex>;
+    fn ui_check_updates(&self) -> bool;
+    fn ui_confirm_destructive(&self) -> bool;
+    fn commands_jj_path(&self) -> String;
+    fn commands_allowed_subcommands(&self) -> Vec<String>;
+    fn debug_trace_size(&self) -> usize;
     #[allow(dead_code)]
     fn ui_recent_workspaces(&self) -> Vec<String>;
+    fn ui_workspace_states(&self) -> Vec<SavedWorkspace>;
 }
 
 impl GGSettings for UserSettings {
@@ -26,16 +204,136 @@ impl GGSettings for UserSettings {
             .unwrap_or(1000) as usize
     }
 
+    fn query_changes_page_size(&self) -> usize {
+        self.config()
+            .get_int("gg.queries.changes-page-size")
+            .unwrap_or(1000) as usize
+    }
+
     fn query_large_repo_heuristic(&self) -> i64 {
         self.config()
             .get_int("gg.queries.large-repo-heuristic")
             .unwrap_or(100000)
     }
 
+    fn query_large_repo_narrowing(&self) -> Option<String> {
+        self.config()
+            .get_string("gg.queries.large-repo-narrowing")
+            .ok()
+            .filter(|narrowing| !narrowing.is_empty())
+    }
+
     fn query_auto_snapshot(&self) -> Option<bool> {
         self.config().get_bool("gg.queries.auto-snapshot").ok()
     }
 
+    fn query_parallel_reads(&self) -> bool {
+        self.config()
+            .get_bool("gg.queries.parallel-reads")
+            .unwrap_or(true)
+    }
+
+    fn query_fallback_encoding(&self) -> &'static Encoding {
+        self.config()
+            .get_string("gg.queries.fallback-encoding")
+            .ok()
+            .and_then(|label| Encoding::for_label(label.as_bytes()))
+            .unwrap_or(encoding_rs::WINDOWS_1252)
+    }
+
+    fn query_default(&self) -> String {
+        self.config()
+            .get_string(DEFAULT_QUERY_KEY)
+            .ok()
+            .filter(|query| !query.is_empty())
+            .unwrap_or_else(|| self.default_revset())
+    }
+
+    fn query_age_buckets(&self) -> [i64; 3] {
+        self.config()
+            .get("gg.queries.age-buckets")
+            .ok()
+            .and_then(|value| parse_int_list(value, 3))
+            .and_then(|days| days.try_into().ok())
+            .unwrap_or([1, 7, 30])
+    }
+
+    fn operations_annotate(&self) -> bool {
+        self.config()
+            .get_bool("gg.operations.annotate")
+            .unwrap_or(true)
+    }
+
+    fn operations_checkout_policy(&self) -> CheckoutPolicy {
+        match self
+            .config()
+            .get_string("gg.operations.checkout-policy")
+            .as_deref()
+        {
+            Ok("bookmark") => CheckoutPolicy::Bookmark,
+            Ok("new-child") => CheckoutPolicy::NewChild,
+            Ok("prompt") => CheckoutPolicy::Prompt,
+            _ => CheckoutPolicy::Keep,
+        }
+    }
+
+    fn operations_rewrite_limit(&self) -> usize {
+        self.config()
+            .get_int("gg.operations.rewrite-limit")
+            .unwrap_or(100)
+            .max(1) as usize
+    }
+
+    fn git_protected_bookmarks(&self) -> Vec<String> {
+        let patterns: Result<Vec<String>, ConfigError> = self
+            .config()
+            .get_array("gg.git.protected-bookmarks")
+            .unwrap_or(vec![])
+            .into_iter()
+            .map(|value| value.into_string())
+            .collect();
+        patterns.unwrap_or(vec![])
+    }
+
+    fn git_env(&self) -> Vec<(String, String)> {
+        let vars: Result<Vec<(String, String)>, ConfigError> = self
+            .config()
+            .get_table("gg.git.env")
+            .unwrap_or_default()
+            .into_iter()
+            .sorted_by(|a, b| a.0.cmp(&b.0))
+            .map(|(name, value)| value.into_string().map(|value| (name, value)))
+            .collect();
+        vars.unwrap_or(vec![])
+    }
+
+    fn git_proxy(&self) -> Option<String> {
+        self.config()
+            .get_string("gg.git.proxy")
+            .ok()
+            .filter(|proxy| !proxy.is_empty())
+    }
+
+    fn descriptions_subject_max_length(&self) -> usize {
+        self.config()
+            .get_int("gg.descriptions.subject-max-length")
+            .unwrap_or(72)
+            .max(0) as usize
+    }
+
+    fn descriptions_body_max_length(&self) -> usize {
+        self.config()
+            .get_int("gg.descriptions.body-max-length")
+            .unwrap_or(72)
+            .max(0) as usize
+    }
+
+    fn descriptions_require_blank_line(&self) -> bool {
+        self.config()
+            .get_bool("gg.descriptions.require-blank-line")
+            .unwrap_or(true)
+    }
+
     fn ui_theme_override(&self) -> Option<String> {
         self.config().get_string("gg.ui.theme-override").ok()
     }
@@ -50,6 +348,67 @@ impl GGSettings for UserSettings {
             )
     }
 
+    fn ui_description_command(&self) -> Option<Vec<String>> {
+        let args: Result<Vec<String>, ConfigError> = self
+            .config()
+            .get_array("gg.ui.description-command")
+            .ok()?
+            .into_iter()
+            .map(|value| value.into_string())
+            .collect();
+        args.ok().filter(|args| !args.is_empty())
+    }
+
+    fn ui_new_commit_description(&self) -> Option<String> {
+        self.config()
+            .get_string("gg.ui.new-commit-description")
+            .ok()
+            .filter(|template| !template.trim().is_empty())
+    }
+
+    fn ui_new_commit_ticket_pattern(&self) -> Option<Regex> {
+        self.config()
+            .get_string("gg.ui.new-commit-ticket-pattern")
+            .ok()
+            .and_then(|pattern| Regex::new(&pattern).ok())
+    }
+
+    fn ui_check_updates(&self) -> bool {
+        self.config()
+            .get_bool("gg.ui.check-updates")
+            .unwrap_or(false)
+    }
+
+    fn ui_confirm_destructive(&self) -> bool {
+        self.config()
+            .get_bool("gg.ui.confirm-destructive")
+            .unwrap_or(false)
+    }
+
+    fn commands_jj_path(&self) -> String {
+        self.config()
+            .get_string("gg.commands.jj-path")
+            .unwrap_or("jj".to_owned())
+    }
+
+    fn commands_allowed_subcommands(&self) -> Vec<String> {
+        let subcommands: Result<Vec<String>, ConfigError> = self
+            .config()
+            .get_array("gg.commands.allowed-subcommands")
+            .unwrap_or(vec![])
+            .into_iter()
+            .map(|value| value.into_string())
+            .collect();
+        subcommands.unwrap_or(vec![])
+    }
+
+    fn debug_trace_size(&self) -> usize {
+        self.config()
+            .get_int("gg.debug.trace-size")
+            .unwrap_or(100)
+            .max(0) as usize
+    }
+
     fn ui_recent_workspaces(&self) -> Vec<String> {
         let paths: Result<Vec<String>, ConfigError> = self
             .config()
@@ -60,9 +419,105 @@ impl GGSettings for UserSettings {
             .collect();
         paths.unwrap_or(vec![])
     }
+
+    fn ui_workspace_states(&self) -> Vec<SavedWorkspace> {
+        self.config()
+            .get_array("gg.ui.workspace-states")
+            .unwrap_or(vec![])
+            .into_iter()
+            .filter_map(|value| {
+                let mut table = value.into_table().ok()?;
+                let mut field = |key: &str| table.remove(key).and_then(|v| v.into_string().ok());
+                Some(SavedWorkspace {
+                    path: field("path")?,
+                    query: field("query"),
+                    selection: field("selection"),
+                })
+            })
+            .collect()
+    }
 }
 
 pub fn read_config(repo_path: &Path) -> Result<(UserSettings, RevsetAliasesMap)> {
+    let configs = read_layered_configs(repo_path)?;
+
+    let settings = build_settings(&configs);
+    let aliases_map = build_aliases_map(&configs)?;
+
+    Ok((settings, aliases_map))
+}
+
+/// Lists the effective revset aliases, each with the layer which defined it.
+pub fn read_revset_aliases(repo_path: &Path) -> Result<Vec<(ConfigSource, String, String)>> {
+    let configs = read_layered_configs(repo_path)?;
+
+    let mut aliases = BTreeMap::new();
+    for (source, config) in configs.sources() {
+        let table = if let Some(table) = config.get_table(REVSET_ALIASES_KEY).optional()? {
+            table
+        } else {
+            continue;
+        };
+        for (decl, value) in table {
+            let definition = value.into_string().map_err(|e| anyhow!(e))?;
+            aliases.insert(decl, (source.clone(), definition));
+        }
+    }
+
+    Ok(aliases
+        .into_iter()
+        .map(|(decl, (source, definition))| (source, decl, definition))
+        .collect())
+}
+
+/// Checks the gg.* settings in each config layer, explaining any which will be ignored.
+pub fn read_config_diagnostics(repo_path: &Path) -> Result<Vec<ConfigDiagnostic>> {
+    let configs = read_layered_configs(repo_path)?;
+
+    let mut diagnostics = Vec::new();
+    for (source, config) in configs.sources() {
+        // the defaults are our own gg.toml
+        if source == ConfigSource::Default {
+            continue;
+        }
+
+        let Some(sections) = config.get_table("gg").optional()? else {
+            continue;
+        };
+
+        let mut report = |key: String, message: String| {
+            diagnostics.push(ConfigDiagnostic {
+                scope: source.clone().into(),
+                key,
+                message,
+            })
+        };
+
+        for (section, value) in sections.into_iter().sorted_by(|a, b| a.0.cmp(&b.0)) {
+            let section_key = format!("gg.{section}");
+            let Ok(settings) = value.into_table() else {
+                report(section_key, "expected a table of settings".to_owned());
+                continue;
+            };
+
+            for (name, value) in settings.into_iter().sorted_by(|a, b| a.0.cmp(&b.0)) {
+                let key = format!("{section_key}.{name}");
+                match SETTINGS_SCHEMA.iter().find(|(k, _)| *k == key) {
+                    Some((_, kind)) => {
+                        if let Some(message) = kind.check(value) {
+                            report(key, message);
+                        }
+                    }
+                    None => report(key, "unknown setting; it will be ignored".to_owned()),
+                }
+            }
+        }
+    }
+
+    Ok(diagnostics)
+}
+
+fn read_layered_configs(repo_path: &Path) -> Result<LayeredConfigs> {
     let defaults = Config::builder()
         .add_source(jj_cli::config::default_config())
         .add_source(config::File::from_str(
@@ -75,10 +530,7 @@ pub fn read_config(repo_path: &Path) -> Result<(UserSettings, RevsetAliasesMap)>
     configs.read_user_config()?;
     configs.read_repo_config(repo_path)?;
 
-    let settings = build_settings(&configs);
-    let aliases_map = build_aliases_map(&configs)?;
-
-    Ok((settings, aliases_map))
+    Ok(configs)
 }
 
 fn build_settings(configs: &LayeredConfigs) -> UserSettings {
@@ -87,12 +539,11 @@ fn build_settings(configs: &LayeredConfigs) -> UserSettings {
 }
 
 fn build_aliases_map(layered_configs: &LayeredConfigs) -> Result<RevsetAliasesMap> {
-    const TABLE_KEY: &str = "revset-aliases";
     let mut aliases_map = RevsetAliasesMap::new();
     // Load from all config layers in order. 'f(x)' in default layer should be
     // overridden by 'f(a)' in user.
     for (_, config) in layered_configs.sources() {
-        let table = if let Some(table) = config.get_table(TABLE_KEY).optional()? {
+        let table = if let Some(table) = config.get_table(REVSET_ALIASES_KEY).optional()? {
             table
         } else {
             continue;
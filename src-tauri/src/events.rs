@@ -0,0 +1,21 @@
+//! Typed notifications for the frontend; see messages::Event.
+
+use serde_json::Value;
+use tauri::{Emitter, Wry};
+
+use crate::messages::Event;
+
+pub trait EventSink {
+    fn send_event(&self, event: Event) -> tauri::Result<()>;
+}
+
+impl<T: Emitter<Wry>> EventSink for T {
+    fn send_event(&self, event: Event) -> tauri::Result<()> {
+        // adjacently tagged, so this is {"name": ..., "payload": ...}
+        let mut value = serde_json::to_value(event)?;
+        match value["name"].take() {
+            Value::String(name) => self.emit(&name, value["payload"].take()),
+            _ => unreachable!("events are tagged with their channel"),
+        }
+    }
+}
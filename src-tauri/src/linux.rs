@@ -0,0 +1,42 @@
+//! Launches from a desktop environment, such as .desktop actions or a file manager's "Open With",
+//! are handed over DBus to the instance which is already running. This handles them there.
+
+use std::path::PathBuf;
+use std::thread;
+
+use clap::Parser;
+use tauri::{AppHandle, Manager};
+
+use crate::{handler, menu, try_open_repository, Args};
+
+/// activates the main window and opens whatever the new launch asked for
+pub fn handle_launch(app: &AppHandle, argv: Vec<String>, cwd: String) {
+    let args = match Args::try_parse_from(argv) {
+        Ok(args) => args,
+        Err(err) => {
+            log::warn!("ignoring launch: {err}");
+            return;
+        }
+    };
+
+    let Some(window) = app.get_webview_window("main") else {
+        log::warn!("no window to activate");
+        return;
+    };
+    handler::optional!(window.unminimize());
+    handler::optional!(window.set_focus());
+
+    let window = window.as_ref().window();
+    if args.open {
+        menu::repo_open(&window);
+    } else {
+        // relative to where the new launch was made, not this process
+        let path = match args.workspace {
+            Some(workspace) => PathBuf::from(cwd).join(workspace),
+            None => PathBuf::from(cwd),
+        };
+
+        // opening waits for the worker, which mustn't happen on the main thread
+        thread::spawn(move || handler::nonfatal!(try_open_repository(&window, Some(path))));
+    }
+}
@@ -0,0 +1,90 @@
+use std::thread;
+
+use objc2::{
+    declare_class, msg_send_id, mutability, rc::Retained, runtime::AnyObject, ClassType,
+    DeclaredClass,
+};
+use objc2_app_kit::{NSApplication, NSPasteboard, NSPasteboardTypeFileURL};
+use objc2_foundation::{MainThreadMarker, NSObject, NSString};
+use tauri::{AppHandle, Manager, Url};
+
+use crate::{handler, try_open_repository};
+
+declare_class!(
+    /// receives the folder selected when "Open in GG" is chosen from the Services menu
+    struct ServiceProvider;
+
+    unsafe impl ClassType for ServiceProvider {
+        type Super = NSObject;
+        type Mutability = mutability::MainThreadOnly;
+        const NAME: &'static str = "GGServiceProvider";
+    }
+
+    impl DeclaredClass for ServiceProvider {
+        type Ivars = AppHandle;
+    }
+
+    unsafe impl ServiceProvider {
+        #[method(openFolder:userData:error:)]
+        fn open_folder(
+            &self,
+            pboard: &NSPasteboard,
+            _user_data: Option<&NSString>,
+            _error: *mut *mut NSString,
+        ) {
+            // safety: NSPasteboardTypeFileURL is a constant provided by AppKit
+            let url = unsafe { pboard.stringForType(NSPasteboardTypeFileURL) };
+            match url.and_then(|url| Url::parse(&url.to_string()).ok()) {
+                Some(url) => open_urls(self.ivars(), vec![url]),
+                None => log::warn!("Open in GG: no folder on the pasteboard"),
+            }
+        }
+    }
+);
+
+impl ServiceProvider {
+    fn new(mtm: MainThreadMarker, app: AppHandle) -> Retained<Self> {
+        let this = mtm.alloc::<Self>().set_ivars(app);
+        // safety: NSObject's designated initialiser
+        unsafe { msg_send_id![super(this), init] }
+    }
+}
+
+/// handles the NSServices declared in Info.plist; must be called on the main thread
+pub fn register_services(app: AppHandle) {
+    let Some(mtm) = MainThreadMarker::new() else {
+        log::warn!("services must be registered on the main thread");
+        return;
+    };
+
+    let provider = ServiceProvider::new(mtm, app);
+    let provider_object: &AnyObject = &provider;
+
+    // safety: the provider implements the methods named by NSMessage
+    unsafe { NSApplication::sharedApplication(mtm).setServicesProvider(Some(provider_object)) };
+
+    // it's needed for as long as the app runs
+    std::mem::forget(provider);
+}
+
+/// opens the first folder given by Finder, whether through Open With, the Dock or Services
+pub fn open_urls(app: &AppHandle, urls: Vec<Url>) {
+    let Some(path) = urls
+        .into_iter()
+        .filter_map(|url| url.to_file_path().ok())
+        .find(|path| path.is_dir())
+    else {
+        log::warn!("nothing to open");
+        return;
+    };
+
+    let Some(window) = app.get_webview_window("main") else {
+        log::warn!("no window to open {}", path.display());
+        return;
+    };
+    handler::optional!(window.set_focus());
+
+    // opening waits for the worker, which mustn't happen on the main thread
+    let window = window.as_ref().window();
+    thread::spawn(move || handler::nonfatal!(try_open_repository(&window, Some(path))));
+}
@@ -2,18 +2,27 @@
 
 mod callbacks;
 mod config;
+mod events;
 mod handler;
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "macos")]
+mod macos;
 mod menu;
 mod messages;
+mod paths;
+mod updates;
 #[cfg(windows)]
 mod windows;
 mod worker;
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc::{channel, Sender};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
 use clap::Parser;
@@ -21,18 +30,32 @@ use jj_cli::config::ConfigSource;
 use log::LevelFilter;
 use tauri::menu::Menu;
 use tauri::{ipc::InvokeError, Manager};
-use tauri::{Emitter, Listener, State, Window, WindowEvent, Wry};
+use tauri::{Listener, State, Window, WindowEvent, Wry};
+use tauri_plugin_dialog::{DialogExt, FilePath};
 use tauri_plugin_window_state::StateFlags;
 
 use messages::{
-    AbandonRevisions, BackoutRevisions, CheckoutRevision, CopyChanges, CreateRef, CreateRevision,
-    DeleteRef, DescribeRevision, DuplicateRevisions, GitFetch, GitPush, InputResponse,
-    InsertRevision, MoveChanges, MoveRef, MoveRevision, MoveSource, MutationResult, RenameBranch,
-    RevId, TrackBranch, UndoOperation, UntrackBranch,
+    AbandonRevisions, AddIgnorePattern, AddParentToMerge, BackoutRevisions, CheckoutRevision,
+    CheckpointWorkingCopy, CleanupBookmarks, ColocateRepository, CommitStaged, CopyChanges,
+    CreateFile, CreateMergeOf, CreateRef, CreateRevision, DeletePaths, DeleteRef, DescribeRevision,
+    DescribeRevisions, DuplicateRevisions, Event, FetchFromPath, FixRevisions, FollowWorkspace,
+    GitFetch, GitPush, GitRemote, GraftRevisions, HideRevisions, InputResponse, InsertRevision,
+    MergeRevisions, MoveChanges, MoveRef, MoveRevision, MoveSource, MutationResult,
+    ParallelizeRevisions, PasteRevisions, RemoveParentFromMerge, RemoveRevsetAlias, RenameBranch,
+    RenamePath, ResolveConflict, ResolveWithMergeTool, RevId, RevsetAlias, RewritePaths,
+    RunJjCommand, RunRebasePlan, SetAuthor, SetDefaultQuery, SetLabel, SetRevsetAlias, SetTrunk,
+    SetUserIdentity, SplitRevision, SwapParents, TraceEntry, TrackBranch, UndoOperation,
+    UnhideRevisions, UntrackBranch,
+};
+use serde::Serialize;
+use worker::{
+    Mutation, PasteBundle, ReaderPool, RecordedEvent, RevisionBundle, Session, SessionEvent,
+    SessionRecorder, WorkerSession,
 };
-use worker::{Mutation, Session, SessionEvent, WorkerSession};
 
 use crate::callbacks::FrontendCallbacks;
+use crate::config::{read_config, GGSettings};
+use crate::events::EventSink;
 
 #[derive(Parser, Debug)]
 #[command(version, author)]
@@ -44,23 +67,60 @@ struct Args {
     workspace: Option<PathBuf>,
     #[arg(short, long, help = "Enable debug logging.")]
     debug: bool,
+    #[arg(long, help = "Choose a workspace to open with a dialog.")]
+    open: bool,
+    #[cfg(target_os = "linux")]
+    #[arg(long, help = "Start a new instance, not activating a running one.")]
+    new_window: bool,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Record mutations to this file, so that they can be replayed in tests."
+    )]
+    record: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Ignore configured external tools and filesystem monitors, to rule out config problems."
+    )]
+    safe_mode: bool,
 }
 
 #[derive(Default)]
-struct AppState(Mutex<HashMap<String, WindowState>>);
+struct AppState {
+    windows: Mutex<HashMap<String, WindowState>>,
+    /// the revisions most recently copied in any window, with the token handed to the frontend;
+    /// bundles hold whole files, so older ones are dropped rather than kept for the app's lifetime
+    bundle: Mutex<Option<(String, Arc<RevisionBundle>)>>,
+    /// never reused, so that pasting an older copy fails instead of pasting the latest one
+    bundle_count: AtomicUsize,
+}
 
 struct WindowState {
     _worker: JoinHandle<()>,
     worker_channel: Sender<SessionEvent>,
     input_channel: Option<Sender<InputResponse>>,
     revision_menu: Menu<Wry>,
+    revisions_menu: Menu<Wry>,
     tree_menu: Menu<Wry>,
     ref_menu: Menu<Wry>,
+    selection: Option<String>,
+    /// mutations sent to the worker and not yet answered, oldest first
+    pending_mutations: Vec<PendingMutation>,
+    recorder: Option<SessionRecorder>,
+    /// show the open dialog once the frontend is ready
+    open_on_ready: bool,
+}
+
+struct PendingMutation {
+    /// the mutation's describe(), shown to the user while it runs
+    name: String,
+    /// the mutation's full contents, used to detect double-submits
+    key: String,
 }
 
 impl AppState {
     fn get_session(&self, window_label: &str) -> Sender<SessionEvent> {
-        self.0
+        self.windows
             .lock()
             .expect("state mutex poisoned")
             .get(window_label)
@@ -70,7 +130,7 @@ impl AppState {
     }
 
     fn set_input(&self, window_label: &str, tx: Sender<InputResponse>) {
-        self.0
+        self.windows
             .lock()
             .expect("state mutex poisoned")
             .get_mut(window_label)
@@ -79,7 +139,7 @@ impl AppState {
     }
 
     fn take_input(&self, window_label: &str) -> Option<Sender<InputResponse>> {
-        self.0
+        self.windows
             .lock()
             .expect("state mutex poisoned")
             .get_mut(window_label)
@@ -87,6 +147,99 @@ impl AppState {
             .input_channel
             .take()
     }
+
+    fn set_selection(&self, window_label: &str, change_hex: Option<String>) {
+        if let Some(state) = self
+            .windows
+            .lock()
+            .expect("state mutex poisoned")
+            .get_mut(window_label)
+        {
+            state.selection = change_hex;
+        }
+    }
+
+    fn get_selection(&self, window_label: &str) -> Option<String> {
+        self.windows
+            .lock()
+            .expect("state mutex poisoned")
+            .get(window_label)
+            .and_then(|state| state.selection.clone())
+    }
+
+    /// queues a mutation behind any already sent to the worker, or rejects it if an identical one
+    /// is still pending; returns the name of the mutation now running
+    fn enqueue_mutation(&self, window_label: &str, name: String, key: String) -> Result<String> {
+        let mut windows = self.windows.lock().expect("state mutex poisoned");
+        let state = windows.get_mut(window_label).expect("session not found");
+        if state
+            .pending_mutations
+            .iter()
+            .any(|pending| pending.key == key)
+        {
+            return Err(anyhow!("{name} is already in progress."));
+        }
+        state.pending_mutations.push(PendingMutation { name, key });
+        Ok(state.pending_mutations[0].name.clone())
+    }
+
+    fn take_open_on_ready(&self, window_label: &str) -> bool {
+        let mut windows = self.windows.lock().expect("state mutex poisoned");
+        windows
+            .get_mut(window_label)
+            .is_some_and(|state| std::mem::take(&mut state.open_on_ready))
+    }
+
+    /// appends to the window's session recording, if there is one
+    fn record(&self, window_label: &str, event: impl FnOnce() -> Result<RecordedEvent>) {
+        let mut windows = self.windows.lock().expect("state mutex poisoned");
+        if let Some(recorder) = windows
+            .get_mut(window_label)
+            .and_then(|state| state.recorder.as_mut())
+        {
+            handler::optional!(event().and_then(|event| recorder.record(&event)));
+        }
+    }
+
+    /// removes a finished mutation from the queue; returns the name of the next one, if any
+    fn dequeue_mutation(&self, window_label: &str, key: &str) -> Option<String> {
+        let mut windows = self.windows.lock().expect("state mutex poisoned");
+        let state = windows.get_mut(window_label)?;
+        if let Some(index) = state
+            .pending_mutations
+            .iter()
+            .position(|pending| pending.key == key)
+        {
+            state.pending_mutations.remove(index);
+        }
+        state
+            .pending_mutations
+            .first()
+            .map(|pending| pending.name.clone())
+    }
+
+    fn add_bundle(&self, bundle: RevisionBundle) -> String {
+        let token = format!(
+            "bundle-{}",
+            self.bundle_count.fetch_add(1, Ordering::Relaxed) + 1
+        );
+        *self.bundle.lock().expect("state mutex poisoned") =
+            Some((token.clone(), Arc::new(bundle)));
+        token
+    }
+
+    fn get_bundle(&self, token: &str) -> Option<Arc<RevisionBundle>> {
+        self.bundle
+            .lock()
+            .expect("state mutex poisoned")
+            .as_ref()
+            .filter(|(latest, _)| latest == token)
+            .map(|(_, bundle)| bundle.clone())
+    }
+
+    fn has_bundles(&self) -> bool {
+        self.bundle.lock().expect("state mutex poisoned").is_some()
+    }
 }
 
 fn main() -> Result<()> {
@@ -98,7 +251,18 @@ fn main() -> Result<()> {
 
     let args = Args::parse();
 
-    tauri::Builder::default()
+    let builder = tauri::Builder::default();
+
+    // later launches are handed to this instance over DBus, unless they want a window of their own;
+    // the running instance couldn't honour flags which configure the process, so those get one too
+    #[cfg(target_os = "linux")]
+    let builder = if args.new_window || args.debug || args.safe_mode || args.record.is_some() {
+        builder
+    } else {
+        builder.plugin(tauri_plugin_single_instance::init(linux::handle_launch))
+    };
+
+    builder
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(
@@ -135,31 +299,104 @@ fn main() -> Result<()> {
         .invoke_handler(tauri::generate_handler![
             notify_window_ready,
             notify_input,
+            reopen_workspace,
+            open_workspace,
             forward_accelerator,
             forward_context_menu,
             query_log,
             query_log_next_page,
+            export_log,
             query_revision,
+            query_revision_paths_next_page,
+            query_working_copy_diff,
+            query_suggested_description,
+            query_change_operations,
+            query_revset_aliases,
+            query_description_check,
+            query_notes,
+            set_note,
+            save_draft,
+            load_draft,
+            discard_draft,
+            query_staged,
+            stage_change,
+            query_trace,
+            query_rewrite_preview,
+            query_revision_stats,
+            query_amend_targets,
+            query_merge_base,
+            query_drop_targets,
+            set_view_operation,
+            query_operation_diff,
+            query_operations,
+            query_activity,
+            query_checkpoints,
+            query_verify_repository,
+            query_merged_bookmarks,
+            copy_revisions,
+            query_path_info,
+            query_schema_version,
+            init_workspace,
+            create_sample_repository,
             query_remotes,
+            query_remote_connection,
+            query_resolve_path,
+            launch_diff_tool,
+            query_conflict,
             abandon_revisions,
+            fix_revisions,
+            commit_staged,
+            split_revision,
+            hide_revisions,
+            unhide_revisions,
             backout_revisions,
             checkout_revision,
             create_revision,
             describe_revision,
+            describe_revisions,
             duplicate_revisions,
+            merge_revisions,
+            parallelize_revisions,
+            create_merge_of,
+            add_parent_to_merge,
+            remove_parent_from_merge,
+            swap_parents,
+            graft_revisions,
+            paste_revisions,
+            run_rebase_plan,
             insert_revision,
             move_revision,
             move_source,
             move_changes,
             copy_changes,
+            resolve_conflict,
+            resolve_with_merge_tool,
+            rewrite_paths,
+            add_ignore_pattern,
+            delete_paths,
+            create_file,
+            rename_path,
+            checkpoint_working_copy,
+            set_user_identity,
+            set_revset_alias,
+            remove_revset_alias,
+            set_trunk,
+            set_author,
+            set_default_query,
+            follow_workspace,
+            set_label,
             track_branch,
             untrack_branch,
             rename_branch,
             create_ref,
             delete_ref,
+            cleanup_bookmarks,
             move_ref,
             git_push,
             git_fetch,
+            fetch_from_path,
+            colocate_repository,
+            run_jj_command,
             undo_operation
         ])
         .menu(menu::build_main)
@@ -170,23 +407,28 @@ fn main() -> Result<()> {
             let (sender, receiver) = channel();
 
             let mut handle = window.as_ref().window();
+            let window_label = window.label().to_owned();
             let window_worker = thread::spawn(move || {
                 log::info!("start worker");
 
-                while let Err(err) =
-                    WorkerSession::new(FrontendCallbacks(handle.clone()), args.workspace.clone())
-                        .handle_events(&receiver)
-                        .context("worker")
+                let readers = Arc::new(ReaderPool::new(2));
+                while let Err(err) = (WorkerSession {
+                    window_label: Some(window_label.clone()),
+                    readers: Some(readers.clone()),
+                    safe_mode: args.safe_mode,
+                    ..WorkerSession::new(FrontendCallbacks(handle.clone()), args.workspace.clone())
+                })
+                .handle_events(&receiver)
+                .context("worker")
                 {
                     log::info!("restart worker: {err:#}");
 
                     // it's ok if the worker has to restart, as long as we can notify the frontend of it
-                    handler::fatal!(handle.emit(
-                        "gg://repo/config",
+                    handler::fatal!(handle.send_event(Event::RepoConfig(
                         messages::RepoConfig::WorkerError {
                             message: format!("{err:#}"),
-                        },
-                    ));
+                        }
+                    )));
                 }
             });
 
@@ -199,41 +441,79 @@ fn main() -> Result<()> {
             window.listen("gg://revision/select", move |event| {
                 let payload: Result<Option<messages::RevHeader>, serde_json::Error> =
                     serde_json::from_str(event.payload());
-                if let Some(menu) = handle.menu() {
-                    if let Ok(selection) = payload {
+                if let Ok(selection) = payload {
+                    handle.state::<AppState>().set_selection(
+                        handle.label(),
+                        selection.as_ref().map(|header| header.id.change.hex.clone()),
+                    );
+                    if let Some(menu) = handle.menu() {
                         handler::fatal!(menu::handle_selection(menu, selection));
                     }
                 }
             });
 
-            let (revision_menu, tree_menu, ref_menu) = menu::build_context(app.handle())?;
+            let (revision_menu, revisions_menu, tree_menu, ref_menu) =
+                menu::build_context(app.handle())?;
+
+            #[cfg(target_os = "macos")]
+            macos::register_services(app.handle().clone());
+
+            let recorder = args
+                .record
+                .as_deref()
+                .map(SessionRecorder::create)
+                .transpose()?;
 
             let app_state = app.state::<AppState>();
-            app_state.0.lock().unwrap().insert(
+            app_state.windows.lock().unwrap().insert(
                 window.label().to_owned(),
                 WindowState {
                     _worker: window_worker,
                     worker_channel: sender,
                     input_channel: None,
                     revision_menu,
+                    revisions_menu,
                     tree_menu,
                     ref_menu,
+                    selection: None,
+                    pending_mutations: Vec::new(),
+                    recorder,
+                    open_on_ready: args.open,
                 },
             );
 
             Ok(())
         })
         .manage(AppState::default())
-        .run(tauri::generate_context!())?;
+        .build(tauri::generate_context!())?
+        .run(|_app, _event| {
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Opened { urls } = _event {
+                macos::open_urls(_app, urls);
+            }
+        });
 
     Ok(())
 }
 
 #[tauri::command(async)]
-fn notify_window_ready(window: Window) {
+fn notify_window_ready(window: Window, app_state: State<AppState>) {
     log::debug!("window opened; loading cwd");
     handler::fatal!(window.show());
     handler::nonfatal!(try_open_repository(&window, None));
+    if app_state.take_open_on_ready(window.label()) {
+        menu::repo_open(&window);
+    }
+}
+
+#[tauri::command(async)]
+fn reopen_workspace(window: Window) {
+    handler::nonfatal!(try_open_repository(&window, None));
+}
+
+#[tauri::command(async)]
+fn open_workspace(window: Window) {
+    menu::repo_open(&window);
 }
 
 #[tauri::command(async)]
@@ -267,6 +547,10 @@ fn query_log(
     window: Window,
     app_state: State<AppState>,
     revset: String,
+    first_parent: bool,
+    show_hidden: bool,
+    elide: Option<messages::LogElision>,
+    full: bool,
 ) -> Result<messages::LogPage, InvokeError> {
     let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
     let (call_tx, call_rx) = channel();
@@ -275,6 +559,10 @@ fn query_log(
         .send(SessionEvent::QueryLog {
             tx: call_tx,
             query: revset,
+            first_parent,
+            show_hidden,
+            elide,
+            full,
         })
         .map_err(InvokeError::from_error)?;
     call_rx
@@ -300,37 +588,67 @@ fn query_log_next_page(
         .map_err(InvokeError::from_anyhow)
 }
 
+/// prompts for a destination file; returns None if the user cancels
 #[tauri::command(async)]
-fn query_revision(
+fn export_log(
     window: Window,
     app_state: State<AppState>,
-    id: RevId,
-) -> Result<messages::RevResult, InvokeError> {
+    query: String,
+    first_parent: bool,
+    show_hidden: bool,
+    format: messages::LogExportFormat,
+) -> Result<Option<usize>, InvokeError> {
+    let (filter_name, extension) = match format {
+        messages::LogExportFormat::Json => ("JSON", "json"),
+        messages::LogExportFormat::Csv => ("CSV", "csv"),
+        messages::LogExportFormat::Template { .. } => ("Text", "txt"),
+    };
+    let path = match window
+        .dialog()
+        .file()
+        .add_filter(filter_name, &[extension])
+        .set_file_name(format!("log.{extension}"))
+        .blocking_save_file()
+    {
+        Some(FilePath::Path(path)) => path,
+        _ => return Ok(None),
+    };
+
     let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
     let (call_tx, call_rx) = channel();
 
     session_tx
-        .send(SessionEvent::QueryRevision { tx: call_tx, id })
+        .send(SessionEvent::ExportLog {
+            tx: call_tx,
+            query,
+            first_parent,
+            show_hidden,
+            format,
+            path,
+        })
         .map_err(InvokeError::from_error)?;
     call_rx
         .recv()
         .map_err(InvokeError::from_error)?
+        .map(Some)
         .map_err(InvokeError::from_anyhow)
 }
 
 #[tauri::command(async)]
-fn query_remotes(
+fn query_revision(
     window: Window,
     app_state: State<AppState>,
-    tracking_branch: Option<String>,
-) -> Result<Vec<String>, InvokeError> {
+    id: RevId,
+    aggregate_directories: Option<bool>,
+) -> Result<messages::RevResult, InvokeError> {
     let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
     let (call_tx, call_rx) = channel();
 
     session_tx
-        .send(SessionEvent::QueryRemotes {
+        .send(SessionEvent::QueryRevision {
             tx: call_tx,
-            tracking_branch,
+            id,
+            aggregate_directories: aggregate_directories.unwrap_or(false),
         })
         .map_err(InvokeError::from_error)?;
     call_rx
@@ -340,129 +658,1141 @@ fn query_remotes(
 }
 
 #[tauri::command(async)]
-fn abandon_revisions(
+fn query_suggested_description(
     window: Window,
     app_state: State<AppState>,
-    mutation: AbandonRevisions,
-) -> Result<MutationResult, InvokeError> {
-    try_mutate(window, app_state, mutation)
+    id: RevId,
+) -> Result<String, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QuerySuggestedDescription { tx: call_tx, id })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
 }
 
 #[tauri::command(async)]
-fn backout_revisions(
+fn query_change_operations(
     window: Window,
     app_state: State<AppState>,
-    mutation: BackoutRevisions,
-) -> Result<MutationResult, InvokeError> {
-    try_mutate(window, app_state, mutation)
+    id: RevId,
+) -> Result<messages::ChangeHistory, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryChangeOperations { tx: call_tx, id })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
 }
 
 #[tauri::command(async)]
-fn checkout_revision(
+fn query_trace(
     window: Window,
     app_state: State<AppState>,
-    mutation: CheckoutRevision,
-) -> Result<MutationResult, InvokeError> {
-    try_mutate(window, app_state, mutation)
+) -> Result<Vec<TraceEntry>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryTrace { tx: call_tx })
+        .map_err(InvokeError::from_error)?;
+    call_rx.recv().map_err(InvokeError::from_error)
 }
 
 #[tauri::command(async)]
-fn create_revision(
+fn query_revset_aliases(
     window: Window,
     app_state: State<AppState>,
-    mutation: CreateRevision,
-) -> Result<MutationResult, InvokeError> {
-    try_mutate(window, app_state, mutation)
+) -> Result<Vec<RevsetAlias>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryRevsetAliases { tx: call_tx })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
 }
 
 #[tauri::command(async)]
-fn insert_revision(
+fn query_description_check(
     window: Window,
     app_state: State<AppState>,
-    mutation: InsertRevision,
-) -> Result<MutationResult, InvokeError> {
-    try_mutate(window, app_state, mutation)
+    text: String,
+) -> Result<messages::DescriptionCheck, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryDescriptionCheck { tx: call_tx, text })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
 }
 
 #[tauri::command(async)]
-fn describe_revision(
+fn query_notes(
     window: Window,
     app_state: State<AppState>,
-    mutation: DescribeRevision,
-) -> Result<MutationResult, InvokeError> {
-    try_mutate(window, app_state, mutation)
+) -> Result<Vec<messages::RevNote>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryNotes { tx: call_tx })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
 }
 
 #[tauri::command(async)]
-fn duplicate_revisions(
+fn set_note(
     window: Window,
     app_state: State<AppState>,
-    mutation: DuplicateRevisions,
-) -> Result<MutationResult, InvokeError> {
-    try_mutate(window, app_state, mutation)
+    change_hex: String,
+    text: String,
+) -> Result<(), InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::SetNote {
+            tx: call_tx,
+            change_hex,
+            text,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
 }
 
 #[tauri::command(async)]
-fn move_revision(
+fn save_draft(
     window: Window,
     app_state: State<AppState>,
-    mutation: MoveRevision,
-) -> Result<MutationResult, InvokeError> {
-    try_mutate(window, app_state, mutation)
+    change_hex: String,
+    text: String,
+) -> Result<(), InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::SaveDraft {
+            tx: call_tx,
+            change_hex,
+            text,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
 }
 
 #[tauri::command(async)]
-fn move_source(
+fn load_draft(
     window: Window,
     app_state: State<AppState>,
-    mutation: MoveSource,
-) -> Result<MutationResult, InvokeError> {
-    try_mutate(window, app_state, mutation)
+    change_hex: String,
+) -> Result<Option<String>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::LoadDraft {
+            tx: call_tx,
+            change_hex,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
 }
 
 #[tauri::command(async)]
-fn move_changes(
+fn discard_draft(
     window: Window,
     app_state: State<AppState>,
-    mutation: MoveChanges,
-) -> Result<MutationResult, InvokeError> {
-    try_mutate(window, app_state, mutation)
+    change_hex: String,
+) -> Result<(), InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::DiscardDraft {
+            tx: call_tx,
+            change_hex,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
 }
 
 #[tauri::command(async)]
-fn copy_changes(
+fn query_staged(
     window: Window,
     app_state: State<AppState>,
-    mutation: CopyChanges,
-) -> Result<MutationResult, InvokeError> {
-    try_mutate(window, app_state, mutation)
+) -> Result<messages::StagedChanges, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryStaged { tx: call_tx })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
 }
 
 #[tauri::command(async)]
-fn track_branch(
+fn stage_change(
     window: Window,
     app_state: State<AppState>,
-    mutation: TrackBranch,
-) -> Result<MutationResult, InvokeError> {
-    try_mutate(window, app_state, mutation)
+    path: messages::TreePath,
+    hunk: Option<messages::HunkLocation>,
+    staged: bool,
+) -> Result<messages::StagedChanges, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::StageChange {
+            tx: call_tx,
+            path,
+            hunk,
+            staged,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
 }
 
 #[tauri::command(async)]
-fn untrack_branch(
+fn query_rewrite_preview(
     window: Window,
     app_state: State<AppState>,
-    mutation: UntrackBranch,
-) -> Result<MutationResult, InvokeError> {
-    try_mutate(window, app_state, mutation)
+    id: RevId,
+    paths: Vec<messages::TreePath>,
+    pattern: String,
+    replacement: String,
+) -> Result<Vec<messages::RewriteMatch>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryRewritePreview {
+            tx: call_tx,
+            id,
+            paths,
+            pattern,
+            replacement,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
 }
 
 #[tauri::command(async)]
-fn rename_branch(
+fn query_revision_paths_next_page(
     window: Window,
     app_state: State<AppState>,
-    mutation: RenameBranch,
-) -> Result<MutationResult, InvokeError> {
-    try_mutate(window, app_state, mutation)
+    id: RevId,
+    skip: usize,
+) -> Result<messages::RevChangesPage, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryRevisionPathsNextPage {
+            tx: call_tx,
+            id,
+            skip,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_working_copy_diff(
+    window: Window,
+    app_state: State<AppState>,
+    base: String,
+    skip: usize,
+    snapshot: bool,
+) -> Result<messages::WorkingCopyDiff, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryWorkingCopyDiff {
+            tx: call_tx,
+            base,
+            skip,
+            snapshot,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_revision_stats(
+    window: Window,
+    app_state: State<AppState>,
+    ids: Vec<messages::CommitId>,
+) -> Result<Vec<messages::RevStats>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryRevisionStats { tx: call_tx, ids })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_amend_targets(
+    window: Window,
+    app_state: State<AppState>,
+    paths: Vec<messages::TreePath>,
+    limit: usize,
+) -> Result<Vec<messages::AmendTarget>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryAmendTargets {
+            tx: call_tx,
+            paths,
+            limit,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_merge_base(
+    window: Window,
+    app_state: State<AppState>,
+    left: messages::CommitId,
+    right: messages::CommitId,
+) -> Result<messages::MergeBase, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryMergeBase {
+            tx: call_tx,
+            left,
+            right,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_drop_targets(
+    window: Window,
+    app_state: State<AppState>,
+    dragged: Vec<messages::CommitId>,
+    visible: Vec<messages::CommitId>,
+) -> Result<Vec<messages::DropTarget>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryDropTargets {
+            tx: call_tx,
+            dragged,
+            visible,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn init_workspace(
+    window: Window,
+    app_state: State<AppState>,
+    path: PathBuf,
+    options: messages::InitWorkspace,
+) -> Result<(), InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::InitWorkspace {
+            tx: call_tx,
+            wd: path.clone(),
+            options,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)?;
+
+    try_open_repository(&window, Some(path)).map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn create_sample_repository(
+    window: Window,
+    app_state: State<AppState>,
+    parent: PathBuf,
+) -> Result<(), InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::CreateSampleRepository {
+            tx: call_tx,
+            parent,
+        })
+        .map_err(InvokeError::from_error)?;
+    let path = call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)?;
+
+    try_open_repository(&window, Some(path)).map_err(InvokeError::from_anyhow)
+}
+
+// answered without the worker, so that it works whether or not a workspace is loaded
+#[tauri::command(async)]
+fn query_path_info(path: PathBuf) -> messages::PathInfo {
+    worker::query_path_info(&path)
+}
+
+// also answered without the worker, so that a stale frontend finds out before it loads anything
+#[tauri::command]
+fn query_schema_version() -> u32 {
+    messages::SCHEMA_VERSION
+}
+
+#[tauri::command(async)]
+fn query_operation_diff(
+    window: Window,
+    app_state: State<AppState>,
+    op: String,
+) -> Result<messages::OperationDiff, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryOperationDiff { tx: call_tx, op })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_operations(
+    window: Window,
+    app_state: State<AppState>,
+) -> Result<Vec<messages::OperationSummary>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryOperations { tx: call_tx })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_activity(
+    window: Window,
+    app_state: State<AppState>,
+    limit: usize,
+) -> Result<Vec<messages::ActivityEntry>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryActivity { tx: call_tx, limit })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_checkpoints(
+    window: Window,
+    app_state: State<AppState>,
+) -> Result<Vec<messages::Checkpoint>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryCheckpoints { tx: call_tx })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_verify_repository(
+    window: Window,
+    app_state: State<AppState>,
+) -> Result<messages::RepoVerification, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryVerifyRepository { tx: call_tx })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_merged_bookmarks(
+    window: Window,
+    app_state: State<AppState>,
+) -> Result<Vec<messages::MergedBookmark>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryMergedBookmarks { tx: call_tx })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn set_view_operation(
+    window: Window,
+    app_state: State<AppState>,
+    op: Option<String>,
+) -> Result<messages::RepoStatus, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::SetViewOperation { tx: call_tx, op })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_remotes(
+    window: Window,
+    app_state: State<AppState>,
+    tracking_branch: Option<String>,
+) -> Result<Vec<GitRemote>, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryRemotes {
+            tx: call_tx,
+            tracking_branch,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_remote_connection(
+    window: Window,
+    app_state: State<AppState>,
+    remote_name: String,
+) -> Result<messages::RemoteConnection, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryRemoteConnection {
+            tx: call_tx,
+            remote_name,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_resolve_path(
+    window: Window,
+    app_state: State<AppState>,
+    path: messages::TreePath,
+) -> Result<messages::ResolvedPath, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryResolvePath { tx: call_tx, path })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn launch_diff_tool(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+    path: Option<messages::TreePath>,
+) -> Result<(), InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::LaunchDiffTool {
+            tx: call_tx,
+            id,
+            path,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn query_conflict(
+    window: Window,
+    app_state: State<AppState>,
+    id: RevId,
+    path: messages::TreePath,
+) -> Result<messages::FileConflict, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::QueryConflict {
+            tx: call_tx,
+            id,
+            path,
+        })
+        .map_err(InvokeError::from_error)?;
+    call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)
+}
+
+#[tauri::command(async)]
+fn fix_revisions(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: FixRevisions,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn abandon_revisions(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: AbandonRevisions,
+    confirmation: Option<String>,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate_confirmed(window, app_state, mutation, confirmation)
+}
+
+#[tauri::command(async)]
+fn commit_staged(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: CommitStaged,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn split_revision(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: SplitRevision,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn hide_revisions(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: HideRevisions,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn unhide_revisions(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: UnhideRevisions,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn backout_revisions(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: BackoutRevisions,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn checkout_revision(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: CheckoutRevision,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn create_revision(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: CreateRevision,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn insert_revision(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: InsertRevision,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn describe_revision(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: DescribeRevision,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn describe_revisions(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: DescribeRevisions,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn parallelize_revisions(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: ParallelizeRevisions,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn duplicate_revisions(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: DuplicateRevisions,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+// bundles are kept by the app rather than the worker, so that any window can paste them
+#[tauri::command(async)]
+fn copy_revisions(
+    window: Window,
+    app_state: State<AppState>,
+    ids: Vec<RevId>,
+) -> Result<String, InvokeError> {
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    let (call_tx, call_rx) = channel();
+
+    session_tx
+        .send(SessionEvent::CopyRevisions { tx: call_tx, ids })
+        .map_err(InvokeError::from_error)?;
+    let bundle = call_rx
+        .recv()
+        .map_err(InvokeError::from_error)?
+        .map_err(InvokeError::from_anyhow)?;
+    Ok(app_state.add_bundle(bundle))
+}
+
+#[tauri::command(async)]
+fn paste_revisions(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: PasteRevisions,
+) -> Result<MutationResult, InvokeError> {
+    let Some(bundle) = app_state.get_bundle(&mutation.token) else {
+        return Ok(MutationResult::PreconditionError {
+            message: "The copied revisions are no longer available".to_owned(),
+        });
+    };
+    // bundles only exist in memory, so pastes can't be recorded
+    execute_mutation(
+        window,
+        app_state,
+        PasteBundle {
+            bundle,
+            parent_ids: mutation.parent_ids,
+        },
+        None,
+    )
+}
+
+#[tauri::command(async)]
+fn merge_revisions(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: MergeRevisions,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn create_merge_of(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: CreateMergeOf,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn add_parent_to_merge(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: AddParentToMerge,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn remove_parent_from_merge(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: RemoveParentFromMerge,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn swap_parents(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: SwapParents,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn graft_revisions(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: GraftRevisions,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn run_rebase_plan(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: RunRebasePlan,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn move_revision(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: MoveRevision,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn move_source(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: MoveSource,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn move_changes(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: MoveChanges,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn copy_changes(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: CopyChanges,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn resolve_conflict(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: ResolveConflict,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn resolve_with_merge_tool(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: ResolveWithMergeTool,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn rewrite_paths(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: RewritePaths,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn checkpoint_working_copy(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: CheckpointWorkingCopy,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn add_ignore_pattern(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: AddIgnorePattern,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn delete_paths(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: DeletePaths,
+    confirmation: Option<String>,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate_confirmed(window, app_state, mutation, confirmation)
+}
+
+#[tauri::command(async)]
+fn create_file(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: CreateFile,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn rename_path(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: RenamePath,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn set_user_identity(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: SetUserIdentity,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn set_revset_alias(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: SetRevsetAlias,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn remove_revset_alias(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: RemoveRevsetAlias,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+/// the workspace is reloaded afterwards, so that its config reports the new default
+#[tauri::command(async)]
+fn set_default_query(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: SetDefaultQuery,
+) -> Result<MutationResult, InvokeError> {
+    let result = try_mutate(window.clone(), app_state, mutation)?;
+    if let MutationResult::Updated { .. } = result {
+        try_open_repository(&window, None).map_err(InvokeError::from_anyhow)?;
+    }
+    Ok(result)
+}
+
+#[tauri::command(async)]
+fn set_author(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: SetAuthor,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+/// the workspace is reloaded afterwards, so that its config reports the followed workspace
+#[tauri::command(async)]
+fn follow_workspace(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: FollowWorkspace,
+) -> Result<MutationResult, InvokeError> {
+    let result = try_mutate(window.clone(), app_state, mutation)?;
+    if let MutationResult::Updated { .. } = result {
+        try_open_repository(&window, None).map_err(InvokeError::from_anyhow)?;
+    }
+    Ok(result)
+}
+
+/// the workspace is reloaded afterwards, so that its config reports the new trunk
+#[tauri::command(async)]
+fn set_trunk(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: SetTrunk,
+) -> Result<MutationResult, InvokeError> {
+    let result = try_mutate(window.clone(), app_state, mutation)?;
+    if let MutationResult::Updated { .. } = result {
+        try_open_repository(&window, None).map_err(InvokeError::from_anyhow)?;
+    }
+    Ok(result)
+}
+
+#[tauri::command(async)]
+fn set_label(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: SetLabel,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn track_branch(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: TrackBranch,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn untrack_branch(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: UntrackBranch,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+#[tauri::command(async)]
+fn rename_branch(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: RenameBranch,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
 }
 
 #[tauri::command(async)]
@@ -479,6 +1809,16 @@ fn delete_ref(
     window: Window,
     app_state: State<AppState>,
     mutation: DeleteRef,
+    confirmation: Option<String>,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate_confirmed(window, app_state, mutation, confirmation)
+}
+
+#[tauri::command(async)]
+fn cleanup_bookmarks(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: CleanupBookmarks,
 ) -> Result<MutationResult, InvokeError> {
     try_mutate(window, app_state, mutation)
 }
@@ -497,8 +1837,9 @@ fn git_push(
     window: Window,
     app_state: State<AppState>,
     mutation: GitPush,
+    confirmation: Option<String>,
 ) -> Result<MutationResult, InvokeError> {
-    try_mutate(window, app_state, mutation)
+    try_mutate_confirmed(window, app_state, mutation, confirmation)
 }
 
 #[tauri::command(async)]
@@ -510,12 +1851,47 @@ fn git_fetch(
     try_mutate(window, app_state, mutation)
 }
 
+#[tauri::command(async)]
+fn fetch_from_path(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: FetchFromPath,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate(window, app_state, mutation)
+}
+
+/// the workspace is always reloaded afterwards, as the worker releases it to move the git backend
+#[tauri::command(async)]
+fn colocate_repository(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: ColocateRepository,
+) -> Result<MutationResult, InvokeError> {
+    let result = try_mutate(window.clone(), app_state, mutation)?;
+    try_open_repository(&window, None).map_err(InvokeError::from_anyhow)?;
+    Ok(result)
+}
+
+/// the workspace is always reloaded afterwards, as the CLI may have changed it even if it failed
+#[tauri::command(async)]
+fn run_jj_command(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: RunJjCommand,
+) -> Result<MutationResult, InvokeError> {
+    let result = try_mutate(window.clone(), app_state, mutation)?;
+    try_open_repository(&window, None).map_err(InvokeError::from_anyhow)?;
+    Ok(result)
+}
+
 #[tauri::command(async)]
 fn undo_operation(
     window: Window,
     app_state: State<AppState>,
+    mutation: UndoOperation,
+    confirmation: Option<String>,
 ) -> Result<MutationResult, InvokeError> {
-    try_mutate(window, app_state, UndoOperation)
+    try_mutate_confirmed(window, app_state, mutation, confirmation)
 }
 
 fn try_open_repository(window: &Window, cwd: Option<PathBuf>) -> Result<()> {
@@ -535,10 +1911,26 @@ fn try_open_repository(window: &Window, cwd: Option<PathBuf>) -> Result<()> {
         Ok(config) => {
             log::debug!("load workspace succeeded");
             match &config {
-                messages::RepoConfig::Workspace { absolute_path, .. } => {
+                messages::RepoConfig::Workspace {
+                    absolute_path,
+                    check_updates,
+                    safe_mode,
+                    ..
+                } => {
                     let repo_path = absolute_path.0.clone();
                     window.set_title((String::from("GG - ") + repo_path.as_str()).as_str())?;
 
+                    if let Some(menu) = window.menu() {
+                        menu::handle_safe_mode(menu, *safe_mode)?;
+                    }
+
+                    if *check_updates {
+                        let proxy = read_config(&Path::new(&repo_path).join(".jj").join("repo"))
+                            .ok()
+                            .and_then(|(settings, _)| settings.git_proxy());
+                        updates::check_in_background(window, proxy);
+                    }
+
                     // on windows, update the shell jumplist; this can be slow
                     #[cfg(windows)]
                     {
@@ -554,39 +1946,88 @@ fn try_open_repository(window: &Window, cwd: Option<PathBuf>) -> Result<()> {
                     window.set_title("GG - Gui for JJ")?;
                 }
             }
-            window.emit("gg://repo/config", config)?;
+            window.send_event(Event::RepoConfig(config))?;
         }
         Err(err) => {
             log::warn!("load workspace failed: {err}");
             window.set_title("GG - Gui for JJ")?;
-            window.emit(
-                "gg://repo/config",
-                messages::RepoConfig::LoadError {
-                    absolute_path: cwd.unwrap_or(PathBuf::new()).into(),
-                    message: format!("{:#?}", err),
-                },
-            )?;
+            window.send_event(Event::RepoConfig(messages::RepoConfig::LoadError {
+                absolute_path: cwd.unwrap_or(PathBuf::new()).into(),
+                message: format!("{:#?}", err),
+            }))?;
         }
     }
 
     Ok(())
 }
 
-fn try_mutate<T: Mutation + Send + Sync + 'static>(
+fn try_set_safe_mode(window: &Window, enabled: bool) -> Result<()> {
+    log::info!("set safe mode {enabled}");
+
+    let app_state = window.state::<AppState>();
+    let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+    session_tx.send(SessionEvent::SetSafeMode { enabled })?;
+
+    try_open_repository(window, None)
+}
+
+fn try_mutate<T: Mutation + Serialize + Send + Sync + 'static>(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: T,
+) -> Result<MutationResult, InvokeError> {
+    try_mutate_confirmed(window, app_state, mutation, None)
+}
+
+// destructive mutations may return ConfirmationRequired, to be resubmitted with its token
+fn try_mutate_confirmed<T: Mutation + Serialize + Send + Sync + 'static>(
+    window: Window,
+    app_state: State<AppState>,
+    mutation: T,
+    confirmation: Option<String>,
+) -> Result<MutationResult, InvokeError> {
+    app_state.record(window.label(), || {
+        RecordedEvent::mutation(&mutation, confirmation.clone())
+    });
+    execute_mutation(window, app_state, mutation, confirmation)
+}
+
+fn execute_mutation<T: Mutation + Send + Sync + 'static>(
     window: Window,
     app_state: State<AppState>,
     mutation: T,
+    confirmation: Option<String>,
 ) -> Result<MutationResult, InvokeError> {
+    // mutations run one at a time; while they wait, the frontend shows which is in progress
+    let name = mutation.describe();
+    let name = name.rsplit("::").next().unwrap_or(&name).to_owned();
+    let key = format!("{mutation:?}");
+    let running = match app_state.enqueue_mutation(window.label(), name, key.clone()) {
+        Ok(running) => running,
+        Err(err) => {
+            return Ok(MutationResult::PreconditionError {
+                message: err.to_string(),
+            })
+        }
+    };
+    handler::optional!(window.send_event(Event::WorkerBusy(Some(running))));
+
     let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
     let (call_tx, call_rx) = channel();
 
-    session_tx
+    let result = session_tx
         .send(SessionEvent::ExecuteMutation {
             tx: call_tx,
             mutation: Box::new(mutation),
+            confirmation,
         })
-        .map_err(InvokeError::from_error)?;
-    call_rx.recv().map_err(InvokeError::from_error)
+        .map_err(InvokeError::from_error)
+        .and_then(|_| call_rx.recv().map_err(InvokeError::from_error));
+
+    let next = app_state.dequeue_mutation(window.label(), &key);
+    handler::optional!(window.send_event(Event::WorkerBusy(next)));
+
+    result
 }
 
 fn handle_window_event(window: &Window, event: &WindowEvent) {
@@ -595,6 +2036,7 @@ fn handle_window_event(window: &Window, event: &WindowEvent) {
             log::debug!("window focused; requesting snapshot");
 
             let app_state = window.state::<AppState>();
+            app_state.record(window.label(), || Ok(RecordedEvent::Snapshot));
 
             let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
             let (call_tx, call_rx) = channel();
@@ -606,10 +2048,28 @@ fn handle_window_event(window: &Window, event: &WindowEvent) {
             let window = window.clone();
             thread::spawn(move || {
                 if let Some(status) = handler::nonfatal!(call_rx.recv()) {
-                    handler::nonfatal!(window.emit("gg://repo/status", status));
+                    handler::nonfatal!(window.send_event(Event::RepoStatus(status)));
                 }
             });
         }
+        WindowEvent::CloseRequested { .. } => {
+            log::debug!("window closing; saving workspace state");
+
+            let app_state = window.state::<AppState>();
+
+            let session_tx: Sender<SessionEvent> = app_state.get_session(window.label());
+            let (call_tx, call_rx) = channel();
+
+            handler::nonfatal!(session_tx.send(SessionEvent::SaveWorkspaceState {
+                tx: call_tx,
+                scope: ConfigSource::User,
+                selection: app_state.get_selection(window.label()),
+            }));
+
+            // the app may exit as soon as this returns, so give the worker a chance to write
+            // the config - but not forever, in case it's busy with something long-running
+            _ = call_rx.recv_timeout(Duration::from_secs(1));
+        }
         _ => (),
     }
 }
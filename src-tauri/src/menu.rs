@@ -2,15 +2,15 @@ use anyhow::{anyhow, Context, Result};
 #[cfg(target_os = "macos")]
 use tauri::menu::AboutMetadata;
 use tauri::{
-    menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu},
+    menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu},
     AppHandle, Emitter, Manager, Window, Wry,
 };
-use tauri_plugin_dialog::{DialogExt, FilePath};
+use tauri_plugin_dialog::{DialogExt, FilePath, MessageDialogKind};
 
 use crate::{
     handler,
-    messages::{Operand, RevHeader, StoreRef},
-    AppState,
+    messages::{Operand, PathInfo, RevHeader, StoreRef},
+    worker, AppState,
 };
 
 pub fn build_main(app_handle: &AppHandle) -> tauri::Result<Menu<Wry>> {
@@ -27,8 +27,9 @@ pub fn build_main(app_handle: &AppHandle) -> tauri::Result<Menu<Wry>> {
         ..Default::default()
     };
 
-    let repo_menu = Submenu::with_items(
+    let repo_menu = Submenu::with_id_and_items(
         app_handle,
+        "repo",
         "Repository",
         true,
         &[
@@ -39,7 +40,93 @@ pub fn build_main(app_handle: &AppHandle) -> tauri::Result<Menu<Wry>> {
                 true,
                 Some("cmdorctrl+o"),
             )?,
+            &MenuItem::with_id(app_handle, "menu_repo_init", "Init...", true, None::<&str>)?,
+            &MenuItem::with_id(
+                app_handle,
+                "menu_repo_sample",
+                "Create Sample...",
+                true,
+                None::<&str>,
+            )?,
             &MenuItem::with_id(app_handle, "menu_repo_reopen", "Reopen", true, Some("f5"))?,
+            &MenuItem::with_id(
+                app_handle,
+                "menu_repo_fetch_from_path",
+                "Fetch From Folder...",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "menu_repo_operation_log",
+                "Operation Log...",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "menu_repo_view_operation",
+                "View At Operation...",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "menu_repo_checkpoint",
+                "Checkpoint...",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "menu_repo_view_checkpoint",
+                "View Checkpoint...",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "menu_repo_compare_working_copy",
+                "Compare Working Copy...",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "menu_repo_cleanup_bookmarks",
+                "Clean Up Bookmarks...",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "menu_repo_colocate",
+                "Colocate With Git...",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "menu_repo_test_connection",
+                "Test Remote Connection...",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "menu_repo_run_command",
+                "Run jj Command...",
+                true,
+                None::<&str>,
+            )?,
+            &CheckMenuItem::with_id(
+                app_handle,
+                "menu_repo_safe_mode",
+                "Safe Mode",
+                true,
+                false,
+                None::<&str>,
+            )?,
             &PredefinedMenuItem::close_window(app_handle, Some("Close"))?,
         ],
     )?;
@@ -71,6 +158,13 @@ pub fn build_main(app_handle: &AppHandle) -> tauri::Result<Menu<Wry>> {
                 true,
                 None::<&str>,
             )?,
+            &MenuItem::with_id(
+                app_handle,
+                "menu_revision_backout_new",
+                "Backout into new revision",
+                true,
+                None::<&str>,
+            )?,
             &MenuItem::with_id(
                 app_handle,
                 "menu_revision_duplicate",
@@ -85,6 +179,14 @@ pub fn build_main(app_handle: &AppHandle) -> tauri::Result<Menu<Wry>> {
                 true,
                 None::<&str>,
             )?,
+            &MenuItem::with_id(app_handle, "menu_revision_hide", "Hide", true, None::<&str>)?,
+            &MenuItem::with_id(
+                app_handle,
+                "menu_revision_unhide",
+                "Unhide",
+                true,
+                None::<&str>,
+            )?,
             &PredefinedMenuItem::separator(app_handle)?,
             &MenuItem::with_id(
                 app_handle,
@@ -100,6 +202,20 @@ pub fn build_main(app_handle: &AppHandle) -> tauri::Result<Menu<Wry>> {
                 true,
                 None::<&str>,
             )?,
+            &MenuItem::with_id(
+                app_handle,
+                "menu_revision_rewrite",
+                "Search and replace...",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "menu_revision_fix",
+                "Run fix tools",
+                true,
+                None::<&str>,
+            )?,
             &PredefinedMenuItem::separator(app_handle)?,
             &MenuItem::with_id(
                 app_handle,
@@ -156,7 +272,7 @@ pub fn build_main(app_handle: &AppHandle) -> tauri::Result<Menu<Wry>> {
 
 pub fn build_context(
     app_handle: &AppHandle<Wry>,
-) -> Result<(Menu<Wry>, Menu<Wry>, Menu<Wry>), tauri::Error> {
+) -> Result<(Menu<Wry>, Menu<Wry>, Menu<Wry>, Menu<Wry>), tauri::Error> {
     let revision_menu = Menu::with_items(
         app_handle,
         &[
@@ -175,6 +291,13 @@ pub fn build_context(
                 true,
                 None::<&str>,
             )?,
+            &MenuItem::with_id(
+                app_handle,
+                "revision_backout_new",
+                "Backout into new revision",
+                true,
+                None::<&str>,
+            )?,
             &MenuItem::with_id(
                 app_handle,
                 "revision_duplicate",
@@ -182,6 +305,14 @@ pub fn build_context(
                 true,
                 None::<&str>,
             )?,
+            &MenuItem::with_id(app_handle, "revision_copy", "Copy", true, None::<&str>)?,
+            &MenuItem::with_id(
+                app_handle,
+                "revision_paste",
+                "Paste onto",
+                true,
+                None::<&str>,
+            )?,
             &MenuItem::with_id(
                 app_handle,
                 "revision_abandon",
@@ -189,6 +320,8 @@ pub fn build_context(
                 true,
                 None::<&str>,
             )?,
+            &MenuItem::with_id(app_handle, "revision_hide", "Hide", true, None::<&str>)?,
+            &MenuItem::with_id(app_handle, "revision_unhide", "Unhide", true, None::<&str>)?,
             &PredefinedMenuItem::separator(app_handle)?,
             &MenuItem::with_id(
                 app_handle,
@@ -204,6 +337,55 @@ pub fn build_context(
                 true,
                 None::<&str>,
             )?,
+            &MenuItem::with_id(
+                app_handle,
+                "revision_swap_parents",
+                "Swap parents",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "revision_rewrite",
+                "Search and replace...",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "revision_fix",
+                "Run fix tools",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "revision_set_author",
+                "Set author...",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "revision_new_file",
+                "New file...",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "revision_diff_tool",
+                "Open in diff tool",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "revision_follow_workspace",
+                "Follow workspace",
+                true,
+                None::<&str>,
+            )?,
             &PredefinedMenuItem::separator(app_handle)?,
             &MenuItem::with_id(
                 app_handle,
@@ -215,6 +397,72 @@ pub fn build_context(
         ],
     )?;
 
+    // labels are updated with the number of revisions when the menu is shown
+    let revisions_menu = Menu::with_items(
+        app_handle,
+        &[
+            &MenuItem::with_id(
+                app_handle,
+                "revisions_backout",
+                "Backout into working copy",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "revisions_duplicate",
+                "Duplicate",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(app_handle, "revisions_copy", "Copy", true, None::<&str>)?,
+            &MenuItem::with_id(app_handle, "revisions_merge", "Merge", true, None::<&str>)?,
+            &MenuItem::with_id(
+                app_handle,
+                "revisions_parallelize",
+                "Parallelize",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "revisions_abandon",
+                "Abandon",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "revisions_fix",
+                "Run fix tools",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "revisions_set_author",
+                "Set author...",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "revisions_describe",
+                "Edit descriptions...",
+                true,
+                None::<&str>,
+            )?,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &MenuItem::with_id(
+                app_handle,
+                "revisions_push",
+                "Push bookmarks",
+                true,
+                None::<&str>,
+            )?,
+        ],
+    )?;
+
     let tree_menu = Menu::with_items(
         app_handle,
         &[
@@ -232,6 +480,45 @@ pub fn build_context(
                 true,
                 None::<&str>,
             )?,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &MenuItem::with_id(
+                app_handle,
+                "tree_ignore_file",
+                "Ignore this file",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "tree_ignore_extension",
+                "Ignore this extension",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app_handle,
+                "tree_ignore_directory",
+                "Ignore this directory",
+                true,
+                None::<&str>,
+            )?,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &MenuItem::with_id(
+                app_handle,
+                "tree_rename",
+                "Rename file...",
+                true,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(app_handle, "tree_delete", "Delete file", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &MenuItem::with_id(
+                app_handle,
+                "tree_diff_tool",
+                "Open in diff tool",
+                true,
+                None::<&str>,
+            )?,
         ],
     )?;
 
@@ -260,10 +547,18 @@ pub fn build_context(
             &PredefinedMenuItem::separator(app_handle)?,
             &MenuItem::with_id(app_handle, "branch_rename", "Rename...", true, None::<&str>)?,
             &MenuItem::with_id(app_handle, "branch_delete", "Delete", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app_handle)?,
+            &MenuItem::with_id(
+                app_handle,
+                "branch_trunk",
+                "Set as trunk",
+                true,
+                None::<&str>,
+            )?,
         ],
     )?;
 
-    Ok((revision_menu, tree_menu, ref_menu))
+    Ok((revision_menu, revisions_menu, tree_menu, ref_menu))
 }
 
 // enables global menu items based on currently selected revision
@@ -277,10 +572,16 @@ pub fn handle_selection(menu: Menu<Wry>, selection: Option<RevHeader>) -> Result
         None => {
             revision_submenu.enable("menu_revision_new", false)?;
             revision_submenu.enable("menu_revision_edit", false)?;
+            revision_submenu.enable("menu_revision_backout", false)?;
+            revision_submenu.enable("menu_revision_backout_new", false)?;
             revision_submenu.enable("menu_revision_duplicate", false)?;
             revision_submenu.enable("menu_revision_abandon", false)?;
+            revision_submenu.enable("menu_revision_hide", false)?;
+            revision_submenu.enable("menu_revision_unhide", false)?;
             revision_submenu.enable("menu_revision_squash", false)?;
             revision_submenu.enable("menu_revision_restore", false)?;
+            revision_submenu.enable("menu_revision_rewrite", false)?;
+            revision_submenu.enable("menu_revision_fix", false)?;
         }
         Some(rev) => {
             revision_submenu.enable("menu_revision_new", true)?;
@@ -289,8 +590,11 @@ pub fn handle_selection(menu: Menu<Wry>, selection: Option<RevHeader>) -> Result
                 !rev.is_immutable && !rev.is_working_copy,
             )?;
             revision_submenu.enable("menu_revision_backout", true)?;
+            revision_submenu.enable("menu_revision_backout_new", true)?;
             revision_submenu.enable("menu_revision_duplicate", true)?;
             revision_submenu.enable("menu_revision_abandon", !rev.is_immutable)?;
+            revision_submenu.enable("menu_revision_hide", !rev.is_immutable && !rev.is_hidden)?;
+            revision_submenu.enable("menu_revision_unhide", rev.is_hidden)?;
             revision_submenu.enable(
                 "menu_revision_squash",
                 !rev.is_immutable && rev.parent_ids.len() == 1,
@@ -299,6 +603,8 @@ pub fn handle_selection(menu: Menu<Wry>, selection: Option<RevHeader>) -> Result
                 "menu_revision_restore",
                 !rev.is_immutable && rev.parent_ids.len() == 1,
             )?;
+            revision_submenu.enable("menu_revision_rewrite", !rev.is_immutable)?;
+            revision_submenu.enable("menu_revision_fix", !rev.is_immutable)?;
             revision_submenu.enable("menu_revision_branch", true)?;
         }
     };
@@ -306,12 +612,29 @@ pub fn handle_selection(menu: Menu<Wry>, selection: Option<RevHeader>) -> Result
     Ok(())
 }
 
+// keeps the toggle in sync with the worker, which may have been started with --safe-mode
+pub fn handle_safe_mode(menu: Menu<Wry>, enabled: bool) -> Result<()> {
+    let repo_submenu = menu
+        .get("repo")
+        .ok_or(anyhow!("Repository menu not found"))?;
+    if let Some(item) = repo_submenu
+        .as_submenu_unchecked()
+        .get("menu_repo_safe_mode")
+        .as_ref()
+        .and_then(|item| item.as_check_menuitem())
+    {
+        item.set_checked(enabled)?;
+    }
+    Ok(())
+}
+
 // enables context menu items for a revision and shows the menu
 pub fn handle_context(window: Window, ctx: Operand) -> Result<()> {
     log::debug!("handling context {ctx:?}");
 
     let state = window.state::<AppState>();
-    let guard = state.0.lock().expect("state mutex poisoned");
+    let has_bundles = state.has_bundles();
+    let guard = state.windows.lock().expect("state mutex poisoned");
 
     match ctx {
         Operand::Revision { header } => {
@@ -326,8 +649,13 @@ pub fn handle_context(window: Window, ctx: Operand) -> Result<()> {
                 !header.is_immutable && !header.is_working_copy,
             )?;
             context_menu.enable("revision_backout", true)?;
+            context_menu.enable("revision_backout_new", true)?;
             context_menu.enable("revision_duplicate", true)?;
+            context_menu.enable("revision_copy", true)?;
+            context_menu.enable("revision_paste", has_bundles)?;
             context_menu.enable("revision_abandon", !header.is_immutable)?;
+            context_menu.enable("revision_hide", !header.is_immutable && !header.is_hidden)?;
+            context_menu.enable("revision_unhide", header.is_hidden)?;
             context_menu.enable(
                 "revision_squash",
                 !header.is_immutable && header.parent_ids.len() == 1,
@@ -336,11 +664,24 @@ pub fn handle_context(window: Window, ctx: Operand) -> Result<()> {
                 "revision_restore",
                 !header.is_immutable && header.parent_ids.len() == 1,
             )?;
+            context_menu.enable(
+                "revision_swap_parents",
+                !header.is_immutable && header.parent_ids.len() == 2,
+            )?;
+            context_menu.enable("revision_rewrite", !header.is_immutable)?;
+            context_menu.enable("revision_fix", !header.is_immutable)?;
+            context_menu.enable("revision_set_author", !header.is_immutable)?;
+            context_menu.enable("revision_new_file", header.is_working_copy)?;
+            context_menu.enable("revision_diff_tool", true)?;
+            context_menu.enable(
+                "revision_follow_workspace",
+                !header.working_copy_of.is_empty(),
+            )?;
             context_menu.enable("revision_branch", true)?;
 
             window.popup_menu(context_menu)?;
         }
-        Operand::Change { header, .. } => {
+        Operand::Change { header, path } => {
             let context_menu = &guard
                 .get(window.label())
                 .expect("session not found")
@@ -355,6 +696,85 @@ pub fn handle_context(window: Window, ctx: Operand) -> Result<()> {
                 !header.is_immutable && header.parent_ids.len() == 1,
             )?;
 
+            // ignore patterns only affect files which are being snapshotted
+            let file_name = path.repo_path.rsplit('/').next().unwrap_or_default();
+            context_menu.enable("tree_ignore_file", header.is_working_copy)?;
+            context_menu.enable(
+                "tree_ignore_extension",
+                header.is_working_copy && file_name.rfind('.').is_some_and(|i| i > 0),
+            )?;
+            context_menu.enable(
+                "tree_ignore_directory",
+                header.is_working_copy && path.repo_path.contains('/'),
+            )?;
+
+            // file operations act on the working copy directory
+            context_menu.enable("tree_rename", header.is_working_copy)?;
+            context_menu.enable("tree_delete", header.is_working_copy)?;
+            context_menu.enable("tree_diff_tool", true)?;
+
+            window.popup_menu(context_menu)?;
+        }
+        Operand::Revisions { headers } => {
+            let context_menu = &guard
+                .get(window.label())
+                .expect("session not found")
+                .revisions_menu;
+
+            let count = headers.len();
+            let any_immutable = headers.iter().any(|header| header.is_immutable);
+            let bookmarks = headers
+                .iter()
+                .flat_map(|header| header.refs.iter())
+                .filter(|r#ref| matches!(r#ref, StoreRef::LocalBookmark { .. }))
+                .count();
+
+            context_menu.label(
+                "revisions_backout",
+                &format!("Backout {count} revisions into working copy"),
+            )?;
+            context_menu.label(
+                "revisions_duplicate",
+                &format!("Duplicate {count} revisions"),
+            )?;
+            context_menu.label("revisions_copy", &format!("Copy {count} revisions"))?;
+            context_menu.label("revisions_merge", &format!("Merge {count} revisions"))?;
+            context_menu.label(
+                "revisions_parallelize",
+                &format!("Parallelize {count} revisions"),
+            )?;
+            context_menu.label("revisions_abandon", &format!("Abandon {count} revisions"))?;
+            context_menu.label(
+                "revisions_fix",
+                &format!("Run fix tools on {count} revisions"),
+            )?;
+            context_menu.label(
+                "revisions_set_author",
+                &format!("Set author of {count} revisions..."),
+            )?;
+            context_menu.label(
+                "revisions_describe",
+                &format!("Edit descriptions of {count} revisions..."),
+            )?;
+            context_menu.label(
+                "revisions_push",
+                &match bookmarks {
+                    1 => "Push 1 bookmark".to_owned(),
+                    n => format!("Push {n} bookmarks"),
+                },
+            )?;
+
+            context_menu.enable("revisions_backout", true)?;
+            context_menu.enable("revisions_duplicate", true)?;
+            context_menu.enable("revisions_copy", true)?;
+            context_menu.enable("revisions_merge", count >= 2)?;
+            context_menu.enable("revisions_parallelize", count >= 2 && !any_immutable)?;
+            context_menu.enable("revisions_abandon", !any_immutable)?;
+            context_menu.enable("revisions_fix", !any_immutable)?;
+            context_menu.enable("revisions_set_author", !any_immutable)?;
+            context_menu.enable("revisions_describe", !any_immutable)?;
+            context_menu.enable("revisions_push", bookmarks > 0)?;
+
             window.popup_menu(context_menu)?;
         }
         Operand::Ref { r#ref, .. } => {
@@ -432,6 +852,9 @@ pub fn handle_context(window: Window, ctx: Operand) -> Result<()> {
                 ),
             )?;
 
+            // tags aren't expected to move, so only bookmarks make useful trunks
+            context_menu.enable("branch_trunk", !matches!(r#ref, StoreRef::Tag { .. }))?;
+
             window.popup_menu(context_menu)?;
         }
         _ => (), // no popup required
@@ -445,25 +868,61 @@ pub fn handle_event(window: &Window, event: MenuEvent) -> Result<()> {
 
     match event.id.0.as_str() {
         "menu_repo_open" => repo_open(window),
+        "menu_repo_init" => repo_init(window),
+        "menu_repo_sample" => repo_sample(window),
         "menu_repo_reopen" => repo_reopen(window),
+        "menu_repo_fetch_from_path" => repo_fetch_from_path(window),
+        "menu_repo_operation_log" => window.emit("gg://menu/operation-log", ())?,
+        "menu_repo_view_operation" => window.emit("gg://menu/view-operation", ())?,
+        "menu_repo_checkpoint" => window.emit("gg://menu/checkpoint", ())?,
+        "menu_repo_view_checkpoint" => window.emit("gg://menu/view-checkpoint", ())?,
+        "menu_repo_compare_working_copy" => window.emit("gg://menu/compare-working-copy", ())?,
+        "menu_repo_cleanup_bookmarks" => window.emit("gg://menu/cleanup-bookmarks", ())?,
+        "menu_repo_colocate" => window.emit("gg://menu/colocate", ())?,
+        "menu_repo_test_connection" => window.emit("gg://menu/test-connection", ())?,
+        "menu_repo_run_command" => window.emit("gg://menu/run-command", ())?,
+        "menu_repo_safe_mode" => repo_safe_mode(window),
         "menu_revision_new" => window.emit("gg://menu/revision", "new")?,
         "menu_revision_edit" => window.emit("gg://menu/revision", "edit")?,
         "menu_revision_backout" => window.emit("gg://menu/revision", "backout")?,
+        "menu_revision_backout_new" => window.emit("gg://menu/revision", "backout_new")?,
         "menu_revision_duplicate" => window.emit("gg://menu/revision", "duplicate")?,
         "menu_revision_abandon" => window.emit("gg://menu/revision", "abandon")?,
+        "menu_revision_hide" => window.emit("gg://menu/revision", "hide")?,
+        "menu_revision_unhide" => window.emit("gg://menu/revision", "unhide")?,
         "menu_revision_squash" => window.emit("gg://menu/revision", "squash")?,
         "menu_revision_restore" => window.emit("gg://menu/revision", "restore")?,
+        "menu_revision_rewrite" => window.emit("gg://menu/revision", "rewrite")?,
+        "menu_revision_fix" => window.emit("gg://menu/revision", "fix")?,
         "menu_revision_branch" => window.emit("gg://menu/revision", "branch")?,
         "revision_new" => window.emit("gg://context/revision", "new")?,
         "revision_edit" => window.emit("gg://context/revision", "edit")?,
         "revision_backout" => window.emit("gg://context/revision", "backout")?,
+        "revision_backout_new" => window.emit("gg://context/revision", "backout_new")?,
         "revision_duplicate" => window.emit("gg://context/revision", "duplicate")?,
+        "revision_copy" => window.emit("gg://context/revision", "copy")?,
+        "revision_paste" => window.emit("gg://context/revision", "paste")?,
         "revision_abandon" => window.emit("gg://context/revision", "abandon")?,
+        "revision_hide" => window.emit("gg://context/revision", "hide")?,
+        "revision_unhide" => window.emit("gg://context/revision", "unhide")?,
         "revision_squash" => window.emit("gg://context/revision", "squash")?,
         "revision_restore" => window.emit("gg://context/revision", "restore")?,
+        "revision_swap_parents" => window.emit("gg://context/revision", "swap_parents")?,
+        "revision_rewrite" => window.emit("gg://context/revision", "rewrite")?,
+        "revision_fix" => window.emit("gg://context/revision", "fix")?,
+        "revision_set_author" => window.emit("gg://context/revision", "set_author")?,
+        "revision_new_file" => window.emit("gg://context/revision", "new_file")?,
+        "revision_diff_tool" => window.emit("gg://context/revision", "diff_tool")?,
+        "revision_follow_workspace" => window.emit("gg://context/revision", "follow_workspace")?,
         "revision_branch" => window.emit("gg://context/revision", "branch")?,
         "tree_squash" => window.emit("gg://context/tree", "squash")?,
         "tree_restore" => window.emit("gg://context/tree", "restore")?,
+        "tree_ignore_file" => window.emit("gg://context/tree", "ignore_file")?,
+        "tree_ignore_extension" => window.emit("gg://context/tree", "ignore_extension")?,
+        "tree_ignore_directory" => window.emit("gg://context/tree", "ignore_directory")?,
+        "tree_rename" => window.emit("gg://context/tree", "rename")?,
+        "tree_delete" => window.emit("gg://context/tree", "delete")?,
+        "tree_diff_tool" => window.emit("gg://context/tree", "diff_tool")?,
         "branch_track" => window.emit("gg://context/branch", "track")?,
         "branch_untrack" => window.emit("gg://context/branch", "untrack")?,
         "branch_push_all" => window.emit("gg://context/branch", "push-all")?,
@@ -472,6 +931,17 @@ pub fn handle_event(window: &Window, event: MenuEvent) -> Result<()> {
         "branch_fetch_single" => window.emit("gg://context/branch", "fetch-single")?,
         "branch_rename" => window.emit("gg://context/branch", "rename")?,
         "branch_delete" => window.emit("gg://context/branch", "delete")?,
+        "branch_trunk" => window.emit("gg://context/branch", "trunk")?,
+        "revisions_backout" => window.emit("gg://context/revisions", "backout")?,
+        "revisions_duplicate" => window.emit("gg://context/revisions", "duplicate")?,
+        "revisions_copy" => window.emit("gg://context/revisions", "copy")?,
+        "revisions_merge" => window.emit("gg://context/revisions", "merge")?,
+        "revisions_parallelize" => window.emit("gg://context/revisions", "parallelize")?,
+        "revisions_abandon" => window.emit("gg://context/revisions", "abandon")?,
+        "revisions_fix" => window.emit("gg://context/revisions", "fix")?,
+        "revisions_set_author" => window.emit("gg://context/revisions", "set_author")?,
+        "revisions_describe" => window.emit("gg://context/revisions", "describe")?,
+        "revisions_push" => window.emit("gg://context/revisions", "push")?,
         _ => (),
     };
 
@@ -482,9 +952,60 @@ pub fn repo_open(window: &Window) {
     let window = window.clone();
     window.dialog().file().pick_folder(move |picked| {
         if let Some(FilePath::Path(cwd)) = picked {
-            handler::fatal!(
-                crate::try_open_repository(&window, Some(cwd)).context("try_open_repository")
-            );
+            // explain folders which can't be opened, rather than replacing the current workspace with an error
+            let problem = match worker::query_path_info(&cwd) {
+                PathInfo::Workspace { .. } => None,
+                PathInfo::GitRepository { root } => Some(format!(
+                    "{} is a Git repository, but not a Jujutsu workspace. Use Repository > Init... to add one.",
+                    root.0
+                )),
+                PathInfo::Unversioned { path } => {
+                    Some(format!("{} is not inside a Jujutsu workspace. Use Repository > Init... to create one.", path.0))
+                }
+                PathInfo::Missing { path } => Some(format!("{} could not be found.", path.0)),
+            };
+
+            match problem {
+                Some(problem) => window
+                    .dialog()
+                    .message(problem)
+                    .title("Open Workspace")
+                    .kind(MessageDialogKind::Warning)
+                    .show(|_| ()),
+                None => handler::fatal!(
+                    crate::try_open_repository(&window, Some(cwd)).context("try_open_repository")
+                ),
+            }
+        }
+    });
+}
+
+// the frontend asks for the new repo's starting content, then initialises and opens it
+fn repo_init(window: &Window) {
+    let window = window.clone();
+    window.dialog().file().pick_folder(move |picked| {
+        if let Some(FilePath::Path(path)) = picked {
+            handler::nonfatal!(window.emit("gg://menu/init", path));
+        }
+    });
+}
+
+// the frontend explains what will be created, then generates and opens it
+fn repo_sample(window: &Window) {
+    let window = window.clone();
+    window.dialog().file().pick_folder(move |picked| {
+        if let Some(FilePath::Path(path)) = picked {
+            handler::nonfatal!(window.emit("gg://menu/sample", path));
+        }
+    });
+}
+
+// the frontend asks for a remote name and executes the mutation
+fn repo_fetch_from_path(window: &Window) {
+    let window = window.clone();
+    window.dialog().file().pick_folder(move |picked| {
+        if let Some(FilePath::Path(path)) = picked {
+            handler::nonfatal!(window.emit("gg://menu/fetch-from-path", path));
         }
     });
 }
@@ -493,10 +1014,35 @@ fn repo_reopen(window: &Window) {
     handler::fatal!(crate::try_open_repository(window, None).context("try_open_repository"));
 }
 
+// the item has already toggled itself
+fn repo_safe_mode(window: &Window) {
+    let enabled = window
+        .menu()
+        .and_then(|menu| menu.get("repo"))
+        .and_then(|repo| repo.as_submenu()?.get("menu_repo_safe_mode"))
+        .and_then(|item| item.as_check_menuitem()?.is_checked().ok())
+        .unwrap_or(false);
+    handler::fatal!(crate::try_set_safe_mode(window, enabled).context("try_set_safe_mode"));
+}
+
 trait Enabler {
     fn enable(&self, id: &str, value: bool) -> tauri::Result<()>;
 }
 
+trait Labeller {
+    fn label(&self, id: &str, text: &str) -> tauri::Result<()>;
+}
+
+impl Labeller for Menu<Wry> {
+    fn label(&self, id: &str, text: &str) -> tauri::Result<()> {
+        if let Some(item) = self.get(id).as_ref().and_then(|item| item.as_menuitem()) {
+            item.set_text(text)
+        } else {
+            Ok(())
+        }
+    }
+}
+
 impl Enabler for Menu<Wry> {
     fn enable(&self, id: &str, value: bool) -> tauri::Result<()> {
         if let Some(item) = self.get(id).as_ref().and_then(|item| item.as_menuitem()) {
@@ -9,10 +9,17 @@ pub use queries::*;
 use std::{collections::HashMap, path::Path};
 
 use anyhow::{anyhow, Result};
+use jj_cli::config::ConfigSource;
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "ts-rs")]
 use ts_rs::TS;
 
+use crate::paths;
+
+/// Incremented whenever a message type changes shape, so that a frontend built against
+/// older bindings can tell that it needs to be reloaded
+pub const SCHEMA_VERSION: u32 = 1;
+
 /// Utility type used to abstract crlf/<br>/etc
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[cfg_attr(
@@ -46,11 +53,7 @@ pub struct DisplayPath(pub String);
 
 impl<T: AsRef<Path>> From<T> for DisplayPath {
     fn from(value: T) -> Self {
-        DisplayPath(
-            dunce::simplified(value.as_ref())
-                .to_string_lossy()
-                .to_string(),
-        )
+        DisplayPath(paths::display(value.as_ref()))
     }
 }
 
@@ -82,21 +85,201 @@ pub enum RepoConfig {
         git_remotes: Vec<String>,
         default_query: String,
         latest_query: String,
+        query_choices: Vec<QueryChoice>,
         status: RepoStatus,
         theme_override: Option<String>,
         mark_unpushed_branches: bool,
+        latest_selection: Option<RevHeader>,
+        /// Head of trunk(), or the main/master bookmark if no remote trunk exists
+        trunk: Option<RevHeader>,
+        config_diagnostics: Vec<ConfigDiagnostic>,
+        /// user.name or user.email is unset, so new commits would have no author
+        missing_identity: bool,
+        check_updates: bool,
+        /// ui.merge-editor names a program which ResolveWithMergeTool can run
+        has_external_merge_tool: bool,
+        /// started with --safe-mode, so external tools and filesystem monitors are ignored
+        safe_mode: bool,
+        /// the workspace whose working copy is @; see FollowWorkspace
+        workspace_name: String,
+        workspaces: Vec<WorkspaceHead>,
+        /// a mutation which was running when gg last exited, and so may not have completed
+        interrupted_mutation: Option<InterruptedMutation>,
+        backend: RepoBackend,
+        schema_version: u32,
     },
     #[allow(dead_code)] // used by frontend
     TimeoutError,
+    #[allow(dead_code)] // used by frontend
+    SchemaMismatch {
+        frontend_version: u32,
+        backend_version: u32,
+    },
     LoadError {
         absolute_path: DisplayPath,
         message: String,
     },
+    WorkspaceMissing {
+        absolute_path: DisplayPath,
+    },
     WorkerError {
         message: String,
     },
 }
 
+/// What a user-chosen directory contains, so that dialogs can offer the right action for it
+#[derive(Serialize, Debug)]
+#[serde(tag = "type")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum PathInfo {
+    /// Inside a jj workspace, which can be opened
+    Workspace { root: DisplayPath },
+    /// Inside a git repo with no jj workspace
+    GitRepository { root: DisplayPath },
+    /// An existing directory which isn't under version control
+    Unversioned { path: DisplayPath },
+    /// Doesn't exist, or isn't a directory
+    Missing { path: DisplayPath },
+}
+
+/// Options for creating a new colocated repo, so that it starts out ready to use
+#[derive(Deserialize, Debug, Default)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct InitWorkspace {
+    /// Names of built-in ignore templates, combined into a .gitignore
+    #[serde(default)]
+    pub gitignore_templates: Vec<String>,
+    /// Description for the initial working copy
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Bookmark to create at the initial working copy
+    #[serde(default)]
+    pub bookmark: Option<String>,
+}
+
+/// An entry in the log pane's query selector
+#[derive(Serialize, Clone, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct QueryChoice {
+    pub label: String,
+    pub value: String,
+}
+
+/// The config layer a setting was read from or should be written to
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum ConfigScope {
+    Default,
+    Env,
+    User,
+    Repo,
+    CommandArg,
+}
+
+impl From<ConfigSource> for ConfigScope {
+    fn from(value: ConfigSource) -> Self {
+        match value {
+            ConfigSource::Default => ConfigScope::Default,
+            ConfigSource::Env => ConfigScope::Env,
+            ConfigSource::User => ConfigScope::User,
+            ConfigSource::Repo => ConfigScope::Repo,
+            ConfigSource::CommandArg => ConfigScope::CommandArg,
+        }
+    }
+}
+
+impl From<ConfigScope> for ConfigSource {
+    fn from(value: ConfigScope) -> Self {
+        match value {
+            ConfigScope::Default => ConfigSource::Default,
+            ConfigScope::Env => ConfigSource::Env,
+            ConfigScope::User => ConfigSource::User,
+            ConfigScope::Repo => ConfigSource::Repo,
+            ConfigScope::CommandArg => ConfigSource::CommandArg,
+        }
+    }
+}
+
+/// A gg.* setting which is malformed or unknown, and so won't have the effect the user intended
+#[derive(Serialize, Clone, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ConfigDiagnostic {
+    pub scope: ConfigScope,
+    pub key: String,
+    pub message: String,
+}
+
+/// The repo's commit storage; remotes, fetch, push and colocation all need git
+#[derive(Serialize, Clone, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RepoBackend {
+    /// as reported by jj, such as "git" or "local"
+    pub name: String,
+    pub supports_git: bool,
+}
+
+/// Another workspace of the same repo, or this one
+#[derive(Serialize, Clone, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct WorkspaceHead {
+    pub name: String,
+    pub working_copy: CommitId,
+}
+
+/// A mutation journal left behind by a gg process which exited before the mutation returned
+#[derive(Serialize, Clone, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct InterruptedMutation {
+    pub mutation: String,
+    /// the operation which the mutation started from
+    pub op_id: String,
+}
+
+/// A `revset-aliases` entry, as defined by the highest-priority layer
+#[derive(Serialize, Clone, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RevsetAlias {
+    pub name: String,
+    pub definition: String,
+    pub scope: ConfigScope,
+}
+
 #[derive(Serialize, Clone, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
@@ -106,6 +289,35 @@ pub enum RepoConfig {
 pub struct RepoStatus {
     pub operation_description: String,
     pub working_copy: CommitId,
+    /// Set when viewing an earlier operation, during which the repo is read-only
+    pub historical_operation: Option<String>,
+    /// Set for colocated repos, whose HEAD can also be moved by git commands
+    pub git_head: Option<GitHead>,
+}
+
+/// Where a colocated repo's git HEAD points
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum GitHead {
+    Branch {
+        name: String,
+        commit: CommitId,
+        /// HEAD is where jj last imported or exported it
+        is_synced: bool,
+    },
+    Detached {
+        commit: CommitId,
+        is_synced: bool,
+    },
+    /// HEAD names a branch with no commits, as in a fresh `git init`
+    Unborn {
+        name: String,
+    },
 }
 
 /// Bookmark or tag name with metadata.
@@ -166,6 +378,9 @@ pub enum Operand {
     Revision {
         header: RevHeader,
     },
+    Revisions {
+        headers: Vec<RevHeader>,
+    },
     Merge {
         header: RevHeader,
     },
@@ -183,6 +398,31 @@ pub enum Operand {
     },
 }
 
+/// Output from a long-running mutation, sent as it happens
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum ProgressEvent {
+    Stdout { line: String },
+    Stderr { line: String },
+}
+
+/// A release newer than the running version, found by the optional update check
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct UpdateAvailable {
+    pub version: String,
+    pub url: String,
+}
+
 #[derive(Serialize, Debug, Clone)]
 #[cfg_attr(
     feature = "ts-rs",
@@ -225,3 +465,40 @@ impl From<&str> for InputField {
         }
     }
 }
+
+/// Notifications pushed from the backend to the frontend. Each is emitted on the channel named by
+/// its tag, so the frontend can check that it listens for the right payload.
+#[derive(Serialize, Clone)]
+#[serde(tag = "name", content = "payload")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum Event {
+    #[serde(rename = "gg://repo/config")]
+    RepoConfig(RepoConfig),
+    /// Only sent when a snapshot changed the repo
+    #[serde(rename = "gg://repo/status")]
+    RepoStatus(RepoStatus),
+    /// The name of the running mutation, if there is one
+    #[serde(rename = "gg://worker/busy")]
+    WorkerBusy(Option<String>),
+    #[serde(rename = "gg://progress")]
+    Progress(ProgressEvent),
+    #[serde(rename = "gg://input")]
+    Input(InputRequest),
+    #[serde(rename = "gg://update")]
+    Update(UpdateAvailable),
+}
+
+// ts-rs exports types, not values, so the constant is written out by the same `npm run gen`
+#[cfg(all(test, feature = "ts-rs"))]
+#[test]
+fn export_schema_version() -> Result<()> {
+    std::fs::write(
+        "../src/messages/SchemaVersion.ts",
+        format!("export const SCHEMA_VERSION = {SCHEMA_VERSION};\n"),
+    )?;
+    Ok(())
+}
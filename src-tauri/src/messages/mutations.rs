@@ -17,6 +17,21 @@ pub enum MutationResult {
         new_status: RepoStatus,
         new_selection: RevHeader,
     },
+    /// Commit ids changed wholesale, so each previously selected change is mapped to its new commit
+    Restored {
+        new_status: RepoStatus,
+        new_selection: RevHeader,
+        restored: Vec<RestoredChange>,
+    },
+    /// The mutation is destructive and gg.ui.confirm-destructive is set; resubmit with the token to proceed
+    ConfirmationRequired {
+        token: String,
+        summary: String,
+    },
+    /// A protected bookmark would be deleted, rewound or force-pushed; resubmit with allow_protected to proceed
+    OverrideRequired {
+        summary: String,
+    },
     PreconditionError {
         message: String,
     },
@@ -26,7 +41,7 @@ pub enum MutationResult {
 }
 
 /// Makes a revision the working copy
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -34,10 +49,31 @@ pub enum MutationResult {
 )]
 pub struct CheckoutRevision {
     pub id: RevId,
+    /// Overrides gg.operations.checkout-policy
+    #[serde(default)]
+    pub policy: Option<CheckoutPolicy>,
+}
+
+/// What to do with a working copy that has changes but no description when another revision is checked out
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum CheckoutPolicy {
+    /// Leave it as an ordinary revision, which can be abandoned later
+    Keep,
+    /// Point a wip/ bookmark at it so that it's easy to find again
+    Bookmark,
+    /// Leave it alone and check out a new child of the revision, rather than editing the revision
+    NewChild,
+    /// Ask which of the other policies to use
+    Prompt,
 }
 
 /// Creates a new revision and makes it the working copy
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -47,7 +83,7 @@ pub struct CreateRevision {
     pub parent_ids: Vec<RevId>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -59,7 +95,7 @@ pub struct InsertRevision {
     pub before_id: RevId,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -70,7 +106,7 @@ pub struct MoveRevision {
     pub parent_ids: Vec<RevId>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -82,7 +118,7 @@ pub struct MoveSource {
 }
 
 /// Updates a revision's description
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -94,8 +130,52 @@ pub struct DescribeRevision {
     pub reset_author: bool,
 }
 
+/// Changes the author of some revisions; fields which are None keep each revision's current value
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SetAuthor {
+    pub ids: Vec<RevId>,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub timestamp: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+/// Edits the descriptions of several revisions at once; revisions the edit doesn't change are left alone
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct DescribeRevisions {
+    pub ids: Vec<RevId>,
+    pub edit: DescriptionEdit,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum DescriptionEdit {
+    /// Replaces the whole description
+    Set { text: String },
+    /// Inserts text at the start of the subject line
+    Prepend { text: String },
+    /// Adds a line to the trailer block, unless the description already contains it
+    AppendTrailer { trailer: String },
+    /// Replaces every occurrence of some text
+    Replace { find: String, replace: String },
+}
+
 /// Creates a copy of the selected revisions with the same parents and content
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -105,7 +185,144 @@ pub struct DuplicateRevisions {
     pub ids: Vec<RevId>,
 }
 
-#[derive(Deserialize, Debug)]
+/// Makes the selected revisions siblings, as `jj parallelize` does. They keep any parents from
+/// outside the selection, and their descendants outside it become merges of them.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ParallelizeRevisions {
+    pub ids: Vec<RevId>,
+}
+
+/// Creates a merge of the selected revisions, none of which may be an ancestor of another
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct MergeRevisions {
+    /// Parents of the merge, in order
+    pub ids: Vec<RevId>,
+    /// Make the merge the working copy
+    pub checkout: bool,
+}
+
+/// Creates a new revision whose parents are exactly the given revisions, without editing it
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct CreateMergeOf {
+    /// Parents of the merge, in order
+    pub ids: Vec<RevId>,
+}
+
+/// Adds a parent to a revision, turning it into a merge or widening an existing one
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct AddParentToMerge {
+    pub id: RevId,
+    pub parent_id: RevId,
+}
+
+/// Removes one parent from a merge, which keeps its other parents
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RemoveParentFromMerge {
+    pub id: RevId,
+    pub parent_id: CommitId,
+}
+
+/// Reverses the parents of a two-parent merge, changing which one is first
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SwapParents {
+    pub id: RevId,
+}
+
+/// Creates a copy of the selected revisions on top of new parents, like a cherry-pick
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct GraftRevisions {
+    pub ids: Vec<RevId>,
+    pub parent_ids: Vec<RevId>,
+}
+
+/// Recreates revisions copied in any window on top of new parents
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct PasteRevisions {
+    pub token: String,
+    pub parent_ids: Vec<RevId>,
+}
+
+/// Rewrites a set of revisions into a linear sequence, like `git rebase -i`
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RunRebasePlan {
+    /// Oldest first; the result is stacked onto the parents of the set's root
+    pub steps: Vec<RebaseStep>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RebaseStep {
+    pub id: RevId,
+    pub action: RebaseAction,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum RebaseAction {
+    Keep,
+    /// Fold into the revision produced by the previous step
+    Squash,
+    Drop,
+    Reword {
+        new_description: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -115,8 +332,79 @@ pub struct AbandonRevisions {
     pub ids: Vec<CommitId>,
 }
 
-/// Adds changes to the working copy which reverse the effect of the selected revisions
-#[derive(Deserialize, Debug)]
+/// Runs the configured `fix.tools` over the files changed in some revisions and their descendants
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct FixRevisions {
+    pub ids: Vec<CommitId>,
+}
+
+/// Splits the staged paths and hunks out of the working copy into a new commit below it
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct CommitStaged {
+    pub description: String,
+}
+
+/// Splits the selected paths and hunks out of a revision, as `jj split` does. The selected changes
+/// stay in the revision and the remainder goes into a new child, which its descendants move onto.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SplitRevision {
+    pub id: RevId,
+    pub paths: Vec<SplitPath>,
+    /// For the selected changes; the remainder keeps the original description
+    pub description: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SplitPath {
+    pub path: TreePath,
+    /// If not set, every change to the path is selected
+    pub hunks: Option<Vec<HunkLocation>>,
+}
+
+/// Removes childless revisions from view without rewriting anything, so they can be unhidden later
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct HideRevisions {
+    pub ids: Vec<CommitId>,
+}
+
+/// Makes hidden revisions (and their ancestors) visible again
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct UnhideRevisions {
+    pub ids: Vec<CommitId>,
+}
+
+/// Reverses the effect of the selected revisions, either in the working copy or in new revisions
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -124,9 +412,12 @@ pub struct AbandonRevisions {
 )]
 pub struct BackoutRevisions {
     pub ids: Vec<RevId>,
+    /// If set, create a revert commit for each revision on top of this one instead of modifying the working copy
+    #[serde(default)]
+    pub new_parent_id: Option<CommitId>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -138,7 +429,7 @@ pub struct MoveChanges {
     pub paths: Vec<TreePath>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -150,7 +441,182 @@ pub struct CopyChanges {
     pub paths: Vec<TreePath>,
 }
 
-#[derive(Deserialize, Debug)]
+/// Appends a pattern to the workspace's .gitignore and untracks the working copy files it matches
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct AddIgnorePattern {
+    pub pattern: String,
+}
+
+/// Deletes files from the working copy directory and snapshots the result
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct DeletePaths {
+    pub paths: Vec<TreePath>,
+}
+
+/// Writes a new file into the working copy directory and snapshots it
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct CreateFile {
+    /// Relative to the workspace root
+    pub path: String,
+    pub content: String,
+}
+
+/// Moves a file within the working copy directory and snapshots the result, which jj will show as
+/// a rename if the contents are unchanged
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RenamePath {
+    pub path: TreePath,
+    /// Relative to the workspace root
+    pub new_path: String,
+}
+
+/// Saves user.name and user.email to the user config, for first-run setup
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SetUserIdentity {
+    pub name: String,
+    pub email: String,
+}
+
+/// Adds or replaces a revset alias in the user or repo config
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SetRevsetAlias {
+    pub scope: ConfigScope,
+    pub name: String,
+    pub definition: String,
+}
+
+/// Removes a revset alias from the user or repo config
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RemoveRevsetAlias {
+    pub scope: ConfigScope,
+    pub name: String,
+}
+
+/// Points trunk() at a bookmark in the repo config, or restores the default definition
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SetTrunk {
+    pub r#ref: Option<StoreRef>,
+}
+
+/// Makes @ refer to another workspace's working copy in this window, or to the open workspace's if None
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct FollowWorkspace {
+    pub name: Option<String>,
+}
+
+/// Sets the query shown when this repo is opened, or restores jj's `revsets.log`
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SetDefaultQuery {
+    pub query: Option<String>,
+}
+
+/// Highlights a change in the log, or removes its highlight; labels are stored locally, not in the repo
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct SetLabel {
+    pub id: RevId,
+    pub label: Option<RevLabel>,
+}
+
+/// Resolves a conflicted file by picking one side of each of its conflict regions, or by replacing it
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ResolveConflict {
+    pub id: RevId,
+    pub path: TreePath,
+    /// for each region in order, an index into its sides
+    pub choices: Vec<usize>,
+    /// the merged file, written as-is instead of applying choices
+    pub content: Option<MultilineString>,
+    /// whether to end content with a newline, as reported by query_conflict
+    pub has_trailing_newline: bool,
+}
+
+/// Runs ui.merge-editor on a conflicted file and writes its output back into the revision
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ResolveWithMergeTool {
+    pub id: RevId,
+    pub path: TreePath,
+}
+
+/// Replaces regex matches line-by-line in the selected files (or all files, if none are selected)
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RewritePaths {
+    pub id: RevId,
+    pub paths: Vec<TreePath>,
+    pub pattern: String,
+    pub replacement: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -160,7 +626,7 @@ pub struct TrackBranch {
     pub r#ref: StoreRef,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -170,7 +636,7 @@ pub struct UntrackBranch {
     pub r#ref: StoreRef,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -181,7 +647,7 @@ pub struct RenameBranch {
     pub new_name: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -192,7 +658,7 @@ pub struct CreateRef {
     pub r#ref: StoreRef,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -200,9 +666,24 @@ pub struct CreateRef {
 )]
 pub struct DeleteRef {
     pub r#ref: StoreRef,
+    #[serde(default)]
+    pub allow_protected: bool,
 }
 
-#[derive(Deserialize, Debug)]
+/// Deletes merged local bookmarks, either forgetting their remote counterparts or pushing their
+/// deletion to those remotes too
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct CleanupBookmarks {
+    pub names: Vec<String>,
+    pub delete_remotes: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -211,9 +692,11 @@ pub struct DeleteRef {
 pub struct MoveRef {
     pub r#ref: StoreRef,
     pub to_id: RevId,
+    #[serde(default)]
+    pub allow_protected: bool,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "type")]
 #[cfg_attr(
     feature = "ts-rs",
@@ -223,17 +706,23 @@ pub struct MoveRef {
 pub enum GitPush {
     AllBookmarks {
         remote_name: String,
+        #[serde(default)]
+        allow_protected: bool,
     },
     AllRemotes {
         branch_ref: StoreRef,
+        #[serde(default)]
+        allow_protected: bool,
     },
     RemoteBookmark {
         remote_name: String,
         branch_ref: StoreRef,
+        #[serde(default)]
+        allow_protected: bool,
     },
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "type")]
 #[cfg_attr(
     feature = "ts-rs",
@@ -253,10 +742,73 @@ pub enum GitFetch {
     },
 }
 
-#[derive(Deserialize, Debug)]
+/// Moves an internal git store to the workspace root, so that git tools can use the repo
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ColocateRepository;
+
+/// Snapshots the working copy in an operation tagged with a label, so that it can be found later
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct CheckpointWorkingCopy {
+    pub label: String,
+}
+
+/// Runs the jj CLI against the workspace, for features GG doesn't have; the subcommand
+/// must be listed in gg.commands.allowed-subcommands
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
     ts(export, export_to = "../src/messages/")
 )]
-pub struct UndoOperation;
+pub struct RunJjCommand {
+    /// Subcommand and its arguments, without the leading `jj`
+    pub args: Vec<String>,
+}
+
+/// Fetches the branches of another git repository on this machine, without configuring it as a remote;
+/// they are imported as remote bookmarks with the given remote name
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct FetchFromPath {
+    pub path: String,
+    pub remote_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct UndoOperation {
+    /// Changes selected in the frontend, primary selection first, to be found again afterwards
+    #[serde(default)]
+    pub selected: Vec<ChangeId>,
+}
+
+/// Where a change selected before an undo ended up
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum RestoredChange {
+    Visible { header: RevHeader },
+    Hidden { change_id: ChangeId },
+}
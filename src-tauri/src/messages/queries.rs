@@ -86,9 +86,30 @@ pub struct RevHeader {
     pub author: RevAuthor,
     pub has_conflict: bool,
     pub is_working_copy: bool,
+    /// Names of every workspace whose working copy this is, including the open one
+    pub working_copy_of: Vec<String>,
     pub is_immutable: bool,
+    /// Whether there is a signature at all; it's only verified for RevResult::Detail
+    pub is_signed: bool,
+    pub is_hidden: bool,
     pub refs: Vec<StoreRef>,
     pub parent_ids: Vec<CommitId>,
+    pub label: Option<RevLabel>,
+    pub age: RevAge,
+}
+
+/// How long ago a revision was committed, bucketed by gg.queries.age-buckets
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum RevAge {
+    Today,
+    Week,
+    Month,
+    Older,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -115,6 +136,44 @@ impl TryFrom<&Signature> for RevAuthor {
     }
 }
 
+/// A revision's signature, as checked by the signing backend which made it
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum RevSignature {
+    Unsigned,
+    Good {
+        key: Option<String>,
+        signer: Option<String>,
+    },
+    Bad {
+        key: Option<String>,
+        signer: Option<String>,
+    },
+    /// The key isn't trusted, or no backend could check it
+    UnknownKey {
+        key: Option<String>,
+        signer: Option<String>,
+    },
+}
+
+/// Summary of a revision's diff against its parents, suitable for badges
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RevStats {
+    pub files_changed: usize,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "ts-rs",
@@ -124,8 +183,59 @@ impl TryFrom<&Signature> for RevAuthor {
 pub struct RevChange {
     pub kind: ChangeKind,
     pub path: TreePath,
+    /// for renames, the path before the change
+    pub old_path: Option<TreePath>,
     pub has_conflict: bool,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    /// binary files have placeholder hunks and no line counts
+    pub is_binary: bool,
+    pub format: TextFormat,
     pub hunks: Vec<ChangeHunk>,
+    /// the conflicted hunks of the new contents, if they are a file conflict
+    pub conflict_regions: Vec<ConflictRegion>,
+}
+
+/// Conventions detected in a file's contents, so that accidental changes stand out
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct TextFormat {
+    pub line_endings: LineEndings,
+    pub indentation: Indentation,
+    /// the encoding the file was decoded from, if it wasn't UTF-8
+    pub encoding: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum LineEndings {
+    #[default]
+    None,
+    Lf,
+    Crlf,
+    Mixed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum Indentation {
+    #[default]
+    None,
+    Tabs,
+    Spaces,
+    Mixed,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -137,6 +247,60 @@ pub struct RevChange {
 pub struct RevConflict {
     pub path: TreePath,
     pub hunk: ChangeHunk,
+    pub regions: Vec<ConflictRegion>,
+    /// whether the sides end with a newline, which their lines don't include
+    pub has_trailing_newline: bool,
+}
+
+/// A path which is conflicted in the revision's own tree
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RevConflictPath {
+    pub path: TreePath,
+    pub num_sides: usize,
+}
+
+/// One conflicted hunk of a file, numbered and labelled as in jj's conflict markers
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ConflictRegion {
+    /// 1-based, like "Conflict 1 of 2"
+    pub index: usize,
+    pub bases: Vec<ConflictSide>,
+    pub sides: Vec<ConflictSide>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ConflictSide {
+    pub label: String,
+    pub lines: MultilineString,
+}
+
+/// Every term of a conflicted file in full, for editors which show the bases and sides side by side
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct FileConflict {
+    pub path: TreePath,
+    pub bases: Vec<ConflictSide>,
+    pub sides: Vec<ConflictSide>,
+    pub regions: Vec<ConflictRegion>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -150,6 +314,8 @@ pub enum ChangeKind {
     Added,
     Deleted,
     Modified,
+    /// renamed to a path differing only by case or unicode normalization
+    CaseRenamed,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -160,10 +326,23 @@ pub enum ChangeKind {
 )]
 pub struct ChangeHunk {
     pub location: HunkLocation,
+    pub kind: HunkKind,
     pub lines: MultilineString,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum HunkKind {
+    Content,
+    /// every changed line differs only by its line ending
+    LineEndings,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -174,7 +353,7 @@ pub struct HunkLocation {
     pub to_file: FileRange,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(
     feature = "ts-rs",
     derive(TS),
@@ -185,6 +364,431 @@ pub struct FileRange {
     pub len: usize,
 }
 
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RevChangesPage {
+    pub changes: Vec<RevChange>,
+    pub has_more: bool,
+}
+
+/// Working copy changes which will be committed together by CommitStaged
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct StagedChanges {
+    pub paths: Vec<StagedPath>,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct StagedPath {
+    pub path: TreePath,
+    /// If not set, every change to the path is staged
+    pub hunks: Option<Vec<HunkLocation>>,
+}
+
+/// The working copy's files compared against some other revision, a page at a time
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct WorkingCopyDiff {
+    pub base: RevHeader,
+    pub changes: Vec<RevChange>,
+    pub has_more: bool,
+    /// Present if a snapshot was taken and it changed the repo
+    pub new_status: Option<RepoStatus>,
+}
+
+/// Changes aggregated under a directory and its subdirectories
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RevDirectory {
+    pub path: TreePath,
+    pub files_changed: usize,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+}
+
+/// A mutable ancestor of the working copy which its changes could be moved into
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct AmendTarget {
+    pub header: RevHeader,
+    /// whether the revision itself modifies any of the queried paths
+    pub touches_paths: bool,
+}
+
+/// How two revisions have diverged since their common ancestors
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct MergeBase {
+    /// usually one; several for criss-cross merges, none for unrelated histories
+    pub bases: Vec<RevHeader>,
+    /// revisions reachable from the first but not the second
+    pub ahead: usize,
+    /// revisions reachable from the second but not the first
+    pub behind: usize,
+}
+
+/// What an operation changed, compared to its parent operation
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct OperationDiff {
+    pub id: String,
+    pub description: String,
+    /// revisions which became visible
+    pub added: Vec<RevHeader>,
+    /// revisions which were abandoned or hidden
+    pub removed: Vec<RevHeader>,
+    /// new versions of revisions whose previous versions were hidden
+    pub rewritten: Vec<RevHeader>,
+    pub bookmarks: Vec<BookmarkChange>,
+    /// more revisions changed than are listed
+    pub truncated: bool,
+}
+
+/// Repo objects which couldn't be read, found by checking the view's heads and working copies
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RepoVerification {
+    pub operation_id: String,
+    pub commits_checked: usize,
+    pub problems: Vec<String>,
+}
+
+/// The operations which changed a change's visible commits, most recent first
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ChangeHistory {
+    pub operations: Vec<ChangeOperation>,
+    /// the op log is longer than the scan limit
+    pub truncated: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ChangeOperation {
+    pub id: String,
+    pub description: String,
+    pub time: chrono::DateTime<Local>,
+    pub kind: ChangeOperationKind,
+    /// the change's visible commits after the operation
+    pub commits: Vec<CommitId>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum ChangeOperationKind {
+    Created,
+    Rewritten,
+    Diverged,
+    Abandoned,
+}
+
+/// An entry in the operation log, with how long its transaction took
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct OperationSummary {
+    pub id: String,
+    pub description: String,
+    pub time: chrono::DateTime<Local>,
+    /// wall-clock time from the start of the transaction to its commit
+    pub duration_ms: usize,
+    /// the mutation which made this operation, if gg made it
+    pub mutation: Option<String>,
+    pub is_gg: bool,
+}
+
+/// An operation created by CheckpointWorkingCopy
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct Checkpoint {
+    pub id: String,
+    pub label: String,
+    pub time: chrono::DateTime<Local>,
+    pub working_copy: CommitId,
+}
+
+/// An operation as shown in the activity feed
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ActivityEntry {
+    pub op_id: String,
+    pub kind: ActivityKind,
+    pub description: String,
+    pub time: chrono::DateTime<Local>,
+    pub bookmarks: Vec<BookmarkChange>,
+    /// heads which the operation added, such as new or rewritten revisions
+    pub revisions: Vec<CommitId>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum ActivityKind {
+    Fetch,
+    Push,
+    Snapshot,
+    /// only bookmarks changed
+    Bookmarks,
+    Operation,
+}
+
+/// A local bookmark which an operation created, moved or deleted
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct BookmarkChange {
+    pub name: String,
+    pub old_target: Option<CommitId>,
+    pub new_target: Option<CommitId>,
+}
+
+/// A local bookmark whose revision is already part of trunk, so it can be deleted without losing work
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct MergedBookmark {
+    pub name: String,
+    pub target: RevHeader,
+    /// trunk() or the remote trunk bookmark which contains the target
+    pub merged_into: String,
+    /// remotes which would delete their copy of the bookmark on the next push
+    pub tracking_remotes: Vec<String>,
+}
+
+/// A git remote as configured in the repo, with the defaults jj would use for it
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct GitRemote {
+    pub name: String,
+    pub url: Option<String>,
+    /// only set if it differs from the fetch url
+    pub push_url: Option<String>,
+    pub fetch_refspecs: Vec<String>,
+    pub push_refspecs: Vec<String>,
+    /// one of git.fetch, which are fetched when no remote is specified
+    pub is_default_fetch: bool,
+    /// git.push, which is pushed to when no remote is specified
+    pub is_default_push: bool,
+    /// the last time gg fetched from this remote, if it ever has
+    pub last_fetched: Option<chrono::DateTime<Local>>,
+}
+
+/// The outcome of trying to reach a remote, as a fetch would
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RemoteConnection {
+    pub remote_name: String,
+    pub url: Option<String>,
+    /// the proxy in effect and where it was configured, with any password hidden
+    pub proxy: Option<String>,
+    pub reachable: bool,
+    pub message: String,
+}
+
+/// Which drag&drop operations a revision can accept from the revisions being dragged
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct DropTarget {
+    pub id: CommitId,
+    /// rebase the dragged revisions onto this one
+    pub can_move: bool,
+    /// insert the dragged revisions between this one and a parent
+    pub can_insert: bool,
+    /// add the dragged revisions as parents of this one
+    pub can_merge: bool,
+}
+
+/// Statistics and rule violations for a draft description
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct DescriptionCheck {
+    pub lines: usize,
+    pub words: usize,
+    pub characters: usize,
+    pub violations: Vec<DescriptionViolation>,
+}
+
+/// A line of a description which breaks one of the configured rules
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct DescriptionViolation {
+    /// 1-based
+    pub line: usize,
+    pub message: String,
+}
+
+/// A private note attached to a change id, which is not part of the repo
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RevNote {
+    pub change_hex: String,
+    pub text: String,
+}
+
+/// A locally-persisted highlight for a change id, which is not part of the repo
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RevLabel {
+    pub text: String,
+    pub color: LabelColor,
+}
+
+/// A subset of the theme palette, so that labels remain legible in both light and dark mode
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum LabelColor {
+    Red,
+    Peach,
+    Yellow,
+    Green,
+    Teal,
+    Blue,
+    Mauve,
+    Pink,
+}
+
+/// A line which would be changed by a RewritePaths mutation
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct RewriteMatch {
+    pub path: TreePath,
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// A worker event which has been handled, recorded for diagnostics
+#[derive(Serialize, Clone, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct TraceEntry {
+    pub id: usize,
+    pub state: String,
+    pub event: String,
+    pub received_at: String,
+    pub duration_ms: f64,
+}
+
+/// Where a repo path lives on disk, for copying or opening in other tools
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ResolvedPath {
+    pub absolute_path: DisplayPath,
+    pub exists: bool,
+}
+
 #[derive(Serialize, Debug)]
 #[serde(tag = "type")]
 #[cfg_attr(
@@ -200,7 +804,12 @@ pub enum RevResult {
         header: RevHeader,
         parents: Vec<RevHeader>,
         changes: Vec<RevChange>,
+        has_more_changes: bool,
         conflicts: Vec<RevConflict>,
+        conflicted_paths: Vec<RevConflictPath>,
+        stats: RevStats,
+        directories: Option<Vec<RevDirectory>>,
+        signature: RevSignature,
     },
 }
 
@@ -253,6 +862,40 @@ pub struct LogRow {
     pub location: LogCoordinates,
     pub padding: usize,
     pub lines: Vec<LogLine>,
+    /// Local bookmarks which would include this revision if pushed
+    pub push_bookmarks: Vec<String>,
+    /// This row summarises a linear run of revisions, starting with its own
+    pub elided: Option<ElidedRevisions>,
+    /// Plain text summary of the row, for screen readers
+    pub accessible_description: String,
+}
+
+/// A linear run of revisions shown as a single row
+#[derive(Serialize, Debug)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct ElidedRevisions {
+    pub count: usize,
+    pub last: CommitId,
+}
+
+/// Collapses linear runs of immutable revisions which have no refs, to keep long histories navigable
+#[derive(Deserialize, Debug, Clone)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct LogElision {
+    /// the shortest run worth replacing with a summary row
+    pub min_run: usize,
+    /// revisions which are never elided, such as the selection
+    pub keep: Vec<CommitId>,
+    /// first revisions of runs which should be shown in full
+    pub expanded: Vec<CommitId>,
 }
 
 #[derive(Serialize)]
@@ -264,10 +907,45 @@ pub struct LogRow {
 pub struct LogPage {
     pub rows: Vec<LogRow>,
     pub has_more: bool,
+    /// The repo changed since the previous page, so the query must be restarted; no rows are included
+    pub invalidated: bool,
+    /// The query named a change whose revisions are all hidden; no rows are included
+    pub hidden_match: Option<HiddenMatch>,
+    /// The query which was asked for, if the repo is large and a narrower one was run instead
+    pub narrowed_from: Option<String>,
+}
+
+/// Revisions which would have satisfied a query if they weren't hidden
+#[derive(Serialize)]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub struct HiddenMatch {
+    pub symbol: String,
+    pub revisions: Vec<RevHeader>,
+}
+
+/// File format for exporting the results of a log query
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type")]
+#[cfg_attr(
+    feature = "ts-rs",
+    derive(TS),
+    ts(export, export_to = "../src/messages/")
+)]
+pub enum LogExportFormat {
+    /// an array of RevHeaders
+    Json,
+    /// one line per revision, with a header row
+    Csv,
+    /// rendered like `jj log --no-graph -T <template>`
+    Template { template: String },
 }
 
 // similar to time_util::datetime_from_timestamp, which is not pub
-fn format_timestamp(context: &Timestamp) -> Result<DateTime<FixedOffset>> {
+pub fn format_timestamp(context: &Timestamp) -> Result<DateTime<FixedOffset>> {
     let utc = match Utc.timestamp_opt(
         context.timestamp.0.div_euclid(1000),
         (context.timestamp.0.rem_euclid(1000)) as u32 * 1000000,
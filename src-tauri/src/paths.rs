@@ -0,0 +1,49 @@
+//! Windows paths in verbatim (\\?\) form, as returned by fs::canonicalize. dunce only simplifies
+//! those which would mean the same thing without the prefix, so UNC shares and paths longer than
+//! MAX_PATH keep it. That's fine for filesystem calls, but not for display, and CreateProcess
+//! won't accept such a path as a working directory at all.
+
+use std::path::{Path, PathBuf};
+
+const VERBATIM_PREFIX: &str = r"\\?\";
+const VERBATIM_UNC_PREFIX: &str = r"\\?\UNC\";
+
+// SetCurrentDirectory's limit, which leaves room for an 8.3 filename
+const MAX_DIRECTORY_LEN: usize = 248;
+
+/// \\?\C:\repo becomes C:\repo and \\?\UNC\server\share\repo becomes \\server\share\repo, however
+/// long they are; other paths are unchanged
+pub fn display(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    strip_verbatim(&path).unwrap_or_else(|| path.into_owned())
+}
+
+/// a path which can be used as a subprocess working directory, if there is one
+pub fn subprocess_dir(path: &Path) -> Option<PathBuf> {
+    if cfg!(windows) {
+        let display = display(path);
+        if display.starts_with(VERBATIM_PREFIX) || display.len() >= MAX_DIRECTORY_LEN {
+            None
+        } else {
+            Some(PathBuf::from(display))
+        }
+    } else {
+        Some(path.to_owned())
+    }
+}
+
+fn strip_verbatim(path: &str) -> Option<String> {
+    if let Some(rest) = path.strip_prefix(VERBATIM_UNC_PREFIX) {
+        return Some(format!(r"\\{rest}"));
+    }
+
+    // other verbatim paths, such as device or volume GUID paths, have no simpler form
+    let rest = path.strip_prefix(VERBATIM_PREFIX)?;
+    let mut chars = rest.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(drive), Some(':'), None | Some('\\')) if drive.is_ascii_alphabetic() => {
+            Some(rest.to_owned())
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,151 @@
+//! Opt-in check for a newer release, run in the background so that it never delays a workspace.
+//! The request is made with curl, which ships with every supported OS, rather than linking a TLS
+//! stack into the app just for this.
+
+use std::{
+    fs,
+    path::PathBuf,
+    process::Command,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tauri::Window;
+
+use crate::{
+    events::EventSink,
+    handler,
+    messages::{Event, UpdateAvailable},
+    worker::redact,
+};
+
+const RELEASES_URL: &str = "https://api.github.com/repos/gulbanana/gg/releases/latest";
+const CACHE_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+// once per run of the app, however many workspaces are opened
+static CHECK_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// the latest release as of the last successful check, shared by all windows
+#[derive(Serialize, Deserialize)]
+struct CachedRelease {
+    checked_at: u64,
+    version: String,
+    url: String,
+}
+
+/// proxy is gg.git.proxy from the first workspace opened, if any
+pub fn check_in_background(window: &Window, proxy: Option<String>) {
+    if CHECK_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let window = window.clone();
+    thread::spawn(move || {
+        let release = handler::nonfatal!(latest_release(proxy.as_deref()));
+        if is_newer(&release.version, env!("CARGO_PKG_VERSION")) {
+            log::info!("update available: {}", release.version);
+            handler::nonfatal!(window.send_event(Event::Update(UpdateAvailable {
+                version: release.version,
+                url: release.url,
+            })));
+        }
+    });
+}
+
+// the GitHub API allows 60 unauthenticated requests an hour, so results are reused for a day
+fn latest_release(proxy: Option<&str>) -> Result<CachedRelease> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    if let Some(cached) = read_cache() {
+        if now.saturating_sub(cached.checked_at) < CACHE_LIFETIME.as_secs() {
+            return Ok(cached);
+        }
+    }
+
+    let release = fetch_release(now, proxy)?;
+    if let Err(err) = write_cache(&release) {
+        log::warn!("failed to cache update check: {err:#}");
+    }
+    Ok(release)
+}
+
+fn fetch_release(now: u64, proxy: Option<&str>) -> Result<CachedRelease> {
+    let mut command = Command::new("curl");
+    if let Some(proxy) = proxy {
+        command.args(["--proxy", proxy]);
+    }
+    let output = command
+        .args([
+            "--fail",
+            "--silent",
+            "--show-error",
+            "--location",
+            "--max-time",
+            "10",
+            "--header",
+            "Accept: application/vnd.github+json",
+            "--user-agent",
+            concat!("gg/", env!("CARGO_PKG_VERSION")),
+            RELEASES_URL,
+        ])
+        .output()?;
+    if !output.status.success() {
+        let via = match proxy {
+            Some(proxy) => format!(" via proxy {}", redact("proxy", proxy)),
+            None => String::new(),
+        };
+        return Err(anyhow!(
+            "curl failed{via}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let release: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let tag = release["tag_name"]
+        .as_str()
+        .ok_or(anyhow!("release has no tag"))?;
+    let url = release["html_url"]
+        .as_str()
+        .ok_or(anyhow!("release has no url"))?;
+
+    Ok(CachedRelease {
+        checked_at: now,
+        version: tag.trim_start_matches('v').to_owned(),
+        url: url.to_owned(),
+    })
+}
+
+fn cache_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("gg").join("latest-release.json"))
+}
+
+fn read_cache() -> Option<CachedRelease> {
+    let content = fs::read(cache_path()?).ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+fn write_cache(release: &CachedRelease) -> Result<()> {
+    let path = cache_path().ok_or(anyhow!("no cache directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_vec(release)?)?;
+    Ok(())
+}
+
+// compares dotted version numbers, ignoring any pre-release or build suffix
+fn is_newer(candidate: &str, current: &str) -> bool {
+    fn parse(version: &str) -> Vec<u64> {
+        version
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    }
+    parse(candidate) > parse(current)
+}
@@ -0,0 +1,73 @@
+//! Private, unversioned data attached to change ids, stored alongside the repo so that it's shared by
+//! its workspaces but never pushed
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::messages::RevLabel;
+
+const FILE_NAME: &str = "gg-annotations.json";
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct Annotations {
+    /// change id (reverse hex) -> note text
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub notes: BTreeMap<String, String>,
+    /// change id (reverse hex) -> label
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, RevLabel>,
+}
+
+impl Annotations {
+    /// a missing file is equivalent to an empty one
+    pub fn load(repo_path: &Path) -> Result<Self> {
+        let path = file_path(repo_path);
+        match fs::read(&path) {
+            Ok(contents) => serde_json::from_slice(&contents)
+                .with_context(|| format!("parse {}", path.display())),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Annotations::default()),
+            Err(err) => Err(err).with_context(|| format!("read {}", path.display())),
+        }
+    }
+
+    pub fn save(&self, repo_path: &Path) -> Result<()> {
+        let path = file_path(repo_path);
+        fs::write(&path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("write {}", path.display()))
+    }
+
+    /// reads, modifies and writes back the annotations
+    pub fn update(repo_path: &Path, f: impl FnOnce(&mut Annotations)) -> Result<()> {
+        let mut annotations = Annotations::load(repo_path)?;
+        f(&mut annotations);
+        annotations.save(repo_path)
+    }
+
+    /// empty text removes the note
+    pub fn set_note(&mut self, change_hex: String, text: String) {
+        if text.trim().is_empty() {
+            self.notes.remove(&change_hex);
+        } else {
+            self.notes.insert(change_hex, text);
+        }
+    }
+
+    /// no label removes the existing one
+    pub fn set_label(&mut self, change_hex: String, label: Option<RevLabel>) {
+        match label {
+            Some(label) => self.labels.insert(change_hex, label),
+            None => self.labels.remove(&change_hex),
+        };
+    }
+}
+
+fn file_path(repo_path: &Path) -> PathBuf {
+    repo_path.join(FILE_NAME)
+}
@@ -0,0 +1,268 @@
+//! Revisions serialized independently of any store, so that they can be copied in one window and
+//! pasted into another window's workspace. The app holds bundles in memory; nothing is written to
+//! either repo until the paste.
+
+use std::{collections::HashMap, io::Read, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use futures_util::StreamExt;
+use jj_lib::{
+    backend::{CommitId, Signature, TreeValue},
+    commit::Commit,
+    matchers::EverythingMatcher,
+    merge::{Merge, MergedTreeValue},
+    merged_tree::{MergedTree, MergedTreeBuilder, TreeDiffEntry},
+    repo::Repo,
+    repo_path::RepoPathBuf,
+    rewrite,
+    store::Store,
+};
+use pollster::FutureExt;
+
+use super::{gui_util::WorkspaceSession, Mutation};
+use crate::messages::{MutationResult, RevId};
+
+/// copied revisions, parents first
+#[derive(Debug)]
+pub struct RevisionBundle {
+    revisions: Vec<BundledRevision>,
+}
+
+#[derive(Debug)]
+struct BundledRevision {
+    description: String,
+    author: Signature,
+    parents: Vec<BundledParent>,
+    /// the revision's diff from its parents, with None for absent paths
+    changes: Vec<BundledChange>,
+}
+
+#[derive(Debug)]
+enum BundledParent {
+    /// the position of an earlier revision in the bundle
+    Bundled(usize),
+    /// a revision outside the bundle, which is kept if the paste target's repo has it
+    Outside(CommitId),
+}
+
+/// both sides are kept, so that pasting can merge the change into a target which differs
+#[derive(Debug)]
+struct BundledChange {
+    path: RepoPathBuf,
+    before: Option<BundledFile>,
+    after: Option<BundledFile>,
+}
+
+#[derive(Debug)]
+enum BundledFile {
+    File { content: Vec<u8>, executable: bool },
+    Symlink(String),
+}
+
+pub fn copy_revisions(ws: &WorkspaceSession, ids: Vec<RevId>) -> Result<RevisionBundle> {
+    let commits = ws.resolve_multiple_changes(ids)?; // in reverse topological order
+    if commits.is_empty() {
+        return Err(anyhow!("Nothing to copy"));
+    }
+
+    let mut positions: HashMap<CommitId, usize> = HashMap::new();
+    let mut revisions = Vec::new();
+    for commit in commits.into_iter().rev() {
+        if commit.has_conflict()? {
+            return Err(anyhow!(
+                "Revision {} has conflicts, which can't be copied",
+                ws.format_change_id(commit.change_id()).prefix
+            ));
+        }
+
+        let parents = commit
+            .parent_ids()
+            .iter()
+            .map(|id| match positions.get(id) {
+                Some(position) => BundledParent::Bundled(*position),
+                None => BundledParent::Outside(id.clone()),
+            })
+            .collect();
+        let changes = read_changes(ws, &commit)?;
+
+        positions.insert(commit.id().clone(), revisions.len());
+        revisions.push(BundledRevision {
+            description: commit.description().to_owned(),
+            author: commit.author().clone(),
+            parents,
+            changes,
+        });
+    }
+
+    Ok(RevisionBundle { revisions })
+}
+
+fn read_changes(ws: &WorkspaceSession, commit: &Commit) -> Result<Vec<BundledChange>> {
+    let store = ws.repo().store();
+    let mut diff_stream = commit
+        .parent_tree(ws.repo())?
+        .diff_stream(&commit.tree()?, &EverythingMatcher);
+
+    let mut changes = Vec::new();
+    async {
+        while let Some(TreeDiffEntry { path, values }) = diff_stream.next().await {
+            let (before, after) = values?;
+            let before = read_file(store, &path, before)?;
+            let after = read_file(store, &path, after)?;
+            changes.push(BundledChange {
+                path,
+                before,
+                after,
+            });
+        }
+        Ok(())
+    }
+    .block_on()?;
+
+    Ok(changes)
+}
+
+fn read_file(
+    store: &Store,
+    path: &RepoPathBuf,
+    value: MergedTreeValue,
+) -> Result<Option<BundledFile>> {
+    match value.into_resolved() {
+        Ok(None) => Ok(None),
+        Ok(Some(TreeValue::File { id, executable })) => {
+            let mut content = Vec::new();
+            store.read_file(path, &id)?.read_to_end(&mut content)?;
+            Ok(Some(BundledFile::File {
+                content,
+                executable,
+            }))
+        }
+        Ok(Some(TreeValue::Symlink(id))) => {
+            Ok(Some(BundledFile::Symlink(store.read_symlink(path, &id)?)))
+        }
+        _ => Err(anyhow!(
+            "{} is not a file or symlink, so it can't be copied",
+            path.as_internal_file_string()
+        )),
+    }
+}
+
+// the base tree with one side of each change applied
+fn write_side(
+    store: &Arc<Store>,
+    base_tree: &MergedTree,
+    changes: &[BundledChange],
+    side: fn(&BundledChange) -> &Option<BundledFile>,
+) -> Result<MergedTree> {
+    let mut tree_builder = MergedTreeBuilder::new(base_tree.id());
+    for change in changes {
+        let path = &change.path;
+        let value = match side(change) {
+            None => Merge::absent(),
+            Some(BundledFile::File {
+                content,
+                executable,
+            }) => Merge::normal(TreeValue::File {
+                id: store.write_file(path, &mut content.as_slice()).block_on()?,
+                executable: *executable,
+            }),
+            Some(BundledFile::Symlink(target)) => Merge::normal(TreeValue::Symlink(
+                store.write_symlink(path, target).block_on()?,
+            )),
+        };
+        tree_builder.set_or_remove(path.clone(), value);
+    }
+    let tree_id = tree_builder.write_tree(store)?;
+    Ok(store.get_root_tree(&tree_id)?)
+}
+
+/// Recreates a bundle's revisions on top of new parents, like a cherry-pick from another repo
+#[derive(Debug)]
+pub struct PasteBundle {
+    pub bundle: Arc<RevisionBundle>,
+    pub parent_ids: Vec<RevId>,
+}
+
+impl Mutation for PasteBundle {
+    fn rewrite_count(&self, _ws: &WorkspaceSession) -> Result<Option<usize>> {
+        Ok(Some(self.bundle.revisions.len()))
+    }
+
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let parents = ws.resolve_multiple_changes(self.parent_ids)?;
+        if parents.is_empty() {
+            return Ok(MutationResult::PreconditionError {
+                message: "Nothing to paste onto".to_owned(),
+            });
+        }
+
+        let store = tx.repo().store().clone();
+        let mut pasted: Vec<Commit> = Vec::new();
+        for revision in &self.bundle.revisions {
+            // roots go onto the paste target; the rest keep their bundled parents, and any outside
+            // parents which the target repo also has
+            let is_root = !revision
+                .parents
+                .iter()
+                .any(|parent| matches!(parent, BundledParent::Bundled(_)));
+            let new_parents: Vec<Commit> = if is_root {
+                parents.clone()
+            } else {
+                let mut new_parents = Vec::new();
+                for parent in &revision.parents {
+                    match parent {
+                        BundledParent::Bundled(position) => {
+                            new_parents.push(pasted[*position].clone())
+                        }
+                        BundledParent::Outside(id) if tx.repo().index().has_id(id) => {
+                            new_parents.push(store.get_commit(id)?)
+                        }
+                        BundledParent::Outside(_) => (),
+                    }
+                }
+                new_parents
+            };
+
+            // like a cherry-pick, the change is merged into the target, conflicting where they differ
+            let base_tree = rewrite::merge_commit_trees(tx.repo(), &new_parents)?;
+            let before_tree = write_side(&store, &base_tree, &revision.changes, |change| {
+                &change.before
+            })?;
+            let after_tree = write_side(&store, &base_tree, &revision.changes, |change| {
+                &change.after
+            })?;
+            let new_tree_id = base_tree.merge(&before_tree, &after_tree)?.id();
+
+            let commit = tx
+                .repo_mut()
+                .new_commit(
+                    &ws.data.settings,
+                    new_parents
+                        .iter()
+                        .map(|parent| parent.id().clone())
+                        .collect(),
+                    new_tree_id,
+                )
+                .set_description(&revision.description)
+                .set_author(revision.author.clone())
+                .write()?;
+            pasted.push(commit);
+        }
+
+        match ws.finish_transaction(tx, format!("paste {} commit(s)", pasted.len()))? {
+            Some(new_status) => {
+                let new_commit = pasted
+                    .last()
+                    .ok_or(anyhow!("pasted bundle should have revisions"))?;
+                let new_selection = ws.format_header(new_commit, None)?;
+                Ok(MutationResult::UpdatedSelection {
+                    new_status,
+                    new_selection,
+                })
+            }
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Derived data which is slow to compute in big repos, kept alongside the repo so that reopening a
+//! workspace doesn't start from scratch. Stats depend only on a commit's contents, so they're never
+//! invalidated, but only the most recently computed are kept; shortest unique prefixes depend on
+//! the whole repo, so they're revalidated when the operation changes. Activity entries depend only
+//! on an operation and its parent, but are cheap enough to keep in memory only.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use indexmap::IndexMap;
+use jj_lib::{backend::CommitId, object_id::ObjectId, op_store::OperationId};
+use serde::{Deserialize, Serialize};
+
+use super::prefixes::PrefixService;
+use crate::messages::{ActivityKind, RevStats};
+
+const DIR_NAME: &str = "gg-cache";
+const STATS_FILE: &str = "stats.json";
+const PREFIXES_FILE: &str = "prefixes.json";
+/// enough for a long history browse, small enough to load without a noticeable pause
+const MAX_STATS: usize = 10_000;
+
+/// in-memory view of the cache, written back when the session ends
+#[derive(Default)]
+pub struct DerivedCache {
+    /// in the order they were computed, oldest first
+    stats: IndexMap<CommitId, RevStats>,
+    prefixes: PrefixService,
+    activity: HashMap<OperationId, ActivityRecord>,
+    stats_dirty: bool,
+    prefixes_dirty: bool,
+}
+
+/// an activity entry before its ids are formatted, since prefixes can change
+#[derive(Clone)]
+pub struct ActivityRecord {
+    pub kind: ActivityKind,
+    pub description: String,
+    pub time: DateTime<Local>,
+    /// name, old target and new target
+    pub bookmarks: Vec<(String, Option<CommitId>, Option<CommitId>)>,
+    pub revisions: Vec<CommitId>,
+}
+
+impl DerivedCache {
+    /// a missing or unreadable cache is empty
+    pub fn load(workspace_root: &Path) -> DerivedCache {
+        let stats: Vec<(String, RevStats)> = read_file(workspace_root, STATS_FILE);
+        let stats = stats
+            .into_iter()
+            .filter_map(|(hex, stats)| Some((CommitId::try_from_hex(&hex).ok()?, stats)))
+            .collect();
+
+        DerivedCache {
+            stats,
+            prefixes: read_file(workspace_root, PREFIXES_FILE),
+            activity: HashMap::new(),
+            stats_dirty: false,
+            prefixes_dirty: false,
+        }
+    }
+
+    pub fn save(&mut self, workspace_root: &Path) -> Result<()> {
+        if self.stats_dirty {
+            let stats: Vec<(String, &RevStats)> = self
+                .stats
+                .iter()
+                .map(|(id, stats)| (id.hex(), stats))
+                .collect();
+            write_file(workspace_root, STATS_FILE, &stats)?;
+            self.stats_dirty = false;
+        }
+
+        if self.prefixes_dirty {
+            write_file(workspace_root, PREFIXES_FILE, &self.prefixes)?;
+            self.prefixes_dirty = false;
+        }
+
+        Ok(())
+    }
+
+    pub fn get_stats(&self, id: &CommitId) -> Option<RevStats> {
+        self.stats.get(id).cloned()
+    }
+
+    pub fn insert_stats(&mut self, id: CommitId, stats: RevStats) {
+        self.stats.insert(id, stats);
+        if self.stats.len() > MAX_STATS {
+            self.stats.shift_remove_index(0);
+        }
+        self.stats_dirty = true;
+    }
+
+    pub fn get_activity(&self, id: &OperationId) -> Option<ActivityRecord> {
+        self.activity.get(id).cloned()
+    }
+
+    pub fn insert_activity(&mut self, id: OperationId, record: ActivityRecord) {
+        self.activity.insert(id, record);
+    }
+
+    pub fn prefixes(&mut self) -> &mut PrefixService {
+        self.prefixes_dirty = true;
+        &mut self.prefixes
+    }
+}
+
+fn dir_path(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(".jj").join(DIR_NAME)
+}
+
+fn read_file<T: for<'de> Deserialize<'de> + Default>(workspace_root: &Path, name: &str) -> T {
+    fs::read(dir_path(workspace_root).join(name))
+        .ok()
+        .and_then(|contents| serde_json::from_slice(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn write_file<T: Serialize>(workspace_root: &Path, name: &str, value: &T) -> Result<()> {
+    let dir = dir_path(workspace_root);
+    fs::create_dir_all(&dir).with_context(|| format!("create {}", dir.display()))?;
+    let path = dir.join(name);
+    fs::write(&path, serde_json::to_vec(value)?)
+        .with_context(|| format!("write {}", path.display()))
+}
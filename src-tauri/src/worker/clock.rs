@@ -0,0 +1,49 @@
+//! Fixed timestamps, randomness and identities, so that tests and benchmarks produce the same
+//! commit, change and operation ids on every run. jj-lib reads all of these from its settings, so
+//! they're applied as overrides on top of the loaded config.
+
+use std::cell::Cell;
+
+use anyhow::Result;
+use config::Config;
+use jj_lib::settings::UserSettings;
+
+/// replaces the current time, random change ids and the identity recorded in commits and operations
+#[derive(Clone)]
+pub struct FixedClock {
+    /// rfc3339, used for both commit and operation timestamps
+    pub timestamp: String,
+    pub seed: u64,
+    // each load gets its own seed, so that reloading settings doesn't reuse change ids
+    loads: Cell<u64>,
+}
+
+impl Default for FixedClock {
+    fn default() -> Self {
+        FixedClock {
+            timestamp: "2001-02-03T04:05:06+07:00".to_owned(),
+            seed: 0,
+            loads: Cell::new(0),
+        }
+    }
+}
+
+impl FixedClock {
+    pub fn apply(&self, settings: UserSettings) -> Result<UserSettings> {
+        let loads = self.loads.get();
+        self.loads.set(loads + 1);
+
+        let config = Config::builder()
+            .add_source(settings.config().clone())
+            .set_override("debug.commit-timestamp", self.timestamp.clone())?
+            .set_override("debug.operation-timestamp", self.timestamp.clone())?
+            .set_override("debug.randomness-seed", (self.seed + loads).to_string())?
+            .set_override("operation.hostname", "host.example.com")?
+            .set_override("operation.username", "test-username")?
+            .set_override("user.name", "Test User")?
+            .set_override("user.email", "test.user@example.com")?
+            .build()?;
+
+        Ok(UserSettings::from_config(config))
+    }
+}
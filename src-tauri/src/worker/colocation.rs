@@ -0,0 +1,71 @@
+//! Moving a repo's internal git store to the workspace root, as in jj's FAQ. The checks are done by
+//! the ColocateRepository mutation, but the move itself waits until the session has released the
+//! workspace, since its git backend keeps files open in the old location. If any step after the
+//! move fails, the move is undone, so that the repo keeps working as it did.
+
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+
+/// a conversion which has passed every check that can be made before moving anything
+#[derive(Debug)]
+pub struct Colocation {
+    pub(super) workspace_root: PathBuf,
+    pub(super) internal_git_path: PathBuf,
+    pub(super) target_path: PathBuf,
+    pub(super) old_target: Vec<u8>,
+    pub(super) was_bare: bool,
+    /// checked out after the move, unless the working copy's parent is the root commit
+    pub(super) head: Option<git2::Oid>,
+}
+
+impl Colocation {
+    pub fn convert(self) -> Result<()> {
+        let dot_git_path = self.workspace_root.join(".git");
+        fs::rename(&self.internal_git_path, &dot_git_path)
+            .with_context(|| format!("move {} to .git", self.internal_git_path.display()))?;
+
+        if let Err(err) = self.convert_moved() {
+            if let Err(restore_err) = self.restore() {
+                log::error!(
+                    "failed to restore {}: {restore_err:#}",
+                    self.internal_git_path.display()
+                );
+            }
+            return Err(err);
+        }
+
+        // git would otherwise show jj's metadata as untracked
+        let jj_ignore_path = self.workspace_root.join(".jj").join(".gitignore");
+        if !jj_ignore_path.exists() {
+            fs::write(&jj_ignore_path, "/*\n").context("write .jj/.gitignore")?;
+        }
+
+        Ok(())
+    }
+
+    fn convert_moved(&self) -> Result<()> {
+        fs::write(&self.target_path, "../../../.git").context("write git_target")?;
+
+        let dot_git_path = self.workspace_root.join(".git");
+        git2::Config::open(&dot_git_path.join("config"))?.set_bool("core.bare", false)?;
+        let git_repo = git2::Repository::open(&self.workspace_root)?;
+
+        // check out the working copy's parent, leaving the files alone; jj tracks it from now on
+        if let Some(head) = self.head {
+            let head = git_repo.find_commit(head)?;
+            git_repo.set_head_detached(head.id())?;
+            git_repo.reset(head.as_object(), git2::ResetType::Mixed, None)?;
+        }
+
+        Ok(())
+    }
+
+    fn restore(&self) -> Result<()> {
+        let dot_git_path = self.workspace_root.join(".git");
+        git2::Config::open(&dot_git_path.join("config"))?.set_bool("core.bare", self.was_bare)?;
+        fs::rename(&dot_git_path, &self.internal_git_path)
+            .with_context(|| format!("move .git back to {}", self.internal_git_path.display()))?;
+        fs::write(&self.target_path, &self.old_target).context("restore git_target")
+    }
+}
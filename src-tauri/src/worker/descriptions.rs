@@ -0,0 +1,75 @@
+//! Formatting rules for descriptions, checked by the describe dialog as a draft is edited
+
+use jj_lib::settings::UserSettings;
+
+use crate::{
+    config::GGSettings,
+    messages::{DescriptionCheck, DescriptionViolation},
+};
+
+/// limits of 0 are disabled
+pub struct DescriptionRules {
+    pub subject_max_length: usize,
+    pub body_max_length: usize,
+    pub require_blank_line: bool,
+}
+
+impl DescriptionRules {
+    pub fn from_settings(settings: &UserSettings) -> Self {
+        DescriptionRules {
+            subject_max_length: settings.descriptions_subject_max_length(),
+            body_max_length: settings.descriptions_body_max_length(),
+            require_blank_line: settings.descriptions_require_blank_line(),
+        }
+    }
+
+    pub fn check(&self, text: &str) -> DescriptionCheck {
+        let lines: Vec<&str> = text.trim_end().lines().collect();
+        let mut violations = Vec::new();
+
+        if let Some(subject) = lines.first() {
+            let length = subject.chars().count();
+            if self.subject_max_length > 0 && length > self.subject_max_length {
+                violations.push(DescriptionViolation {
+                    line: 1,
+                    message: format!(
+                        "Subject is {length} characters; the limit is {}.",
+                        self.subject_max_length
+                    ),
+                });
+            }
+        }
+
+        if self.require_blank_line && lines.len() > 1 && !lines[1].trim().is_empty() {
+            violations.push(DescriptionViolation {
+                line: 2,
+                message: "Subject should be followed by a blank line.".to_owned(),
+            });
+        }
+
+        // a line with no spaces, such as a URL, can't be wrapped
+        for (index, line) in lines.iter().enumerate().skip(1) {
+            let length = line.chars().count();
+            if self.body_max_length > 0
+                && length > self.body_max_length
+                && line.trim().contains(char::is_whitespace)
+            {
+                violations.push(DescriptionViolation {
+                    line: index + 1,
+                    message: format!(
+                        "Line {} is {length} characters; the limit is {}.",
+                        index + 1,
+                        self.body_max_length
+                    ),
+                });
+            }
+        }
+
+        DescriptionCheck {
+            lines: lines.len(),
+            words: text.split_whitespace().count(),
+            characters: text.trim_end().chars().count(),
+            violations,
+        }
+    }
+}
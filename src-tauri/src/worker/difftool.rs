@@ -0,0 +1,137 @@
+//! Shows a revision's changes in `ui.diff-editor`. Both sides are written to temporary
+//! directories, which are removed when the tool exits; unlike `jj diffedit`, nothing is read back.
+
+use std::{fs, io::Read, path::Path, process::Command, thread};
+
+use anyhow::{anyhow, Context, Result};
+use futures_util::StreamExt;
+use jj_cli::{
+    config::CommandNameAndArgs,
+    merge_tools::{get_external_tool_config, ExternalMergeTool},
+};
+use jj_lib::{
+    conflicts::{self, MaterializedTreeValue},
+    matchers::{EverythingMatcher, FilesMatcher, Matcher},
+    merge::MergedTreeValue,
+    merged_tree::TreeDiffEntry,
+    repo::Repo,
+    repo_path::RepoPath,
+    settings::{ConfigResultExt, UserSettings},
+    store::Store,
+};
+use pollster::FutureExt;
+use tempfile::TempDir;
+
+use super::gui_util::WorkspaceSession;
+use crate::messages::{RevId, TreePath};
+
+/// ui.diff-editor, unless it's unset or the builtin editor, which needs a terminal
+pub fn read_tool(settings: &UserSettings) -> Result<Option<ExternalMergeTool>> {
+    let Some(args) = settings
+        .config()
+        .get::<CommandNameAndArgs>("ui.diff-editor")
+        .optional()?
+    else {
+        return Ok(None);
+    };
+
+    let tool = match &args {
+        CommandNameAndArgs::String(name) if name == ":builtin" => return Ok(None),
+        CommandNameAndArgs::String(name) => get_external_tool_config(settings, name)?
+            .unwrap_or_else(|| ExternalMergeTool::with_edit_args(&args)),
+        _ => ExternalMergeTool::with_edit_args(&args),
+    };
+    Ok(Some(tool))
+}
+
+/// returns once the tool has started; it's waited for on another thread, so the worker stays free
+pub fn launch(ws: &WorkspaceSession, id: RevId, path: Option<TreePath>) -> Result<()> {
+    if ws.session.safe_mode {
+        return Err(anyhow!("External diff tools are disabled in safe mode"));
+    }
+
+    let Some(tool) = read_tool(&ws.data.settings)? else {
+        return Err(anyhow!(
+            "No external diff tool is configured; set ui.diff-editor"
+        ));
+    };
+
+    let commit = ws.resolve_single_change(&id)?;
+    let matcher: Box<dyn Matcher> = match &path {
+        Some(path) => Box::new(FilesMatcher::new([RepoPath::from_internal_string(
+            &path.repo_path,
+        )])),
+        None => Box::new(EverythingMatcher),
+    };
+
+    let left_dir = tempfile::Builder::new().prefix("gg-left-").tempdir()?;
+    let right_dir = tempfile::Builder::new().prefix("gg-right-").tempdir()?;
+
+    let store = ws.repo().store();
+    let mut diff_stream = commit
+        .parent_tree(ws.repo())?
+        .diff_stream(&commit.tree()?, matcher.as_ref());
+    async {
+        while let Some(TreeDiffEntry { path, values }) = diff_stream.next().await {
+            let (before, after) = values?;
+            write_value(store, &path, before, left_dir.path()).await?;
+            write_value(store, &path, after, right_dir.path()).await?;
+        }
+        anyhow::Ok(())
+    }
+    .block_on()?;
+
+    let mut child = Command::new(&tool.program)
+        .args(tool.edit_args.iter().map(|arg| {
+            arg.replace("$left", &left_dir.path().to_string_lossy())
+                .replace("$right", &right_dir.path().to_string_lossy())
+        }))
+        .spawn()
+        .with_context(|| format!("run {}", tool.program))?;
+
+    thread::spawn(move || {
+        if let Err(err) = child.wait() {
+            log::warn!("diff tool: {err}");
+        }
+        cleanup(left_dir);
+        cleanup(right_dir);
+    });
+
+    Ok(())
+}
+
+// conflicts are written with markers, and symlinks as files containing their targets
+async fn write_value(
+    store: &Store,
+    path: &RepoPath,
+    value: MergedTreeValue,
+    root: &Path,
+) -> Result<()> {
+    let content = match conflicts::materialize_tree_value(store, path, value).await? {
+        MaterializedTreeValue::File { mut reader, .. } => {
+            let mut content = Vec::new();
+            reader.read_to_end(&mut content)?;
+            content
+        }
+        MaterializedTreeValue::Symlink { target, .. } => target.into_bytes(),
+        MaterializedTreeValue::FileConflict { contents, .. } => {
+            let mut content = Vec::new();
+            conflicts::materialize_merge_result(&contents, &mut content)?;
+            content
+        }
+        _ => return Ok(()),
+    };
+
+    let fs_path = path.to_fs_path(root)?;
+    if let Some(parent) = fs_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&fs_path, content).with_context(|| format!("write {}", fs_path.display()))
+}
+
+fn cleanup(dir: TempDir) {
+    let path = dir.path().to_owned();
+    if let Err(err) = dir.close() {
+        log::warn!("remove {}: {err}", path.display());
+    }
+}
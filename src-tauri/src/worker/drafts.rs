@@ -0,0 +1,52 @@
+//! Description drafts which haven't been applied yet, stored per workspace so that an edit in progress
+//! survives the window closing
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+
+const DIR_NAME: &str = "gg-drafts";
+
+/// an unsaved description, one file per change id (reverse hex)
+pub struct DraftDescription;
+
+impl DraftDescription {
+    /// a missing draft is not an error
+    pub fn load(workspace_root: &Path, change_hex: &str) -> Result<Option<String>> {
+        let path = file_path(workspace_root, change_hex)?;
+        match fs::read_to_string(&path) {
+            Ok(text) => Ok(Some(text)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err).with_context(|| format!("read {}", path.display())),
+        }
+    }
+
+    pub fn save(workspace_root: &Path, change_hex: &str, text: &str) -> Result<()> {
+        let path = file_path(workspace_root, change_hex)?;
+        let dir = workspace_root.join(".jj").join(DIR_NAME);
+        fs::create_dir_all(&dir).with_context(|| format!("create {}", dir.display()))?;
+        fs::write(&path, text).with_context(|| format!("write {}", path.display()))
+    }
+
+    /// discarding a missing draft is not an error
+    pub fn discard(workspace_root: &Path, change_hex: &str) -> Result<()> {
+        let path = file_path(workspace_root, change_hex)?;
+        match fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err).with_context(|| format!("remove {}", path.display())),
+        }
+    }
+}
+
+// change ids are used as file names, so anything else could escape the directory
+fn file_path(workspace_root: &Path, change_hex: &str) -> Result<PathBuf> {
+    if change_hex.is_empty() || !change_hex.chars().all(|c| ('k'..='z').contains(&c)) {
+        return Err(anyhow!("Invalid change id: {change_hex}"));
+    }
+    Ok(workspace_root.join(".jj").join(DIR_NAME).join(change_hex))
+}
@@ -0,0 +1,47 @@
+//! When gg last fetched from each remote. jj doesn't record this, and git only does for remotes
+//! fetched by git itself, so gg keeps its own record alongside the repo.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+
+const FILE_NAME: &str = "gg-fetches";
+
+/// milliseconds since the epoch, per remote name
+pub struct FetchHistory(HashMap<String, i64>);
+
+impl FetchHistory {
+    /// a missing or unreadable history is empty
+    pub fn load(repo_path: &Path) -> FetchHistory {
+        let history = fs::read(file_path(repo_path))
+            .ok()
+            .and_then(|contents| serde_json::from_slice(&contents).ok())
+            .unwrap_or_default();
+        FetchHistory(history)
+    }
+
+    pub fn get(&self, remote_name: &str) -> Option<i64> {
+        self.0.get(remote_name).copied()
+    }
+
+    pub fn record(repo_path: &Path, remote_names: &[String]) -> Result<()> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+        let mut history = FetchHistory::load(repo_path);
+        for remote_name in remote_names {
+            history.0.insert(remote_name.clone(), now);
+        }
+
+        let path = file_path(repo_path);
+        fs::write(&path, serde_json::to_vec(&history.0)?)
+            .with_context(|| format!("write {}", path.display()))
+    }
+}
+
+fn file_path(repo_path: &Path) -> PathBuf {
+    repo_path.join(FILE_NAME)
+}
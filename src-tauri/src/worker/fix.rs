@@ -0,0 +1,116 @@
+//! The formatters configured in `fix.tools` for `jj fix`, run the same way: every tool whose
+//! patterns match a file gets the previous tool's output on stdin, in name order, and a tool which
+//! fails is skipped rather than failing the whole fix.
+
+use std::{collections::HashMap, io::Write, process::Stdio, thread};
+
+use anyhow::{anyhow, Context, Result};
+use itertools::Itertools;
+use jj_cli::config::CommandNameAndArgs;
+use jj_lib::{
+    fileset::{self, FilesetDiagnostics, FilesetExpression},
+    matchers::Matcher,
+    repo_path::{RepoPath, RepoPathUiConverter},
+    settings::UserSettings,
+};
+use serde::Deserialize;
+
+pub struct FixTool {
+    name: String,
+    command: CommandNameAndArgs,
+    matcher: Box<dyn Matcher>,
+}
+
+#[derive(Deserialize)]
+struct RawFixTool {
+    command: CommandNameAndArgs,
+    patterns: Vec<String>,
+}
+
+/// the fixed contents, and a message for each tool which failed
+pub struct FixOutput {
+    pub content: Vec<u8>,
+    pub errors: Vec<String>,
+}
+
+pub fn read_tools(settings: &UserSettings) -> Result<Vec<FixTool>> {
+    let Ok(table) = settings.config().get_table("fix.tools") else {
+        return Ok(Vec::new());
+    };
+
+    // patterns are relative to the repo root, whatever directory gg was started in
+    let path_converter = RepoPathUiConverter::Fs {
+        cwd: "".into(),
+        base: "".into(),
+    };
+
+    table
+        .into_iter()
+        .sorted_by(|a, b| a.0.cmp(&b.0))
+        .map(|(name, value)| {
+            let tool: RawFixTool = value
+                .try_deserialize()
+                .with_context(|| format!("fix.tools.{name}"))?;
+            let mut diagnostics = FilesetDiagnostics::new();
+            let patterns = tool
+                .patterns
+                .iter()
+                .map(|pattern| fileset::parse(&mut diagnostics, pattern, &path_converter))
+                .try_collect()
+                .map_err(|err| anyhow!("fix.tools.{name}: {err}"))?;
+            Ok(FixTool {
+                name,
+                command: tool.command,
+                matcher: FilesetExpression::union_all(patterns).to_matcher(),
+            })
+        })
+        .collect()
+}
+
+pub fn fix_content(tools: &[FixTool], path: &RepoPath, content: &[u8]) -> FixOutput {
+    let mut output = FixOutput {
+        content: content.to_vec(),
+        errors: Vec::new(),
+    };
+
+    for tool in tools.iter().filter(|tool| tool.matcher.matches(path)) {
+        match run_tool(tool, path, &output.content) {
+            Ok(fixed) => output.content = fixed,
+            Err(err) => output.errors.push(format!("{}: {err:#}", tool.name)),
+        }
+    }
+
+    output
+}
+
+fn run_tool(tool: &FixTool, path: &RepoPath, content: &[u8]) -> Result<Vec<u8>> {
+    let mut variables = HashMap::new();
+    variables.insert("path", path.as_internal_file_string());
+
+    let mut child = tool
+        .command
+        .to_command_with_variables(&variables)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // written on another thread so that a tool which fills its stdout pipe can't deadlock
+    let mut stdin = child.stdin.take().ok_or(anyhow!("no stdin"))?;
+    let output = thread::scope(|scope| {
+        scope.spawn(move || {
+            _ = stdin.write_all(content);
+        });
+        child.wait_with_output()
+    })?;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(anyhow!(
+            "{} {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
@@ -0,0 +1,109 @@
+//! Environment variables from gg.git.env and gg.git.proxy, for remotes which can only be reached
+//! with extra configuration. Subprocesses receive them directly. The process environment is shared
+//! with every other thread, so in-process git operations get only the proxy, through a config file
+//! added to their repo handle.
+
+use std::{
+    fmt::{self, Debug},
+    fs,
+    path::Path,
+    process::Command,
+};
+
+use anyhow::{Context, Result};
+use jj_lib::settings::UserSettings;
+
+use crate::config::GGSettings;
+
+const PROXY_CONFIG_FILE: &str = "gg-proxy.gitconfig";
+
+// values of variables whose names contain these are never logged
+const SECRET_NAMES: [&str; 6] = ["TOKEN", "PASSWORD", "SECRET", "KEY", "AUTH", "CREDENTIAL"];
+
+pub struct GitEnvironment {
+    vars: Vec<(String, String)>,
+}
+
+impl GitEnvironment {
+    pub fn from_settings(settings: &UserSettings) -> GitEnvironment {
+        let mut vars = settings.git_env();
+
+        // libgit2 and curl both read these; explicit entries in gg.git.env win
+        if let Some(proxy) = settings.git_proxy() {
+            for name in ["HTTPS_PROXY", "HTTP_PROXY"] {
+                if !vars.iter().any(|(existing, _)| existing == name) {
+                    vars.push((name.to_owned(), proxy.clone()));
+                }
+            }
+        }
+
+        GitEnvironment { vars }
+    }
+
+    pub fn apply(&self, command: &mut Command) {
+        if !self.vars.is_empty() {
+            log::debug!("subprocess git environment: {self:?}");
+        }
+        command.envs(self.vars.iter().map(|(name, value)| (name, value)));
+    }
+
+    /// makes libgit2 use the proxy for remotes of this handle, unless git's own config sets one
+    pub fn configure(&self, git_repo: &git2::Repository, repo_path: &Path) -> Result<()> {
+        let Some(proxy) = ["HTTPS_PROXY", "HTTP_PROXY"].iter().find_map(|name| {
+            self.vars
+                .iter()
+                .find(|(existing, _)| existing.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value)
+        }) else {
+            return Ok(());
+        };
+
+        // git's own http.proxy still wins, as it would over the environment; a remote's proxy
+        // setting is checked before either
+        let mut config = git_repo.config()?;
+        if config.get_string("http.proxy").is_ok() {
+            return Ok(());
+        }
+
+        log::debug!("in-process git proxy: {}", redact("proxy", proxy));
+        let path = repo_path.join(PROXY_CONFIG_FILE);
+        let escaped = proxy.replace('\\', "\\\\").replace('"', "\\\"");
+        fs::write(&path, format!("[http]\n\tproxy = \"{escaped}\"\n"))
+            .with_context(|| format!("write {}", path.display()))?;
+        config.add_file(&path, git2::ConfigLevel::App, true)?;
+        Ok(())
+    }
+}
+
+impl Debug for GitEnvironment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.vars
+                    .iter()
+                    .map(|(name, value)| (name, redact(name, value))),
+            )
+            .finish()
+    }
+}
+
+/// hides values which look like credentials: secret-sounding names and urls with a password
+pub fn redact(name: &str, value: &str) -> String {
+    let upper_name = name.to_uppercase();
+    if SECRET_NAMES
+        .iter()
+        .any(|secret| upper_name.contains(secret))
+    {
+        return "<redacted>".to_owned();
+    }
+
+    if let Some((scheme, rest)) = value.split_once("://") {
+        if let Some((userinfo, host)) = rest.split_once('@') {
+            if userinfo.contains(':') && !userinfo.contains('/') {
+                return format!("{scheme}://<redacted>@{host}");
+            }
+        }
+    }
+
+    value.to_owned()
+}
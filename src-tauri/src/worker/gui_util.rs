@@ -3,11 +3,14 @@
 
 use std::{
     cell::OnceCell,
-    collections::HashMap,
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
     env::VarError,
+    io::Read,
+    iter,
     path::{Path, PathBuf},
     rc::Rc,
-    sync::Arc,
+    sync::{Arc, Mutex, MutexGuard},
 };
 
 use anyhow::{anyhow, Context, Result};
@@ -16,44 +19,81 @@ use git2::Repository;
 use itertools::Itertools;
 use jj_cli::{
     cli_util::{check_stale_working_copy, short_operation_hash, WorkingCopyFreshness},
+    commit_templater::{CommitTemplateLanguage, CommitTemplateLanguageExtension},
+    config::{write_config_value_to_file, CommandNameAndArgs, ConfigNamePathBuf, ConfigSource},
     git_util::{self, is_colocated_git_workspace},
+    merge_tools::MergeEditor,
     revset_util,
+    template_parser::{TemplateAliasesMap, TemplateDiagnostics},
+    templater::TemplateRenderer,
+    ui::Ui,
 };
 use jj_lib::{
-    backend::{BackendError, ChangeId, CommitId},
+    backend::{BackendError, ChangeId, CommitId, TreeValue},
     commit::Commit,
     default_index::{AsCompositeIndex, DefaultReadonlyIndex},
-    file_util, git,
+    file_util,
+    fsmonitor::FsmonitorSettings,
+    git::{self, REMOTE_NAME_FOR_LOCAL_GIT_REPO},
     git_backend::GitBackend,
     gitignore::GitIgnoreFile,
+    hex_util::to_forward_hex,
     id_prefix::{IdPrefixContext, IdPrefixIndex},
-    matchers::EverythingMatcher,
-    object_id::ObjectId,
+    matchers::{EverythingMatcher, Matcher},
+    merged_tree::MergedTree,
+    object_id::{HexPrefix, ObjectId, PrefixResolution},
     op_heads_store,
-    op_store::WorkspaceId,
+    op_store::{RefTarget, WorkspaceId},
+    op_walk,
     operation::Operation,
     repo::{ReadonlyRepo, Repo, RepoLoaderError, StoreFactories},
-    repo_path::{RepoPath, RepoPathUiConverter},
+    repo_path::{RepoPath, RepoPathBuf, RepoPathUiConverter},
     revset::{
         self, DefaultSymbolResolver, Revset, RevsetAliasesMap, RevsetDiagnostics,
         RevsetEvaluationError, RevsetExpression, RevsetExtensions, RevsetIteratorExt,
         RevsetParseContext, RevsetResolutionError, RevsetWorkspaceContext, SymbolResolverExtension,
     },
     rewrite,
-    settings::UserSettings,
+    settings::{ConfigResultExt, UserSettings},
+    str_util::StringPattern,
     transaction::Transaction,
     view::View,
     working_copy::{CheckoutStats, SnapshotOptions},
     workspace::{self, DefaultWorkspaceLoaderFactory, Workspace, WorkspaceLoaderFactory},
 };
+use regex::Regex;
 use thiserror::Error;
 
-use super::WorkerSession;
+use super::{annotations::Annotations, cache::DerivedCache, WorkerSession};
 use crate::{
-    config::{read_config, GGSettings},
+    config::{
+        read_config, read_config_diagnostics, GGSettings, SavedWorkspace, MUTATION_TAG, TOOL_TAG,
+        TRUNK_ALIAS, WINDOW_TAG,
+    },
     messages::{self, RevId},
 };
 
+// repos with many tracked bookmarks would otherwise swamp the query selector
+const MAX_BOOKMARK_QUERY_CHOICES: usize = 10;
+
+// oldest entries are forgotten, so that the config file doesn't grow forever
+const MAX_SAVED_WORKSPACES: usize = 20;
+
+// the same names as jj's default trunk(), for repos which have no remote yet
+pub const TRUNK_BOOKMARKS: &[&str] = &["main", "master", "trunk"];
+
+// offered when initialising a repo; deliberately small, since users can edit the result
+const GITIGNORE_TEMPLATES: &[(&str, &str)] = &[
+    ("rust", "/target\n"),
+    ("node", "node_modules/\ndist/\n"),
+    ("python", "__pycache__/\n*.py[cod]\n.venv/\n"),
+    ("dotnet", "bin/\nobj/\n"),
+    ("macos", ".DS_Store\n"),
+    ("windows", "Thumbs.db\ndesktop.ini\n"),
+    ("jetbrains", ".idea/\n"),
+    ("vscode", ".vscode/\n"),
+];
+
 /// jj-dependent state, available when a workspace is open
 pub struct WorkspaceSession<'a> {
     pub(crate) session: &'a mut WorkerSession,
@@ -66,6 +106,10 @@ pub struct WorkspaceSession<'a> {
     // operation-specific data, containing a repo view and derived extras
     operation: SessionOperation,
     is_colocated: bool,
+    is_historical: bool, // loaded at an earlier operation than the head, and therefore read-only
+
+    // name of the mutation being executed, if any
+    pub(crate) mutation_name: Option<String>,
 }
 
 pub struct WorkspaceData {
@@ -73,6 +117,10 @@ pub struct WorkspaceData {
     extensions: RevsetExtensions,
     pub settings: UserSettings,
     pub aliases_map: RevsetAliasesMap,
+    // stats and id prefixes, persisted between sessions and shared by every query and reader
+    pub cache: Arc<Mutex<DerivedCache>>,
+    // read once rather than for every header
+    age_buckets: [i64; 3],
 }
 
 /// state derived from a specific operation
@@ -80,6 +128,8 @@ pub struct SessionOperation {
     pub repo: Arc<ReadonlyRepo>,
     pub wc_id: CommitId,
     ref_index: OnceCell<Rc<RefIndex>>,
+    labels: OnceCell<Rc<BTreeMap<String, messages::RevLabel>>>,
+    push_targets: OnceCell<Rc<HashMap<CommitId, Vec<String>>>>,
     prefix_context: IdPrefixContext,
 }
 
@@ -104,7 +154,10 @@ impl WorkerSession {
         let factory = DefaultWorkspaceLoaderFactory;
         let loader = factory.create(find_workspace_dir(cwd))?;
 
-        let (settings, aliases_map) = read_config(loader.repo_path())?;
+        let (settings, aliases_map) = self.read_settings(loader.repo_path())?;
+        self.trace
+            .borrow_mut()
+            .set_capacity(settings.debug_trace_size());
 
         let workspace = loader.load(
             &settings,
@@ -118,10 +171,14 @@ impl WorkerSession {
         };
 
         let data: WorkspaceData = WorkspaceData {
+            age_buckets: settings.query_age_buckets(),
             settings,
             path_converter,
             aliases_map,
             extensions: Default::default(),
+            cache: self.shared_cache.clone().unwrap_or_else(|| {
+                Arc::new(Mutex::new(DerivedCache::load(workspace.workspace_root())))
+            }),
         };
 
         let operation = load_at_head(&workspace, &data)?;
@@ -146,8 +203,97 @@ impl WorkerSession {
             is_large,
             operation,
             is_colocated,
+            is_historical: false,
+            mutation_name: None,
         })
     }
+
+    /// reads a repo's config, pinned to the session's fixed clock if it has one
+    pub fn read_settings(&self, repo_path: &Path) -> Result<(UserSettings, RevsetAliasesMap)> {
+        let (settings, aliases_map) = read_config(repo_path)?;
+        match &self.clock {
+            Some(clock) => Ok((clock.apply(settings)?, aliases_map)),
+            None => Ok((settings, aliases_map)),
+        }
+    }
+
+    /// creates a colocated repo in an existing directory, adopting its .git if there is one
+    pub fn init_directory(&self, path: &Path, options: &messages::InitWorkspace) -> Result<()> {
+        if path.ancestors().any(|dir| dir.join(".jj").is_dir()) {
+            return Err(anyhow!(
+                "{} is already in a Jujutsu workspace",
+                path.display()
+            ));
+        }
+
+        let gitignore = options
+            .gitignore_templates
+            .iter()
+            .map(|name| {
+                GITIGNORE_TEMPLATES
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name.trim()))
+                    .map(|(_, content)| *content)
+                    .ok_or_else(|| anyhow!("Unknown ignore template: {name}"))
+            })
+            .collect::<Result<String>>()?;
+
+        let (settings, _) = self.read_settings(&path.join(".jj").join("repo"))?;
+        let git_dir = path.join(".git");
+        let (mut workspace, repo) = if git_dir.exists() {
+            Workspace::init_external_git(&settings, path, &git_dir)?
+        } else {
+            Workspace::init_colocated_git(&settings, path)?
+        };
+
+        // jj's own files are kept out of the colocated git repo
+        std::fs::write(path.join(".jj").join(".gitignore"), "/*\n")?;
+        if !gitignore.is_empty() {
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path.join(".gitignore"))?;
+            std::io::Write::write_all(&mut file, gitignore.as_bytes())?;
+        }
+
+        let mut tx = repo.start_transaction(&settings);
+        git::import_head(tx.repo_mut())?;
+        git::import_refs(tx.repo_mut(), &settings.git_settings())?;
+        if let Some(head_id) = tx.repo().view().git_head().as_normal().cloned() {
+            let head = tx.repo().store().get_commit(&head_id)?;
+            tx.repo_mut()
+                .check_out(workspace.workspace_id().clone(), &settings, &head)?;
+        }
+
+        let wc_id = tx
+            .repo()
+            .view()
+            .get_wc_commit_id(workspace.workspace_id())
+            .ok_or(anyhow!("No working copy found for workspace"))?
+            .clone();
+        let mut wc = tx.repo().store().get_commit(&wc_id)?;
+        if let Some(description) = options.description.as_ref().filter(|d| !d.is_empty()) {
+            wc = tx
+                .repo_mut()
+                .rewrite_commit(&settings, &wc)
+                .set_description(description)
+                .write()?;
+            tx.repo_mut().rebase_descendants(&settings)?;
+        }
+        if let Some(bookmark) = options.bookmark.as_ref().filter(|b| !b.is_empty()) {
+            tx.repo_mut()
+                .set_local_bookmark_target(bookmark, RefTarget::normal(wc.id().clone()));
+            git::export_refs(tx.repo_mut())?;
+        }
+        let repo = tx.commit("initialise workspace");
+
+        // the files on disk are already correct, and will be snapshotted when the workspace is opened
+        let mut locked_ws = workspace.start_working_copy_mutation()?;
+        locked_ws.locked_wc().reset(&wc)?;
+        locked_ws.finish(repo.op_id().clone())?;
+
+        Ok(())
+    }
 }
 
 impl WorkspaceSession<'_> {
@@ -159,6 +305,14 @@ impl WorkspaceSession<'_> {
         &self.operation.wc_id
     }
 
+    /// the workspace whose working copy @ refers to, unless it has been forgotten
+    pub fn followed_id(&self) -> &WorkspaceId {
+        match &self.session.followed_workspace {
+            Some(id) if self.repo().view().get_wc_commit_id(id).is_some() => id,
+            _ => self.workspace.workspace_id(),
+        }
+    }
+
     // XXX maybe: hunt down uses and make nonpub
     pub fn repo(&self) -> &ReadonlyRepo {
         self.operation.repo.as_ref()
@@ -172,6 +326,20 @@ impl WorkspaceSession<'_> {
         Ok(self.operation.repo.store().get_commit(&id)?)
     }
 
+    /// false if the workspace or its repo has been deleted or moved since it was loaded
+    pub fn is_present(&self) -> bool {
+        self.workspace.workspace_root().is_dir() && self.workspace.repo_path().is_dir()
+    }
+
+    pub fn is_colocated(&self) -> bool {
+        self.is_colocated
+    }
+
+    /// the name jj gives the repo's backend, for explaining why git features are unavailable
+    pub fn backend_name(&self) -> &str {
+        self.operation.repo.store().backend_impl().name()
+    }
+
     pub fn git_repo(&self) -> Result<Option<Repository>> {
         match self.operation.git_backend() {
             Some(backend) => Ok(Some(backend.open_git_repo()?)),
@@ -189,6 +357,43 @@ impl WorkspaceSession<'_> {
         }
     }
 
+    /// writes derived data back to disk, so the next session can skip computing it
+    pub fn save_cache(&self) -> Result<()> {
+        self.data.cache().save(self.workspace.workspace_root())
+    }
+
+    pub fn is_historical(&self) -> bool {
+        self.is_historical
+    }
+
+    /// views the repo as of an earlier operation, which can be browsed but not changed; None returns to the head
+    pub fn load_at_operation(&mut self, op_str: Option<&str>) -> Result<()> {
+        let head = load_at_head(&self.workspace, &self.data)?;
+        let op = match op_str {
+            Some(op_str) => op_walk::resolve_op_with_repo(&head.repo, op_str)?,
+            None => head.repo.operation().clone(),
+        };
+
+        if op.id() == head.repo.op_id() {
+            self.operation = head;
+            self.is_historical = false;
+            return Ok(());
+        }
+
+        let repo = self.workspace.repo_loader().load_at(&op)?;
+        if repo.view().get_wc_commit_id(self.id()).is_none() {
+            return Err(anyhow!(
+                "Workspace {} did not exist at operation {}",
+                self.id().as_str(),
+                op.id().hex()
+            ));
+        }
+
+        self.operation = SessionOperation::new(self.id(), &self.data, repo);
+        self.is_historical = true;
+        Ok(())
+    }
+
     /***********************************************************/
     /* Functions for evaluating revset expressions             */
     /* unfortunately parse_context and resolver are not cached */
@@ -235,6 +440,22 @@ impl WorkspaceSession<'_> {
         self.evaluate_revset_expr(expr)
     }
 
+    /// evaluates a revset as if every indexed commit were visible
+    pub fn evaluate_revset_str_with_hidden<'op>(
+        &'op self,
+        revset_str: &str,
+    ) -> Result<Box<dyn Revset + 'op>, RevsetError> {
+        let revset_expr = parse_revset(&self.parse_context(), revset_str)?;
+        let visible_heads = match self.operation.repo.index().all_heads_for_gc() {
+            Ok(heads) => heads.collect(),
+            Err(_) => self.operation.repo.view().heads().iter().cloned().collect(),
+        };
+        self.evaluate_revset_expr(Rc::new(RevsetExpression::WithinVisibility {
+            candidates: revset_expr,
+            visible_heads,
+        }))
+    }
+
     pub fn evaluate_immutable(&self) -> Result<Box<dyn Revset + '_>> {
         let mut diagnostics = RevsetDiagnostics::new(); // XXX pass this down, then include it in the Result
         let expr =
@@ -352,6 +573,12 @@ impl WorkspaceSession<'_> {
         self.resolve_single(revset)
     }
 
+    /// for user-entered revsets which must name exactly one revision
+    pub fn resolve_single_str(&self, revset_str: &str) -> Result<Commit, RevsetError> {
+        let revset = self.evaluate_revset_str(revset_str)?;
+        self.resolve_single(revset)
+    }
+
     pub fn resolve_multiple<'op, 'set: 'op, T: AsRef<dyn Revset + 'set>>(
         &'op self,
         revset: T,
@@ -389,7 +616,46 @@ impl WorkspaceSession<'_> {
      *************************************************************/
 
     pub fn parse_context<'a>(&'a self) -> RevsetParseContext<'a> {
-        self.data.parse_context(self.workspace.workspace_id())
+        self.data.parse_context(self.followed_id())
+    }
+
+    pub fn now(&self) -> chrono::DateTime<chrono::Local> {
+        self.data.now()
+    }
+
+    /// equivalent to `jj log -T`, including any template-aliases from the config
+    pub fn parse_commit_template(
+        &self,
+        template_text: &str,
+    ) -> Result<TemplateRenderer<'_, Commit>> {
+        let mut aliases_map = TemplateAliasesMap::new();
+        if let Ok(table) = self.data.settings.config().get_table("template-aliases") {
+            for (decl, value) in table.into_iter().sorted_by(|a, b| a.0.cmp(&b.0)) {
+                aliases_map.insert(&decl, value.into_string()?)?;
+            }
+        }
+
+        let mut diagnostics = RevsetDiagnostics::new();
+        let immutable_heads =
+            revset_util::parse_immutable_heads_expression(&mut diagnostics, &self.parse_context())?;
+        let language = CommitTemplateLanguage::new(
+            self.operation.repo.as_ref(),
+            &self.data.path_converter,
+            self.followed_id(),
+            self.parse_context(),
+            &self.operation.prefix_context,
+            immutable_heads.ancestors(),
+            &[] as &[Box<dyn CommitTemplateLanguageExtension>],
+        );
+
+        let mut diagnostics = TemplateDiagnostics::new();
+        Ok(jj_cli::template_builder::parse(
+            &language,
+            &mut diagnostics,
+            template_text,
+            &aliases_map,
+            CommitTemplateLanguage::wrap_commit,
+        )?)
     }
 
     // the prefix context caches this itself, but the way it does so is not convenient for us - you need a fallible method and the &dyn Repo
@@ -414,6 +680,62 @@ impl WorkspaceSession<'_> {
             .get_or_init(|| Rc::new(build_ref_index(self.operation.repo.as_ref())))
     }
 
+    /// annotations aren't versioned, but are reread for each operation to pick up changes from other windows
+    pub fn labels(&self) -> &Rc<BTreeMap<String, messages::RevLabel>> {
+        self.operation
+            .labels
+            .get_or_init(|| match Annotations::load(self.workspace.repo_path()) {
+                Ok(annotations) => Rc::new(annotations.labels),
+                Err(err) => {
+                    log::warn!("failed to load labels: {err:#}");
+                    Rc::default()
+                }
+            })
+    }
+
+    /// for each mutable commit, the local bookmarks whose push would include it
+    pub fn push_targets(&self) -> &Rc<HashMap<CommitId, Vec<String>>> {
+        self.operation
+            .push_targets
+            .get_or_init(|| match self.build_push_targets() {
+                Ok(targets) => Rc::new(targets),
+                Err(err) => {
+                    log::warn!("failed to compute push targets: {err:#}");
+                    Rc::default()
+                }
+            })
+    }
+
+    fn build_push_targets(&self) -> Result<HashMap<CommitId, Vec<String>>> {
+        let mut targets: HashMap<CommitId, Vec<String>> = HashMap::new();
+
+        for (branch_name, branch_target) in self.operation.repo.view().bookmarks() {
+            let Some(head_id) = branch_target.local_target.as_normal() else {
+                continue;
+            };
+
+            // a push sends everything the remotes don't already have, which excludes immutable history
+            let revset = self.evaluate_revset_str(&format!(
+                "::{} ~ ::(immutable_heads() | remote_bookmarks())",
+                head_id.hex()
+            ))?;
+            for commit_id in revset.iter() {
+                targets
+                    .entry(commit_id?)
+                    .or_default()
+                    .push(branch_name.to_owned());
+            }
+        }
+
+        Ok(targets)
+    }
+
+    pub fn update_annotations(&mut self, f: impl FnOnce(&mut Annotations)) -> Result<()> {
+        Annotations::update(self.workspace.repo_path(), f)?;
+        self.operation.labels = OnceCell::default();
+        Ok(())
+    }
+
     /************************************
      * IPC-message formatting functions *
      ************************************/
@@ -431,7 +753,7 @@ impl WorkspaceSession<'_> {
             None => vec![],
         };
 
-        let default_query = self.data.settings.default_revset();
+        let default_query = self.data.settings.query_default();
 
         let latest_query = self
             .session
@@ -440,17 +762,168 @@ impl WorkspaceSession<'_> {
             .unwrap_or_else(|| &default_query)
             .clone();
 
+        let query_choices = self.format_query_choices(&default_query, &git_remotes);
+
+        let trunk = match self.resolve_trunk()? {
+            Some(commit) => Some(self.format_header(&commit, None)?),
+            None => None,
+        };
+
         Ok(messages::RepoConfig::Workspace {
             absolute_path,
             git_remotes,
             default_query,
             latest_query,
+            query_choices,
             status: self.format_status(),
             theme_override: self.data.settings.ui_theme_override(),
             mark_unpushed_branches: self.data.settings.ui_mark_unpushed_bookmarks(),
+            latest_selection: self.format_restored_selection(),
+            trunk,
+            config_diagnostics: read_config_diagnostics(self.workspace.repo_path())?,
+            missing_identity: self.data.settings.user_name().is_empty()
+                || self.data.settings.user_email().is_empty(),
+            check_updates: self.data.settings.ui_check_updates(),
+            has_external_merge_tool: matches!(self.external_merge_editor(), Ok(Some(_))),
+            safe_mode: self.session.safe_mode,
+            workspace_name: self.followed_id().as_str().to_owned(),
+            workspaces: self
+                .repo()
+                .view()
+                .wc_commit_ids()
+                .iter()
+                .sorted_by(|a, b| a.0.cmp(b.0))
+                .map(|(id, commit_id)| messages::WorkspaceHead {
+                    name: id.as_str().to_owned(),
+                    working_copy: self.format_commit_id(commit_id),
+                })
+                .collect(),
+            interrupted_mutation: self.session.interrupted_mutation.as_ref().map(|journal| {
+                messages::InterruptedMutation {
+                    mutation: journal
+                        .mutation
+                        .rsplit("::")
+                        .next()
+                        .unwrap_or(&journal.mutation)
+                        .to_owned(),
+                    op_id: journal.op_id.clone(),
+                }
+            }),
+            backend: messages::RepoBackend {
+                name: self.backend_name().to_owned(),
+                supports_git: self.operation.git_backend().is_some(),
+            },
+            schema_version: messages::SCHEMA_VERSION,
         })
     }
 
+    /// in a large repo, the default query is limited to recent revisions until the full one is asked for
+    pub fn narrow_query(&self, revset_str: &str) -> Option<String> {
+        if !self.is_large || revset_str != self.data.settings.query_default() {
+            return None;
+        }
+        let narrowing = self.data.settings.query_large_repo_narrowing()?;
+        Some(format!("({revset_str}) & ({narrowing})"))
+    }
+
+    /// trunk() as configured, falling back to a conventional local bookmark when the default
+    /// alias finds no remote - None means there is nothing for trunk-relative features to anchor to
+    pub fn resolve_trunk(&self) -> Result<Option<Commit>> {
+        let store = self.operation.repo.store();
+
+        // an unparseable user override is reported by the log query, not here
+        if let Ok(revset) = self.evaluate_revset_str(&format!("present({TRUNK_ALIAS})")) {
+            if let Some(commit) = revset.iter().commits(store).next().transpose()? {
+                if commit.id() != store.root_commit_id() {
+                    return Ok(Some(commit));
+                }
+            }
+        }
+
+        for name in TRUNK_BOOKMARKS {
+            if let Some(id) = self.view().get_local_bookmark(name).as_normal() {
+                return Ok(Some(self.get_commit(id)?));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // the saved change may have been abandoned or rewritten out of existence in the meantime
+    fn format_restored_selection(&self) -> Option<messages::RevHeader> {
+        let change_hex = self.session.restored_selection.as_ref()?;
+        let commit = self
+            .evaluate_revset_str(change_hex)
+            .ok()?
+            .iter()
+            .commits(self.operation.repo.store())
+            .next()?
+            .ok()?;
+        self.format_header(&commit, None).ok()
+    }
+
+    /// presets followed by repo-specific queries for each remote and tracked bookmark
+    fn format_query_choices(
+        &self,
+        default_query: &str,
+        git_remotes: &[String],
+    ) -> Vec<messages::QueryChoice> {
+        let choice = |label: &str, value: String| messages::QueryChoice {
+            label: label.to_owned(),
+            value,
+        };
+
+        let mut choices = vec![
+            choice("Default", default_query.to_owned()),
+            choice(
+                "Tracked Bookmarks",
+                "@ | ancestors(bookmarks(), 5)".to_owned(),
+            ),
+            choice(
+                "Remote Bookmarks",
+                "@ | ancestors(remote_bookmarks(), 5)".to_owned(),
+            ),
+            choice("All Revisions", "all()".to_owned()),
+        ];
+
+        for remote_name in git_remotes {
+            choices.push(choice(
+                &format!("Remote: {remote_name}"),
+                format!(
+                    "@ | ancestors(remote_bookmarks(remote=exact:{}), 5)",
+                    quote_revset_string(remote_name)
+                ),
+            ));
+        }
+
+        let mut bookmark_choices = 0;
+        for (branch_name, branch_target) in self.operation.repo.view().bookmarks() {
+            if !branch_target.local_target.is_present() {
+                continue;
+            }
+
+            for &(remote_name, remote_ref) in &branch_target.remote_refs {
+                if remote_name == REMOTE_NAME_FOR_LOCAL_GIT_REPO || !remote_ref.is_tracking() {
+                    continue;
+                }
+
+                if bookmark_choices == MAX_BOOKMARK_QUERY_CHOICES {
+                    return choices;
+                }
+                bookmark_choices += 1;
+
+                let query = format!(
+                    "{}@{}..@",
+                    quote_revset_symbol(branch_name),
+                    quote_revset_symbol(remote_name)
+                );
+                choices.push(choice(&query, query.clone()));
+            }
+        }
+
+        choices
+    }
+
     pub fn format_status(&self) -> messages::RepoStatus {
         messages::RepoStatus {
             operation_description: self
@@ -462,13 +935,54 @@ impl WorkspaceSession<'_> {
                 .description
                 .clone(),
             working_copy: self.format_commit_id(&self.operation.wc_id),
+            historical_operation: self
+                .is_historical
+                .then(|| self.operation.repo.op_id().hex()[..12].to_owned()),
+            git_head: self.format_git_head(),
+        }
+    }
+
+    // git commands can move HEAD without telling jj, so this is read from git itself; failures
+    // just hide the indicator, as the status is needed even if the git repo is broken
+    fn format_git_head(&self) -> Option<messages::GitHead> {
+        if !self.is_colocated {
+            return None;
+        }
+
+        let git_repo = self.git_repo().ok()??;
+        let head = match git_repo.head() {
+            Ok(head) => head,
+            Err(err) if err.code() == git2::ErrorCode::UnbornBranch => {
+                let head = git_repo.find_reference("HEAD").ok()?;
+                let name = head.symbolic_target()?;
+                return Some(messages::GitHead::Unborn {
+                    name: name.strip_prefix("refs/heads/").unwrap_or(name).to_owned(),
+                });
+            }
+            Err(_) => return None,
+        };
+
+        let commit_id = CommitId::from_bytes(head.target()?.as_bytes());
+        let is_synced = self.view().git_head().as_normal() == Some(&commit_id);
+        let commit = self.format_commit_id(&commit_id);
+
+        if git_repo.head_detached().ok()? {
+            Some(messages::GitHead::Detached { commit, is_synced })
+        } else {
+            Some(messages::GitHead::Branch {
+                name: head.shorthand()?.to_owned(),
+                commit,
+                is_synced,
+            })
         }
     }
 
     pub fn format_commit_id(&self, id: &CommitId) -> messages::CommitId {
-        let prefix_len = self
-            .prefix_index()
-            .shortest_commit_prefix_len(self.operation.repo.as_ref(), id);
+        let prefix_len = self.data.cache().prefixes().commit_prefix_len(
+            self.operation.repo.as_ref(),
+            id,
+            || self.prefix_index(),
+        );
 
         let hex = id.hex();
         let mut prefix = hex.clone();
@@ -477,9 +991,11 @@ impl WorkspaceSession<'_> {
     }
 
     pub fn format_change_id(&self, id: &ChangeId) -> messages::ChangeId {
-        let prefix_len = self
-            .prefix_index()
-            .shortest_change_prefix_len(self.operation.repo.as_ref(), id);
+        let prefix_len = self.data.cache().prefixes().change_prefix_len(
+            self.operation.repo.as_ref(),
+            id,
+            || self.prefix_index(),
+        );
 
         let hex = &id.reverse_hex();
         let mut prefix = hex.clone();
@@ -516,16 +1032,107 @@ impl WorkspaceSession<'_> {
             author: commit.author().try_into()?,
             has_conflict: commit.has_conflict()?,
             is_working_copy: *commit.id() == self.operation.wc_id,
+            working_copy_of: self
+                .repo()
+                .view()
+                .wc_commit_ids()
+                .iter()
+                .filter(|(_, wc_id)| *wc_id == commit.id())
+                .map(|(id, _)| id.as_str().to_owned())
+                .sorted()
+                .collect(),
             is_immutable,
+            is_signed: commit.is_signed(),
+            is_hidden: self.is_hidden(commit),
             refs: branches,
             parent_ids: commit
                 .parent_ids()
                 .iter()
                 .map(|commit_id| self.format_commit_id(commit_id))
                 .collect(),
+            label: self
+                .labels()
+                .get(&commit.change_id().reverse_hex())
+                .cloned(),
+            age: self.format_age(commit),
         })
     }
 
+    fn format_age(&self, commit: &Commit) -> messages::RevAge {
+        const MILLIS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+        let committed = commit.committer().timestamp.timestamp.0;
+        let age = self.now().timestamp_millis() - committed;
+
+        let [today, week, month] = self.data.age_buckets;
+        if age < today * MILLIS_PER_DAY {
+            messages::RevAge::Today
+        } else if age < week * MILLIS_PER_DAY {
+            messages::RevAge::Week
+        } else if age < month * MILLIS_PER_DAY {
+            messages::RevAge::Month
+        } else {
+            messages::RevAge::Older
+        }
+    }
+
+    /// resolves a change id prefix against every indexed commit, returning those which are hidden
+    pub fn resolve_hidden_change(&self, symbol: &str) -> Result<Vec<Commit>> {
+        let Some(prefix) = to_forward_hex(symbol).as_deref().and_then(HexPrefix::new) else {
+            return Ok(vec![]);
+        };
+
+        let all_heads: Vec<CommitId> = match self.operation.repo.index().all_heads_for_gc() {
+            Ok(heads) => heads.collect(),
+            Err(_) => return Ok(vec![]),
+        };
+        let change_index = self
+            .operation
+            .repo
+            .readonly_index()
+            .change_id_index(&mut all_heads.iter());
+
+        match change_index.resolve_prefix(&prefix) {
+            PrefixResolution::SingleMatch(ids) => {
+                let mut commits = ids
+                    .iter()
+                    .map(|id| self.get_commit(id))
+                    .collect::<Result<Vec<_>>>()?;
+                commits.retain(|commit| self.is_hidden(commit));
+                commits.sort_by_key(|commit| Reverse(commit.committer().timestamp.timestamp));
+                Ok(commits)
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// every indexed commit with the change id, including versions which have been rewritten or abandoned
+    pub fn resolve_change_versions(&self, change_id: &ChangeId) -> Result<Vec<CommitId>> {
+        let all_heads: Vec<CommitId> = match self.operation.repo.index().all_heads_for_gc() {
+            Ok(heads) => heads.collect(),
+            Err(_) => self.operation.repo.view().heads().iter().cloned().collect(),
+        };
+        let change_index = self
+            .operation
+            .repo
+            .readonly_index()
+            .change_id_index(&mut all_heads.iter());
+
+        let prefix = HexPrefix::new(&change_id.hex()).ok_or(anyhow!("Invalid change id"))?;
+        match change_index.resolve_prefix(&prefix) {
+            PrefixResolution::SingleMatch(ids) => Ok(ids),
+            _ => Ok(vec![]),
+        }
+    }
+
+    // same definition as jj's `hidden` template keyword
+    pub fn is_hidden(&self, commit: &Commit) -> bool {
+        !self
+            .operation
+            .repo
+            .resolve_change_id(commit.change_id())
+            .is_some_and(|ids| ids.contains(commit.id()))
+    }
+
     pub fn format_path<T: AsRef<RepoPath>>(&self, repo_path: T) -> Result<messages::TreePath> {
         let base_path = self.workspace.workspace_root();
         let relative_path =
@@ -536,6 +1143,138 @@ impl WorkspaceSession<'_> {
         })
     }
 
+    /// whether a snapshot would skip this path, following the same .gitignore files
+    pub fn is_ignored(&self, repo_path: &RepoPath) -> Result<bool> {
+        let workspace_root = self.workspace.workspace_root();
+        let mut dirs: Vec<_> = iter::successors(repo_path.parent(), |dir| dir.parent()).collect();
+        dirs.reverse();
+
+        let mut ignores = self.operation.base_ignores()?;
+        for dir in dirs {
+            ignores = ignores.chain_with_file(
+                &dir.to_internal_dir_string(),
+                dir.to_fs_path(workspace_root)?.join(".gitignore"),
+            )?;
+        }
+
+        Ok(ignores.matches(repo_path.as_internal_file_string()))
+    }
+
+    /// ui.merge-editor, unless it's unset or the builtin editor, which needs a terminal
+    pub fn external_merge_editor(&self) -> Result<Option<MergeEditor>> {
+        if self.session.safe_mode {
+            return Ok(None);
+        }
+
+        let config = self.data.settings.config();
+        let Some(args) = config
+            .get::<CommandNameAndArgs>("ui.merge-editor")
+            .optional()?
+        else {
+            return Ok(None);
+        };
+        if matches!(&args, CommandNameAndArgs::String(name) if name == ":builtin") {
+            return Ok(None);
+        }
+
+        let ui = Ui::with_config(config).map_err(|err| anyhow!("{err:?}"))?;
+        Ok(Some(MergeEditor::from_settings(&ui, &self.data.settings)?))
+    }
+
+    /// protected bookmarks need confirmation before they can be deleted or rewound
+    pub fn is_protected_bookmark(&self, branch_name: &str) -> Result<bool> {
+        for pattern in self.data.settings.git_protected_bookmarks() {
+            if StringPattern::parse(&pattern)?.matches(branch_name) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// the file which holds settings for a writable config layer
+    pub fn config_path(&self, scope: &ConfigSource) -> Result<PathBuf> {
+        match scope {
+            ConfigSource::User => jj_cli::config::new_config_path()
+                .map_err(|err| anyhow!(err))
+                .and_then(|path| path.ok_or(anyhow!("No repo config path found to edit"))),
+            ConfigSource::Repo => Ok(self.workspace.repo_path().join("config.toml")),
+            _ => Err(anyhow!("Can't get path for config source {scope:?}")),
+        }
+    }
+
+    pub fn reload_config(&mut self) -> Result<()> {
+        (self.data.settings, self.data.aliases_map) =
+            self.session.read_settings(self.workspace.repo_path())?;
+        self.data.age_buckets = self.data.settings.query_age_buckets();
+        Ok(())
+    }
+
+    fn workspace_key(&self) -> String {
+        messages::DisplayPath::from(self.workspace.workspace_root()).0
+    }
+
+    /// applies the query and selection saved when this workspace was last closed
+    pub fn restore_workspace_state(&mut self) {
+        let key = self.workspace_key();
+        let Some(state) = self
+            .data
+            .settings
+            .ui_workspace_states()
+            .into_iter()
+            .find(|state| state.path == key)
+        else {
+            return;
+        };
+
+        if state.query.is_some() {
+            self.session.latest_query = state.query;
+        }
+        self.session.restored_selection = state.selection;
+    }
+
+    /// records the current query and selection, most recent workspace first
+    pub fn save_workspace_state(
+        &mut self,
+        scope: &ConfigSource,
+        selection: Option<String>,
+    ) -> Result<()> {
+        let key = self.workspace_key();
+
+        let mut states = self.data.settings.ui_workspace_states();
+        states.retain(|state| state.path != key);
+        states.insert(
+            0,
+            SavedWorkspace {
+                path: key,
+                query: self.session.latest_query.clone(),
+                selection,
+            },
+        );
+        states.truncate(MAX_SAVED_WORKSPACES);
+
+        let toml_array: toml_edit::Array = states
+            .into_iter()
+            .map(|state| {
+                let mut table = toml_edit::InlineTable::new();
+                table.insert("path", state.path.into());
+                if let Some(query) = state.query {
+                    table.insert("query", query.into());
+                }
+                if let Some(selection) = state.selection {
+                    table.insert("selection", selection.into());
+                }
+                toml_edit::Value::InlineTable(table)
+            })
+            .collect();
+
+        let name: ConfigNamePathBuf = ["gg", "ui", "workspace-states"].into_iter().collect();
+        let path = self.config_path(scope)?;
+        write_config_value_to_file(&name, toml_edit::Value::Array(toml_array), &path)
+            .map_err(|err| anyhow!("{err:?}"))?;
+
+        self.reload_config()
+    }
+
     pub fn check_immutable(&self, ids: impl IntoIterator<Item = CommitId>) -> Result<bool> {
         let check_revset = RevsetExpression::commits(ids.into_iter().collect());
 
@@ -552,6 +1291,18 @@ impl WorkspaceSession<'_> {
         Ok(first.is_some())
     }
 
+    /// the number of revisions a rewrite of these would touch, including their descendants
+    pub fn count_descendants(&self, ids: impl IntoIterator<Item = CommitId>) -> Result<usize> {
+        let expr = RevsetExpression::commits(ids.into_iter().collect()).descendants();
+        let revset = self.evaluate_revset_expr(expr)?;
+        let mut count = 0;
+        for id in revset.iter() {
+            id?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
     /*********************************************************************
      * Transaction functions - these are very similar to cli_util        *
      * Ideally in future the code can be extracted to not depend on TUI. *
@@ -559,19 +1310,77 @@ impl WorkspaceSession<'_> {
 
     pub fn start_transaction(&mut self) -> Result<Transaction> {
         self.import_and_snapshot(true)?;
-        Ok(self.operation.repo.start_transaction(&self.data.settings))
+        let mut tx = self.operation.repo.start_transaction(&self.data.settings);
+        self.annotate_transaction(&mut tx);
+        Ok(tx)
+    }
+
+    /// tags operations so that gg's changes can be distinguished in the op log
+    fn operation_tags(&self) -> Vec<(String, String)> {
+        let mut tags = Vec::new();
+        if !self.data.settings.operations_annotate() {
+            return tags;
+        }
+
+        tags.push((
+            TOOL_TAG.to_owned(),
+            format!("gg {}", env!("CARGO_PKG_VERSION")),
+        ));
+        if let Some(mutation_name) = &self.mutation_name {
+            tags.push((MUTATION_TAG.to_owned(), mutation_name.clone()));
+        }
+        if let Some(window_label) = &self.session.window_label {
+            tags.push((WINDOW_TAG.to_owned(), window_label.clone()));
+        }
+        tags
+    }
+
+    fn annotate_transaction(&self, tx: &mut Transaction) {
+        for (key, value) in self.operation_tags() {
+            tx.set_tag(key, value);
+        }
     }
 
     pub fn finish_transaction(
+        &mut self,
+        tx: Transaction,
+        description: impl Into<String>,
+    ) -> Result<Option<messages::RepoStatus>> {
+        self.finish_transaction_with(tx, description, true, true)
+    }
+
+    /// for changes to the working copy commit which already match the disk, such as untracking files
+    pub fn finish_transaction_without_checkout(
+        &mut self,
+        tx: Transaction,
+        description: impl Into<String>,
+    ) -> Result<Option<messages::RepoStatus>> {
+        self.finish_transaction_with(tx, description, false, true)
+    }
+
+    /// for operations which are worth recording even if they change nothing, such as checkpoints
+    pub fn finish_transaction_unconditionally(
+        &mut self,
+        tx: Transaction,
+        description: impl Into<String>,
+    ) -> Result<messages::RepoStatus> {
+        self.finish_transaction_with(tx, description, true, false)?
+            .ok_or(anyhow!("transaction was not committed"))
+    }
+
+    fn finish_transaction_with(
         &mut self,
         mut tx: Transaction,
         description: impl Into<String>,
+        checkout: bool,
+        require_changes: bool,
     ) -> Result<Option<messages::RepoStatus>> {
-        if !tx.repo().has_changes() {
+        if require_changes && !tx.repo().has_changes() {
             return Ok(None);
         }
 
         tx.repo_mut().rebase_descendants(&self.data.settings)?;
+        self.annotate_transaction(&mut tx);
 
         let old_repo = tx.base_repo().clone();
 
@@ -602,7 +1411,13 @@ impl WorkspaceSession<'_> {
 
         // XXX do this only if loaded at head, which is currently always true, but won't be once we have undo-redo
         if let Some(new_commit) = &maybe_new_wc_commit {
-            self.update_working_copy(maybe_old_wc_commit.as_ref(), new_commit)?;
+            if checkout {
+                self.update_working_copy(maybe_old_wc_commit.as_ref(), new_commit)?;
+            } else {
+                let mut locked_ws = self.workspace.start_working_copy_mutation()?;
+                locked_ws.locked_wc().reset(new_commit)?;
+                locked_ws.finish(self.operation.repo.op_id().clone())?;
+            }
         }
 
         Ok(Some(self.format_status()))
@@ -647,6 +1462,7 @@ impl WorkspaceSession<'_> {
         };
 
         let base_ignores = self.operation.base_ignores()?;
+        let operation_tags = self.operation_tags();
 
         // Compare working-copy tree and operation with repo's, and reload as needed.
         let mut locked_ws = self.workspace.start_working_copy_mutation()?;
@@ -683,7 +1499,11 @@ impl WorkspaceSession<'_> {
 
         let new_tree_id = locked_ws.locked_wc().snapshot(&SnapshotOptions {
             base_ignores,
-            fsmonitor_settings: self.data.settings.fsmonitor_settings()?,
+            fsmonitor_settings: if self.session.safe_mode {
+                FsmonitorSettings::None
+            } else {
+                self.data.settings.fsmonitor_settings()?
+            },
             progress: None,
             max_new_file_size: self.data.settings.max_new_file_size()?,
             start_tracking_matcher: &EverythingMatcher,
@@ -706,6 +1526,9 @@ impl WorkspaceSession<'_> {
                 git::export_refs(mut_repo)?;
             }
 
+            for (key, value) in operation_tags {
+                tx.set_tag(key, value);
+            }
             self.operation = SessionOperation::new(
                 &workspace_id,
                 &self.data,
@@ -763,6 +1586,7 @@ impl WorkspaceSession<'_> {
                 &new_git_head_commit,
             )?;
 
+            self.annotate_transaction(&mut tx);
             let mut locked_ws = self.workspace.start_working_copy_mutation()?;
 
             locked_ws.locked_wc().reset(&new_git_head_commit)?;
@@ -860,26 +1684,92 @@ impl WorkspaceSession<'_> {
 
         Ok(rebased_commit_ids)
     }
+
+    /// applies a regex to each line of the matching text files in a tree, skipping conflicts and binaries
+    pub fn find_line_replacements(
+        &self,
+        tree: &MergedTree,
+        matcher: &dyn Matcher,
+        regex: &Regex,
+        replacement: &str,
+    ) -> Result<Vec<FileReplacement>> {
+        let mut files = Vec::new();
+        for (path, value) in tree.entries_matching(matcher) {
+            let Some(Some(TreeValue::File { id, executable })) = value?.as_resolved().cloned()
+            else {
+                continue;
+            };
+
+            let mut content = Vec::new();
+            self.operation
+                .repo
+                .store()
+                .read_file(&path, &id)?
+                .read_to_end(&mut content)?;
+            let Ok(content) = String::from_utf8(content) else {
+                continue;
+            };
+
+            let mut new_content = String::with_capacity(content.len());
+            let mut lines = Vec::new();
+            for (index, line) in content.split_inclusive('\n').enumerate() {
+                let text = line.trim_end_matches(['\r', '\n']);
+                let ending = &line[text.len()..];
+                let new_text = regex.replace_all(text, replacement);
+                if new_text != text {
+                    lines.push((index + 1, text.to_owned(), new_text.to_string()));
+                }
+                new_content.push_str(&new_text);
+                new_content.push_str(ending);
+            }
+
+            if !lines.is_empty() {
+                files.push(FileReplacement {
+                    path,
+                    executable,
+                    new_content,
+                    lines,
+                });
+            }
+        }
+        Ok(files)
+    }
+}
+
+pub struct FileReplacement {
+    pub path: RepoPathBuf,
+    pub executable: bool,
+    pub new_content: String,
+    /// 1-based line number, old text, new text
+    pub lines: Vec<(usize, String, String)>,
 }
 
 impl WorkspaceData {
-    // unfortunately not cached as it borrows from everything
-    fn parse_context<'a>(&'a self, id: &'a WorkspaceId) -> RevsetParseContext<'a> {
-        let workspace_context = RevsetWorkspaceContext {
-            path_converter: &self.path_converter,
-            workspace_id: id,
-        };
-        let now = if let Some(timestamp) = self.settings.commit_timestamp() {
+    pub fn cache(&self) -> MutexGuard<'_, DerivedCache> {
+        self.cache.lock().expect("cache mutex poisoned")
+    }
+
+    /// the configured commit timestamp, if any, so that relative dates agree with new commits
+    fn now(&self) -> chrono::DateTime<chrono::Local> {
+        if let Some(timestamp) = self.settings.commit_timestamp() {
             chrono::Local
                 .timestamp_millis_opt(timestamp.timestamp.0)
                 .unwrap()
         } else {
             chrono::Local::now()
+        }
+    }
+
+    // unfortunately not cached as it borrows from everything
+    fn parse_context<'a>(&'a self, id: &'a WorkspaceId) -> RevsetParseContext<'a> {
+        let workspace_context = RevsetWorkspaceContext {
+            path_converter: &self.path_converter,
+            workspace_id: id,
         };
         RevsetParseContext::new(
             &self.aliases_map,
             self.settings.user_email(),
-            now.into(),
+            self.now().into(),
             &self.extensions,
             Some(workspace_context),
         )
@@ -917,6 +1807,8 @@ impl SessionOperation {
             repo,
             wc_id,
             ref_index: OnceCell::default(),
+            labels: OnceCell::default(),
+            push_targets: OnceCell::default(),
             prefix_context,
         }
     }
@@ -982,6 +1874,24 @@ fn parse_revset(
     Ok(expression)
 }
 
+fn quote_revset_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// bare symbols can't contain revset operators, so anything unusual gets quoted
+fn quote_revset_symbol(value: &str) -> String {
+    let is_plain = !value.is_empty()
+        && !value.starts_with('-')
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '/');
+    if is_plain {
+        value.to_owned()
+    } else {
+        quote_revset_string(value)
+    }
+}
+
 /*************************/
 /* from commit_templater */
 /*************************/
@@ -1003,7 +1913,7 @@ impl RefIndex {
         }
     }
 
-    fn get(&self, id: &CommitId) -> &[messages::StoreRef] {
+    pub fn get(&self, id: &CommitId) -> &[messages::StoreRef] {
         if let Some(names) = self.index.get(id) {
             names
         } else {
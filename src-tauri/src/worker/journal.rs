@@ -0,0 +1,138 @@
+//! A record of the mutation in progress, written before it starts and removed when it returns. If
+//! gg is killed in between, the record survives and the next session can tell the user about it.
+//! Each window keeps its own record, so that windows and processes sharing a repo don't clear or
+//! report each other's mutations.
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+const DIR_NAME: &str = "gg-journals";
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MutationJournal {
+    /// the mutation's describe()
+    pub mutation: String,
+    /// the operation which the mutation started from
+    pub op_id: String,
+    /// distinguishes another running process's mutation from one that was interrupted
+    pub process_id: u32,
+}
+
+impl MutationJournal {
+    pub fn begin(
+        workspace_root: &Path,
+        window_label: Option<&str>,
+        mutation: String,
+        op_id: String,
+    ) -> Result<()> {
+        let dir = dir_path(workspace_root);
+        fs::create_dir_all(&dir).with_context(|| format!("create {}", dir.display()))?;
+
+        let path = file_path(workspace_root, window_label);
+        let journal = MutationJournal {
+            mutation,
+            op_id,
+            process_id: process::id(),
+        };
+        fs::write(&path, serde_json::to_vec_pretty(&journal)?)
+            .with_context(|| format!("write {}", path.display()))
+    }
+
+    /// ending without a journal is not an error
+    pub fn end(workspace_root: &Path, window_label: Option<&str>) -> Result<()> {
+        remove(&file_path(workspace_root, window_label))
+    }
+
+    /// removes and returns a journal left behind by a process which is no longer running
+    pub fn take_interrupted(workspace_root: &Path) -> Result<Option<MutationJournal>> {
+        let dir = dir_path(workspace_root);
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err).with_context(|| format!("read {}", dir.display())),
+        };
+
+        let mut interrupted = None;
+        for entry in entries {
+            let path = entry?.path();
+            let contents = match fs::read(&path) {
+                Ok(contents) => contents,
+                Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                Err(err) => return Err(err).with_context(|| format!("read {}", path.display())),
+            };
+
+            // a torn write means the process died while starting the mutation, before it did anything
+            let Ok(journal) = serde_json::from_slice::<MutationJournal>(&contents) else {
+                log::warn!("discarding unreadable {}", path.display());
+                remove(&path)?;
+                continue;
+            };
+
+            if journal.process_id == process::id() || is_running(journal.process_id) {
+                continue;
+            }
+
+            remove(&path)?;
+            if interrupted.is_none() {
+                interrupted = Some(journal);
+            } else {
+                log::warn!("another mutation was interrupted: {}", journal.mutation);
+            }
+        }
+
+        Ok(interrupted)
+    }
+}
+
+fn dir_path(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(".jj").join(DIR_NAME)
+}
+
+fn file_path(workspace_root: &Path, window_label: Option<&str>) -> PathBuf {
+    let window_label: String = window_label
+        .unwrap_or("main")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    dir_path(workspace_root).join(format!("{}-{window_label}.json", process::id()))
+}
+
+fn remove(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err).with_context(|| format!("remove {}", path.display())),
+    }
+}
+
+// when unsure, a process is assumed to be running, so that its journal is left alone
+#[cfg(target_os = "linux")]
+fn is_running(process_id: u32) -> bool {
+    Path::new("/proc").join(process_id.to_string()).exists()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn is_running(process_id: u32) -> bool {
+    process::Command::new("kill")
+        .args(["-0", &process_id.to_string()])
+        .stderr(process::Stdio::null())
+        .status()
+        .map_or(true, |status| status.success())
+}
+
+#[cfg(windows)]
+fn is_running(process_id: u32) -> bool {
+    process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {process_id}"), "/NH", "/FO", "CSV"])
+        .output()
+        .map_or(true, |output| {
+            String::from_utf8_lossy(&output.stdout).contains(&format!("\"{process_id}\""))
+        })
+}
@@ -1,25 +1,52 @@
 //! Worker per window, owning repo data (jj-lib is not thread-safe)
 //! The worker thread is a state machine, running different handle functions based on loaded data
 
+mod annotations;
+mod bundles;
+mod cache;
+mod clock;
+mod colocation;
+mod descriptions;
+mod difftool;
+mod drafts;
+mod fetches;
+mod fix;
+mod git_env;
 mod gui_util;
+mod journal;
 mod mutations;
+mod prefixes;
 mod queries;
+mod readers;
+mod replay;
+mod sample;
 mod session;
+mod staging;
 #[cfg(all(test, not(feature = "ts-rs")))]
 mod tests;
+mod trace;
 
 use std::{
+    cell::RefCell,
     env::{self, VarError},
     fmt::Debug,
-    fs,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
 };
 
 use anyhow::{anyhow, Error, Result};
-use jj_lib::{git::RemoteCallbacks, repo::MutableRepo};
+use jj_lib::{git::RemoteCallbacks, op_store::WorkspaceId, repo::MutableRepo};
 
 use crate::messages;
+pub use bundles::{PasteBundle, RevisionBundle};
+pub use clock::FixedClock;
+pub use git_env::redact;
 use gui_util::WorkspaceSession;
+pub use queries::query_path_info;
+pub use readers::ReaderPool;
+#[cfg(test)]
+pub use replay::{load_recording, replay, ReplayedEvent};
+pub use replay::{RecordedEvent, SessionRecorder};
 pub use session::{Session, SessionEvent};
 
 /// implemented by structured-change commands
@@ -28,6 +55,16 @@ pub trait Mutation: Debug {
         std::any::type_name::<Self>().to_owned()
     }
 
+    /// what would be lost, for mutations which should be confirmed when gg.ui.confirm-destructive is set
+    fn destructive_summary(&self, _ws: &WorkspaceSession) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// how many revisions would be rewritten or created, for mutations which should be confirmed above gg.operations.rewrite-limit
+    fn rewrite_count(&self, _ws: &WorkspaceSession) -> Result<Option<usize>> {
+        Ok(None)
+    }
+
     fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<messages::MutationResult>;
 
     #[cfg(test)]
@@ -48,6 +85,13 @@ pub trait WorkerCallbacks {
     ) -> Result<()>;
 
     fn select_remote(&self, choices: &[&str]) -> Option<String>;
+
+    /// None cancels the checkout
+    fn select_checkout_policy(&self, change: &str) -> Option<messages::CheckoutPolicy>;
+
+    fn workspace_missing(&self, absolute_path: &Path);
+
+    fn report_progress(&self, event: messages::ProgressEvent);
 }
 
 struct NoCallbacks;
@@ -64,14 +108,42 @@ impl WorkerCallbacks for NoCallbacks {
     fn select_remote(&self, choices: &[&str]) -> Option<String> {
         choices.get(0).map(|choice| choice.to_string())
     }
+
+    fn select_checkout_policy(&self, _change: &str) -> Option<messages::CheckoutPolicy> {
+        Some(messages::CheckoutPolicy::Keep)
+    }
+
+    fn workspace_missing(&self, _absolute_path: &Path) {}
+
+    fn report_progress(&self, _event: messages::ProgressEvent) {}
 }
 
 /// state that doesn't depend on jj-lib borrowings
 pub struct WorkerSession {
     pub force_log_page_size: Option<usize>,
+    pub force_changes_page_size: Option<usize>,
     pub latest_query: Option<String>,
+    /// what was actually run for latest_query, if it had to be narrowed
+    pub latest_narrowed_query: Option<String>,
+    pub restored_selection: Option<String>,
     pub callbacks: Box<dyn WorkerCallbacks>,
     pub working_directory: Option<PathBuf>,
+    pub window_label: Option<String>,
+    pub(crate) trace: RefCell<trace::EventTrace>,
+    pub(crate) staging: Option<staging::Staging>,
+    pub(crate) interrupted_mutation: Option<journal::MutationJournal>,
+    /// a repo layout change which has to wait until the workspace is released
+    pub(crate) colocation: Option<colocation::Colocation>,
+    /// answers pure queries off the worker thread, if set
+    pub readers: Option<Arc<ReaderPool>>,
+    /// makes new commits and operations reproducible, if set
+    pub clock: Option<FixedClock>,
+    /// ignore configured external tools and filesystem monitors
+    pub safe_mode: bool,
+    /// another workspace whose working copy @ refers to in queries; files still come from the open one
+    pub followed_workspace: Option<WorkspaceId>,
+    /// derived data belonging to another session's workspace, used instead of loading a copy
+    pub(crate) shared_cache: Option<Arc<Mutex<cache::DerivedCache>>>,
 }
 
 impl WorkerSession {
@@ -87,7 +159,7 @@ impl WorkerSession {
     pub fn get_cwd(&self) -> Result<PathBuf> {
         self.working_directory
             .as_ref()
-            .map(|cwd| Ok(fs::canonicalize(cwd.clone())?))
+            .map(|cwd| Ok(dunce::canonicalize(cwd)?))
             .or_else(|| match env::var("OWD") {
                 Ok(var) => Some(Ok(PathBuf::from(var))),
                 Err(VarError::NotPresent) => None,
@@ -101,9 +173,22 @@ impl Default for WorkerSession {
     fn default() -> Self {
         WorkerSession {
             force_log_page_size: None,
+            force_changes_page_size: None,
             latest_query: None,
+            latest_narrowed_query: None,
+            restored_selection: None,
             callbacks: Box::new(NoCallbacks),
             working_directory: None,
+            window_label: None,
+            trace: RefCell::new(trace::EventTrace::default()),
+            staging: None,
+            interrupted_mutation: None,
+            colocation: None,
+            readers: None,
+            clock: None,
+            safe_mode: false,
+            followed_workspace: None,
+            shared_cache: None,
         }
     }
 }
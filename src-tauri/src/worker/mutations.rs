@@ -1,32 +1,72 @@
-use std::fmt::Display;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+    fs::{self, OpenOptions},
+    io::{self, BufRead, BufReader, Read, Write},
+    iter,
+    process::{Command, Stdio},
+    sync::mpsc::{channel, Sender},
+    thread,
+};
 
 use anyhow::{anyhow, Context, Result};
-use indexmap::IndexMap;
+use config::ConfigError;
+use futures_util::StreamExt;
+use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools;
+use jj_cli::config::{
+    remove_config_value_from_file, write_config_value_to_file, ConfigNamePathBuf, ConfigSource,
+};
 use jj_lib::{
-    backend::{BackendError, CommitId},
+    backend::{BackendError, CommitId, FileId, Signature, Timestamp, TreeValue},
     commit::Commit,
-    git::{self, GitBranchPushTargets, REMOTE_NAME_FOR_LOCAL_GIT_REPO},
+    conflicts::{self, MaterializedTreeValue},
+    files::{self, MergeResult},
+    git::{self, GitBranchPushTargets, RefName, REMOTE_NAME_FOR_LOCAL_GIT_REPO},
+    gitignore::GitIgnoreFile,
     matchers::{EverythingMatcher, FilesMatcher, Matcher},
+    merge::Merge,
+    merged_tree::{MergedTreeBuilder, TreeDiffEntry},
     object_id::ObjectId,
-    op_store::{RefTarget, RemoteRef, RemoteRefState},
+    op_store::{RefTarget, RemoteRef, RemoteRefState, WorkspaceId},
     op_walk,
     refs::{self, BookmarkPushAction, BookmarkPushUpdate, LocalAndRemoteRef},
     repo::Repo,
-    repo_path::RepoPath,
-    revset::{self, RevsetIteratorExt},
+    repo_path::{RepoPath, RepoPathBuf},
+    revset::{self, RevsetAliasesMap, RevsetExpression, RevsetIteratorExt},
     rewrite,
     settings::UserSettings,
     str_util::StringPattern,
+    view::View,
 };
+use pollster::FutureExt;
+use regex::Regex;
 
-use super::{gui_util::WorkspaceSession, Mutation};
+use super::{
+    colocation::Colocation, fetches::FetchHistory, fix, git_env::GitEnvironment,
+    gui_util::WorkspaceSession, staging, Mutation,
+};
+use crate::config::{
+    GGSettings, CHECKPOINT_TAG, DEFAULT_QUERY_KEY, REVSET_ALIASES_KEY, TRUNK_ALIAS,
+};
 use crate::messages::{
-    AbandonRevisions, BackoutRevisions, CheckoutRevision, CopyChanges, CreateRef, CreateRevision,
-    DeleteRef, DescribeRevision, DuplicateRevisions, GitFetch, GitPush, InsertRevision,
-    MoveChanges, MoveRef, MoveRevision, MoveSource, MutationResult, RenameBranch, StoreRef,
-    TrackBranch, TreePath, UndoOperation, UntrackBranch,
+    AbandonRevisions, AddIgnorePattern, AddParentToMerge, BackoutRevisions, ChangeId,
+    CheckoutPolicy, CheckoutRevision, CheckpointWorkingCopy, CleanupBookmarks, ColocateRepository,
+    CommitStaged, ConfigScope, CopyChanges, CreateFile, CreateMergeOf, CreateRef, CreateRevision,
+    DeletePaths, DeleteRef, DescribeRevision, DescribeRevisions, DescriptionEdit,
+    DuplicateRevisions, FetchFromPath, FixRevisions, FollowWorkspace, GitFetch, GitPush,
+    GraftRevisions, HideRevisions, InsertRevision, MergeRevisions, MoveChanges, MoveRef,
+    MoveRevision, MoveSource, MutationResult, ParallelizeRevisions, ProgressEvent, RebaseAction,
+    RemoveParentFromMerge, RemoveRevsetAlias, RenameBranch, RenamePath, ResolveConflict,
+    ResolveWithMergeTool, RestoredChange, RevId, RevLabel, RewritePaths, RunJjCommand,
+    RunRebasePlan, SetAuthor, SetDefaultQuery, SetLabel, SetRevsetAlias, SetTrunk, SetUserIdentity,
+    SplitRevision, StoreRef, SwapParents, TrackBranch, TreePath, UndoOperation, UnhideRevisions,
+    UntrackBranch,
 };
+use crate::paths;
+
+// global options which would point the command at another repo or operation
+const RETARGETING_ARGS: &[&str] = &["-R", "--repository", "--at-op", "--at-operation"];
 
 macro_rules! precondition {
     ($($args:tt)*) => {
@@ -34,7 +74,28 @@ macro_rules! precondition {
     }
 }
 
+macro_rules! protected {
+    ($($args:tt)*) => {
+        return Ok(MutationResult::OverrideRequired { summary: format!($($args)*) })
+    }
+}
+
 impl Mutation for AbandonRevisions {
+    fn destructive_summary(&self, _ws: &WorkspaceSession) -> Result<Option<String>> {
+        Ok(Some(match self.ids.as_slice() {
+            [id] => format!("Abandon revision {}?", id.prefix),
+            ids => format!("Abandon {} revisions?", ids.len()),
+        }))
+    }
+
+    fn rewrite_count(&self, ws: &WorkspaceSession) -> Result<Option<usize>> {
+        let ids = self
+            .ids
+            .iter()
+            .map(|id| CommitId::try_from_hex(&id.hex).expect("frontend-validated id"));
+        Ok(Some(ws.count_descendants(ids)?))
+    }
+
     fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
         let mut tx = ws.start_transaction()?;
 
@@ -70,29 +131,305 @@ impl Mutation for AbandonRevisions {
     }
 }
 
+impl Mutation for CommitStaged {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        // staged hunks are checked against the snapshot taken by the transaction
+        let Some(staged_tree_id) = staging::build_staged_tree(ws)? else {
+            precondition!("No changes are staged");
+        };
+
+        let wc_commit = ws.get_commit(ws.wc_id())?;
+        let staged_commit = tx
+            .repo_mut()
+            .new_commit(
+                &ws.data.settings,
+                wc_commit.parent_ids().to_vec(),
+                staged_tree_id,
+            )
+            .set_description(self.description)
+            .write()?;
+        tx.repo_mut()
+            .rewrite_commit(&ws.data.settings, &wc_commit)
+            .set_parents(vec![staged_commit.id().clone()])
+            .write()?;
+        tx.repo_mut().rebase_descendants(&ws.data.settings)?;
+
+        staging::clear(ws);
+
+        match ws.finish_transaction(
+            tx,
+            format!("commit staged changes below {}", wc_commit.id().hex()),
+        )? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for SplitRevision {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let commit = ws.resolve_single_change(&self.id)?;
+        if ws.check_immutable(vec![commit.id().clone()])? {
+            precondition!("Revision is immutable");
+        }
+
+        let selection: BTreeMap<_, _> = self
+            .paths
+            .into_iter()
+            .map(|path| {
+                (
+                    RepoPathBuf::from_internal_string(&path.path.repo_path),
+                    path.hunks,
+                )
+            })
+            .collect();
+
+        let parents: Result<Vec<_>, _> = commit.parents().collect();
+        let parent_tree = rewrite::merge_commit_trees(tx.repo(), &parents?)?;
+        let selected_tree_id =
+            staging::write_selected_tree(ws, &parent_tree, &commit.tree()?, &selection)?;
+        if selected_tree_id == parent_tree.id() {
+            precondition!("No changes are selected");
+        }
+        if &selected_tree_id == commit.tree_id() {
+            precondition!("Every change is selected, so there's nothing to split off");
+        }
+
+        // the selected changes keep the change id and the remainder becomes a new change on top
+        let selected_commit = tx
+            .repo_mut()
+            .rewrite_commit(&ws.data.settings, &commit)
+            .set_tree_id(selected_tree_id)
+            .set_description(self.description)
+            .write()?;
+        let remainder_commit = tx
+            .repo_mut()
+            .rewrite_commit(&ws.data.settings, &commit)
+            .set_parents(vec![selected_commit.id().clone()])
+            .generate_new_change_id()
+            .write()?;
+
+        // descendants, including any working copy, follow the remainder
+        tx.repo_mut()
+            .set_rewritten_commit(commit.id().clone(), remainder_commit.id().clone());
+        tx.repo_mut().rebase_descendants(&ws.data.settings)?;
+
+        match ws.finish_transaction(tx, format!("split commit {}", commit.id().hex()))? {
+            Some(new_status) => {
+                let new_selection = ws.format_header(&selected_commit, None)?;
+                Ok(MutationResult::UpdatedSelection {
+                    new_status,
+                    new_selection,
+                })
+            }
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for HideRevisions {
+    fn destructive_summary(&self, _ws: &WorkspaceSession) -> Result<Option<String>> {
+        Ok(match self.ids.as_slice() {
+            [] => None,
+            [id] => Some(format!("Hide revision {}?", id.prefix)),
+            ids => Some(format!(
+                "Hide revisions {}?",
+                ids.iter().map(|id| &id.prefix).join(", ")
+            )),
+        })
+    }
+
+    fn rewrite_count(&self, ws: &WorkspaceSession) -> Result<Option<usize>> {
+        let ids = self
+            .ids
+            .iter()
+            .map(|id| CommitId::try_from_hex(&id.hex).expect("frontend-validated id"));
+        Ok(Some(ws.count_descendants(ids)?))
+    }
+
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        if self.ids.is_empty() {
+            precondition!("No revisions to hide");
+        }
+
+        let mut tx = ws.start_transaction()?;
+
+        let hidden_ids = self
+            .ids
+            .into_iter()
+            .map(|id| CommitId::try_from_hex(&id.hex).expect("frontend-validated id"))
+            .collect_vec();
+
+        if ws.check_immutable(hidden_ids.clone())? {
+            precondition!("Some revisions are immutable");
+        }
+
+        // a visible head has no visible descendants, so nothing needs to be rewritten
+        if hidden_ids
+            .iter()
+            .any(|id| !tx.repo().view().heads().contains(id))
+        {
+            precondition!("Some revisions have descendants; abandon them instead");
+        }
+
+        for id in &hidden_ids {
+            tx.repo_mut().record_abandoned_commit(id.clone());
+        }
+        tx.repo_mut().rebase_descendants(&ws.data.settings)?;
+
+        let transaction_description = if hidden_ids.len() == 1 {
+            format!("hide commit {}", hidden_ids[0].hex())
+        } else {
+            format!(
+                "hide commit {} and {} more",
+                hidden_ids[0].hex(),
+                hidden_ids.len() - 1
+            )
+        };
+
+        match ws.finish_transaction(tx, transaction_description)? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for UnhideRevisions {
+    fn rewrite_count(&self, _ws: &WorkspaceSession) -> Result<Option<usize>> {
+        Ok(Some(self.ids.len()))
+    }
+
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let mut unhidden_commits = Vec::new();
+        for id in &self.ids {
+            let commit = ws.resolve_single_commit(id)?;
+            if ws.is_hidden(&commit) {
+                unhidden_commits.push(commit);
+            }
+        }
+
+        if unhidden_commits.is_empty() {
+            precondition!("Revisions are already visible");
+        }
+
+        tx.repo_mut().add_heads(&unhidden_commits)?;
+
+        let transaction_description = if unhidden_commits.len() == 1 {
+            format!("unhide commit {}", unhidden_commits[0].id().hex())
+        } else {
+            format!(
+                "unhide commit {} and {} more",
+                unhidden_commits[0].id().hex(),
+                unhidden_commits.len() - 1
+            )
+        };
+
+        match ws.finish_transaction(tx, transaction_description)? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
 impl Mutation for BackoutRevisions {
+    // a chain of new commits, or a rewrite of @ and its descendants
+    fn rewrite_count(&self, ws: &WorkspaceSession) -> Result<Option<usize>> {
+        if self.new_parent_id.is_some() {
+            Ok(Some(self.ids.len()))
+        } else {
+            Ok(Some(ws.count_descendants([ws.wc_id().clone()])?))
+        }
+    }
+
     fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
-        if self.ids.len() != 1 {
-            precondition!("Not implemented for >1 rev");
+        if self.ids.is_empty() {
+            precondition!("No revisions to back out");
         }
 
         let mut tx = ws.start_transaction()?;
 
+        let reverted = ws.resolve_multiple_changes(self.ids)?; // in reverse topological order
+
+        if let Some(new_parent_id) = self.new_parent_id {
+            // like `jj backout`, create a chain of reviewable commits and leave @ alone
+            let mut parent = ws.resolve_single_commit(&new_parent_id)?;
+            for reverted_commit in &reverted {
+                let reverted_parents: Vec<_> = reverted_commit.parents().try_collect()?;
+                let old_base_tree = rewrite::merge_commit_trees(tx.repo(), &reverted_parents)?;
+                let old_tree = reverted_commit.tree()?;
+                let new_tree = parent.tree()?.merge(&old_tree, &old_base_tree)?;
+
+                let description = format!(
+                    "Back out \"{}\"\n\nThis backs out commit {}.\n",
+                    reverted_commit
+                        .description()
+                        .lines()
+                        .next()
+                        .unwrap_or_default(),
+                    reverted_commit.id().hex()
+                );
+                parent = tx
+                    .repo_mut()
+                    .new_commit(&ws.data.settings, vec![parent.id().clone()], new_tree.id())
+                    .set_description(description)
+                    .write()?;
+            }
+
+            let transaction_description = if reverted.len() == 1 {
+                format!("back out commit {} into new commit", reverted[0].id().hex())
+            } else {
+                format!(
+                    "back out commit {} and {} more into new commits",
+                    reverted[0].id().hex(),
+                    reverted.len() - 1
+                )
+            };
+
+            return match ws.finish_transaction(tx, transaction_description)? {
+                Some(new_status) => {
+                    let new_selection = ws.format_header(&parent, Some(false))?;
+                    Ok(MutationResult::UpdatedSelection {
+                        new_status,
+                        new_selection,
+                    })
+                }
+                None => Ok(MutationResult::Unchanged),
+            };
+        }
+
         let working_copy = ws.get_commit(ws.wc_id())?;
-        let reverted = ws.resolve_multiple_changes(self.ids)?;
-        let reverted_parents: Result<Vec<_>, BackendError> = reverted[0].parents().collect();
 
-        let old_base_tree = rewrite::merge_commit_trees(tx.repo(), &reverted_parents?)?;
-        let new_base_tree = working_copy.tree()?;
-        let old_tree = reverted[0].tree()?;
-        let new_tree = new_base_tree.merge(&old_tree, &old_base_tree)?;
+        // the revisions needn't be contiguous, so back each one out of the accumulated tree
+        let mut new_tree = working_copy.tree()?;
+        for reverted_commit in &reverted {
+            let reverted_parents: Vec<_> = reverted_commit.parents().try_collect()?;
+            let old_base_tree = rewrite::merge_commit_trees(tx.repo(), &reverted_parents)?;
+            let old_tree = reverted_commit.tree()?;
+            new_tree = new_tree.merge(&old_tree, &old_base_tree)?;
+        }
 
         tx.repo_mut()
-            .rewrite_commit(&ws.data.settings, &&working_copy)
+            .rewrite_commit(&ws.data.settings, &working_copy)
             .set_tree_id(new_tree.id())
             .write()?;
 
-        match ws.finish_transaction(tx, format!("back out commit {}", reverted[0].id().hex()))? {
+        let transaction_description = if reverted.len() == 1 {
+            format!("back out commit {}", reverted[0].id().hex())
+        } else {
+            format!(
+                "back out commit {} and {} more",
+                reverted[0].id().hex(),
+                reverted.len() - 1
+            )
+        };
+
+        match ws.finish_transaction(tx, transaction_description)? {
             Some(new_status) => Ok(MutationResult::Updated { new_status }),
             None => Ok(MutationResult::Unchanged),
         }
@@ -113,11 +450,56 @@ impl Mutation for CheckoutRevision {
             return Ok(MutationResult::Unchanged);
         }
 
-        tx.repo_mut().edit(ws.id().clone(), &edited)?;
+        // undescribed work is easy to lose track of once it's no longer @
+        let wc = ws.get_commit(ws.wc_id())?;
+        let mut new_child = false;
+        if wc.description().is_empty() && !wc.is_empty(tx.repo())? {
+            let policy = match self
+                .policy
+                .unwrap_or_else(|| ws.data.settings.operations_checkout_policy())
+            {
+                CheckoutPolicy::Prompt => {
+                    let change = ws.format_change_id(wc.change_id()).prefix;
+                    match ws.session.callbacks.select_checkout_policy(&change) {
+                        Some(policy) => policy,
+                        None => return Ok(MutationResult::Unchanged),
+                    }
+                }
+                policy => policy,
+            };
+
+            match policy {
+                CheckoutPolicy::Bookmark => {
+                    let name = wip_bookmark_name(tx.repo().view(), &wc);
+                    tx.repo_mut()
+                        .set_local_bookmark_target(&name, RefTarget::normal(wc.id().clone()));
+                }
+                CheckoutPolicy::NewChild => new_child = true,
+                _ => (),
+            }
+        }
+
+        // a new child leaves the revision itself untouched by further changes
+        let (checked_out, transaction_description) = if new_child {
+            let child = tx
+                .repo_mut()
+                .new_commit(
+                    &ws.data.settings,
+                    vec![edited.id().clone()],
+                    edited.tree_id().clone(),
+                )
+                .write()?;
+            let description = format!("new empty commit on {}", edited.id().hex());
+            (child, description)
+        } else {
+            let description = format!("edit commit {}", edited.id().hex());
+            (edited, description)
+        };
+        tx.repo_mut().edit(ws.id().clone(), &checked_out)?;
 
-        match ws.finish_transaction(tx, format!("edit commit {}", edited.id().hex()))? {
+        match ws.finish_transaction(tx, transaction_description)? {
             Some(new_status) => {
-                let new_selection = ws.format_header(&edited, Some(false))?;
+                let new_selection = ws.format_header(&checked_out, Some(false))?;
                 Ok(MutationResult::UpdatedSelection {
                     new_status,
                     new_selection,
@@ -128,6 +510,19 @@ impl Mutation for CheckoutRevision {
     }
 }
 
+// unlike the shortest unique prefix, a fixed-length id doesn't change as the repo grows
+fn wip_bookmark_name(view: &View, wc: &Commit) -> String {
+    let base = format!("wip/{}", &wc.change_id().reverse_hex()[..12]);
+    let is_free = |name: &String| {
+        let target = view.get_local_bookmark(name);
+        target.is_absent() || target.as_normal() == Some(wc.id())
+    };
+    iter::once(base.clone())
+        .chain((2..).map(|n| format!("{base}-{n}")))
+        .find(is_free)
+        .expect("unbounded search")
+}
+
 impl Mutation for CreateRevision {
     fn execute<'a>(self: Box<Self>, ws: &'a mut WorkspaceSession) -> Result<MutationResult> {
         let mut tx = ws.start_transaction()?;
@@ -143,10 +538,12 @@ impl Mutation for CreateRevision {
         let parent_ids: Result<_, _> = parents_revset.iter().collect();
         let parent_commits = ws.resolve_multiple(parents_revset)?;
         let merged_tree = rewrite::merge_commit_trees(tx.repo(), &parent_commits)?;
+        let description = new_commit_description(ws, &parent_commits).unwrap_or_default();
 
         let new_commit = tx
             .repo_mut()
             .new_commit(&ws.data.settings, parent_ids?, merged_tree.id())
+            .set_description(description)
             .write()?;
 
         tx.repo_mut().edit(ws.id().clone(), &new_commit)?;
@@ -164,6 +561,43 @@ impl Mutation for CreateRevision {
     }
 }
 
+/// gg.ui.new-commit-description, with its placeholders filled in from the parents
+fn new_commit_description(ws: &WorkspaceSession, parents: &[Commit]) -> Option<String> {
+    let template = ws.data.settings.ui_new_commit_description()?;
+
+    let view = ws.repo().view();
+    let bookmarks = parents
+        .iter()
+        .flat_map(|parent| {
+            view.local_bookmarks_for_commit(parent.id())
+                .map(|(name, _)| name.to_owned())
+        })
+        .collect_vec();
+
+    // bookmarks are more likely than descriptions to be named after a ticket
+    let ticket = ws
+        .data
+        .settings
+        .ui_new_commit_ticket_pattern()
+        .and_then(|pattern| {
+            bookmarks
+                .iter()
+                .map(String::as_str)
+                .chain(parents.iter().map(|parent| parent.description()))
+                .find_map(|text| {
+                    let captures = pattern.captures(text)?;
+                    let matched = captures.get(1).or_else(|| captures.get(0))?;
+                    Some(matched.as_str().to_owned())
+                })
+        });
+
+    Some(
+        template
+            .replace("{bookmark}", bookmarks.first().map_or("", String::as_str))
+            .replace("{ticket}", ticket.as_deref().unwrap_or_default()),
+    )
+}
+
 impl Mutation for DescribeRevision {
     fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
         let mut tx = ws.start_transaction()?;
@@ -197,7 +631,174 @@ impl Mutation for DescribeRevision {
     }
 }
 
+impl Mutation for SetAuthor {
+    fn rewrite_count(&self, ws: &WorkspaceSession) -> Result<Option<usize>> {
+        let ids = self
+            .ids
+            .iter()
+            .map(|id| CommitId::try_from_hex(&id.commit.hex).expect("frontend-validated id"));
+        Ok(Some(ws.count_descendants(ids)?))
+    }
+
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        if self.name.is_none() && self.email.is_none() && self.timestamp.is_none() {
+            return Ok(MutationResult::Unchanged);
+        }
+
+        let mut tx = ws.start_transaction()?;
+
+        let target_ids: HashSet<CommitId> = ws
+            .resolve_multiple_changes(self.ids)?
+            .iter()
+            .map(|commit| commit.id().clone())
+            .collect();
+
+        if ws.check_immutable(target_ids.iter().cloned())? {
+            precondition!("Some revisions are immutable");
+        }
+
+        let mut num_changed = 0;
+        tx.repo_mut().transform_descendants(
+            &ws.data.settings,
+            target_ids.iter().cloned().collect(),
+            |rewriter| {
+                if target_ids.contains(rewriter.old_commit().id()) {
+                    let old_author = rewriter.old_commit().author();
+                    let new_author = Signature {
+                        name: self.name.clone().unwrap_or(old_author.name.clone()),
+                        email: self.email.clone().unwrap_or(old_author.email.clone()),
+                        timestamp: self
+                            .timestamp
+                            .map(Timestamp::from_datetime)
+                            .unwrap_or(old_author.timestamp.clone()),
+                    };
+                    if new_author != *old_author {
+                        num_changed += 1;
+                        rewriter
+                            .reparent(&ws.data.settings)?
+                            .set_author(new_author)
+                            .write()?;
+                        return Ok(());
+                    }
+                }
+
+                if rewriter.parents_changed() {
+                    rewriter.reparent(&ws.data.settings)?.write()?;
+                }
+                Ok(())
+            },
+        )?;
+
+        match ws.finish_transaction(tx, format!("set author of {num_changed} commits"))? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for DescribeRevisions {
+    fn rewrite_count(&self, ws: &WorkspaceSession) -> Result<Option<usize>> {
+        let ids = self
+            .ids
+            .iter()
+            .map(|id| CommitId::try_from_hex(&id.commit.hex).expect("frontend-validated id"));
+        Ok(Some(ws.count_descendants(ids)?))
+    }
+
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        match &self.edit {
+            DescriptionEdit::AppendTrailer { trailer } if trailer.trim().is_empty() => {
+                precondition!("Trailer is empty")
+            }
+            DescriptionEdit::Replace { find, .. } if find.is_empty() => {
+                precondition!("Nothing to find")
+            }
+            _ => (),
+        }
+
+        let mut tx = ws.start_transaction()?;
+
+        let target_ids: HashSet<CommitId> = ws
+            .resolve_multiple_changes(self.ids)?
+            .iter()
+            .map(|commit| commit.id().clone())
+            .collect();
+
+        if ws.check_immutable(target_ids.iter().cloned())? {
+            precondition!("Some revisions are immutable");
+        }
+
+        let mut num_changed = 0;
+        tx.repo_mut().transform_descendants(
+            &ws.data.settings,
+            target_ids.iter().cloned().collect(),
+            |rewriter| {
+                if target_ids.contains(rewriter.old_commit().id()) {
+                    let old_description = rewriter.old_commit().description();
+                    let new_description = edit_description(old_description, &self.edit);
+                    if new_description != old_description {
+                        num_changed += 1;
+                        rewriter
+                            .reparent(&ws.data.settings)?
+                            .set_description(new_description)
+                            .write()?;
+                        return Ok(());
+                    }
+                }
+
+                if rewriter.parents_changed() {
+                    rewriter.reparent(&ws.data.settings)?.write()?;
+                }
+                Ok(())
+            },
+        )?;
+
+        match ws.finish_transaction(tx, format!("describe {num_changed} commits"))? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+fn edit_description(description: &str, edit: &DescriptionEdit) -> String {
+    match edit {
+        DescriptionEdit::Set { text } => text.clone(),
+        DescriptionEdit::Prepend { text } if description.is_empty() => format!("{text}\n"),
+        DescriptionEdit::Prepend { text } => format!("{text}{description}"),
+        DescriptionEdit::AppendTrailer { trailer } => {
+            let trailer = trailer.trim();
+            let body = description.trim_end();
+            if body.lines().any(|line| line.trim() == trailer) {
+                description.to_owned()
+            } else if body.is_empty() {
+                format!("{trailer}\n")
+            } else if is_trailer_block(body) {
+                format!("{body}\n{trailer}\n")
+            } else {
+                format!("{body}\n\n{trailer}\n")
+            }
+        }
+        DescriptionEdit::Replace { find, replace } => description.replace(find, replace),
+    }
+}
+
+// whether the last paragraph, which can't be the subject, consists of `Key: value` lines
+fn is_trailer_block(body: &str) -> bool {
+    let Some((_, last_paragraph)) = body.rsplit_once("\n\n") else {
+        return false;
+    };
+    last_paragraph.lines().all(|line| {
+        line.split_once(": ").is_some_and(|(key, _)| {
+            !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '-')
+        })
+    })
+}
+
 impl Mutation for DuplicateRevisions {
+    fn rewrite_count(&self, _ws: &WorkspaceSession) -> Result<Option<usize>> {
+        Ok(Some(self.ids.len()))
+    }
+
     fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
         let mut tx = ws.start_transaction()?;
 
@@ -249,12 +850,490 @@ impl Mutation for DuplicateRevisions {
     }
 }
 
-impl Mutation for InsertRevision {
-    fn execute<'a>(self: Box<Self>, ws: &'a mut WorkspaceSession) -> Result<MutationResult> {
-        let mut tx = ws.start_transaction()?;
+impl Mutation for MergeRevisions {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        create_merge(ws, &self.ids, self.checkout)
+    }
+}
 
-        let target = ws
-            .resolve_single_change(&self.id)
+impl Mutation for ParallelizeRevisions {
+    fn rewrite_count(&self, ws: &WorkspaceSession) -> Result<Option<usize>> {
+        let ids = self
+            .ids
+            .iter()
+            .map(|id| CommitId::try_from_hex(&id.commit.hex).expect("frontend-validated id"));
+        Ok(Some(ws.count_descendants(ids)?))
+    }
+
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let targets = ws.resolve_multiple_changes(self.ids)?; // in reverse topological order
+        if targets.len() < 2 {
+            precondition!("Select at least two revisions to parallelize");
+        }
+        if ws.check_immutable(targets.iter().map(|commit| commit.id().clone()))? {
+            precondition!("Some revisions are immutable");
+        }
+
+        // targets no longer depend on each other, so each inherits its target parents' parents
+        let mut new_target_parents: HashMap<CommitId, Vec<CommitId>> = HashMap::new();
+        for commit in targets.iter().rev() {
+            let mut new_parents = vec![];
+            for old_parent in commit.parent_ids() {
+                match new_target_parents.get(old_parent) {
+                    Some(grandparents) => new_parents.extend_from_slice(grandparents),
+                    None => new_parents.push(old_parent.clone()),
+                }
+            }
+            new_target_parents.insert(commit.id().clone(), new_parents);
+        }
+
+        // a child of a target must still descend from every target it descended from before
+        let mut new_child_parents: HashMap<CommitId, IndexSet<CommitId>> = HashMap::new();
+        for commit in targets.iter().rev() {
+            let mut new_parents = IndexSet::new();
+            for old_parent in commit.parent_ids() {
+                if let Some(parents) = new_child_parents.get(old_parent) {
+                    new_parents.extend(parents.iter().cloned());
+                }
+            }
+            new_parents.insert(commit.id().clone());
+            new_child_parents.insert(commit.id().clone(), new_parents);
+        }
+
+        let target_ids = targets.iter().map(|commit| commit.id().clone()).collect();
+        tx.repo_mut()
+            .transform_descendants(&ws.data.settings, target_ids, |mut rewriter| {
+                if let Some(new_parents) = new_target_parents.get(rewriter.old_commit().id()) {
+                    rewriter.set_new_rewritten_parents(new_parents);
+                } else if rewriter
+                    .old_commit()
+                    .parent_ids()
+                    .iter()
+                    .any(|id| new_child_parents.contains_key(id))
+                {
+                    let mut new_parents = vec![];
+                    for parent in rewriter.old_commit().parent_ids() {
+                        match new_child_parents.get(parent) {
+                            Some(parents) => new_parents.extend(parents.iter().cloned()),
+                            None => new_parents.push(parent.clone()),
+                        }
+                    }
+                    rewriter.set_new_rewritten_parents(&new_parents);
+                }
+                if rewriter.parents_changed() {
+                    rewriter.rebase(&ws.data.settings)?.write()?;
+                }
+                Ok(())
+            })?;
+
+        match ws.finish_transaction(tx, format!("parallelize {} commits", targets.len()))? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for CreateMergeOf {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        create_merge(ws, &self.ids, false)
+    }
+}
+
+fn create_merge(
+    ws: &mut WorkspaceSession,
+    ids: &[RevId],
+    checkout: bool,
+) -> Result<MutationResult> {
+    let mut tx = ws.start_transaction()?;
+
+    // resolved one at a time, so that the parents keep the selection's order
+    let parents = ids
+        .iter()
+        .map(|id| ws.resolve_single_change(id))
+        .collect::<Result<Vec<_>, _>>()?;
+    if parents.len() < 2 {
+        precondition!("A merge needs at least two parents");
+    }
+
+    for (ancestor, descendant) in parents.iter().tuple_combinations() {
+        for (ancestor, descendant) in [(ancestor, descendant), (descendant, ancestor)] {
+            if tx
+                .repo()
+                .index()
+                .is_ancestor(ancestor.id(), descendant.id())
+            {
+                precondition!(
+                    "Revision {} is an ancestor of {}",
+                    ws.format_change_id(ancestor.change_id()).prefix,
+                    ws.format_change_id(descendant.change_id()).prefix
+                );
+            }
+        }
+    }
+
+    let merged_tree = rewrite::merge_commit_trees(tx.repo(), &parents)?;
+    let merge = tx
+        .repo_mut()
+        .new_commit(
+            &ws.data.settings,
+            parents.iter().map(|parent| parent.id().clone()).collect(),
+            merged_tree.id(),
+        )
+        .write()?;
+
+    if checkout {
+        tx.repo_mut().edit(ws.id().clone(), &merge)?;
+    }
+
+    match ws.finish_transaction(tx, format!("merge {} commits", parents.len()))? {
+        Some(new_status) => {
+            let new_selection = ws.format_header(&merge, None)?;
+            Ok(MutationResult::UpdatedSelection {
+                new_status,
+                new_selection,
+            })
+        }
+        None => Ok(MutationResult::Unchanged),
+    }
+}
+
+impl Mutation for AddParentToMerge {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let target = ws.resolve_single_change(&self.id)?;
+        let new_parent = ws.resolve_single_change(&self.parent_id)?;
+
+        if ws.check_immutable(vec![target.id().clone()])? {
+            precondition!("Revision {} is immutable", self.id.change.prefix);
+        }
+        if target.parent_ids().contains(new_parent.id()) {
+            precondition!(
+                "Revision {} is already a parent of {}",
+                self.parent_id.change.prefix,
+                self.id.change.prefix
+            );
+        }
+        if tx.repo().index().is_ancestor(target.id(), new_parent.id()) {
+            precondition!(
+                "Revision {} is {} or one of its descendants",
+                self.parent_id.change.prefix,
+                self.id.change.prefix
+            );
+        }
+
+        // a parent related to another would add nothing to the merge
+        let index = tx.repo().index();
+        for parent_id in target.parent_ids() {
+            if index.is_ancestor(parent_id, new_parent.id())
+                || index.is_ancestor(new_parent.id(), parent_id)
+            {
+                precondition!(
+                    "Revision {} is an ancestor or descendant of the existing parent {}",
+                    self.parent_id.change.prefix,
+                    ws.format_commit_id(parent_id).prefix
+                );
+            }
+        }
+
+        let mut parent_ids = target.parent_ids().to_vec();
+        parent_ids.push(new_parent.id().clone());
+
+        let rebased_id = target.id().hex();
+        rewrite::rebase_commit(&ws.data.settings, tx.repo_mut(), target, parent_ids)?;
+
+        match ws.finish_transaction(
+            tx,
+            format!(
+                "add parent {} to commit {}",
+                new_parent.id().hex(),
+                rebased_id
+            ),
+        )? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for RemoveParentFromMerge {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let target = ws.resolve_single_change(&self.id)?;
+        let old_parent = ws.resolve_single_commit(&self.parent_id)?;
+
+        if ws.check_immutable(vec![target.id().clone()])? {
+            precondition!("Revision {} is immutable", self.id.change.prefix);
+        }
+        if !target.parent_ids().contains(old_parent.id()) {
+            precondition!(
+                "Revision {} is not a parent of {}",
+                self.parent_id.prefix,
+                self.id.change.prefix
+            );
+        }
+        if target.parent_ids().len() < 2 {
+            precondition!("Revision {} has only one parent", self.id.change.prefix);
+        }
+
+        let parent_ids = target
+            .parent_ids()
+            .iter()
+            .filter(|id| *id != old_parent.id())
+            .cloned()
+            .collect();
+
+        let rebased_id = target.id().hex();
+        rewrite::rebase_commit(&ws.data.settings, tx.repo_mut(), target, parent_ids)?;
+
+        match ws.finish_transaction(
+            tx,
+            format!(
+                "remove parent {} from commit {}",
+                old_parent.id().hex(),
+                rebased_id
+            ),
+        )? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for SwapParents {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let target = ws.resolve_single_change(&self.id)?;
+
+        if ws.check_immutable(vec![target.id().clone()])? {
+            precondition!("Revision {} is immutable", self.id.change.prefix);
+        }
+        if target.parent_ids().len() != 2 {
+            precondition!(
+                "Revision {} has {} parent(s); only a merge of two can be swapped",
+                self.id.change.prefix,
+                target.parent_ids().len()
+            );
+        }
+
+        let parent_ids = target.parent_ids().iter().rev().cloned().collect();
+
+        let rebased_id = target.id().hex();
+        rewrite::rebase_commit(&ws.data.settings, tx.repo_mut(), target, parent_ids)?;
+
+        match ws.finish_transaction(tx, format!("swap parents of commit {}", rebased_id))? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for GraftRevisions {
+    fn rewrite_count(&self, _ws: &WorkspaceSession) -> Result<Option<usize>> {
+        Ok(Some(self.ids.len()))
+    }
+
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let grafts = ws.resolve_multiple_changes(self.ids)?; // in reverse topological order
+        let parents = ws.resolve_multiple_changes(self.parent_ids)?;
+        if grafts.is_empty() || parents.is_empty() {
+            precondition!("Nothing to graft");
+        }
+
+        let graft_ids: HashSet<_> = grafts.iter().map(|commit| commit.id().clone()).collect();
+        if parents.iter().any(|parent| graft_ids.contains(parent.id())) {
+            precondition!("Can't graft a revision onto itself");
+        }
+
+        let num_grafts = grafts.len();
+        let mut clones: IndexMap<Commit, Commit> = IndexMap::new();
+
+        // roots of the set are moved to the new parents, and the rest keep their relative structure;
+        // a merge with parents outside the set keeps those, like a duplicate would
+        for graft in grafts.into_iter().rev() {
+            let mut clone_parents = Vec::new();
+            let mut is_root = true;
+            for parent in graft.parents() {
+                let parent = parent?;
+                if let Some(cloned_parent) = clones.get(&parent) {
+                    clone_parents.push(cloned_parent.id().clone());
+                    is_root = false;
+                } else {
+                    clone_parents.push(parent.id().clone());
+                }
+            }
+            if is_root {
+                clone_parents = parents.iter().map(|parent| parent.id().clone()).collect();
+            }
+
+            // apply the revision's changes to its new base
+            let old_parents: Vec<_> = graft.parents().try_collect()?;
+            let old_base_tree = rewrite::merge_commit_trees(tx.repo(), &old_parents)?;
+            let new_parents: Vec<_> = clone_parents
+                .iter()
+                .map(|id| tx.repo().store().get_commit(id))
+                .try_collect()?;
+            let new_base_tree = rewrite::merge_commit_trees(tx.repo(), &new_parents)?;
+            let new_tree = new_base_tree.merge(&old_base_tree, &graft.tree()?)?;
+
+            let clone = tx
+                .repo_mut()
+                .rewrite_commit(&ws.data.settings, &graft)
+                .generate_new_change_id()
+                .set_parents(clone_parents)
+                .set_tree_id(new_tree.id())
+                .write()?;
+            clones.insert(graft, clone);
+        }
+
+        match ws.finish_transaction(tx, format!("grafting {} commit(s)", num_grafts))? {
+            Some(new_status) => {
+                let new_commit = clones
+                    .last()
+                    .ok_or(anyhow!("grafted sources should have copies"))?
+                    .1;
+                let new_selection = ws.format_header(new_commit, None)?;
+                Ok(MutationResult::UpdatedSelection {
+                    new_status,
+                    new_selection,
+                })
+            }
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for RunRebasePlan {
+    fn rewrite_count(&self, ws: &WorkspaceSession) -> Result<Option<usize>> {
+        let ids = self.steps.iter().map(|step| {
+            CommitId::try_from_hex(&step.id.commit.hex).expect("frontend-validated id")
+        });
+        Ok(Some(ws.count_descendants(ids)?))
+    }
+
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        if self.steps.is_empty() {
+            precondition!("Rebase plan is empty");
+        }
+
+        let mut tx = ws.start_transaction()?;
+
+        let mut planned = Vec::new();
+        for step in self.steps {
+            planned.push((ws.resolve_single_change(&step.id)?, step.action));
+        }
+
+        let planned_ids: HashSet<_> = planned
+            .iter()
+            .map(|(commit, _)| commit.id().clone())
+            .collect();
+        if planned_ids.len() != planned.len() {
+            precondition!("Rebase plan contains a revision more than once");
+        }
+        if ws.check_immutable(planned_ids.iter().cloned())? {
+            precondition!("Some revisions are immutable");
+        }
+
+        // the plan is stacked onto the parents of its root, wherever that appears in the plan
+        let mut roots = planned.iter().filter(|(commit, _)| {
+            !commit
+                .parent_ids()
+                .iter()
+                .any(|parent_id| planned_ids.contains(parent_id))
+        });
+        let (Some((root, _)), None) = (roots.next(), roots.next()) else {
+            precondition!("Rebase plan must have a single root");
+        };
+        let mut parent_ids = root.parent_ids().to_vec();
+        let mut previous: Option<Commit> = None;
+        let mut previous_dropped = false;
+
+        for (commit, action) in &planned {
+            let old_parents: Vec<_> = commit.parents().try_collect()?;
+            let old_base_tree = rewrite::merge_commit_trees(tx.repo(), &old_parents)?;
+
+            match action {
+                RebaseAction::Drop => {
+                    tx.repo_mut().record_abandoned_commit_with_parents(
+                        commit.id().clone(),
+                        parent_ids.clone(),
+                    );
+                }
+                RebaseAction::Squash => {
+                    // squashing past a dropped revision would fold into an earlier one than it appears to
+                    if previous_dropped {
+                        precondition!("Can't squash a revision into one which is dropped");
+                    }
+                    let Some(destination) = previous.take() else {
+                        precondition!("Can't squash the first revision of a rebase plan");
+                    };
+                    let new_tree = destination.tree()?.merge(&old_base_tree, &commit.tree()?)?;
+                    let description = combine_messages(commit, &destination, true);
+                    let squashed = tx
+                        .repo_mut()
+                        .rewrite_commit(&ws.data.settings, &destination)
+                        .set_tree_id(new_tree.id())
+                        .set_description(description)
+                        .write()?;
+                    tx.repo_mut()
+                        .set_rewritten_commit(commit.id().clone(), squashed.id().clone());
+                    parent_ids = vec![squashed.id().clone()];
+                    previous = Some(squashed);
+                }
+                RebaseAction::Keep | RebaseAction::Reword { .. } => {
+                    let new_description = match action {
+                        RebaseAction::Reword { new_description } => Some(new_description),
+                        _ => None,
+                    };
+
+                    let kept = if new_description.is_none() && commit.parent_ids() == parent_ids {
+                        commit.clone()
+                    } else {
+                        let new_parents: Vec<_> = parent_ids
+                            .iter()
+                            .map(|id| tx.repo().store().get_commit(id))
+                            .try_collect()?;
+                        let new_base_tree = rewrite::merge_commit_trees(tx.repo(), &new_parents)?;
+                        let new_tree = new_base_tree.merge(&old_base_tree, &commit.tree()?)?;
+                        let mut builder = tx
+                            .repo_mut()
+                            .rewrite_commit(&ws.data.settings, commit)
+                            .set_parents(parent_ids.clone())
+                            .set_tree_id(new_tree.id());
+                        if let Some(new_description) = new_description {
+                            builder = builder.set_description(new_description);
+                        }
+                        builder.write()?
+                    };
+                    parent_ids = vec![kept.id().clone()];
+                    previous = Some(kept);
+                }
+            }
+            previous_dropped = matches!(action, RebaseAction::Drop);
+        }
+
+        match ws.finish_transaction(
+            tx,
+            format!("run rebase plan for {} commit(s)", planned.len()),
+        )? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for InsertRevision {
+    fn execute<'a>(self: Box<Self>, ws: &'a mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let target = ws
+            .resolve_single_change(&self.id)
             .context("resolve change_id")?;
         let before = ws
             .resolve_single_change(&self.before_id)
@@ -405,14 +1484,652 @@ impl Mutation for MoveChanges {
         let new_to_tree = to_tree.merge(&parent_tree, &split_tree)?;
         let description = combine_messages(&from, &to, abandon_source);
         tx.repo_mut()
-            .rewrite_commit(&ws.data.settings, &to)
-            .set_tree_id(new_to_tree.id().clone())
-            .set_description(description)
+            .rewrite_commit(&ws.data.settings, &to)
+            .set_tree_id(new_to_tree.id().clone())
+            .set_description(description)
+            .write()?;
+
+        match ws.finish_transaction(
+            tx,
+            format!("move changes from {} to {}", from.id().hex(), to.id().hex()),
+        )? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for CopyChanges {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let from_tree = ws.resolve_single_commit(&self.from_id)?.tree()?;
+        let to = ws.resolve_single_change(&self.to_id)?;
+        let matcher = build_matcher(&self.paths);
+
+        if ws.check_immutable(vec![to.id().clone()])? {
+            precondition!("Revisions are immutable");
+        }
+
+        // construct a restore tree - the destination with some portions overwritten by the source
+        let to_tree = to.tree()?;
+        let new_to_tree_id = rewrite::restore_tree(&from_tree, &to_tree, matcher.as_ref())?;
+        if &new_to_tree_id == to.tree_id() {
+            Ok(MutationResult::Unchanged)
+        } else {
+            tx.repo_mut()
+                .rewrite_commit(&ws.data.settings, &to)
+                .set_tree_id(new_to_tree_id)
+                .write()?;
+
+            tx.repo_mut().rebase_descendants(&ws.data.settings)?;
+
+            match ws.finish_transaction(tx, format!("restore into commit {}", to.id().hex()))? {
+                Some(new_status) => Ok(MutationResult::Updated { new_status }),
+                None => Ok(MutationResult::Unchanged),
+            }
+        }
+    }
+}
+
+impl Mutation for AddIgnorePattern {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let pattern = self.pattern.trim();
+        if pattern.is_empty() || pattern.contains('\n') {
+            precondition!("Ignore pattern must be a single non-empty line");
+        }
+
+        let ignore = match GitIgnoreFile::empty().chain("", pattern.as_bytes()) {
+            Ok(ignore) => ignore,
+            Err(err) => precondition!("Invalid pattern: {err}"),
+        };
+
+        // append to the top-level .gitignore, which the next snapshot will pick up
+        let ignore_path = ws.workspace.workspace_root().join(".gitignore");
+        let existing = match fs::read_to_string(&ignore_path) {
+            Ok(existing) => existing,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err.into()),
+        };
+        if !existing.lines().any(|line| line.trim() == pattern) {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&ignore_path)?;
+            if !existing.is_empty() && !existing.ends_with('\n') {
+                writeln!(file)?;
+            }
+            writeln!(file, "{pattern}")?;
+        }
+
+        let mut tx = ws.start_transaction()?;
+
+        // files added in the working copy commit stay tracked until removed from it; files from
+        // earlier commits are left alone, since removing them would delete them from history
+        let wc_commit = ws.get_commit(ws.wc_id())?;
+        let parent_tree = wc_commit.parent_tree(tx.repo())?;
+        let mut tree_builder = MergedTreeBuilder::new(wc_commit.tree_id().clone());
+        let mut untracked = 0;
+        for (path, _) in wc_commit.tree()?.entries() {
+            if ignore.matches(path.as_internal_file_string())
+                && parent_tree.path_value(&path)?.is_absent()
+            {
+                tree_builder.set_or_remove(path, Merge::absent());
+                untracked += 1;
+            }
+        }
+
+        if untracked > 0 {
+            let new_tree_id = tree_builder.write_tree(tx.repo().store())?;
+            tx.repo_mut()
+                .rewrite_commit(&ws.data.settings, &wc_commit)
+                .set_tree_id(new_tree_id)
+                .write()?;
+        }
+
+        // the working copy files are left in place - only their tracking state changes
+        match ws.finish_transaction_without_checkout(
+            tx,
+            format!("ignore {pattern}, untracking {untracked} file(s)"),
+        )? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Updated {
+                new_status: ws.format_status(),
+            }),
+        }
+    }
+}
+
+impl Mutation for DeletePaths {
+    fn destructive_summary(&self, _ws: &WorkspaceSession) -> Result<Option<String>> {
+        Ok(Some(match self.paths.as_slice() {
+            [path] => format!("Delete {}?", path.relative_path.0),
+            paths => format!("Delete {} files?", paths.len()),
+        }))
+    }
+
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        if self.paths.is_empty() {
+            precondition!("No files to delete");
+        }
+
+        // record the current contents first, so that the deletion can be undone
+        ws.import_and_snapshot(true)?;
+
+        let workspace_root = ws.workspace.workspace_root().to_owned();
+        for path in &self.paths {
+            let fs_path =
+                RepoPathBuf::from_internal_string(&path.repo_path).to_fs_path(&workspace_root)?;
+            match fs::remove_file(&fs_path) {
+                Ok(()) => (),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                Err(err) => {
+                    return Err(err).with_context(|| format!("delete {}", path.relative_path.0))
+                }
+            }
+        }
+
+        ws.import_and_snapshot(true)?;
+        Ok(MutationResult::Updated {
+            new_status: ws.format_status(),
+        })
+    }
+}
+
+impl Mutation for RenamePath {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let new_path = match RepoPathBuf::from_relative_path(self.new_path.trim()) {
+            Ok(new_path) if !new_path.is_root() && !is_reserved(&new_path) => new_path,
+            _ => precondition!(
+                "{} is not a path within the workspace",
+                self.new_path.trim()
+            ),
+        };
+        let path = RepoPathBuf::from_internal_string(&self.path.repo_path);
+        if is_reserved(&path) {
+            precondition!(
+                "{} is not a file in the working copy",
+                self.path.relative_path.0
+            );
+        }
+
+        ws.import_and_snapshot(true)?;
+
+        let workspace_root = ws.workspace.workspace_root().to_owned();
+        let from = path.to_fs_path(&workspace_root)?;
+        let to = new_path.to_fs_path(&workspace_root)?;
+        if !from.is_file() {
+            precondition!(
+                "{} is not a file in the working copy",
+                self.path.relative_path.0
+            );
+        }
+        if to.exists() {
+            precondition!("{} already exists", new_path.as_internal_file_string());
+        }
+
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&from, &to).with_context(|| format!("rename {}", self.path.relative_path.0))?;
+
+        ws.import_and_snapshot(true)?;
+        Ok(MutationResult::Updated {
+            new_status: ws.format_status(),
+        })
+    }
+}
+
+impl Mutation for CreateFile {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let path = match RepoPathBuf::from_relative_path(self.path.trim()) {
+            Ok(path) if !path.is_root() && !is_reserved(&path) => path,
+            _ => precondition!("{} is not a path within the workspace", self.path.trim()),
+        };
+
+        ws.import_and_snapshot(true)?;
+
+        let fs_path = path.to_fs_path(ws.workspace.workspace_root())?;
+        if fs_path.symlink_metadata().is_ok() {
+            precondition!("{} already exists", path.as_internal_file_string());
+        }
+        if ws.is_ignored(&path)? {
+            precondition!(
+                "{} is ignored, so it would not be added to the working copy",
+                path.as_internal_file_string()
+            );
+        }
+
+        if let Some(parent) = fs_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&fs_path, self.content)
+            .with_context(|| format!("write {}", path.as_internal_file_string()))?;
+
+        ws.import_and_snapshot(true)?;
+
+        // select the working copy, whose change list now includes the file
+        let wc = ws.get_commit(ws.wc_id())?;
+        Ok(MutationResult::UpdatedSelection {
+            new_status: ws.format_status(),
+            new_selection: ws.format_header(&wc, Some(false))?,
+        })
+    }
+}
+
+// jj and git metadata, which is never part of the working copy
+fn is_reserved(path: &RepoPath) -> bool {
+    path.components().next().is_some_and(|first| {
+        let first = first.as_internal_str();
+        first.eq_ignore_ascii_case(".jj") || first.eq_ignore_ascii_case(".git")
+    })
+}
+
+impl Mutation for SetUserIdentity {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let name = self.name.trim();
+        let email = self.email.trim();
+        if name.is_empty() || email.is_empty() {
+            precondition!("Both a name and an email address are required");
+        }
+
+        let path = ws.config_path(&ConfigSource::User)?;
+        for (key, value) in [("name", name), ("email", email)] {
+            let key: ConfigNamePathBuf = ["user", key].into_iter().collect();
+            write_config_value_to_file(&key, value.into(), &path)
+                .map_err(|err| anyhow!("{err:?}"))?;
+        }
+
+        ws.reload_config()?;
+
+        // rewriting a commit with no author fills it in from the new settings
+        let mut tx = ws.start_transaction()?;
+        let wc = ws.get_commit(ws.wc_id())?;
+        let author = wc.author();
+        if author.name.is_empty()
+            || author.name == UserSettings::USER_NAME_PLACEHOLDER
+            || author.email.is_empty()
+            || author.email == UserSettings::USER_EMAIL_PLACEHOLDER
+        {
+            tx.repo_mut()
+                .rewrite_commit(&ws.data.settings, &wc)
+                .write()?;
+            tx.repo_mut().rebase_descendants(&ws.data.settings)?;
+        }
+
+        match ws.finish_transaction(tx, format!("set user identity to {name} <{email}>"))? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Updated {
+                new_status: ws.format_status(),
+            }),
+        }
+    }
+}
+
+impl Mutation for SetRevsetAlias {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        if !matches!(self.scope, ConfigScope::User | ConfigScope::Repo) {
+            precondition!("Aliases can only be saved to user or repo config");
+        }
+
+        // validate the declaration the same way it will be when the config is reloaded
+        let name = self.name.trim();
+        if let Err(err) = RevsetAliasesMap::new().insert(name, &self.definition) {
+            precondition!("Invalid alias {name}: {err}");
+        }
+
+        let path = ws.config_path(&self.scope.into())?;
+        let key: ConfigNamePathBuf = [REVSET_ALIASES_KEY, name].into_iter().collect();
+        write_config_value_to_file(&key, self.definition.as_str().into(), &path)
+            .map_err(|err| anyhow!("{err:?}"))?;
+
+        ws.reload_config()?;
+
+        Ok(MutationResult::Updated {
+            new_status: ws.format_status(),
+        })
+    }
+}
+
+impl Mutation for RemoveRevsetAlias {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        if !matches!(self.scope, ConfigScope::User | ConfigScope::Repo) {
+            precondition!("Aliases can only be removed from user or repo config");
+        }
+
+        let path = ws.config_path(&self.scope.into())?;
+        let key: ConfigNamePathBuf = [REVSET_ALIASES_KEY, &self.name].into_iter().collect();
+        if let Err(err) = remove_config_value_from_file(&key, &path) {
+            if let Some(ConfigError::NotFound(_)) = err.error.downcast_ref::<ConfigError>() {
+                precondition!("{} is not defined in {:?} config", self.name, self.scope);
+            }
+            return Err(anyhow!("{err:?}"));
+        }
+
+        ws.reload_config()?;
+
+        Ok(MutationResult::Updated {
+            new_status: ws.format_status(),
+        })
+    }
+}
+
+impl Mutation for SetTrunk {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let definition = match self.r#ref {
+            Some(StoreRef::LocalBookmark { branch_name, .. }) => {
+                Some(format!("bookmarks(exact:{branch_name:?})"))
+            }
+            Some(StoreRef::RemoteBookmark {
+                branch_name,
+                remote_name,
+                ..
+            }) => Some(format!(
+                "remote_bookmarks(exact:{branch_name:?}, exact:{remote_name:?})"
+            )),
+            Some(StoreRef::Tag { tag_name }) => {
+                precondition!("{tag_name} is a tag; only bookmarks can be trunk");
+            }
+            None => None,
+        };
+
+        let path = ws.config_path(&ConfigScope::Repo.into())?;
+        let key: ConfigNamePathBuf = [REVSET_ALIASES_KEY, TRUNK_ALIAS].into_iter().collect();
+        match definition {
+            Some(definition) => write_config_value_to_file(&key, definition.into(), &path)
+                .map_err(|err| anyhow!("{err:?}"))?,
+            // without an override, the default trunk() applies again
+            None => {
+                if let Err(err) = remove_config_value_from_file(&key, &path) {
+                    if let Some(ConfigError::NotFound(_)) = err.error.downcast_ref::<ConfigError>()
+                    {
+                        return Ok(MutationResult::Unchanged);
+                    }
+                    return Err(anyhow!("{err:?}"));
+                }
+            }
+        }
+
+        ws.reload_config()?;
+
+        Ok(MutationResult::Updated {
+            new_status: ws.format_status(),
+        })
+    }
+}
+
+impl Mutation for FollowWorkspace {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let followed = match self.name {
+            Some(name) if name != ws.id().as_str() => {
+                let id = WorkspaceId::new(name);
+                if ws.repo().view().get_wc_commit_id(&id).is_none() {
+                    precondition!("No workspace named {}", id.as_str());
+                }
+                Some(id)
+            }
+            _ => None,
+        };
+
+        if followed == ws.session.followed_workspace {
+            return Ok(MutationResult::Unchanged);
+        }
+
+        ws.session.followed_workspace = followed;
+
+        Ok(MutationResult::Updated {
+            new_status: ws.format_status(),
+        })
+    }
+}
+
+impl Mutation for SetDefaultQuery {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let path = ws.config_path(&ConfigScope::Repo.into())?;
+        let key: ConfigNamePathBuf = DEFAULT_QUERY_KEY.split('.').collect();
+
+        match self.query.as_deref().map(str::trim) {
+            Some(query) if !query.is_empty() => {
+                if let Err(err) = ws.evaluate_revset_str(query) {
+                    precondition!("Invalid query {query}: {err}");
+                }
+                write_config_value_to_file(&key, query.into(), &path)
+                    .map_err(|err| anyhow!("{err:?}"))?;
+            }
+            _ => {
+                if let Err(err) = remove_config_value_from_file(&key, &path) {
+                    if let Some(ConfigError::NotFound(_)) = err.error.downcast_ref::<ConfigError>()
+                    {
+                        return Ok(MutationResult::Unchanged);
+                    }
+                    return Err(anyhow!("{err:?}"));
+                }
+            }
+        }
+
+        ws.reload_config()?;
+
+        Ok(MutationResult::Updated {
+            new_status: ws.format_status(),
+        })
+    }
+}
+
+impl Mutation for SetLabel {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let commit = ws.resolve_single_commit(&self.id.commit)?;
+        let change_hex = commit.change_id().reverse_hex();
+
+        let label = self.label.map(|label| RevLabel {
+            text: label.text.trim().to_owned(),
+            color: label.color,
+        });
+        if ws.labels().get(&change_hex) == label.as_ref() {
+            return Ok(MutationResult::Unchanged);
+        }
+
+        ws.update_annotations(|annotations| annotations.set_label(change_hex, label))?;
+
+        Ok(MutationResult::Updated {
+            new_status: ws.format_status(),
+        })
+    }
+}
+
+impl Mutation for ResolveConflict {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let mut tx = ws.start_transaction()?;
+
+        let commit = ws.resolve_single_change(&self.id)?;
+        if ws.check_immutable(vec![commit.id().clone()])? {
+            precondition!("Revision {} is immutable", self.id.change.prefix);
+        }
+
+        let store = tx.repo().store().clone();
+        let repo_path = RepoPathBuf::from_internal_string(&self.path.repo_path);
+        let value = commit.tree()?.path_value(&repo_path)?;
+        let (contents, executable) =
+            match conflicts::materialize_tree_value(&store, &repo_path, value).block_on()? {
+                MaterializedTreeValue::FileConflict {
+                    contents,
+                    executable,
+                    ..
+                } => (contents, executable),
+                _ => precondition!("{} is not a conflicted file", self.path.relative_path.0),
+            };
+
+        let mut resolved = Vec::new();
+        if let Some(content) = self.content {
+            // query_conflict refuses these, so the content would be a lossy decoding at best
+            if contents
+                .iter()
+                .any(|term| std::str::from_utf8(term).is_err())
+            {
+                precondition!(
+                    "{} is not a text file; resolve it by choosing sides instead",
+                    self.path.relative_path.0
+                );
+            }
+
+            // inverse of the trailing-newline stripping done by query_conflict
+            resolved.extend_from_slice(content.lines.join("\n").as_bytes());
+            if self.has_trailing_newline {
+                resolved.push(b'\n');
+            }
+        } else {
+            // the regions are numbered the same way as the ones sent by query_revision
+            let MergeResult::Conflict(hunks) = files::merge(&contents) else {
+                precondition!("{} has no conflicted regions", self.path.relative_path.0);
+            };
+            let num_regions = hunks
+                .iter()
+                .filter(|hunk| hunk.as_resolved().is_none())
+                .count();
+            if self.choices.len() != num_regions {
+                precondition!(
+                    "{} has {} conflicted regions, but {} were resolved",
+                    self.path.relative_path.0,
+                    num_regions,
+                    self.choices.len()
+                );
+            }
+
+            let mut choices = self.choices.iter().enumerate();
+            for hunk in &hunks {
+                if let Some(content) = hunk.as_resolved() {
+                    resolved.extend_from_slice(content);
+                } else if let Some((index, choice)) = choices.next() {
+                    let Some(side) = hunk.get_add(*choice) else {
+                        precondition!("Conflict {} has no side #{}", index + 1, choice + 1);
+                    };
+                    resolved.extend_from_slice(side);
+                }
+            }
+        }
+
+        let file_id = store
+            .write_file(&repo_path, &mut resolved.as_slice())
+            .block_on()?;
+        let mut tree_builder = MergedTreeBuilder::new(commit.tree_id().clone());
+        tree_builder.set_or_remove(
+            repo_path,
+            Merge::normal(TreeValue::File {
+                id: file_id,
+                executable,
+            }),
+        );
+        let tree_id = tree_builder.write_tree(&store)?;
+
+        tx.repo_mut()
+            .rewrite_commit(&ws.data.settings, &commit)
+            .set_tree_id(tree_id)
+            .write()?;
+        tx.repo_mut().rebase_descendants(&ws.data.settings)?;
+
+        match ws.finish_transaction(
+            tx,
+            format!(
+                "resolve conflicts in {} in commit {}",
+                self.path.relative_path.0,
+                commit.id().hex()
+            ),
+        )? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for ResolveWithMergeTool {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let Some(editor) = ws.external_merge_editor()? else {
+            precondition!("No external merge tool is configured; set ui.merge-editor");
+        };
+
+        let mut tx = ws.start_transaction()?;
+
+        let commit = ws.resolve_single_change(&self.id)?;
+        if ws.check_immutable(vec![commit.id().clone()])? {
+            precondition!("Revision {} is immutable", self.id.change.prefix);
+        }
+
+        // blocks the worker until the tool exits
+        let repo_path = RepoPathBuf::from_internal_string(&self.path.repo_path);
+        let tree_id = match editor.edit_file(&commit.tree()?, &repo_path) {
+            Ok(tree_id) => tree_id,
+            Err(err) => precondition!("{err}"),
+        };
+
+        tx.repo_mut()
+            .rewrite_commit(&ws.data.settings, &commit)
+            .set_tree_id(tree_id)
+            .write()?;
+        tx.repo_mut().rebase_descendants(&ws.data.settings)?;
+
+        match ws.finish_transaction(
+            tx,
+            format!(
+                "resolve conflicts in {} in commit {} with a merge tool",
+                self.path.relative_path.0,
+                commit.id().hex()
+            ),
+        )? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for RewritePaths {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let regex = match Regex::new(&self.pattern) {
+            Ok(regex) => regex,
+            Err(err) => precondition!("Invalid pattern: {err}"),
+        };
+
+        let mut tx = ws.start_transaction()?;
+
+        let commit = ws.resolve_single_change(&self.id)?;
+        if ws.check_immutable(vec![commit.id().clone()])? {
+            precondition!("Revision {} is immutable", self.id.change.prefix);
+        }
+
+        let tree = commit.tree()?;
+        let matcher = build_matcher(&self.paths);
+        let files =
+            ws.find_line_replacements(&tree, matcher.as_ref(), &regex, &self.replacement)?;
+        if files.is_empty() {
+            return Ok(MutationResult::Unchanged);
+        }
+
+        let store = tx.repo().store().clone();
+        let mut tree_builder = MergedTreeBuilder::new(commit.tree_id().clone());
+        for file in &files {
+            let id = store
+                .write_file(&file.path, &mut file.new_content.as_bytes())
+                .block_on()?;
+            tree_builder.set_or_remove(
+                file.path.clone(),
+                Merge::normal(TreeValue::File {
+                    id,
+                    executable: file.executable,
+                }),
+            );
+        }
+        let new_tree_id = tree_builder.write_tree(&store)?;
+
+        tx.repo_mut()
+            .rewrite_commit(&ws.data.settings, &commit)
+            .set_tree_id(new_tree_id)
             .write()?;
 
         match ws.finish_transaction(
             tx,
-            format!("move changes from {} to {}", from.id().hex(), to.id().hex()),
+            format!(
+                "rewrite {} file(s) in commit {}",
+                files.len(),
+                commit.id().hex()
+            ),
         )? {
             Some(new_status) => Ok(MutationResult::Updated { new_status }),
             None => Ok(MutationResult::Unchanged),
@@ -420,35 +2137,149 @@ impl Mutation for MoveChanges {
     }
 }
 
-impl Mutation for CopyChanges {
+impl Mutation for FixRevisions {
+    fn rewrite_count(&self, ws: &WorkspaceSession) -> Result<Option<usize>> {
+        let ids = self
+            .ids
+            .iter()
+            .map(|id| CommitId::try_from_hex(&id.hex).expect("frontend-validated id"));
+        Ok(Some(ws.count_descendants(ids)?))
+    }
+
     fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
-        let mut tx = ws.start_transaction()?;
+        if ws.session.safe_mode {
+            precondition!("Fix tools are disabled in safe mode");
+        }
 
-        let from_tree = ws.resolve_single_commit(&self.from_id)?.tree()?;
-        let to = ws.resolve_single_change(&self.to_id)?;
-        let matcher = build_matcher(&self.paths);
+        let tools = fix::read_tools(&ws.data.settings)?;
+        if tools.is_empty() {
+            precondition!("No fix.tools are configured");
+        }
 
-        if ws.check_immutable(vec![to.id().clone()])? {
-            precondition!("Revisions are immutable");
+        let root_ids = self
+            .ids
+            .into_iter()
+            .map(|id| CommitId::try_from_hex(&id.hex).expect("frontend-validated id"))
+            .collect_vec();
+
+        if ws.check_immutable(root_ids.clone())? {
+            precondition!("Some revisions are immutable");
         }
 
-        // construct a restore tree - the destination with some portions overwritten by the source
-        let to_tree = to.tree()?;
-        let new_to_tree_id = rewrite::restore_tree(&from_tree, &to_tree, matcher.as_ref())?;
-        if &new_to_tree_id == to.tree_id() {
-            Ok(MutationResult::Unchanged)
-        } else {
-            tx.repo_mut()
-                .rewrite_commit(&ws.data.settings, &to)
-                .set_tree_id(new_to_tree_id)
-                .write()?;
+        let mut tx = ws.start_transaction()?;
+        let store = tx.repo().store().clone();
 
-            tx.repo_mut().rebase_descendants(&ws.data.settings)?;
+        // like jj fix, a path fixed in a commit is also fixed in its descendants, so that they
+        // don't have to be rebased onto the fixes (and likely conflict with them)
+        let commits: Vec<Commit> = RevsetExpression::commits(root_ids.clone())
+            .descendants()
+            .evaluate_programmatic(tx.base_repo().as_ref())?
+            .iter()
+            .commits(&store)
+            .try_collect()?;
+        let mut commit_paths: HashMap<CommitId, HashSet<RepoPathBuf>> = HashMap::new();
+        let mut inputs: HashSet<(RepoPathBuf, FileId)> = HashSet::new();
+        for commit in commits.iter().rev() {
+            let mut paths: HashSet<RepoPathBuf> = HashSet::new();
+            for parent_id in commit.parent_ids() {
+                if let Some(parent_paths) = commit_paths.get(parent_id) {
+                    paths.extend(parent_paths.iter().cloned());
+                }
+            }
 
-            match ws.finish_transaction(tx, format!("restore into commit {}", to.id().hex()))? {
-                Some(new_status) => Ok(MutationResult::Updated { new_status }),
-                None => Ok(MutationResult::Unchanged),
+            let mut diff_stream = commit
+                .parent_tree(tx.repo())?
+                .diff_stream(&commit.tree()?, &EverythingMatcher);
+            async {
+                while let Some(TreeDiffEntry { path, values }) = diff_stream.next().await {
+                    let (_, after) = values?;
+                    // deleted files have no terms, and each side of a conflict is fixed separately
+                    for term in after.into_iter().flatten() {
+                        if let TreeValue::File { id, .. } = term {
+                            inputs.insert((path.clone(), id));
+                            paths.insert(path.clone());
+                        }
+                    }
+                }
+                Ok::<(), BackendError>(())
             }
+            .block_on()?;
+
+            commit_paths.insert(commit.id().clone(), paths);
+        }
+
+        // tools are expected to be deterministic, so each version of a file is only fixed once
+        let mut fixed_ids: HashMap<(RepoPathBuf, FileId), FileId> = HashMap::new();
+        for (path, id) in inputs.into_iter().sorted() {
+            ws.session.callbacks.report_progress(ProgressEvent::Stdout {
+                line: path.as_internal_file_string().to_owned(),
+            });
+
+            let mut content = Vec::new();
+            store.read_file(&path, &id)?.read_to_end(&mut content)?;
+            let output = fix::fix_content(&tools, &path, &content);
+            for error in output.errors {
+                ws.session.callbacks.report_progress(ProgressEvent::Stderr {
+                    line: format!("{}: {error}", path.as_internal_file_string()),
+                });
+            }
+
+            if output.content != content {
+                let new_id = store
+                    .write_file(&path, &mut output.content.as_slice())
+                    .block_on()?;
+                fixed_ids.insert((path, id), new_id);
+            }
+        }
+
+        let mut num_checked = 0;
+        let mut num_fixed = 0;
+        tx.repo_mut()
+            .transform_descendants(&ws.data.settings, root_ids, |mut rewriter| {
+                let old_tree = rewriter.old_commit().tree()?;
+                let mut tree_builder = MergedTreeBuilder::new(old_tree.id().clone());
+                let mut changed = false;
+                for path in commit_paths
+                    .get(rewriter.old_commit().id())
+                    .into_iter()
+                    .flatten()
+                {
+                    let old_value = old_tree.path_value(path)?;
+                    let new_value = old_value.map(|term| match term {
+                        Some(TreeValue::File { id, executable }) => {
+                            match fixed_ids.get(&(path.clone(), id.clone())) {
+                                Some(new_id) => Some(TreeValue::File {
+                                    id: new_id.clone(),
+                                    executable: *executable,
+                                }),
+                                None => term.clone(),
+                            }
+                        }
+                        _ => term.clone(),
+                    });
+                    if new_value != old_value {
+                        tree_builder.set_or_remove(path.clone(), new_value);
+                        changed = true;
+                    }
+                }
+
+                num_checked += 1;
+                if changed {
+                    num_fixed += 1;
+                    let new_tree_id = tree_builder.write_tree(rewriter.mut_repo().store())?;
+                    rewriter
+                        .reparent(&ws.data.settings)?
+                        .set_tree_id(new_tree_id)
+                        .write()?;
+                } else if rewriter.parents_changed() {
+                    rewriter.reparent(&ws.data.settings)?.write()?;
+                }
+                Ok(())
+            })?;
+
+        match ws.finish_transaction(tx, format!("fix {num_fixed} of {num_checked} commits"))? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
         }
     }
 }
@@ -631,7 +2462,29 @@ impl Mutation for CreateRef {
 }
 
 impl Mutation for DeleteRef {
+    fn destructive_summary(&self, _ws: &WorkspaceSession) -> Result<Option<String>> {
+        Ok(Some(match &self.r#ref {
+            StoreRef::RemoteBookmark {
+                branch_name,
+                remote_name,
+                ..
+            } => format!("Forget bookmark {branch_name}@{remote_name}?"),
+            StoreRef::LocalBookmark { branch_name, .. } => {
+                format!("Delete bookmark {branch_name}?")
+            }
+            StoreRef::Tag { tag_name } => format!("Delete tag {tag_name}?"),
+        }))
+    }
+
     fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        if let StoreRef::LocalBookmark { branch_name, .. }
+        | StoreRef::RemoteBookmark { branch_name, .. } = &self.r#ref
+        {
+            if !self.allow_protected && ws.is_protected_bookmark(branch_name)? {
+                protected!("Bookmark {branch_name} is protected. Delete it anyway?");
+            }
+        }
+
         match self.r#ref {
             StoreRef::RemoteBookmark {
                 branch_name,
@@ -681,6 +2534,105 @@ impl Mutation for DeleteRef {
     }
 }
 
+impl Mutation for CleanupBookmarks {
+    fn destructive_summary(&self, _ws: &WorkspaceSession) -> Result<Option<String>> {
+        if self.names.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(if self.delete_remotes {
+            format!(
+                "Delete {} here and on tracked remotes?",
+                combine_bookmarks(&self.names)
+            )
+        } else {
+            format!("Delete {}?", combine_bookmarks(&self.names))
+        }))
+    }
+
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        if self.names.is_empty() {
+            return Ok(MutationResult::Unchanged);
+        }
+
+        for name in &self.names {
+            if ws.is_protected_bookmark(name)? {
+                precondition!("Bookmark {name} is protected");
+            }
+            if ws.view().get_local_bookmark(name).is_absent() {
+                precondition!("Bookmark {name} no longer exists");
+            }
+        }
+
+        let mut tx = ws.start_transaction()?;
+
+        // tracked remotes are either deleted along with the bookmark or untracked, so that a later
+        // push doesn't delete them unexpectedly
+        let mut remote_deletions: IndexMap<String, Vec<(String, BookmarkPushUpdate)>> =
+            IndexMap::new();
+        for name in &self.names {
+            tx.repo_mut()
+                .set_local_bookmark_target(name, RefTarget::absent());
+
+            for ((_, remote), remote_ref) in ws.view().remote_bookmarks_matching(
+                &StringPattern::exact(name),
+                &StringPattern::everything(),
+            ) {
+                if remote == REMOTE_NAME_FOR_LOCAL_GIT_REPO || !remote_ref.is_tracking() {
+                    continue;
+                }
+
+                if self.delete_remotes {
+                    let Some(old_target) = remote_ref.target.as_normal() else {
+                        precondition!("Bookmark {name}@{remote} is conflicted");
+                    };
+                    remote_deletions
+                        .entry(remote.to_owned())
+                        .or_default()
+                        .push((
+                            name.clone(),
+                            BookmarkPushUpdate {
+                                old_target: Some(old_target.clone()),
+                                new_target: None,
+                            },
+                        ));
+                } else {
+                    tx.repo_mut().untrack_remote_bookmark(name, remote);
+                }
+            }
+        }
+
+        if !remote_deletions.is_empty() {
+            let git_repo = match ws.git_repo()? {
+                Some(git_repo) => git_repo,
+                None => precondition!(
+                    "Deleting remote bookmarks requires a git backend, but this repo uses the {} backend",
+                    ws.backend_name()
+                ),
+            };
+
+            GitEnvironment::from_settings(&ws.data.settings)
+                .configure(&git_repo, ws.workspace.repo_path())?;
+            for (remote_name, branch_updates) in remote_deletions {
+                let targets = GitBranchPushTargets { branch_updates };
+                ws.session.callbacks.with_git(tx.repo_mut(), &|repo, cb| {
+                    Ok(git::push_branches(
+                        repo,
+                        &git_repo,
+                        &remote_name,
+                        &targets,
+                        cb,
+                    )?)
+                })?;
+            }
+        }
+
+        match ws.finish_transaction(tx, format!("clean up {}", combine_bookmarks(&self.names)))? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
 // does not currently enforce fast-forwards
 impl Mutation for MoveRef {
     fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
@@ -702,6 +2654,17 @@ impl Mutation for MoveRef {
                     precondition!("No such bookmark: {branch_name}");
                 }
 
+                if !self.allow_protected
+                    && ws.is_protected_bookmark(&branch_name)?
+                    && !old_target
+                        .added_ids()
+                        .all(|old_id| ws.repo().index().is_ancestor(old_id, commit.id()))
+                {
+                    protected!(
+                        "Bookmark {branch_name} is protected and would be moved backwards or sideways. Move it anyway?"
+                    );
+                }
+
                 tx.repo_mut().set_local_bookmark_target(
                     &branch_name,
                     RefTarget::normal(commit.id().clone()),
@@ -737,19 +2700,85 @@ impl Mutation for MoveRef {
 }
 
 impl Mutation for GitPush {
+    // only pushes which would discard commits on a remote need confirming
+    fn destructive_summary(&self, ws: &WorkspaceSession) -> Result<Option<String>> {
+        let view = ws.view();
+        let candidates: Vec<(&str, &str, LocalAndRemoteRef)> = match self {
+            GitPush::AllBookmarks { remote_name, .. } => view
+                .local_remote_bookmarks(remote_name)
+                .filter(|(_, targets)| targets.remote_ref.is_tracking())
+                .map(|(branch_name, targets)| (remote_name.as_str(), branch_name, targets))
+                .collect(),
+            GitPush::AllRemotes { branch_ref, .. } => {
+                let branch_name = branch_ref.as_branch()?;
+                view.all_remote_bookmarks()
+                    .filter(|((branch, _), remote_ref)| {
+                        remote_ref.is_tracking() && *branch == branch_name
+                    })
+                    .map(|((_, remote_name), remote_ref)| {
+                        let targets = LocalAndRemoteRef {
+                            local_target: view.get_local_bookmark(branch_name),
+                            remote_ref,
+                        };
+                        (remote_name, branch_name, targets)
+                    })
+                    .collect()
+            }
+            GitPush::RemoteBookmark {
+                remote_name,
+                branch_ref,
+                ..
+            } => {
+                let branch_name = branch_ref.as_branch()?;
+                let targets = LocalAndRemoteRef {
+                    local_target: view.get_local_bookmark(branch_name),
+                    remote_ref: view.get_remote_bookmark(branch_name, remote_name),
+                };
+                vec![(remote_name.as_str(), branch_name, targets)]
+            }
+        };
+
+        // unpushable bookmarks are left for execute() to report
+        let forced = candidates
+            .into_iter()
+            .filter_map(|(remote_name, branch_name, targets)| {
+                match classify_branch_push(branch_name, remote_name, targets) {
+                    Ok(Some(update)) if is_forced_push(ws, &update) => {
+                        Some(format!("{branch_name}@{remote_name}"))
+                    }
+                    _ => None,
+                }
+            })
+            .collect_vec();
+
+        Ok(if forced.is_empty() {
+            None
+        } else {
+            Some(format!(
+                "Force-push {}? Commits on the remote will be discarded.",
+                forced.join(", ")
+            ))
+        })
+    }
+
     fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
         let mut tx = ws.start_transaction()?;
 
         let git_repo = match ws.git_repo()? {
             Some(git_repo) => git_repo,
-            None => precondition!("No git backend"),
+            None => precondition!(
+                "Pushing requires a git backend, but this repo uses the {} backend",
+                ws.backend_name()
+            ),
         };
 
         // determine bookmarks to push, recording the old and new commits
         let mut remote_branch_updates: Vec<(&str, Vec<(String, refs::BookmarkPushUpdate)>)> =
             Vec::new();
         let remote_branch_refs: Vec<_> = match &*self {
-            GitPush::AllBookmarks { ref remote_name } => {
+            GitPush::AllBookmarks {
+                ref remote_name, ..
+            } => {
                 let mut branch_updates = Vec::new();
                 for (branch_name, targets) in ws.view().local_remote_bookmarks(&remote_name) {
                     if !targets.remote_ref.is_tracking() {
@@ -766,7 +2795,7 @@ impl Mutation for GitPush {
 
                 ws.view().remote_bookmarks(&remote_name).collect()
             }
-            GitPush::AllRemotes { branch_ref } => {
+            GitPush::AllRemotes { branch_ref, .. } => {
                 let branch_name = branch_ref.as_branch()?;
 
                 let mut remote_branch_refs = Vec::new();
@@ -808,6 +2837,7 @@ impl Mutation for GitPush {
             GitPush::RemoteBookmark {
                 ref remote_name,
                 ref branch_ref,
+                ..
             } => {
                 let branch_name = branch_ref.as_branch()?;
                 let local_target = ws.view().get_local_bookmark(branch_name);
@@ -836,6 +2866,30 @@ impl Mutation for GitPush {
             }
         };
 
+        // check for protected bookmarks which would be deleted or rewound
+        let allow_protected = match &*self {
+            GitPush::AllBookmarks {
+                allow_protected, ..
+            }
+            | GitPush::AllRemotes {
+                allow_protected, ..
+            }
+            | GitPush::RemoteBookmark {
+                allow_protected, ..
+            } => *allow_protected,
+        };
+        if !allow_protected {
+            for (remote_name, branch_updates) in &remote_branch_updates {
+                for (branch_name, update) in branch_updates {
+                    if is_forced_push(ws, update) && ws.is_protected_bookmark(branch_name)? {
+                        protected!(
+                            "Bookmark {branch_name} is protected and would be force-pushed to {remote_name}. Push anyway?"
+                        );
+                    }
+                }
+            }
+        }
+
         // check for conflicts
         let mut new_heads = vec![];
         for (_, branch_updates) in &mut remote_branch_updates {
@@ -888,6 +2942,8 @@ impl Mutation for GitPush {
         }
 
         // push to each remote
+        GitEnvironment::from_settings(&ws.data.settings)
+            .configure(&git_repo, ws.workspace.repo_path())?;
         for (remote_name, branch_updates) in remote_branch_updates.into_iter() {
             let targets = GitBranchPushTargets { branch_updates };
 
@@ -905,10 +2961,10 @@ impl Mutation for GitPush {
         match ws.finish_transaction(
             tx,
             match *self {
-                GitPush::AllBookmarks { remote_name } => {
+                GitPush::AllBookmarks { remote_name, .. } => {
                     format!("push all tracked branches to git remote {}", remote_name)
                 }
-                GitPush::AllRemotes { branch_ref } => {
+                GitPush::AllRemotes { branch_ref, .. } => {
                     format!(
                         "push {} to all tracked git remotes",
                         branch_ref.as_branch()?
@@ -917,6 +2973,7 @@ impl Mutation for GitPush {
                 GitPush::RemoteBookmark {
                     remote_name,
                     branch_ref,
+                    ..
                 } => {
                     format!(
                         "push {} to git remote {}",
@@ -938,7 +2995,10 @@ impl Mutation for GitFetch {
 
         let git_repo = match ws.git_repo()? {
             Some(git_repo) => git_repo,
-            None => precondition!("No git backend"),
+            None => precondition!(
+                "Fetching requires a git backend, but this repo uses the {} backend",
+                ws.backend_name()
+            ),
         };
 
         let mut remote_patterns = Vec::new();
@@ -965,12 +3025,14 @@ impl Mutation for GitFetch {
             }
         }
 
-        for (remote_name, pattern) in remote_patterns {
+        GitEnvironment::from_settings(&ws.data.settings)
+            .configure(&git_repo, ws.workspace.repo_path())?;
+        for (remote_name, pattern) in &remote_patterns {
             ws.session.callbacks.with_git(tx.repo_mut(), &|repo, cb| {
                 git::fetch(
                     repo,
                     &git_repo,
-                    &remote_name,
+                    remote_name,
                     &[pattern
                         .clone()
                         .map(StringPattern::exact)
@@ -983,6 +3045,14 @@ impl Mutation for GitFetch {
             })?;
         }
 
+        let fetched_remotes = remote_patterns
+            .into_iter()
+            .map(|(remote_name, _)| remote_name)
+            .collect_vec();
+        if let Err(err) = FetchHistory::record(ws.workspace.repo_path(), &fetched_remotes) {
+            log::warn!("failed to record fetch: {err:#}");
+        }
+
         match ws.finish_transaction(tx, format!("fetch from git remote(s)"))? {
             Some(new_status) => Ok(MutationResult::Updated { new_status }),
             None => Ok(MutationResult::Unchanged),
@@ -990,8 +3060,251 @@ impl Mutation for GitFetch {
     }
 }
 
+// checks for the steps in jj's FAQ; the session carries them out once it has released the workspace
+impl Mutation for ColocateRepository {
+    fn destructive_summary(&self, _ws: &WorkspaceSession) -> Result<Option<String>> {
+        Ok(Some(
+            "Move the repo's git store to .git? This can't be undone from gg.".to_owned(),
+        ))
+    }
+
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        if ws.is_colocated() {
+            precondition!("Repository is already colocated");
+        }
+
+        let git_repo = match ws.git_repo()? {
+            Some(git_repo) => git_repo,
+            None => precondition!(
+                "Colocating requires a git backend, but this repo uses the {} backend",
+                ws.backend_name()
+            ),
+        };
+
+        let workspace_root = ws.workspace.workspace_root().to_owned();
+        let repo_path = ws.workspace.repo_path().to_owned();
+        if !repo_path.starts_with(workspace_root.join(".jj")) {
+            precondition!("Only the repo's main workspace can be colocated");
+        }
+
+        let store_path = repo_path.join("store");
+        let internal_git_path = store_path.join("git");
+        if dunce::canonicalize(git_repo.path())? != dunce::canonicalize(&internal_git_path)? {
+            precondition!(
+                "Repository uses an external git repo: {}",
+                git_repo.path().display()
+            );
+        }
+
+        let dot_git_path = workspace_root.join(".git");
+        if dot_git_path.symlink_metadata().is_ok() {
+            precondition!("{} already exists", dot_git_path.display());
+        }
+
+        // everything which can be checked is, so that nothing is moved unless the rest is likely to work
+        let wc_commit = ws.get_commit(ws.wc_id())?;
+        let first_parent_id = &wc_commit.parent_ids()[0];
+        let head = if first_parent_id != ws.repo().store().root_commit_id() {
+            let head = git2::Oid::from_bytes(first_parent_id.as_bytes())?;
+            git_repo.find_commit(head)?;
+            Some(head)
+        } else {
+            None
+        };
+        let was_bare = git2::Config::open(&internal_git_path.join("config"))?
+            .get_bool("core.bare")
+            .unwrap_or(true);
+
+        // keep the old target, so that the conversion can be reversed by hand
+        let target_path = store_path.join("git_target");
+        let old_target = fs::read(&target_path).context("read git_target")?;
+        fs::write(store_path.join("git_target.bak"), &old_target).context("backup git_target")?;
+
+        ws.session.colocation = Some(Colocation {
+            workspace_root,
+            internal_git_path,
+            target_path,
+            old_target,
+            was_bare,
+            head,
+        });
+
+        Ok(MutationResult::Updated {
+            new_status: ws.format_status(),
+        })
+    }
+}
+
+impl Mutation for FetchFromPath {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let git_repo = match ws.git_repo()? {
+            Some(git_repo) => git_repo,
+            None => precondition!(
+                "Fetching requires a git backend, but this repo uses the {} backend",
+                ws.backend_name()
+            ),
+        };
+
+        let remote_name = self.remote_name.trim();
+        if remote_name.is_empty() || remote_name.contains(['/', ' ']) {
+            precondition!("Invalid remote name: {remote_name:?}");
+        }
+        if remote_name == REMOTE_NAME_FOR_LOCAL_GIT_REPO {
+            precondition!("Remote name '{remote_name}' is reserved for the local git repo");
+        }
+        if git_repo.find_remote(remote_name).is_ok() {
+            precondition!(
+                "A remote named '{remote_name}' is already configured; fetch from it instead"
+            );
+        }
+
+        // accept either a git repo or a jj repo which uses an internal git store
+        let source_path = ws.workspace.workspace_root().join(self.path.trim());
+        let source_repo = match git2::Repository::open(&source_path) {
+            Ok(source_repo) => source_repo,
+            Err(_) => match git2::Repository::open(source_path.join(".jj/repo/store/git")) {
+                Ok(source_repo) => source_repo,
+                Err(_) => precondition!("Not a git repository: {}", source_path.display()),
+            },
+        };
+        if dunce::canonicalize(source_repo.path()).ok() == dunce::canonicalize(git_repo.path()).ok()
+        {
+            precondition!("Cannot fetch a repository from itself");
+        }
+
+        // stale branches are removed by the prune, just like for a configured remote
+        let url = source_repo.path().to_string_lossy().into_owned();
+        let refspec = format!("+refs/heads/*:refs/remotes/{remote_name}/*");
+        let mut fetch_options = git2::FetchOptions::new();
+        fetch_options.prune(git2::FetchPrune::On);
+        git_repo
+            .remote_anonymous(&url)?
+            .fetch(&[&refspec], Some(&mut fetch_options), None)
+            .with_context(|| format!("fetch from {url}"))?;
+
+        let mut tx = ws.start_transaction()?;
+        git::import_some_refs(
+            tx.repo_mut(),
+            &ws.data.settings.git_settings(),
+            |ref_name| matches!(ref_name, RefName::RemoteBranch { remote, .. } if remote == remote_name),
+        )?;
+
+        match ws.finish_transaction(tx, format!("fetch from {url}"))? {
+            Some(new_status) => Ok(MutationResult::Updated { new_status }),
+            None => Ok(MutationResult::Unchanged),
+        }
+    }
+}
+
+impl Mutation for CheckpointWorkingCopy {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        let label = self.label.trim();
+        if label.is_empty() {
+            precondition!("Checkpoints need a label");
+        }
+
+        // the snapshot is made by starting the transaction; the checkpoint's own
+        // operation usually changes nothing, and just carries the tag
+        let mut tx = ws.start_transaction()?;
+        tx.set_tag(CHECKPOINT_TAG.to_owned(), label.to_owned());
+
+        let new_status =
+            ws.finish_transaction_unconditionally(tx, format!("checkpoint: {label}"))?;
+        Ok(MutationResult::Updated { new_status })
+    }
+}
+
+// the worker is blocked while jj runs, so GG can't race it for the repo; afterwards the
+// workspace must be reloaded, as the CLI may have rewritten the working copy state
+impl Mutation for RunJjCommand {
+    fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
+        if ws.session.safe_mode {
+            precondition!("jj commands are disabled in safe mode");
+        }
+
+        let Some(subcommand) = self.args.first() else {
+            precondition!("No command given");
+        };
+        if !ws
+            .data
+            .settings
+            .commands_allowed_subcommands()
+            .contains(subcommand)
+        {
+            precondition!("jj {subcommand} is not in gg.commands.allowed-subcommands");
+        }
+        if let Some(arg) = self.args.iter().find(|arg| {
+            RETARGETING_ARGS.iter().any(|flag| {
+                // short flags can have their value attached, long flags can use =
+                let attached = if flag.starts_with("--") {
+                    format!("{flag}=")
+                } else {
+                    flag.to_string()
+                };
+                arg.as_str() == *flag || arg.starts_with(&attached)
+            })
+        }) {
+            precondition!("{arg} can't be used; commands always run against this workspace");
+        }
+
+        // --repository is enough for jj, so a path which can't be a working directory is no problem
+        let mut command = Command::new(ws.data.settings.commands_jj_path());
+        GitEnvironment::from_settings(&ws.data.settings).apply(&mut command);
+        if let Some(dir) = paths::subprocess_dir(ws.workspace.workspace_root()) {
+            command.current_dir(dir);
+        }
+        let mut child = command
+            .arg("--repository")
+            .arg(ws.workspace.workspace_root())
+            .args(["--no-pager", "--color", "never"])
+            .args(&self.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("run {}", ws.data.settings.commands_jj_path()))?;
+
+        // callbacks belong to the worker thread, so the readers send lines back to it
+        let (tx, rx) = channel();
+        let stdout = child.stdout.take().ok_or(anyhow!("no stdout"))?;
+        let stderr = child.stderr.take().ok_or(anyhow!("no stderr"))?;
+        let readers = [
+            read_lines(stdout, tx.clone(), |line| ProgressEvent::Stdout { line }),
+            read_lines(stderr, tx, |line| ProgressEvent::Stderr { line }),
+        ];
+        for event in rx {
+            ws.session.callbacks.report_progress(event);
+        }
+        for reader in readers {
+            _ = reader.join();
+        }
+
+        let status = child.wait()?;
+        let updated = ws.load_at_head()?;
+        if !status.success() {
+            precondition!("jj {subcommand} failed: {status}");
+        }
+
+        if updated {
+            Ok(MutationResult::Updated {
+                new_status: ws.format_status(),
+            })
+        } else {
+            Ok(MutationResult::Unchanged)
+        }
+    }
+}
+
 // this is another case where it would be nice if we could reuse jj-cli's error messages
 impl Mutation for UndoOperation {
+    fn destructive_summary(&self, ws: &WorkspaceSession) -> Result<Option<String>> {
+        let head_op = op_walk::resolve_op_with_repo(ws.repo(), "@")?;
+        Ok(Some(format!(
+            "Undo operation \"{}\"?",
+            head_op.metadata().description
+        )))
+    }
+
     fn execute(self: Box<Self>, ws: &mut WorkspaceSession) -> Result<MutationResult> {
         let head_op = op_walk::resolve_op_with_repo(ws.repo(), "@")?; // XXX this should be behind an abstraction, maybe reused in snapshot
         let mut parent_ops = head_op.parents();
@@ -1014,11 +3327,26 @@ impl Mutation for UndoOperation {
 
         match ws.finish_transaction(tx, format!("undo operation {}", head_op.id().hex()))? {
             Some(new_status) => {
-                let working_copy = ws.get_commit(ws.wc_id())?;
-                let new_selection = ws.format_header(&working_copy, None)?;
-                Ok(MutationResult::UpdatedSelection {
+                let mut restored = Vec::new();
+                for change_id in self.selected {
+                    restored.push(match find_visible_change(ws, &change_id)? {
+                        Some(commit) => RestoredChange::Visible {
+                            header: ws.format_header(&commit, None)?,
+                        },
+                        None => RestoredChange::Hidden { change_id },
+                    });
+                }
+
+                // the primary selection falls back to the working copy if it was hidden
+                let new_selection = match restored.first() {
+                    Some(RestoredChange::Visible { header }) => header.clone(),
+                    _ => ws.format_header(&ws.get_commit(ws.wc_id())?, None)?,
+                };
+
+                Ok(MutationResult::Restored {
                     new_status,
                     new_selection,
+                    restored,
                 })
             }
             None => Ok(MutationResult::Unchanged),
@@ -1026,6 +3354,39 @@ impl Mutation for UndoOperation {
     }
 }
 
+// an unknown change id fails to resolve, which is the same as being hidden
+fn find_visible_change(ws: &WorkspaceSession, change_id: &ChangeId) -> Result<Option<Commit>> {
+    let Ok(revset) = ws.evaluate_revset_str(&change_id.hex) else {
+        return Ok(None);
+    };
+    let commit = revset
+        .as_ref()
+        .iter()
+        .commits(ws.repo().store())
+        .next()
+        .transpose()?;
+    Ok(commit)
+}
+
+fn read_lines(
+    source: impl Read + Send + 'static,
+    tx: Sender<ProgressEvent>,
+    event: fn(String) -> ProgressEvent,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        for line in BufReader::new(source).lines() {
+            match line {
+                Ok(line) => {
+                    if tx.send(event(line)).is_err() {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    })
+}
+
 fn combine_messages(source: &Commit, destination: &Commit, abandon_source: bool) -> String {
     if abandon_source {
         if source.description().is_empty() {
@@ -1047,7 +3408,7 @@ fn combine_bookmarks(branch_names: &[impl Display]) -> String {
     }
 }
 
-fn build_matcher(paths: &Vec<TreePath>) -> Box<dyn Matcher> {
+pub(super) fn build_matcher(paths: &Vec<TreePath>) -> Box<dyn Matcher> {
     if paths.is_empty() {
         Box::new(EverythingMatcher)
     } else {
@@ -1059,6 +3420,15 @@ fn build_matcher(paths: &Vec<TreePath>) -> Box<dyn Matcher> {
     }
 }
 
+// moving a bookmark backwards or sideways, or deleting it, discards commits on the remote
+fn is_forced_push(ws: &WorkspaceSession, update: &BookmarkPushUpdate) -> bool {
+    match (&update.old_target, &update.new_target) {
+        (Some(old_id), Some(new_id)) => !ws.repo().index().is_ancestor(old_id, new_id),
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
 fn classify_branch_push(
     branch_name: &str,
     remote_name: &str,
@@ -0,0 +1,102 @@
+//! Shortest unique prefixes for change and commit ids, shared by every query and reader so that an
+//! id is shown the same way in the log, in revision details and on later pages. When the operation changes,
+//! known lengths are kept as long as they still resolve to the same id, so only ids made ambiguous
+//! by new commits are recomputed.
+
+use std::collections::HashMap;
+
+use jj_lib::{
+    backend::{ChangeId, CommitId},
+    id_prefix::IdPrefixIndex,
+    object_id::{HexPrefix, ObjectId, PrefixResolution},
+    op_store::OperationId,
+    repo::{ReadonlyRepo, Repo},
+};
+use serde::{Deserialize, Serialize};
+
+/// prefix lengths by forward hex id, persisted with the derived data cache
+#[derive(Default, Serialize, Deserialize)]
+pub struct PrefixService {
+    op_id: String,
+    commits: HashMap<String, usize>,
+    changes: HashMap<String, usize>,
+    /// lengths from earlier operations, not yet checked against the current one
+    #[serde(skip)]
+    stale_commits: HashMap<String, usize>,
+    #[serde(skip)]
+    stale_changes: HashMap<String, usize>,
+}
+
+impl PrefixService {
+    pub fn commit_prefix_len<'a>(
+        &mut self,
+        repo: &ReadonlyRepo,
+        id: &CommitId,
+        index: impl Fn() -> IdPrefixIndex<'a>,
+    ) -> usize {
+        self.use_operation(repo.op_id());
+        lookup(
+            &mut self.commits,
+            &mut self.stale_commits,
+            id.hex(),
+            |prefix| match index().resolve_commit_prefix(repo, prefix) {
+                PrefixResolution::SingleMatch(found) => found == *id,
+                _ => false,
+            },
+            || index().shortest_commit_prefix_len(repo, id),
+        )
+    }
+
+    pub fn change_prefix_len<'a>(
+        &mut self,
+        repo: &ReadonlyRepo,
+        id: &ChangeId,
+        index: impl Fn() -> IdPrefixIndex<'a>,
+    ) -> usize {
+        self.use_operation(repo.op_id());
+        lookup(
+            &mut self.changes,
+            &mut self.stale_changes,
+            id.hex(),
+            |prefix| match index().resolve_change_prefix(repo, prefix) {
+                PrefixResolution::SingleMatch(found) => Some(found) == repo.resolve_change_id(id),
+                _ => false,
+            },
+            || index().shortest_change_prefix_len(repo, id),
+        )
+    }
+
+    // lengths from the previous operation become candidates, rather than being thrown away
+    fn use_operation(&mut self, op_id: &OperationId) {
+        let op_hex = op_id.hex();
+        if self.op_id != op_hex {
+            self.op_id = op_hex;
+            self.stale_commits.extend(self.commits.drain());
+            self.stale_changes.extend(self.changes.drain());
+        }
+    }
+}
+
+fn lookup(
+    current: &mut HashMap<String, usize>,
+    stale: &mut HashMap<String, usize>,
+    hex: String,
+    still_unique: impl FnOnce(&HexPrefix) -> bool,
+    compute: impl FnOnce() -> usize,
+) -> usize {
+    if let Some(len) = current.get(&hex) {
+        return *len;
+    }
+
+    // a length which still resolves to this id is kept, even if a shorter one would now do
+    let len = stale
+        .remove(&hex)
+        .filter(|len| {
+            hex.get(..*len)
+                .and_then(HexPrefix::new)
+                .is_some_and(|prefix| still_unique(&prefix))
+        })
+        .unwrap_or_else(compute);
+    current.insert(hex, len);
+    len
+}
@@ -1,40 +1,103 @@
 use std::{
-    borrow::Borrow,
-    io::Write,
+    borrow::{Borrow, Cow},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    env,
+    io::{Read, Write},
     iter::{Peekable, Skip},
     mem,
     ops::Range,
+    path::Path,
+    process::{Command, Stdio},
+    rc::Rc,
+    thread,
+    time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, TimeDelta, TimeZone};
 
+use encoding_rs::{Encoding, UTF_8};
 use futures_util::{try_join, StreamExt};
-use gix::bstr::ByteVec;
-use itertools::Itertools;
-use jj_cli::diff_util::{LineCompareMode, LineDiffOptions};
+use gix::bstr::{BString, ByteVec};
+use itertools::{EitherOrBoth, Itertools};
+use jj_cli::{
+    diff_util::{LineCompareMode, LineDiffOptions},
+    formatter::PlainTextFormatter,
+    revset_util,
+};
 use jj_lib::{
     backend::CommitId,
+    commit::Commit,
     conflicts::{self, MaterializedTreeValue},
     diff::{
         find_line_ranges, CompareBytesExactly, CompareBytesIgnoreAllWhitespace,
         CompareBytesIgnoreWhitespaceAmount, Diff, DiffHunk, DiffHunkKind,
     },
+    files::{self, MergeResult},
+    git::REMOTE_NAME_FOR_LOCAL_GIT_REPO,
     graph::{GraphEdge, GraphEdgeType, TopoGroupedGraphIterator},
-    matchers::EverythingMatcher,
+    matchers::{EverythingMatcher, FilesMatcher},
+    merge::Merge,
     merged_tree::{TreeDiffEntry, TreeDiffStream},
+    object_id::ObjectId,
+    op_store::OperationId,
+    op_walk,
+    operation::Operation,
     repo::Repo,
-    repo_path::RepoPath,
-    revset::{Revset, RevsetEvaluationError},
+    repo_path::{RepoPath, RepoPathBuf},
+    revset::{
+        Revset, RevsetDiagnostics, RevsetEvaluationError, RevsetExpression, RevsetIteratorExt,
+        RevsetResolutionError,
+    },
     rewrite,
+    signing::{SigStatus, Verification},
+    str_util::StringPattern,
+    view::View,
 };
 use pollster::FutureExt;
+use regex::Regex;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{
+    config::{
+        read_revset_aliases, GGSettings, CHECKPOINT_TAG, MUTATION_TAG, TOOL_TAG, TRUNK_ALIAS,
+    },
+    messages::{
+        self, ActivityEntry, ActivityKind, AmendTarget, BookmarkChange, ChangeHistory, ChangeHunk,
+        ChangeKind, ChangeOperation, ChangeOperationKind, Checkpoint, ConflictRegion, ConflictSide,
+        DescriptionCheck, DropTarget, ElidedRevisions, FileConflict, FileRange, GitRemote,
+        HiddenMatch, HunkKind, HunkLocation, Indentation, LineEndings, LogCoordinates, LogElision,
+        LogExportFormat, LogLine, LogPage, LogRow, MergeBase, MergedBookmark, MultilineString,
+        OperationDiff, OperationSummary, PathInfo, RemoteConnection, RepoVerification,
+        ResolvedPath, RevChange, RevChangesPage, RevConflict, RevConflictPath, RevDirectory,
+        RevHeader, RevId, RevNote, RevResult, RevSignature, RevStats, RevsetAlias, RewriteMatch,
+        StoreRef, TextFormat, TreePath, WorkingCopyDiff,
+    },
+    paths,
+};
 
-use crate::messages::{
-    ChangeHunk, ChangeKind, FileRange, HunkLocation, LogCoordinates, LogLine, LogPage, LogRow,
-    MultilineString, RevChange, RevConflict, RevId, RevResult,
+use super::{
+    annotations::Annotations,
+    cache::ActivityRecord,
+    descriptions::DescriptionRules,
+    fetches::FetchHistory,
+    git_env::{self, GitEnvironment},
+    gui_util::{RevsetError, TRUNK_BOOKMARKS},
+    mutations::build_matcher,
+    WorkspaceSession,
 };
 
-use super::WorkspaceSession;
+// imports and fetches can change far more revisions than are useful to list
+const MAX_OPERATION_DIFF_REVISIONS: usize = 100;
+
+// loading operations is slow, so long op logs aren't searched all the way back
+const MAX_CHECKPOINT_SCAN_OPERATIONS: usize = 1000;
+const MAX_CHANGE_HISTORY_OPERATIONS: usize = 1000;
+const MAX_OPERATION_LOG_ENTRIES: usize = 200;
+const MAX_ACTIVITY_REVISIONS: usize = 20;
+
+// the worker waits for the description command, so a hung one mustn't freeze the window
+const DESCRIPTION_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
 
 struct LogStem {
     source: LogCoordinates,
@@ -52,53 +115,100 @@ pub struct QueryState {
     next_row: usize,
     /// ongoing vertical lines; nodes will be placed on or around these
     stems: Vec<Option<LogStem>>,
+    /// follow only the first parent of merges, like `git log --first-parent`
+    first_parent: bool,
+    /// include commits which are no longer visible, such as abandoned or rewritten ones
+    pub show_hidden: bool,
+    /// collapse linear runs of uninteresting commits into summary rows
+    elision: Option<LogElision>,
+    /// operation the first page was evaluated at; later pages from another operation
+    /// would skip or repeat rows, and their lines wouldn't join up
+    op_id: Option<OperationId>,
+    /// the query the user asked for, when a narrower one is being paged
+    narrowed_from: Option<String>,
 }
 
 impl QueryState {
-    pub fn new(page_size: usize) -> QueryState {
+    pub fn new(page_size: usize, first_parent: bool, show_hidden: bool) -> QueryState {
         QueryState {
             page_size,
             next_row: 0,
             stems: Vec::new(),
+            first_parent,
+            show_hidden,
+            elision: None,
+            op_id: None,
+            narrowed_from: None,
         }
     }
+
+    pub fn with_elision(mut self, elision: Option<LogElision>) -> QueryState {
+        self.elision = elision;
+        self
+    }
+
+    pub fn with_narrowing(mut self, narrowed_from: Option<String>) -> QueryState {
+        self.narrowed_from = narrowed_from;
+        self
+    }
+
+    /// true if the repo has changed since the query's first page
+    pub fn is_stale(&self, ws: &WorkspaceSession) -> bool {
+        self.op_id
+            .as_ref()
+            .is_some_and(|op_id| op_id != ws.repo().op_id())
+    }
+}
+
+type GraphNodeResult = Result<(CommitId, Vec<GraphEdge<CommitId>>), RevsetEvaluationError>;
+type ImmutableFn<'q> = Box<dyn Fn(&CommitId) -> Result<bool, RevsetEvaluationError> + 'q>;
+type LogNodeResult =
+    Result<(CommitId, Vec<GraphEdge<CommitId>>, Option<ElidedRun>), RevsetEvaluationError>;
+
+/// commits after a row's own which it stands in for; the row takes the last one's edges
+struct ElidedRun {
+    count: usize,
+    last: CommitId,
 }
 
 /// live instance of a query
 pub struct QuerySession<'q, 'w: 'q> {
     pub ws: &'q WorkspaceSession<'w>,
     pub state: QueryState,
-    iter: Peekable<
-        Skip<
-            TopoGroupedGraphIterator<
-                CommitId,
-                Box<
-                    dyn Iterator<
-                            Item = Result<
-                                (CommitId, Vec<GraphEdge<CommitId>>),
-                                RevsetEvaluationError,
-                            >,
-                        > + 'q,
-                >,
-            >,
-        >,
-    >,
-    is_immutable: Box<dyn Fn(&CommitId) -> Result<bool, RevsetEvaluationError> + 'q>,
+    iter: Peekable<Skip<Box<dyn Iterator<Item = LogNodeResult> + 'q>>>,
+    is_immutable: ImmutableFn<'q>,
 }
 
 impl<'q, 'w> QuerySession<'q, 'w> {
     pub fn new(
         ws: &'q WorkspaceSession<'w>,
         revset: &'q dyn Revset,
-        state: QueryState,
+        mut state: QueryState,
     ) -> QuerySession<'q, 'w> {
-        let iter = TopoGroupedGraphIterator::new(revset.iter_graph())
-            .skip(state.next_row)
-            .peekable();
+        state.op_id.get_or_insert_with(|| ws.repo().op_id().clone());
+
+        let graph = if state.first_parent {
+            Box::new(FirstParentIterator::new(revset.iter_graph()))
+        } else {
+            revset.iter_graph()
+        };
 
         let immutable_revset = ws.evaluate_immutable().unwrap();
         let is_immutable = immutable_revset.containing_fn();
 
+        // elision is applied after grouping, which keeps linear runs together
+        let grouped = TopoGroupedGraphIterator::new(graph);
+        let nodes: Box<dyn Iterator<Item = LogNodeResult> + 'q> = match &state.elision {
+            Some(elision) => Box::new(ElidingIterator::new(
+                grouped,
+                ws,
+                elision,
+                immutable_revset.containing_fn(),
+            )),
+            None => Box::new(grouped.map(|node| node.map(|(id, edges)| (id, edges, None)))),
+        };
+        let iter = nodes.skip(state.next_row).peekable();
+
         QuerySession {
             ws,
             iter,
@@ -114,7 +224,7 @@ impl<'q, 'w> QuerySession<'q, 'w> {
 
         let root_id = self.ws.repo().store().root_commit_id().clone();
 
-        while let Some(Ok((commit_id, commit_edges))) = self.iter.next() {
+        while let Some(Ok((commit_id, commit_edges, elided))) = self.iter.next() {
             // output lines to draw for the current row
             let mut lines: Vec<LogLine> = Vec::new();
 
@@ -229,11 +339,28 @@ impl<'q, 'w> QuerySession<'q, 'w> {
                 }));
             }
 
+            let push_bookmarks = if header.is_immutable {
+                vec![]
+            } else {
+                self.ws
+                    .push_targets()
+                    .get(&commit_id)
+                    .cloned()
+                    .unwrap_or_default()
+            };
+
+            let elided = elided.map(|run| ElidedRevisions {
+                count: run.count,
+                last: self.ws.format_commit_id(&run.last),
+            });
             rows.push(LogRow {
+                accessible_description: describe_row(&header, elided.as_ref(), self.ws.now()),
                 revision: header,
                 location: LogCoordinates(column, row),
                 padding,
                 lines,
+                push_bookmarks,
+                elided,
             });
             row = row + 1;
 
@@ -265,6 +392,9 @@ impl<'q, 'w> QuerySession<'q, 'w> {
         Ok(LogPage {
             rows,
             has_more: self.iter.peek().is_some(),
+            invalidated: false,
+            hidden_match: None,
+            narrowed_from: self.state.narrowed_from.clone(),
         })
     }
 
@@ -281,16 +411,427 @@ impl<'q, 'w> QuerySession<'q, 'w> {
     }
 }
 
+/// filters a graph (in reverse topological order) down to the heads and their first-parent chains.
+/// this is deterministic, so restarting a query and skipping rows yields consistent pages
+struct FirstParentIterator<'q> {
+    inner: Box<dyn Iterator<Item = GraphNodeResult> + 'q>,
+    // targets of any edge seen so far; a node which is not a target is a head
+    reached: HashSet<CommitId>,
+    // targets of first-parent edges from nodes which were yielded
+    wanted: HashSet<CommitId>,
+}
+
+impl<'q> FirstParentIterator<'q> {
+    fn new(inner: Box<dyn Iterator<Item = GraphNodeResult> + 'q>) -> Self {
+        FirstParentIterator {
+            inner,
+            reached: HashSet::new(),
+            wanted: HashSet::new(),
+        }
+    }
+}
+
+impl Iterator for FirstParentIterator<'_> {
+    type Item = GraphNodeResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (id, mut edges) = match self.inner.next()? {
+                Ok(node) => node,
+                Err(err) => return Some(Err(err)),
+            };
+
+            let is_head = !self.reached.contains(&id);
+            let is_wanted = self.wanted.remove(&id);
+            for edge in &edges {
+                self.reached.insert(edge.target.clone());
+            }
+
+            if is_head || is_wanted {
+                edges.truncate(1);
+                if let Some(edge) = edges.first() {
+                    self.wanted.insert(edge.target.clone());
+                }
+                return Some(Ok((id, edges)));
+            }
+        }
+    }
+}
+
+type ElidableNodeResult = Result<(CommitId, Vec<GraphEdge<CommitId>>, bool), RevsetEvaluationError>;
+
+/// replaces linear runs of immutable commits with no refs by their first commit. interior commits
+/// must have no other children, so that no edge is left pointing into the middle of a run
+struct ElidingIterator<'q, 'w: 'q, I: Iterator<Item = GraphNodeResult>> {
+    inner: Peekable<I>,
+    ws: &'q WorkspaceSession<'w>,
+    min_run: usize,
+    keep: HashSet<CommitId>,
+    expanded: HashSet<CommitId>,
+    is_immutable: ImmutableFn<'q>,
+    // parents of immutable commits, which are immutable too; see LogStem::known_immutable
+    known_immutable: HashSet<CommitId>,
+    // number of edges seen so far which target each commit
+    reached: HashMap<CommitId, usize>,
+    // commits from a run which was too short or expanded, yet to be yielded
+    pending: VecDeque<(CommitId, Vec<GraphEdge<CommitId>>)>,
+}
+
+impl<'q, 'w: 'q, I: Iterator<Item = GraphNodeResult>> ElidingIterator<'q, 'w, I> {
+    fn new(
+        inner: I,
+        ws: &'q WorkspaceSession<'w>,
+        elision: &LogElision,
+        is_immutable: ImmutableFn<'q>,
+    ) -> Self {
+        let parse_ids = |ids: &[messages::CommitId]| {
+            ids.iter()
+                .filter_map(|id| CommitId::try_from_hex(&id.hex).ok())
+                .collect()
+        };
+        ElidingIterator {
+            inner: inner.peekable(),
+            ws,
+            min_run: elision.min_run.max(2),
+            keep: parse_ids(&elision.keep),
+            expanded: parse_ids(&elision.expanded),
+            is_immutable,
+            known_immutable: HashSet::new(),
+            reached: HashMap::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// takes the next node, along with whether it could be part of a run
+    fn consume(&mut self) -> Option<ElidableNodeResult> {
+        let (id, edges) = match self.inner.next()? {
+            Ok(node) => node,
+            Err(err) => return Some(Err(err)),
+        };
+
+        for edge in &edges {
+            *self.reached.entry(edge.target.clone()).or_default() += 1;
+        }
+
+        let immutable = match self.known_immutable.remove(&id) {
+            true => true,
+            false => match (self.is_immutable)(&id) {
+                Ok(immutable) => immutable,
+                Err(err) => return Some(Err(err)),
+            },
+        };
+        if immutable {
+            for edge in &edges {
+                self.known_immutable.insert(edge.target.clone());
+            }
+        }
+
+        let elidable = immutable && self.is_uninteresting(&id, &edges);
+        Some(Ok((id, edges, elidable)))
+    }
+
+    fn is_uninteresting(&self, id: &CommitId, edges: &[GraphEdge<CommitId>]) -> bool {
+        edges.len() == 1
+            && edges[0].edge_type == GraphEdgeType::Direct
+            && !self.keep.contains(id)
+            && self.ws.ref_index().get(id).is_empty()
+            && !self
+                .ws
+                .repo()
+                .view()
+                .wc_commit_ids()
+                .values()
+                .any(|wc_id| wc_id == id)
+    }
+
+    // the next node continues a run if it's the only parent of the run's end and has no other
+    // children; as the parent of an immutable commit, it's immutable itself
+    fn continues_run(&mut self, target: &CommitId) -> bool {
+        match self.inner.peek() {
+            Some(Ok((id, _))) if id != target => false,
+            Some(Ok((id, edges))) => {
+                let (id, edges) = (id.clone(), edges.clone());
+                self.reached.get(&id) == Some(&1) && self.is_uninteresting(&id, &edges)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<I: Iterator<Item = GraphNodeResult>> Iterator for ElidingIterator<'_, '_, I> {
+    type Item = LogNodeResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((id, edges)) = self.pending.pop_front() {
+            return Some(Ok((id, edges, None)));
+        }
+
+        let (id, edges, elidable) = match self.consume()? {
+            Ok(node) => node,
+            Err(err) => return Some(Err(err)),
+        };
+        if !elidable {
+            return Some(Ok((id, edges, None)));
+        }
+
+        let mut run = vec![(id, edges)];
+        while self.continues_run(&run[run.len() - 1].1[0].target) {
+            match self.consume()? {
+                Ok((id, edges, _)) => run.push((id, edges)),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        if run.len() < self.min_run || self.expanded.contains(&run[0].0) {
+            let mut run = run.into_iter();
+            let (id, edges) = run.next()?;
+            self.pending.extend(run);
+            return Some(Ok((id, edges, None)));
+        }
+
+        let count = run.len();
+        let (last, last_edges) = run.pop()?;
+        let (first, _) = run.swap_remove(0);
+        Some(Ok((first, last_edges, Some(ElidedRun { count, last }))))
+    }
+}
+
+/// e.g. "merge commit by Alice, 3 days ago, bookmark main, has conflict"
+fn describe_row(
+    header: &RevHeader,
+    elided: Option<&ElidedRevisions>,
+    now: DateTime<Local>,
+) -> String {
+    let kind = if header.parent_ids.len() > 1 {
+        "merge commit"
+    } else {
+        "commit"
+    };
+    let state = if header.is_working_copy {
+        "working copy "
+    } else if header.is_hidden {
+        "hidden "
+    } else if header.is_immutable {
+        "immutable "
+    } else {
+        ""
+    };
+    let author = if !header.author.name.is_empty() {
+        &header.author.name
+    } else if !header.author.email.is_empty() {
+        &header.author.email
+    } else {
+        "unknown author"
+    };
+    let age = describe_age(now - header.author.timestamp);
+
+    let mut parts = vec![match elided {
+        Some(run) => format!("{} collapsed commits, newest by {author}, {age}", run.count),
+        None => format!("{state}{kind} by {author}, {age}"),
+    }];
+    for store_ref in &header.refs {
+        parts.push(match store_ref {
+            StoreRef::LocalBookmark { branch_name, .. } => format!("bookmark {branch_name}"),
+            StoreRef::RemoteBookmark {
+                branch_name,
+                remote_name,
+                ..
+            } => format!("bookmark {branch_name}@{remote_name}"),
+            StoreRef::Tag { tag_name } => format!("tag {tag_name}"),
+        });
+    }
+    if header.has_conflict {
+        parts.push("has conflict".to_owned());
+    }
+
+    parts.join(", ")
+}
+
+fn describe_age(age: TimeDelta) -> String {
+    let (count, unit) = if age.num_days() >= 365 {
+        (age.num_days() / 365, "year")
+    } else if age.num_days() >= 30 {
+        (age.num_days() / 30, "month")
+    } else if age.num_days() >= 1 {
+        (age.num_days(), "day")
+    } else if age.num_hours() >= 1 {
+        (age.num_hours(), "hour")
+    } else if age.num_minutes() >= 1 {
+        (age.num_minutes(), "minute")
+    } else {
+        return "just now".to_owned();
+    };
+
+    if count == 1 {
+        format!("1 {unit} ago")
+    } else {
+        format!("{count} {unit}s ago")
+    }
+}
+
 #[cfg(test)]
 pub fn query_log(ws: &WorkspaceSession, revset_str: &str, max_results: usize) -> Result<LogPage> {
-    let state = QueryState::new(max_results);
-    let revset = ws.evaluate_revset_str(revset_str)?;
+    query_log_with(ws, revset_str, max_results, false, false)
+}
+
+#[cfg(test)]
+pub fn query_log_with(
+    ws: &WorkspaceSession,
+    revset_str: &str,
+    max_results: usize,
+    first_parent: bool,
+    show_hidden: bool,
+) -> Result<LogPage> {
+    let state = QueryState::new(max_results, first_parent, show_hidden);
+    let revset = if show_hidden {
+        ws.evaluate_revset_str_with_hidden(revset_str)
+    } else {
+        ws.evaluate_revset_str(revset_str)
+    };
+    let revset = match revset {
+        Ok(revset) => revset,
+        Err(err) => match query_hidden_match(ws, &err)? {
+            Some(page) => return Ok(page),
+            None => return Err(err.into()),
+        },
+    };
     let mut session = QuerySession::new(ws, &*revset, state);
     session.get_page()
 }
 
+/// when a query fails because it names a change with no visible revisions, offers its hidden ones instead
+pub fn query_hidden_match(ws: &WorkspaceSession, err: &RevsetError) -> Result<Option<LogPage>> {
+    let RevsetError::Resolution(RevsetResolutionError::NoSuchRevision { name, .. }) = err else {
+        return Ok(None);
+    };
+
+    let commits = ws.resolve_hidden_change(name)?;
+    if commits.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(LogPage {
+        rows: Vec::new(),
+        has_more: false,
+        invalidated: false,
+        narrowed_from: None,
+        hidden_match: Some(HiddenMatch {
+            symbol: name.clone(),
+            revisions: commits
+                .iter()
+                .map(|commit| ws.format_header(commit, None))
+                .collect::<Result<_>>()?,
+        }),
+    }))
+}
+
+/// writes every row of a query to the output, a page at a time; returns the number of rows written
+pub fn export_log(
+    ws: &WorkspaceSession,
+    revset_str: &str,
+    first_parent: bool,
+    show_hidden: bool,
+    page_size: usize,
+    format: &LogExportFormat,
+    output: &mut impl Write,
+) -> Result<usize> {
+    let template = match format {
+        LogExportFormat::Template { template } => Some(ws.parse_commit_template(template)?),
+        _ => None,
+    };
+
+    let revset = if show_hidden {
+        ws.evaluate_revset_str_with_hidden(revset_str)?
+    } else {
+        ws.evaluate_revset_str(revset_str)?
+    };
+    let state = QueryState::new(page_size, first_parent, show_hidden);
+    let mut session = QuerySession::new(ws, &*revset, state);
+
+    match format {
+        LogExportFormat::Json => output.write_all(b"[")?,
+        LogExportFormat::Csv => output.write_all(
+            b"change_id,commit_id,author_name,author_email,timestamp,description,refs,parents\n",
+        )?,
+        LogExportFormat::Template { .. } => (),
+    }
+
+    let mut count = 0;
+    loop {
+        let page = session.get_page()?;
+        for row in page.rows {
+            let header = row.revision;
+            match format {
+                LogExportFormat::Json => {
+                    output.write_all(if count == 0 { b"\n" } else { b",\n" })?;
+                    serde_json::to_writer(&mut *output, &header)?;
+                }
+                LogExportFormat::Csv => {
+                    let refs = header
+                        .refs
+                        .iter()
+                        .map(|r| match r {
+                            StoreRef::LocalBookmark { branch_name, .. } => branch_name.clone(),
+                            StoreRef::RemoteBookmark {
+                                branch_name,
+                                remote_name,
+                                ..
+                            } => format!("{branch_name}@{remote_name}"),
+                            StoreRef::Tag { tag_name } => tag_name.clone(),
+                        })
+                        .join(" ");
+                    let parents = header.parent_ids.iter().map(|id| &id.hex).join(" ");
+                    let fields = [
+                        &header.id.change.hex,
+                        &header.id.commit.hex,
+                        &header.author.name,
+                        &header.author.email,
+                        &header.author.timestamp.to_rfc3339(),
+                        &header.description.lines.join("\n"),
+                        &refs,
+                        &parents,
+                    ];
+                    writeln!(output, "{}", fields.iter().map(|f| csv_field(f)).join(","))?;
+                }
+                LogExportFormat::Template { .. } => {
+                    let commit = ws.get_commit(&CommitId::try_from_hex(&header.id.commit.hex)?)?;
+                    let mut formatter = PlainTextFormatter::new(&mut *output);
+                    template
+                        .as_ref()
+                        .expect("template parsed")
+                        .format(&commit, &mut formatter)?;
+                }
+            }
+            count += 1;
+        }
+
+        if !page.has_more {
+            break;
+        }
+    }
+
+    if let LogExportFormat::Json = format {
+        output.write_all(if count == 0 { b"]\n" } else { b"\n]\n" })?;
+    }
+    output.flush()?;
+
+    Ok(count)
+}
+
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains(['"', ',', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
 // XXX this is reloading the header, which the client already has
-pub fn query_revision(ws: &WorkspaceSession, id: RevId) -> Result<RevResult> {
+pub fn query_revision(
+    ws: &WorkspaceSession,
+    id: RevId,
+    aggregate_directories: bool,
+) -> Result<RevResult> {
     let commit = match ws.resolve_optional_id(&id)? {
         Some(commit) => commit,
         None => return Ok(RevResult::NotFound { id }),
@@ -315,6 +856,7 @@ pub fn query_revision(ws: &WorkspaceSession, id: RevId) -> Result<RevResult> {
                             conflicts.push(RevConflict {
                                 path: ws.format_path(path)?,
                                 hunk,
+                                regions: format_conflict_regions(&contents),
                             });
                         }
                     }
@@ -326,11 +868,38 @@ pub fn query_revision(ws: &WorkspaceSession, id: RevId) -> Result<RevResult> {
         }
     }
 
+    let page_size = ws
+        .session
+        .force_changes_page_size
+        .unwrap_or(ws.data.settings.query_changes_page_size());
+
     let mut changes = Vec::new();
     let tree_diff = parent_tree.diff_stream(&tree, &EverythingMatcher);
-    format_tree_changes(ws, &mut changes, tree_diff).block_on()?;
+    let has_more_changes =
+        format_tree_changes(ws, &mut changes, tree_diff, 0, page_size).block_on()?;
+
+    // conflicts which are present in this revision, whether or not it introduced them
+    let mut conflicted_paths = Vec::new();
+    if commit.has_conflict()? {
+        for (path, value) in tree.conflicts() {
+            conflicted_paths.push(RevConflictPath {
+                path: ws.format_path(path)?,
+                num_sides: value.num_sides(),
+            });
+        }
+    }
 
     let header = ws.format_header(&commit, None)?;
+    let stats = get_revision_stats(ws, &commit)?;
+    let signature = format_signature(ws, &commit);
+
+    let directories = if aggregate_directories {
+        let tree_diff = parent_tree.diff_stream(&tree, &EverythingMatcher);
+        let file_stats = format_tree_stats(ws, tree_diff).block_on()?;
+        Some(aggregate_directories_stats(ws, file_stats)?)
+    } else {
+        None
+    };
 
     let parents = commit
         .parents()
@@ -352,126 +921,1679 @@ pub fn query_revision(ws: &WorkspaceSession, id: RevId) -> Result<RevResult> {
         header,
         parents,
         changes,
+        has_more_changes,
         conflicts,
+        conflicted_paths,
+        stats,
+        directories,
+        signature,
     })
 }
 
-pub fn query_remotes(
+// verification can run gpg or ssh-keygen, so it's only done for one revision at a time; the store
+// caches results, so reselecting a revision is cheap
+fn format_signature(ws: &WorkspaceSession, commit: &Commit) -> RevSignature {
+    if !commit.is_signed() {
+        return RevSignature::Unsigned;
+    }
+
+    // verifying runs the configured gpg or ssh-keygen program
+    if ws.session.safe_mode {
+        return RevSignature::UnknownKey {
+            key: None,
+            signer: None,
+        };
+    }
+
+    match commit.verification() {
+        Ok(None) => RevSignature::Unsigned,
+        Ok(Some(Verification {
+            status,
+            key,
+            display,
+        })) => match status {
+            SigStatus::Good => RevSignature::Good {
+                key,
+                signer: display,
+            },
+            SigStatus::Bad => RevSignature::Bad {
+                key,
+                signer: display,
+            },
+            SigStatus::Unknown => RevSignature::UnknownKey {
+                key,
+                signer: display,
+            },
+        },
+        Err(err) => {
+            log::warn!("verify signature of {}: {err}", commit.id().hex());
+            RevSignature::UnknownKey {
+                key: None,
+                signer: None,
+            }
+        }
+    }
+}
+
+/// continues a revision's change list after the first page, which is returned by query_revision
+pub fn query_revision_paths_page(
     ws: &WorkspaceSession,
-    tracking_branch: Option<String>,
-) -> Result<Vec<String>> {
-    let git_repo = match ws.git_repo()? {
-        Some(git_repo) => git_repo,
-        None => return Err(anyhow!("No git backend")),
-    };
+    id: RevId,
+    skip: usize,
+) -> Result<RevChangesPage> {
+    // the exact commit, so that pages are consistent even if the change has since been rewritten
+    let commit = ws.resolve_single_commit(&id.commit)?;
 
-    let all_remotes: Vec<String> = git_repo
-        .remotes()?
-        .into_iter()
-        .filter_map(|remote| remote.map(|remote| remote.to_owned()))
-        .collect();
+    let page_size = ws
+        .session
+        .force_changes_page_size
+        .unwrap_or(ws.data.settings.query_changes_page_size());
 
-    let matching_remotes = match tracking_branch {
-        Some(branch_name) => all_remotes
-            .into_iter()
-            .filter(|remote_name| {
-                let remote_ref = ws.view().get_remote_bookmark(&branch_name, &remote_name);
-                !remote_ref.is_absent() && remote_ref.is_tracking()
-            })
-            .collect(),
-        None => all_remotes,
-    };
+    let commit_parents: Result<Vec<_>, _> = commit.parents().collect();
+    let parent_tree = rewrite::merge_commit_trees(ws.repo(), &commit_parents?)?;
+    let tree = commit.tree()?;
 
-    Ok(matching_remotes)
-}
+    let mut changes = Vec::new();
+    let tree_diff = parent_tree.diff_stream(&tree, &EverythingMatcher);
+    let has_more = format_tree_changes(ws, &mut changes, tree_diff, skip, page_size).block_on()?;
 
-async fn format_tree_changes(
-    ws: &WorkspaceSession<'_>,
-    changes: &mut Vec<RevChange>,
-    mut tree_diff: TreeDiffStream<'_>,
-) -> Result<()> {
-    let store = ws.repo().store();
+    Ok(RevChangesPage { changes, has_more })
+}
 
-    while let Some(TreeDiffEntry { path, values }) = tree_diff.next().await {
-        let (before, after) = values?;
+/// compares the working copy's tree (as of its latest snapshot) directly against any revision,
+/// such as the total diff of a stack against trunk
+pub fn query_working_copy_diff(
+    ws: &WorkspaceSession,
+    base: &str,
+    skip: usize,
+) -> Result<WorkingCopyDiff> {
+    let base_commit = ws.resolve_single_str(base)?;
+    let wc_commit = ws.get_commit(ws.wc_id())?;
 
-        let kind = if before.is_present() && after.is_present() {
-            ChangeKind::Modified
-        } else if before.is_absent() {
-            ChangeKind::Added
-        } else {
-            ChangeKind::Deleted
-        };
+    let page_size = ws
+        .session
+        .force_changes_page_size
+        .unwrap_or(ws.data.settings.query_changes_page_size());
 
-        let has_conflict = !after.is_resolved();
+    let mut changes = Vec::new();
+    let tree_diff = base_commit
+        .tree()?
+        .diff_stream(&wc_commit.tree()?, &EverythingMatcher);
+    let has_more = format_tree_changes(ws, &mut changes, tree_diff, skip, page_size).block_on()?;
 
-        let before_future = conflicts::materialize_tree_value(store, &path, before);
-        let after_future = conflicts::materialize_tree_value(store, &path, after);
-        let (before_value, after_value) = try_join!(before_future, after_future)?;
+    Ok(WorkingCopyDiff {
+        base: ws.format_header(&base_commit, None)?,
+        changes,
+        has_more,
+        new_status: None,
+    })
+}
 
-        let hunks = get_value_hunks(3, &path, before_value, after_value)?;
+pub fn query_revision_stats(
+    ws: &WorkspaceSession,
+    ids: Vec<messages::CommitId>,
+) -> Result<Vec<RevStats>> {
+    ids.iter()
+        .map(|id| {
+            let commit = ws.get_commit(&CommitId::try_from_hex(&id.hex)?)?;
+            get_revision_stats(ws, &commit)
+        })
+        .collect()
+}
 
-        changes.push(RevChange {
-            path: ws.format_path(path)?,
-            kind,
-            has_conflict,
-            hunks,
+/// mutable ancestors of the working copy, nearest first, as destinations for its changes
+pub fn query_amend_targets(
+    ws: &WorkspaceSession,
+    paths: Vec<TreePath>,
+    limit: usize,
+) -> Result<Vec<AmendTarget>> {
+    let mut diagnostics = RevsetDiagnostics::new();
+    let immutable_heads =
+        revset_util::parse_immutable_heads_expression(&mut diagnostics, &ws.parse_context())?;
+    let targets_expr = RevsetExpression::commits(vec![ws.wc_id().clone()])
+        .parents()
+        .ancestors()
+        .minus(&immutable_heads.ancestors());
+
+    let matcher = FilesMatcher::new(
+        paths
+            .iter()
+            .map(|path| RepoPath::from_internal_string(&path.repo_path)),
+    );
+
+    let targets = ws.evaluate_revset_expr(targets_expr)?;
+    let mut results = Vec::new();
+    for commit in targets.iter().commits(ws.repo().store()).take(limit) {
+        let commit = commit?;
+
+        let commit_parents: Result<Vec<_>, _> = commit.parents().collect();
+        let parent_tree = rewrite::merge_commit_trees(ws.repo(), &commit_parents?)?;
+        let touches_paths = !paths.is_empty()
+            && parent_tree
+                .diff_stream(&commit.tree()?, &matcher)
+                .next()
+                .block_on()
+                .is_some();
+
+        results.push(AmendTarget {
+            header: ws.format_header(&commit, Some(false))?,
+            touches_paths,
         });
     }
-    Ok(())
+
+    Ok(results)
 }
 
-fn get_value_hunks(
-    num_context_lines: usize,
-    path: &RepoPath,
-    left_value: MaterializedTreeValue,
-    right_value: MaterializedTreeValue,
-) -> Result<Vec<ChangeHunk>> {
-    if left_value.is_absent() {
-        let right_part = get_value_contents(path, right_value)?;
-        get_unified_hunks(num_context_lines, &[], &right_part)
-    } else if right_value.is_present() {
-        let left_part = get_value_contents(&path, left_value)?;
-        let right_part = get_value_contents(&path, right_value)?;
-        get_unified_hunks(num_context_lines, &left_part, &right_part)
-    } else {
-        let left_part = get_value_contents(&path, left_value)?;
-        get_unified_hunks(num_context_lines, &left_part, &[])
-    }
+/// common ancestors of two revisions, and how many revisions each has which the other lacks
+pub fn query_merge_base(
+    ws: &WorkspaceSession,
+    left: messages::CommitId,
+    right: messages::CommitId,
+) -> Result<MergeBase> {
+    let left = ws.get_commit(&CommitId::try_from_hex(&left.hex)?)?;
+    let right = ws.get_commit(&CommitId::try_from_hex(&right.hex)?)?;
+
+    let left_ancestors = RevsetExpression::commits(vec![left.id().clone()]).ancestors();
+    let right_ancestors = RevsetExpression::commits(vec![right.id().clone()]).ancestors();
+
+    let bases = ws
+        .evaluate_revset_expr(left_ancestors.intersection(&right_ancestors).heads())?
+        .iter()
+        .commits(ws.repo().store())
+        .map(|commit| ws.format_header(&commit?, None))
+        .collect::<Result<Vec<_>>>()?;
+
+    let ahead = ws
+        .evaluate_revset_expr(left_ancestors.minus(&right_ancestors))?
+        .iter()
+        .count();
+    let behind = ws
+        .evaluate_revset_expr(right_ancestors.minus(&left_ancestors))?
+        .iter()
+        .count();
+
+    Ok(MergeBase {
+        bases,
+        ahead,
+        behind,
+    })
 }
 
-fn get_value_contents(path: &RepoPath, value: MaterializedTreeValue) -> Result<Vec<u8>> {
-    match value {
-        MaterializedTreeValue::Absent => Err(anyhow!(
-            "Absent path {path:?} in diff should have been handled by caller"
-        )),
-        MaterializedTreeValue::File { mut reader, .. } => {
-            let mut contents = vec![];
-            reader.read_to_end(&mut contents)?;
+/// classifies a directory by the nearest enclosing jj workspace or git repo; needs no loaded workspace
+pub fn query_path_info(path: &Path) -> PathInfo {
+    if !path.is_dir() {
+        return PathInfo::Missing { path: path.into() };
+    }
 
-            let start = &contents[..8000.min(contents.len())]; // same heuristic git uses
-            let is_binary = start.contains(&b'\0');
-            if is_binary {
-                contents.clear();
-                contents.push_str("(binary)");
-            }
-            Ok(contents)
-        }
-        MaterializedTreeValue::Symlink { target, .. } => Ok(target.into_bytes()),
-        MaterializedTreeValue::GitSubmodule(_) => Ok("(submodule)".to_owned().into_bytes()),
-        MaterializedTreeValue::FileConflict { contents, .. } => {
-            let mut hunk_content = vec![];
-            conflicts::materialize_merge_result(&contents, &mut hunk_content)?;
-            Ok(hunk_content)
-        }
-        MaterializedTreeValue::OtherConflict { id } => Ok(id.describe().into_bytes()),
-        MaterializedTreeValue::Tree(_) => Err(anyhow!("Unexpected tree in diff at path {path:?}")),
-        MaterializedTreeValue::AccessDenied(error) => Err(anyhow!(error)),
+    if let Some(root) = path.ancestors().find(|dir| dir.join(".jj").is_dir()) {
+        return PathInfo::Workspace { root: root.into() };
+    }
+
+    match git2::Repository::discover(path) {
+        Ok(repo) => PathInfo::GitRepository {
+            root: repo.workdir().unwrap_or(repo.path()).into(),
+        },
+        Err(_) => PathInfo::Unversioned { path: path.into() },
     }
 }
 
-fn get_unified_hunks(
-    num_context_lines: usize,
+/// revisions and bookmarks changed by an operation, compared to its first parent
+pub fn query_operation_diff(ws: &WorkspaceSession, op_str: &str) -> Result<OperationDiff> {
+    let op = op_walk::resolve_op_with_repo(ws.repo(), op_str)?;
+    let loader = ws.workspace.repo_loader();
+    let new_repo = loader.load_at(&op)?;
+    let old_repo = match op.parents().next() {
+        Some(parent) => loader.load_at(&parent?)?,
+        None => new_repo.clone(),
+    };
+
+    // the operation's index contains all of its parent's commits, so both sides can be evaluated against it
+    let new_ancestors =
+        RevsetExpression::commits(new_repo.view().heads().iter().cloned().collect()).ancestors();
+    let old_ancestors =
+        RevsetExpression::commits(old_repo.view().heads().iter().cloned().collect()).ancestors();
+    let mut truncated = false;
+    let mut load_commits = |expr: Rc<RevsetExpression>| -> Result<Vec<Commit>> {
+        let ids: Vec<CommitId> = expr
+            .evaluate_programmatic(new_repo.as_ref())?
+            .iter()
+            .take(MAX_OPERATION_DIFF_REVISIONS + 1)
+            .collect::<Result<_, _>>()?;
+        truncated |= ids.len() > MAX_OPERATION_DIFF_REVISIONS;
+        ids.iter()
+            .take(MAX_OPERATION_DIFF_REVISIONS)
+            .map(|id| ws.get_commit(id))
+            .collect()
+    };
+    let added = load_commits(new_ancestors.minus(&old_ancestors))?;
+    let removed = load_commits(old_ancestors.minus(&new_ancestors))?;
+
+    // a change which lost one commit and gained another was rewritten
+    let added_changes: HashSet<_> = added.iter().map(|commit| commit.change_id()).collect();
+    let removed_changes: HashSet<_> = removed.iter().map(|commit| commit.change_id()).collect();
+    let (rewritten, added): (Vec<_>, Vec<_>) = added
+        .iter()
+        .partition(|commit| removed_changes.contains(commit.change_id()));
+    let removed = removed
+        .iter()
+        .filter(|commit| !added_changes.contains(commit.change_id()));
+
+    let names: BTreeSet<&str> = old_repo
+        .view()
+        .local_bookmarks()
+        .chain(new_repo.view().local_bookmarks())
+        .map(|(name, _)| name)
+        .collect();
+    let mut bookmarks = Vec::new();
+    for name in names {
+        let old_target = old_repo.view().get_local_bookmark(name);
+        let new_target = new_repo.view().get_local_bookmark(name);
+        if old_target != new_target {
+            bookmarks.push(BookmarkChange {
+                name: name.to_owned(),
+                old_target: old_target
+                    .added_ids()
+                    .next()
+                    .map(|id| ws.format_commit_id(id)),
+                new_target: new_target
+                    .added_ids()
+                    .next()
+                    .map(|id| ws.format_commit_id(id)),
+            });
+        }
+    }
+
+    Ok(OperationDiff {
+        id: op.id().hex()[..12].to_owned(),
+        description: op.metadata().description.clone(),
+        added: added
+            .into_iter()
+            .map(|commit| ws.format_header(commit, None))
+            .collect::<Result<_>>()?,
+        removed: removed
+            .map(|commit| ws.format_header(commit, None))
+            .collect::<Result<_>>()?,
+        rewritten: rewritten
+            .into_iter()
+            .map(|commit| ws.format_header(commit, None))
+            .collect::<Result<_>>()?,
+        bookmarks,
+        truncated,
+    })
+}
+
+/// reads the objects which the current view refers to directly, along with the working copies'
+/// trees - a full check of history is left to `jj debug` commands
+pub fn query_verify_repository(ws: &WorkspaceSession) -> Result<RepoVerification> {
+    let repo = ws.repo();
+    let store = repo.store();
+    let view = repo.view();
+    let mut problems = Vec::new();
+
+    let op_heads = repo.op_heads_store().get_op_heads();
+    if op_heads.len() > 1 {
+        problems.push(format!(
+            "The operation log has {} heads; they will be merged by the next operation.",
+            op_heads.len()
+        ));
+    }
+    for parent in repo.operation().parents() {
+        if let Err(err) = parent {
+            problems.push(format!("The previous operation can't be read: {err}"));
+        }
+    }
+
+    let mut ids: Vec<(String, &CommitId)> = Vec::new();
+    for id in view.heads() {
+        ids.push((format!("Head {}", id.hex()), id));
+    }
+    for (name, target) in view.local_bookmarks() {
+        for id in target.added_ids() {
+            ids.push((format!("Bookmark {name}"), id));
+        }
+    }
+    for (workspace_id, id) in view.wc_commit_ids() {
+        ids.push((format!("Working copy of {}", workspace_id.as_str()), id));
+    }
+
+    let mut checked = HashSet::new();
+    for (name, id) in ids {
+        if !repo.index().has_id(id) {
+            problems.push(format!("{name} ({}) is missing from the index.", id.hex()));
+        }
+
+        let commit = match store.get_commit(id) {
+            Ok(commit) => commit,
+            Err(err) => {
+                problems.push(format!("{name} ({}) can't be read: {err}", id.hex()));
+                continue;
+            }
+        };
+        checked.insert(id.clone());
+
+        for parent_id in commit.parent_ids() {
+            if let Err(err) = store.get_commit(parent_id) {
+                problems.push(format!(
+                    "{name} has a parent ({}) which can't be read: {err}",
+                    parent_id.hex()
+                ));
+            }
+        }
+
+        if view.wc_commit_ids().values().any(|wc_id| wc_id == id) {
+            match commit.tree() {
+                Ok(tree) => {
+                    for (path, value) in tree.entries() {
+                        if let Err(err) = value {
+                            problems.push(format!(
+                                "{name} has a file ({}) which can't be read: {err}",
+                                path.as_internal_file_string()
+                            ));
+                        }
+                    }
+                }
+                Err(err) => problems.push(format!("{name} has a tree which can't be read: {err}")),
+            }
+        }
+    }
+
+    Ok(RepoVerification {
+        operation_id: repo.op_id().hex(),
+        commits_checked: checked.len(),
+        problems,
+    })
+}
+
+/// the most recent operations, newest first
+pub fn query_operations(ws: &WorkspaceSession) -> Result<Vec<OperationSummary>> {
+    let head_op = ws.repo().operation().clone();
+    let mut operations = Vec::new();
+    for op in op_walk::walk_ancestors(&[head_op]).take(MAX_OPERATION_LOG_ENTRIES) {
+        let op = op?;
+        let metadata = op.metadata();
+
+        // jj times every transaction, so gg's operations need no extra bookkeeping
+        let duration_ms = (metadata.end_time.timestamp.0 - metadata.start_time.timestamp.0).max(0);
+
+        operations.push(OperationSummary {
+            id: op.id().hex()[..12].to_owned(),
+            description: metadata.description.clone(),
+            time: messages::format_timestamp(&metadata.end_time)?.with_timezone(&Local),
+            duration_ms: duration_ms as usize,
+            mutation: metadata.tags.get(MUTATION_TAG).cloned(),
+            is_gg: metadata.tags.contains_key(TOOL_TAG),
+        });
+    }
+    Ok(operations)
+}
+
+/// the most recent operations, newest first, summarised for the activity feed
+pub fn query_activity(ws: &WorkspaceSession, limit: usize) -> Result<Vec<ActivityEntry>> {
+    let head_op = ws.repo().operation().clone();
+    let mut entries = Vec::new();
+    for op in op_walk::walk_ancestors(&[head_op]).take(limit.min(MAX_OPERATION_LOG_ENTRIES)) {
+        let op = op?;
+
+        let cached = ws.data.cache().get_activity(op.id());
+        let record = match cached {
+            Some(record) => record,
+            None => {
+                let Some(record) = summarise_activity(&op)? else {
+                    continue; // the root operation
+                };
+                ws.data
+                    .cache()
+                    .insert_activity(op.id().clone(), record.clone());
+                record
+            }
+        };
+
+        entries.push(ActivityEntry {
+            op_id: op.id().hex()[..12].to_owned(),
+            kind: record.kind,
+            description: record.description,
+            time: record.time,
+            bookmarks: record
+                .bookmarks
+                .iter()
+                .map(|(name, old_target, new_target)| BookmarkChange {
+                    name: name.clone(),
+                    old_target: old_target.as_ref().map(|id| ws.format_commit_id(id)),
+                    new_target: new_target.as_ref().map(|id| ws.format_commit_id(id)),
+                })
+                .collect(),
+            revisions: record
+                .revisions
+                .iter()
+                .map(|id| ws.format_commit_id(id))
+                .collect(),
+        });
+    }
+    Ok(entries)
+}
+
+// compares views rather than evaluating revsets, so that each entry stays cheap
+fn summarise_activity(op: &Operation) -> Result<Option<ActivityRecord>> {
+    let Some(parent) = op.parents().next() else {
+        return Ok(None);
+    };
+    let view = op.view()?;
+    let parent_view = parent?.view()?;
+    let metadata = op.metadata();
+
+    let names: BTreeSet<&str> = parent_view
+        .local_bookmarks()
+        .chain(view.local_bookmarks())
+        .map(|(name, _)| name)
+        .collect();
+    let mut bookmarks = Vec::new();
+    for name in names {
+        let old_target = parent_view.get_local_bookmark(name);
+        let new_target = view.get_local_bookmark(name);
+        if old_target != new_target {
+            bookmarks.push((
+                name.to_owned(),
+                old_target.added_ids().next().cloned(),
+                new_target.added_ids().next().cloned(),
+            ));
+        }
+    }
+
+    let revisions = view
+        .heads()
+        .iter()
+        .filter(|id| !parent_view.heads().contains(*id))
+        .take(MAX_ACTIVITY_REVISIONS)
+        .cloned()
+        .collect_vec();
+
+    let description = metadata.description.as_str();
+    let kind = if description.starts_with("fetch from") {
+        ActivityKind::Fetch
+    } else if description.starts_with("push ") {
+        ActivityKind::Push
+    } else if description.starts_with("snapshot working copy") {
+        ActivityKind::Snapshot
+    } else if revisions.is_empty() && !bookmarks.is_empty() {
+        ActivityKind::Bookmarks
+    } else {
+        ActivityKind::Operation
+    };
+
+    // jj's description, capitalised, followed by the bookmarks it touched
+    let mut chars = description.chars();
+    let mut text = match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => "Unnamed operation".to_owned(),
+    };
+    if !bookmarks.is_empty() {
+        let changes = bookmarks
+            .iter()
+            .map(
+                |(name, old_target, new_target)| match (old_target, new_target) {
+                    (None, Some(_)) => format!("created {name}"),
+                    (Some(_), None) => format!("deleted {name}"),
+                    _ => format!("moved {name}"),
+                },
+            )
+            .join(", ");
+        text = format!("{text} ({changes})");
+    }
+
+    Ok(Some(ActivityRecord {
+        kind,
+        description: text,
+        time: messages::format_timestamp(&metadata.end_time)?.with_timezone(&Local),
+        bookmarks,
+        revisions,
+    }))
+}
+
+/// most recent first; checkpoints further back in the op log than the scan limit aren't found
+pub fn query_checkpoints(ws: &WorkspaceSession) -> Result<Vec<Checkpoint>> {
+    let head_op = ws.repo().operation().clone();
+    let mut checkpoints = Vec::new();
+    for op in op_walk::walk_ancestors(&[head_op]).take(MAX_CHECKPOINT_SCAN_OPERATIONS) {
+        let op = op?;
+        let Some(label) = op.metadata().tags.get(CHECKPOINT_TAG) else {
+            continue;
+        };
+
+        let view = op.view()?;
+        let Some(wc_id) = view.get_wc_commit_id(ws.id()) else {
+            continue;
+        };
+
+        checkpoints.push(Checkpoint {
+            id: op.id().hex()[..12].to_owned(),
+            label: label.clone(),
+            time: messages::format_timestamp(&op.metadata().end_time)?.with_timezone(&Local),
+            working_copy: ws.format_commit_id(wc_id),
+        });
+    }
+    Ok(checkpoints)
+}
+
+/// operations which created, rewrote or abandoned commits of the change, found by comparing its
+/// visible commits in each operation's view with those in its parent's
+pub fn query_change_operations(ws: &WorkspaceSession, id: RevId) -> Result<ChangeHistory> {
+    let commit = ws.resolve_single_commit(&id.commit)?;
+    let versions = ws.resolve_change_versions(commit.change_id())?;
+
+    // every operation's commits are in the current index, but heads from other op log branches may not be
+    let index = ws.repo().index();
+    let visible_versions = |view: &View| -> Vec<CommitId> {
+        versions
+            .iter()
+            .filter(|version| {
+                view.heads()
+                    .iter()
+                    .any(|head| index.has_id(head) && index.is_ancestor(version, head))
+            })
+            .cloned()
+            .collect()
+    };
+
+    let head_op = ws.repo().operation().clone();
+    let mut operations = Vec::new();
+    let mut truncated = false;
+    for (scanned, op) in op_walk::walk_ancestors(&[head_op]).enumerate() {
+        if scanned == MAX_CHANGE_HISTORY_OPERATIONS {
+            truncated = true;
+            break;
+        }
+
+        let op = op?;
+        let after = visible_versions(&op.view()?);
+        let before = match op.parents().next() {
+            Some(parent) => visible_versions(&parent?.view()?),
+            None => Vec::new(),
+        };
+        if before == after {
+            continue;
+        }
+
+        let kind = if before.is_empty() {
+            ChangeOperationKind::Created
+        } else if after.is_empty() {
+            ChangeOperationKind::Abandoned
+        } else if after.len() > 1 && after.len() > before.len() {
+            ChangeOperationKind::Diverged
+        } else {
+            ChangeOperationKind::Rewritten
+        };
+
+        operations.push(ChangeOperation {
+            id: op.id().hex()[..12].to_owned(),
+            description: op.metadata().description.clone(),
+            time: messages::format_timestamp(&op.metadata().end_time)?.with_timezone(&Local),
+            kind,
+            commits: after.iter().map(|id| ws.format_commit_id(id)).collect(),
+        });
+    }
+
+    Ok(ChangeHistory {
+        operations,
+        truncated,
+    })
+}
+
+/// which of the visible revisions the dragged ones can be dropped onto, by the same rules the mutations enforce
+pub fn query_drop_targets(
+    ws: &WorkspaceSession,
+    dragged: Vec<messages::CommitId>,
+    visible: Vec<messages::CommitId>,
+) -> Result<Vec<DropTarget>> {
+    let dragged_ids = dragged
+        .iter()
+        .map(|id| CommitId::try_from_hex(&id.hex))
+        .collect::<Result<Vec<_>, _>>()?;
+    let visible_ids = visible
+        .iter()
+        .map(|id| CommitId::try_from_hex(&id.hex))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut diagnostics = RevsetDiagnostics::new();
+    let immutable_heads =
+        revset_util::parse_immutable_heads_expression(&mut diagnostics, &ws.parse_context())?;
+    let candidates = RevsetExpression::commits(
+        dragged_ids
+            .iter()
+            .chain(visible_ids.iter())
+            .cloned()
+            .collect(),
+    );
+    let immutable: HashSet<CommitId> = ws
+        .evaluate_revset_expr(candidates.intersection(&immutable_heads.ancestors()))?
+        .iter()
+        .collect::<Result<_, _>>()?;
+
+    let index = ws.repo().index();
+    let dragged_mutable = !dragged_ids.iter().any(|id| immutable.contains(id));
+
+    let mut results = Vec::new();
+    for (id, target_id) in visible.into_iter().zip(visible_ids) {
+        let is_dragged = dragged_ids.contains(&target_id);
+        let target_mutable = !immutable.contains(&target_id);
+
+        // a new parent can't be the target itself or one of its descendants
+        let target = ws.get_commit(&target_id)?;
+        let merge_creates_cycle = dragged_ids
+            .iter()
+            .any(|dragged_id| index.is_ancestor(&target_id, dragged_id));
+        let already_parent = dragged_ids
+            .iter()
+            .any(|dragged_id| target.parent_ids().contains(dragged_id));
+
+        results.push(DropTarget {
+            id,
+            can_move: dragged_mutable && !is_dragged,
+            can_insert: dragged_mutable && target_mutable && !is_dragged,
+            can_merge: target_mutable && !merge_creates_cycle && !already_parent,
+        });
+    }
+
+    Ok(results)
+}
+
+pub fn query_remotes(
+    ws: &WorkspaceSession,
+    tracking_branch: Option<String>,
+) -> Result<Vec<GitRemote>> {
+    let git_repo = match ws.git_repo()? {
+        Some(git_repo) => git_repo,
+        None => {
+            return Err(anyhow!(
+                "Remotes require a git backend, but this repo uses the {} backend",
+                ws.backend_name()
+            ))
+        }
+    };
+
+    let all_remotes: Vec<String> = git_repo
+        .remotes()?
+        .into_iter()
+        .filter_map(|remote| remote.map(|remote| remote.to_owned()))
+        .collect();
+
+    let matching_remotes: Vec<String> = match tracking_branch {
+        Some(branch_name) => all_remotes
+            .into_iter()
+            .filter(|remote_name| {
+                let remote_ref = ws.view().get_remote_bookmark(&branch_name, &remote_name);
+                !remote_ref.is_absent() && remote_ref.is_tracking()
+            })
+            .collect(),
+        None => all_remotes,
+    };
+
+    // the same settings and defaults as jj git fetch/push
+    let config = ws.data.settings.config();
+    let fetch_defaults = config
+        .get::<Vec<String>>("git.fetch")
+        .or_else(|_| config.get_string("git.fetch").map(|remote| vec![remote]))
+        .unwrap_or_else(|_| vec!["origin".to_owned()]);
+    let push_default = config
+        .get_string("git.push")
+        .unwrap_or_else(|_| "origin".to_owned());
+
+    let history = FetchHistory::load(ws.workspace.repo_path());
+
+    matching_remotes
+        .into_iter()
+        .map(|name| {
+            let remote = git_repo.find_remote(&name)?;
+            let url = remote.url().map(|url| url.to_owned());
+            let push_url = remote
+                .pushurl()
+                .filter(|push_url| Some(*push_url) != remote.url())
+                .map(|push_url| push_url.to_owned());
+            let fetch_refspecs = remote
+                .fetch_refspecs()?
+                .iter()
+                .flatten()
+                .map(|refspec| refspec.to_owned())
+                .collect();
+            let push_refspecs = remote
+                .push_refspecs()?
+                .iter()
+                .flatten()
+                .map(|refspec| refspec.to_owned())
+                .collect();
+            let last_fetched = history
+                .get(&name)
+                .and_then(|millis| Local.timestamp_millis_opt(millis).single());
+
+            Ok(GitRemote {
+                is_default_fetch: fetch_defaults.contains(&name),
+                is_default_push: push_default == name,
+                name,
+                url,
+                push_url,
+                fetch_refspecs,
+                push_refspecs,
+                last_fetched,
+            })
+        })
+        .collect()
+}
+
+/// connects to a remote without transferring anything, explaining what went wrong if it can't
+pub fn query_remote_connection(
+    ws: &WorkspaceSession,
+    remote_name: String,
+) -> Result<RemoteConnection> {
+    let git_repo = match ws.git_repo()? {
+        Some(git_repo) => git_repo,
+        None => {
+            return Err(anyhow!(
+                "Remotes require a git backend, but this repo uses the {} backend",
+                ws.backend_name()
+            ))
+        }
+    };
+
+    let mut remote = git_repo.find_remote(&remote_name)?;
+    let url = remote.url().map(|url| url.to_owned());
+    let proxy = describe_proxy(ws, &git_repo, &remote_name);
+
+    GitEnvironment::from_settings(&ws.data.settings)
+        .configure(&git_repo, ws.workspace.repo_path())?;
+    let mut proxy_options = git2::ProxyOptions::new();
+    proxy_options.auto();
+    let result = remote
+        .connect_auth(git2::Direction::Fetch, None, Some(proxy_options))
+        .map(|_| ());
+
+    let target = url.as_deref().unwrap_or(&remote_name);
+    let (reachable, message) = match result {
+        Ok(()) => (true, format!("Connected to {target}.")),
+        // there are no credentials here, so being asked for them means the remote was reached
+        Err(err) if err.code() == git2::ErrorCode::Auth || err.class() == git2::ErrorClass::Ssh => (
+            true,
+            format!("Reached {target}, which asks for credentials when fetching or pushing."),
+        ),
+        Err(err) => match &proxy {
+            Some(proxy) => (
+                false,
+                format!(
+                    "Couldn't reach {target} through the proxy {proxy}: {}. Check that the proxy is running and allows connections to this remote.",
+                    err.message()
+                ),
+            ),
+            None => (
+                false,
+                format!("Couldn't reach {target}: {}", err.message()),
+            ),
+        },
+    };
+
+    Ok(RemoteConnection {
+        remote_name,
+        url,
+        proxy,
+        reachable,
+        message,
+    })
+}
+
+// in the order libgit2 applies them; gg.git.proxy is passed in the environment
+fn describe_proxy(
+    ws: &WorkspaceSession,
+    git_repo: &git2::Repository,
+    remote_name: &str,
+) -> Option<String> {
+    let from_git = git_repo.config().ok().and_then(|config| {
+        config
+            .get_string(&format!("remote.{remote_name}.proxy"))
+            .or_else(|_| config.get_string("http.proxy"))
+            .ok()
+    });
+
+    let (proxy, source) = if let Some(proxy) = from_git {
+        (proxy, "git config")
+    } else if let Some(proxy) = ws.data.settings.git_proxy() {
+        (proxy, "gg.git.proxy")
+    } else {
+        let proxy = ["https_proxy", "HTTPS_PROXY", "http_proxy", "HTTP_PROXY"]
+            .iter()
+            .find_map(|name| env::var(name).ok())?;
+        (proxy, "environment")
+    };
+
+    Some(format!(
+        "{} (from {source})",
+        git_env::redact("proxy", &proxy)
+    ))
+}
+
+/// local bookmarks which trunk, or a remote trunk bookmark that's ahead of it, already contains
+pub fn query_merged_bookmarks(ws: &WorkspaceSession) -> Result<Vec<MergedBookmark>> {
+    let Some(trunk) = ws.resolve_trunk()? else {
+        return Ok(Vec::new());
+    };
+
+    let mut upstreams = vec![(TRUNK_ALIAS.to_owned(), trunk.id().clone())];
+    for name in TRUNK_BOOKMARKS {
+        for ((_, remote), remote_ref) in ws
+            .view()
+            .remote_bookmarks_matching(&StringPattern::exact(*name), &StringPattern::everything())
+        {
+            if remote == REMOTE_NAME_FOR_LOCAL_GIT_REPO {
+                continue;
+            }
+            if let Some(id) = remote_ref.target.as_normal() {
+                upstreams.push((format!("{name}@{remote}"), id.clone()));
+            }
+        }
+    }
+
+    let index = ws.repo().index();
+    let mut merged = Vec::new();
+    for (name, bookmark) in ws.view().bookmarks() {
+        if TRUNK_BOOKMARKS.contains(&name) || ws.is_protected_bookmark(name)? {
+            continue;
+        }
+
+        // conflicted bookmarks need resolving, not deleting
+        let Some(id) = bookmark.local_target.as_normal() else {
+            continue;
+        };
+
+        // trunk() may be aliased to this bookmark's remote, in which case it's trunk itself
+        if id == trunk.id()
+            && bookmark
+                .remote_refs
+                .iter()
+                .any(|(_, remote_ref)| remote_ref.target.as_normal() == Some(id))
+        {
+            continue;
+        }
+
+        let Some((merged_into, _)) = upstreams
+            .iter()
+            .find(|(_, upstream)| index.is_ancestor(id, upstream))
+        else {
+            continue;
+        };
+
+        let tracking_remotes = bookmark
+            .remote_refs
+            .iter()
+            .filter(|(remote, remote_ref)| {
+                *remote != REMOTE_NAME_FOR_LOCAL_GIT_REPO && remote_ref.is_tracking()
+            })
+            .map(|(remote, _)| (*remote).to_owned())
+            .collect();
+
+        merged.push(MergedBookmark {
+            name: name.to_owned(),
+            target: ws.format_header(&ws.get_commit(id)?, None)?,
+            merged_into: merged_into.clone(),
+            tracking_remotes,
+        });
+    }
+
+    Ok(merged)
+}
+
+pub fn query_resolve_path(ws: &WorkspaceSession, path: TreePath) -> Result<ResolvedPath> {
+    let repo_path = RepoPath::from_internal_string(&path.repo_path);
+    let fs_path = repo_path.to_fs_path(ws.workspace.workspace_root())?;
+
+    // symlink_metadata so that dangling links still count as present
+    Ok(ResolvedPath {
+        exists: fs_path.symlink_metadata().is_ok(),
+        absolute_path: fs_path.into(),
+    })
+}
+
+pub fn query_conflict(ws: &WorkspaceSession, id: RevId, path: TreePath) -> Result<FileConflict> {
+    let commit = ws.resolve_single_change(&id)?;
+    let repo_path = RepoPathBuf::from_internal_string(&path.repo_path);
+    let value = commit.tree()?.path_value(&repo_path)?;
+
+    let MaterializedTreeValue::FileConflict { contents, .. } =
+        conflicts::materialize_tree_value(ws.repo().store(), &repo_path, value).block_on()?
+    else {
+        return Err(anyhow!("{} is not a conflicted file", path.relative_path.0));
+    };
+
+    if contents
+        .iter()
+        .any(|term| std::str::from_utf8(term).is_err())
+    {
+        return Err(anyhow!("{} is not a text file", path.relative_path.0));
+    }
+
+    let num_bases = contents.removes().len();
+    let has_trailing_newline = contents.adds().all(|content| content.ends_with(b"\n"));
+    Ok(FileConflict {
+        path,
+        bases: contents
+            .removes()
+            .enumerate()
+            .map(|(base_index, content)| ConflictSide {
+                label: if num_bases == 1 {
+                    "base".to_owned()
+                } else {
+                    format!("base #{}", base_index + 1)
+                },
+                lines: format_conflict_lines(content),
+            })
+            .collect(),
+        sides: contents
+            .adds()
+            .enumerate()
+            .map(|(side_index, content)| ConflictSide {
+                label: format!("side #{}", side_index + 1),
+                lines: format_conflict_lines(content),
+            })
+            .collect(),
+        regions: format_conflict_regions(&contents),
+        has_trailing_newline,
+    })
+}
+
+/// dry run of the RewritePaths mutation
+pub fn query_rewrite_preview(
+    ws: &WorkspaceSession,
+    id: RevId,
+    paths: Vec<TreePath>,
+    pattern: String,
+    replacement: String,
+) -> Result<Vec<RewriteMatch>> {
+    let regex = Regex::new(&pattern)?;
+    let commit = ws.resolve_single_change(&id)?;
+
+    // the same matcher as RewritePaths, so that the preview can't differ from the result
+    let matcher = build_matcher(&paths);
+
+    let mut matches = Vec::new();
+    for file in
+        ws.find_line_replacements(&commit.tree()?, matcher.as_ref(), &regex, &replacement)?
+    {
+        let path = ws.format_path(&file.path)?;
+        for (line, before, after) in file.lines {
+            matches.push(RewriteMatch {
+                path: path.clone(),
+                line,
+                before,
+                after,
+            });
+        }
+    }
+    Ok(matches)
+}
+
+pub fn query_revset_aliases(ws: &WorkspaceSession) -> Result<Vec<RevsetAlias>> {
+    Ok(read_revset_aliases(ws.workspace.repo_path())?
+        .into_iter()
+        .map(|(source, name, definition)| RevsetAlias {
+            name,
+            definition,
+            scope: source.into(),
+        })
+        .collect())
+}
+
+pub fn query_description_check(ws: &WorkspaceSession, text: &str) -> Result<DescriptionCheck> {
+    Ok(DescriptionRules::from_settings(&ws.data.settings).check(text))
+}
+
+pub fn query_notes(ws: &WorkspaceSession) -> Result<Vec<RevNote>> {
+    Ok(Annotations::load(ws.workspace.repo_path())?
+        .notes
+        .into_iter()
+        .map(|(change_hex, text)| RevNote { change_hex, text })
+        .collect())
+}
+
+/// drafts a description for the describe dialog, using an external command if one is configured
+pub fn query_suggested_description(ws: &WorkspaceSession, id: RevId) -> Result<String> {
+    let commit = ws.resolve_single_commit(&id.commit)?;
+
+    let commit_parents: Result<Vec<_>, _> = commit.parents().collect();
+    let parent_tree = rewrite::merge_commit_trees(ws.repo(), &commit_parents?)?;
+    let tree = commit.tree()?;
+
+    let mut changes = Vec::new();
+    let tree_diff = parent_tree.diff_stream(&tree, &EverythingMatcher);
+    format_tree_changes(ws, &mut changes, tree_diff, 0, usize::MAX).block_on()?;
+
+    if let Some(args) = ws
+        .data
+        .settings
+        .ui_description_command()
+        .filter(|_| !ws.session.safe_mode)
+    {
+        match run_description_command(ws, &args, &format_diff_text(&changes)) {
+            Ok(description) if !description.trim().is_empty() => {
+                return Ok(description.trim().to_owned())
+            }
+            Ok(_) => log::warn!("description command produced no output"),
+            Err(err) => log::warn!("description command failed: {err:#}"),
+        }
+    }
+
+    Ok(summarise_changes(&changes))
+}
+
+fn format_diff_text(changes: &[RevChange]) -> String {
+    let mut text = String::new();
+    for change in changes {
+        let old_path = change.old_path.as_ref().unwrap_or(&change.path);
+        text.push_str(&format!(
+            "diff --git a/{} b/{}\n",
+            old_path.repo_path, change.path.repo_path
+        ));
+        for hunk in &change.hunks {
+            text.push_str(&format!(
+                "@@ -{},{} +{},{} @@\n",
+                hunk.location.from_file.start,
+                hunk.location.from_file.len,
+                hunk.location.to_file.start,
+                hunk.location.to_file.len
+            ));
+            for line in &hunk.lines.lines {
+                text.push_str(line.trim_end_matches(['\r', '\n']));
+                text.push('\n');
+            }
+        }
+    }
+    text
+}
+
+fn run_description_command(ws: &WorkspaceSession, args: &[String], diff: &str) -> Result<String> {
+    let mut command = Command::new(&args[0]);
+    if let Some(dir) = paths::subprocess_dir(ws.workspace.workspace_root()) {
+        command.current_dir(dir);
+    }
+    let mut child = command
+        .args(&args[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // write and read from other threads so that a chatty child can't deadlock us
+    let mut stdin = child.stdin.take().ok_or(anyhow!("no stdin"))?;
+    let diff = diff.to_owned();
+    let writer = thread::spawn(move || stdin.write_all(diff.as_bytes()));
+    let mut stdout = child.stdout.take().ok_or(anyhow!("no stdout"))?;
+    let reader = thread::spawn(move || {
+        let mut output = Vec::new();
+        stdout.read_to_end(&mut output).map(|_| output)
+    });
+
+    let deadline = Instant::now() + DESCRIPTION_COMMAND_TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            _ = child.kill();
+            _ = child.wait();
+            return Err(anyhow!(
+                "timed out after {}s",
+                DESCRIPTION_COMMAND_TIMEOUT.as_secs()
+            ));
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+
+    _ = writer.join();
+    let output = reader
+        .join()
+        .map_err(|_| anyhow!("stdout reader panicked"))??;
+    if !status.success() {
+        return Err(anyhow!("exited with {status}"));
+    }
+
+    Ok(String::from_utf8(output)?)
+}
+
+// a deterministic summary: a subject line, then the changed files grouped by directory
+fn summarise_changes(changes: &[RevChange]) -> String {
+    let mut directories: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for change in changes {
+        let (directory, file_name) = change
+            .path
+            .repo_path
+            .rsplit_once('/')
+            .unwrap_or(("", &change.path.repo_path));
+        let verb = match change.kind {
+            ChangeKind::None => "modified",
+            ChangeKind::Added => "added",
+            ChangeKind::Deleted => "deleted",
+            ChangeKind::Modified => "modified",
+            ChangeKind::CaseRenamed => "renamed",
+        };
+        directories
+            .entry(directory)
+            .or_default()
+            .push(format!("{verb} {file_name}"));
+    }
+
+    let subject = match (changes, directories.len()) {
+        ([], _) => return String::new(),
+        ([change], _) => {
+            let verb = match change.kind {
+                ChangeKind::Added => "Add",
+                ChangeKind::Deleted => "Delete",
+                ChangeKind::CaseRenamed => "Rename",
+                ChangeKind::None | ChangeKind::Modified => "Update",
+            };
+            return format!("{verb} {}", change.path.repo_path);
+        }
+        (changes, 1) => {
+            let directory = directories.keys().next().copied().unwrap_or_default();
+            if directory.is_empty() {
+                format!("Update {} files", changes.len())
+            } else {
+                format!("Update {} files in {directory}", changes.len())
+            }
+        }
+        (changes, num_directories) => format!(
+            "Update {} files in {num_directories} directories",
+            changes.len()
+        ),
+    };
+
+    let mut description = subject;
+    description.push('\n');
+    for (directory, files) in directories {
+        let directory = if directory.is_empty() { "." } else { directory };
+        description.push_str(&format!("\n{directory}: {}", files.join(", ")));
+    }
+    description
+}
+
+// sorted by path, so that parents precede their children
+fn aggregate_directories_stats(
+    ws: &WorkspaceSession,
+    file_stats: Vec<(RepoPathBuf, RevStats)>,
+) -> Result<Vec<RevDirectory>> {
+    let mut totals: BTreeMap<RepoPathBuf, RevStats> = BTreeMap::new();
+
+    for (path, stats) in file_stats {
+        let mut dir = path.parent();
+        while let Some(parent) = dir {
+            let total = totals.entry(parent.to_owned()).or_insert(RevStats {
+                files_changed: 0,
+                lines_added: 0,
+                lines_removed: 0,
+            });
+            total.files_changed += stats.files_changed;
+            total.lines_added += stats.lines_added;
+            total.lines_removed += stats.lines_removed;
+            dir = parent.parent();
+        }
+    }
+
+    totals
+        .into_iter()
+        .map(|(path, stats)| {
+            Ok(RevDirectory {
+                path: ws.format_path(path)?,
+                files_changed: stats.files_changed,
+                lines_added: stats.lines_added,
+                lines_removed: stats.lines_removed,
+            })
+        })
+        .collect()
+}
+
+// computed lazily, as this requires a diff of every changed file
+fn get_revision_stats(ws: &WorkspaceSession, commit: &Commit) -> Result<RevStats> {
+    if let Some(stats) = ws.data.cache().get_stats(commit.id()) {
+        return Ok(stats);
+    }
+
+    let commit_parents: Result<Vec<_>, _> = commit.parents().collect();
+    let parent_tree = rewrite::merge_commit_trees(ws.repo(), &commit_parents?)?;
+    let tree = commit.tree()?;
+    let tree_diff = parent_tree.diff_stream(&tree, &EverythingMatcher);
+    let file_stats = format_tree_stats(ws, tree_diff).block_on()?;
+
+    let mut stats = RevStats {
+        files_changed: 0,
+        lines_added: 0,
+        lines_removed: 0,
+    };
+    for (_, file) in file_stats {
+        stats.files_changed += file.files_changed;
+        stats.lines_added += file.lines_added;
+        stats.lines_removed += file.lines_removed;
+    }
+
+    ws.data
+        .cache()
+        .insert_stats(commit.id().clone(), stats.clone());
+
+    Ok(stats)
+}
+
+// per-file counts, in diff order
+async fn format_tree_stats(
+    ws: &WorkspaceSession<'_>,
+    mut tree_diff: TreeDiffStream<'_>,
+) -> Result<Vec<(RepoPathBuf, RevStats)>> {
+    let store = ws.repo().store();
+
+    let mut file_stats = Vec::new();
+
+    while let Some(TreeDiffEntry { path, values }) = tree_diff.next().await {
+        let (before, after) = values?;
+
+        let before_future = conflicts::materialize_tree_value(store, &path, before);
+        let after_future = conflicts::materialize_tree_value(store, &path, after);
+        let (before_value, after_value) = try_join!(before_future, after_future)?;
+
+        let fallback_encoding = ws.data.settings.query_fallback_encoding();
+        let before_content = get_value_contents(&path, before_value, fallback_encoding)?;
+        let after_content = get_value_contents(&path, after_value, fallback_encoding)?;
+
+        // binary files have no meaningful lines to count
+        if before_content.is_binary || after_content.is_binary {
+            file_stats.push((
+                path,
+                RevStats {
+                    files_changed: 1,
+                    lines_added: 0,
+                    lines_removed: 0,
+                },
+            ));
+            continue;
+        }
+
+        let diff = diff_by_line(
+            [&before_content.text, &after_content.text],
+            &LineDiffOptions {
+                compare_mode: LineCompareMode::Exact,
+            },
+        );
+        let mut stats = RevStats {
+            files_changed: 1,
+            lines_added: 0,
+            lines_removed: 0,
+        };
+        for hunk in diff.hunks() {
+            if hunk.kind == DiffHunkKind::Different {
+                stats.lines_removed += hunk.contents[0].split_inclusive(|b| *b == b'\n').count();
+                stats.lines_added += hunk.contents[1].split_inclusive(|b| *b == b'\n').count();
+            }
+        }
+
+        file_stats.push((path, stats));
+    }
+
+    Ok(file_stats)
+}
+
+// formats at most `limit` changes after skipping `skip`, returning whether any remain
+async fn format_tree_changes(
+    ws: &WorkspaceSession<'_>,
+    changes: &mut Vec<RevChange>,
+    mut tree_diff: TreeDiffStream<'_>,
+    skip: usize,
+    limit: usize,
+) -> Result<bool> {
+    let store = ws.repo().store();
+
+    let mut entries = Vec::new();
+    while let Some(TreeDiffEntry { path, values }) = tree_diff.next().await {
+        let (before, after) = values?;
+        entries.push((path, before, after));
+    }
+
+    // on case-insensitive or normalizing filesystems, these pairs are really renames of one file
+    let mut deletions = HashMap::new();
+    for (index, (path, before, after)) in entries.iter().enumerate() {
+        if before.is_present() && after.is_absent() {
+            deletions.insert(normalize_path(path), index);
+        }
+    }
+
+    let mut renamed_from = HashMap::new();
+    for (index, (path, before, after)) in entries.iter().enumerate() {
+        if before.is_absent() && after.is_present() {
+            if let Some(deleted_index) = deletions.remove(&normalize_path(path)) {
+                renamed_from.insert(index, deleted_index);
+            }
+        }
+    }
+    let renamed_to: HashSet<usize> = renamed_from.values().copied().collect();
+
+    let mut entries = entries.into_iter().map(Some).collect_vec();
+
+    let mut position = 0;
+    for index in 0..entries.len() {
+        if renamed_to.contains(&index) {
+            continue;
+        }
+
+        if position == skip + limit {
+            return Ok(true);
+        }
+        position += 1;
+        if position <= skip {
+            continue;
+        }
+
+        let Some((path, mut before, after)) = entries[index].take() else {
+            continue;
+        };
+
+        let mut old_path = None;
+        let kind = if let Some(Some((deleted_path, deleted_before, _))) = renamed_from
+            .get(&index)
+            .map(|deleted_index| entries[*deleted_index].take())
+        {
+            before = deleted_before;
+            old_path = Some(deleted_path);
+            ChangeKind::CaseRenamed
+        } else if before.is_present() && after.is_present() {
+            ChangeKind::Modified
+        } else if before.is_absent() {
+            ChangeKind::Added
+        } else {
+            ChangeKind::Deleted
+        };
+
+        let has_conflict = !after.is_resolved();
+
+        let before_path = old_path.as_ref().unwrap_or(&path);
+        let before_future = conflicts::materialize_tree_value(store, before_path, before);
+        let after_future = conflicts::materialize_tree_value(store, &path, after);
+        let (before_value, after_value) = try_join!(before_future, after_future)?;
+        let conflict_regions = match &after_value {
+            MaterializedTreeValue::FileConflict { contents, .. } => {
+                format_conflict_regions(contents)
+            }
+            _ => Vec::new(),
+        };
+
+        let ValueDiff {
+            hunks,
+            is_binary,
+            format,
+        } = get_value_hunks(
+            3,
+            &path,
+            before_value,
+            after_value,
+            ws.data.settings.query_fallback_encoding(),
+        )?;
+        let (lines_added, lines_removed) = if is_binary {
+            (0, 0)
+        } else {
+            count_changed_lines(&hunks)
+        };
+
+        changes.push(RevChange {
+            path: ws.format_path(path)?,
+            old_path: old_path.map(|path| ws.format_path(path)).transpose()?,
+            kind,
+            has_conflict,
+            lines_added,
+            lines_removed,
+            is_binary,
+            format,
+            hunks,
+            conflict_regions,
+        });
+    }
+    Ok(false)
+}
+
+// the same hunks, labels and numbering as conflicts::materialize_merge_result, without the markers
+fn format_conflict_regions(contents: &Merge<BString>) -> Vec<ConflictRegion> {
+    let MergeResult::Conflict(hunks) = files::merge(contents) else {
+        return Vec::new();
+    };
+
+    hunks
+        .iter()
+        .filter(|hunk| hunk.as_resolved().is_none())
+        .enumerate()
+        .map(|(index, hunk)| {
+            let num_bases = hunk.removes().len();
+            ConflictRegion {
+                index: index + 1,
+                bases: hunk
+                    .removes()
+                    .enumerate()
+                    .map(|(base_index, content)| ConflictSide {
+                        label: if num_bases == 1 {
+                            "base".to_owned()
+                        } else {
+                            format!("base #{}", base_index + 1)
+                        },
+                        lines: format_conflict_lines(content),
+                    })
+                    .collect(),
+                sides: hunk
+                    .adds()
+                    .enumerate()
+                    .map(|(side_index, content)| ConflictSide {
+                        label: format!("side #{}", side_index + 1),
+                        lines: format_conflict_lines(content),
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+fn format_conflict_lines(content: &BString) -> MultilineString {
+    let text = String::from_utf8_lossy(content);
+    text.strip_suffix('\n').unwrap_or(&text).into()
+}
+
+// case-folded NFC, which is how most case-insensitive filesystems compare names
+fn normalize_path(path: &RepoPath) -> String {
+    path.as_internal_file_string()
+        .nfc()
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+struct ValueDiff {
+    hunks: Vec<ChangeHunk>,
+    /// either side is binary, in which case the hunks are placeholders
+    is_binary: bool,
+    /// of the new contents, or the old contents if the file was deleted
+    format: TextFormat,
+}
+
+fn get_value_hunks(
+    num_context_lines: usize,
+    path: &RepoPath,
+    left_value: MaterializedTreeValue,
+    right_value: MaterializedTreeValue,
+    fallback_encoding: &'static Encoding,
+) -> Result<ValueDiff> {
+    let is_deletion = right_value.is_absent();
+    let left_part = get_value_contents(path, left_value, fallback_encoding)?;
+    let right_part = get_value_contents(path, right_value, fallback_encoding)?;
+
+    let is_binary = left_part.is_binary || right_part.is_binary;
+    let format = if is_binary {
+        TextFormat::default()
+    } else if is_deletion {
+        detect_text_format(&left_part)
+    } else {
+        detect_text_format(&right_part)
+    };
+
+    Ok(ValueDiff {
+        hunks: get_unified_hunks(num_context_lines, &left_part.text, &right_part.text)?,
+        is_binary,
+        format,
+    })
+}
+
+fn detect_text_format(contents: &ValueContents) -> TextFormat {
+    let mut crlf = false;
+    let mut lf = false;
+    let mut tabs = false;
+    let mut spaces = false;
+
+    for line in contents.text.split_inclusive(|b| *b == b'\n') {
+        if line.ends_with(b"\r\n") {
+            crlf = true;
+        } else if line.ends_with(b"\n") {
+            lf = true;
+        }
+
+        match line.first() {
+            Some(b'\t') => tabs = true,
+            Some(b' ') => spaces = true,
+            _ => (),
+        }
+    }
+
+    TextFormat {
+        line_endings: match (lf, crlf) {
+            (false, false) => LineEndings::None,
+            (true, false) => LineEndings::Lf,
+            (false, true) => LineEndings::Crlf,
+            (true, true) => LineEndings::Mixed,
+        },
+        indentation: match (tabs, spaces) {
+            (false, false) => Indentation::None,
+            (true, false) => Indentation::Tabs,
+            (false, true) => Indentation::Spaces,
+            (true, true) => Indentation::Mixed,
+        },
+        encoding: contents.encoding.map(|encoding| encoding.name().to_owned()),
+    }
+}
+
+// a hunk whose removed and added lines pair up exactly, apart from their terminators
+fn classify_hunk(lines: &[String]) -> HunkKind {
+    let removed = lines.iter().filter_map(|line| line.strip_prefix('-'));
+    let added = lines.iter().filter_map(|line| line.strip_prefix('+'));
+
+    let mut any_changed = false;
+    for pair in removed.zip_longest(added) {
+        let EitherOrBoth::Both(before, after) = pair else {
+            return HunkKind::Content;
+        };
+        if before.trim_end_matches(['\r', '\n']) != after.trim_end_matches(['\r', '\n']) {
+            return HunkKind::Content;
+        }
+        any_changed |= before != after;
+    }
+
+    if any_changed {
+        HunkKind::LineEndings
+    } else {
+        HunkKind::Content
+    }
+}
+
+// (added, removed); hunks include every changed line, so this matches a full diff
+fn count_changed_lines(hunks: &[ChangeHunk]) -> (usize, usize) {
+    let mut added = 0;
+    let mut removed = 0;
+    for line in hunks.iter().flat_map(|hunk| hunk.lines.lines.iter()) {
+        if line.starts_with('+') {
+            added += 1;
+        } else if line.starts_with('-') {
+            removed += 1;
+        }
+    }
+    (added, removed)
+}
+
+struct ValueContents {
+    /// UTF-8, or a placeholder for binary files
+    text: Vec<u8>,
+    is_binary: bool,
+    /// set if the text was transcoded from another encoding
+    encoding: Option<&'static Encoding>,
+}
+
+impl ValueContents {
+    fn new(text: Vec<u8>) -> Self {
+        ValueContents {
+            text,
+            is_binary: false,
+            encoding: None,
+        }
+    }
+}
+
+fn get_value_contents(
+    path: &RepoPath,
+    value: MaterializedTreeValue,
+    fallback_encoding: &'static Encoding,
+) -> Result<ValueContents> {
+    match value {
+        MaterializedTreeValue::Absent => Ok(ValueContents::new(vec![])),
+        MaterializedTreeValue::File { mut reader, .. } => {
+            let mut contents = vec![];
+            reader.read_to_end(&mut contents)?;
+            Ok(decode_file_contents(contents, fallback_encoding))
+        }
+        MaterializedTreeValue::Symlink { target, .. } => {
+            Ok(ValueContents::new(target.into_bytes()))
+        }
+        MaterializedTreeValue::GitSubmodule(_) => {
+            Ok(ValueContents::new("(submodule)".to_owned().into_bytes()))
+        }
+        MaterializedTreeValue::FileConflict { contents, .. } => {
+            let mut hunk_content = vec![];
+            conflicts::materialize_merge_result(&contents, &mut hunk_content)?;
+            Ok(ValueContents::new(hunk_content))
+        }
+        MaterializedTreeValue::OtherConflict { id } => {
+            Ok(ValueContents::new(id.describe().into_bytes()))
+        }
+        MaterializedTreeValue::Tree(_) => Err(anyhow!("Unexpected tree in diff at path {path:?}")),
+        MaterializedTreeValue::AccessDenied(error) => Err(anyhow!(error)),
+    }
+}
+
+// a byte order mark wins, then UTF-8 if the contents are valid, then the configured fallback.
+// BOMs are checked first because UTF-16 text would otherwise look binary
+fn decode_file_contents(contents: Vec<u8>, fallback_encoding: &'static Encoding) -> ValueContents {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(&contents) {
+        if encoding != UTF_8 {
+            let (text, _) = encoding.decode_without_bom_handling(&contents[bom_length..]);
+            return ValueContents {
+                text: text.into_owned().into_bytes(),
+                is_binary: false,
+                encoding: Some(encoding),
+            };
+        }
+    }
+
+    let start = &contents[..8000.min(contents.len())]; // same heuristic git uses
+    if start.contains(&b'\0') {
+        return ValueContents {
+            text: b"(binary)".to_vec(),
+            is_binary: true,
+            encoding: None,
+        };
+    }
+
+    if std::str::from_utf8(&contents).is_ok() {
+        return ValueContents::new(contents);
+    }
+
+    let (text, _) = fallback_encoding.decode_without_bom_handling(&contents);
+    ValueContents {
+        text: text.into_owned().into_bytes(),
+        is_binary: false,
+        encoding: Some(fallback_encoding),
+    }
+}
+
+fn get_unified_hunks(
+    num_context_lines: usize,
     left_content: &[u8],
     right_content: &[u8],
 ) -> Result<Vec<ChangeHunk>> {
@@ -525,6 +2647,7 @@ fn get_unified_hunks(
 
         hunks.push(ChangeHunk {
             location,
+            kind: classify_hunk(&lines),
             lines: MultilineString { lines },
         });
     }
@@ -532,6 +2655,32 @@ fn get_unified_hunks(
     Ok(hunks)
 }
 
+/// the locations of the hunks shown for a text file, so that they can be selected individually
+pub(super) fn diff_hunk_locations(left_content: &[u8], right_content: &[u8]) -> Vec<HunkLocation> {
+    unified_diff_hunks(
+        left_content,
+        right_content,
+        &UnifiedDiffOptions {
+            context: 3,
+            line_diff: LineDiffOptions {
+                compare_mode: LineCompareMode::Exact,
+            },
+        },
+    )
+    .into_iter()
+    .map(|hunk| HunkLocation {
+        from_file: FileRange {
+            start: hunk.left_line_range.start,
+            len: hunk.left_line_range.len(),
+        },
+        to_file: FileRange {
+            start: hunk.right_line_range.start,
+            len: hunk.right_line_range.len(),
+        },
+    })
+    .collect()
+}
+
 /**************************/
 /* from jj_cli::diff_util */
 /**************************/
@@ -0,0 +1,150 @@
+//! Read-only workers for queries which depend on nothing but a revision and an operation. Each
+//! request loads its own copy of the workspace at the operation the session worker was viewing
+//! when the query arrived, so a slow diff doesn't hold up log paging or mutations. Readers share
+//! the session's derived data cache, so stats they compute are saved with it. Queries which use
+//! other session state (log cursors, snapshots) stay on the session worker.
+
+use std::{
+    path::PathBuf,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use anyhow::Result;
+use jj_lib::{object_id::ObjectId, repo::Repo};
+
+use super::{cache::DerivedCache, gui_util::WorkspaceSession, queries, FixedClock, WorkerSession};
+use crate::{config::GGSettings, messages};
+
+/// queries which can be answered by any worker loaded at the right operation
+#[derive(Debug)]
+pub enum ReadQuery {
+    Revision {
+        tx: Sender<Result<messages::RevResult>>,
+        id: messages::RevId,
+        aggregate_directories: bool,
+    },
+    RevisionPathsPage {
+        tx: Sender<Result<messages::RevChangesPage>>,
+        id: messages::RevId,
+        skip: usize,
+    },
+}
+
+impl ReadQuery {
+    /// answers the query; only fails if the requester has gone away
+    pub fn run(self, ws: &WorkspaceSession) -> Result<()> {
+        match self {
+            ReadQuery::Revision {
+                tx,
+                id,
+                aggregate_directories,
+            } => tx.send(queries::query_revision(ws, id, aggregate_directories))?,
+            ReadQuery::RevisionPathsPage { tx, id, skip } => {
+                tx.send(queries::query_revision_paths_page(ws, id, skip))?
+            }
+        }
+        Ok(())
+    }
+
+    fn fail(self, err: anyhow::Error) -> Result<()> {
+        match self {
+            ReadQuery::Revision { tx, .. } => tx.send(Err(err))?,
+            ReadQuery::RevisionPathsPage { tx, .. } => tx.send(Err(err))?,
+        }
+        Ok(())
+    }
+}
+
+struct ReadRequest {
+    workspace_root: PathBuf,
+    op_id: String,
+    changes_page_size: Option<usize>,
+    safe_mode: bool,
+    clock: Option<FixedClock>,
+    cache: Arc<Mutex<DerivedCache>>,
+    query: ReadQuery,
+}
+
+/// a fixed set of reader threads, shared by every session a window's worker goes through
+pub struct ReaderPool {
+    tx: Sender<ReadRequest>,
+    _threads: Vec<JoinHandle<()>>,
+}
+
+impl ReaderPool {
+    pub fn new(size: usize) -> ReaderPool {
+        let (tx, rx) = channel::<ReadRequest>();
+        let rx = Arc::new(Mutex::new(rx));
+        let threads = (0..size)
+            .map(|_| {
+                let rx = rx.clone();
+                thread::spawn(move || read_loop(&rx))
+            })
+            .collect();
+        ReaderPool {
+            tx,
+            _threads: threads,
+        }
+    }
+}
+
+/// hands a query to the session's reader pool, or runs it on the session worker if parallel reads
+/// are unavailable or disabled
+pub fn dispatch(ws: &WorkspaceSession, query: ReadQuery) -> Result<()> {
+    let pool = match &ws.session.readers {
+        Some(pool) if ws.data.settings.query_parallel_reads() => pool,
+        _ => return query.run(ws),
+    };
+
+    let request = ReadRequest {
+        workspace_root: ws.workspace.workspace_root().to_owned(),
+        op_id: ws.repo().op_id().hex(),
+        changes_page_size: ws.session.force_changes_page_size,
+        safe_mode: ws.session.safe_mode,
+        clock: ws.session.clock.clone(),
+        cache: ws.data.cache.clone(),
+        query,
+    };
+    if let Err(err) = pool.tx.send(request) {
+        log::warn!("reader pool unavailable; querying on the session worker");
+        err.0.query.run(ws)?;
+    }
+    Ok(())
+}
+
+fn read_loop(rx: &Mutex<Receiver<ReadRequest>>) {
+    loop {
+        // the lock is only held while waiting, so idle readers queue up behind one another
+        let request = match rx.lock().expect("reader mutex poisoned").recv() {
+            Ok(request) => request,
+            Err(_) => return, // the pool was dropped
+        };
+        if let Err(err) = read(request) {
+            log::debug!("reader response dropped: {err:#}");
+        }
+    }
+}
+
+fn read(request: ReadRequest) -> Result<()> {
+    let mut session = WorkerSession {
+        force_changes_page_size: request.changes_page_size,
+        safe_mode: request.safe_mode,
+        clock: request.clock,
+        shared_cache: Some(request.cache),
+        ..Default::default()
+    };
+    let ws = session
+        .load_directory(&request.workspace_root)
+        .and_then(|mut ws| {
+            ws.load_at_operation(Some(&request.op_id))?;
+            Ok(ws)
+        });
+    match ws {
+        Ok(ws) => request.query.run(&ws),
+        Err(err) => request.query.fail(err),
+    }
+}
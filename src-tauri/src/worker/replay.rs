@@ -0,0 +1,233 @@
+//! Recordings of the events a real session received, one JSON object per line, which can be
+//! replayed against a test repo to reproduce a bug end-to-end without the GUI. Only events which
+//! change the repo are recorded; queries can be made against the result instead.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    path::Path,
+    sync::mpsc::{channel, Receiver},
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{Mutation, Session, SessionEvent, WorkerSession};
+use crate::messages;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type")]
+pub enum RecordedEvent {
+    /// the short type name and the arguments it was deserialized from
+    Mutation {
+        name: String,
+        payload: serde_json::Value,
+        #[serde(default)]
+        confirmation: Option<String>,
+    },
+    Snapshot,
+}
+
+impl RecordedEvent {
+    pub fn mutation<T: Mutation + Serialize>(
+        mutation: &T,
+        confirmation: Option<String>,
+    ) -> Result<RecordedEvent> {
+        let name = mutation.describe();
+        Ok(RecordedEvent::Mutation {
+            name: name.rsplit("::").next().unwrap_or(&name).to_owned(),
+            payload: serde_json::to_value(mutation)?,
+            confirmation,
+        })
+    }
+}
+
+/// what the session sent back for each replayed event
+#[allow(dead_code)] // used by tests
+#[derive(Debug)]
+pub enum ReplayedEvent {
+    Mutation {
+        name: String,
+        result: messages::MutationResult,
+    },
+    Snapshot {
+        status: Option<messages::RepoStatus>,
+    },
+}
+
+/// appends events to a recording as they happen, so that a crash doesn't lose them
+pub struct SessionRecorder {
+    writer: BufWriter<File>,
+}
+
+impl SessionRecorder {
+    pub fn create(path: &Path) -> Result<SessionRecorder> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(path)
+            .with_context(|| format!("create {}", path.display()))?;
+        Ok(SessionRecorder {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub fn record(&mut self, event: &RecordedEvent) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, event)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[allow(dead_code)] // used by tests
+pub fn load_recording(path: &Path) -> Result<Vec<RecordedEvent>> {
+    fs::read_to_string(path)
+        .with_context(|| format!("read {}", path.display()))?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).with_context(|| format!("{}:{}", path.display(), index + 1))
+        })
+        .collect()
+}
+
+/// opens the workspace in `session`, sends it every event and collects the responses
+#[allow(dead_code)] // used by tests
+pub fn replay(
+    session: WorkerSession,
+    workspace: &Path,
+    events: Vec<RecordedEvent>,
+) -> Result<Vec<ReplayedEvent>> {
+    enum Pending {
+        Mutation(String, Receiver<messages::MutationResult>),
+        Snapshot(Receiver<Option<messages::RepoStatus>>),
+    }
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel();
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(workspace.to_owned()),
+    })?;
+
+    let mut pending = Vec::new();
+    for event in events {
+        match event {
+            RecordedEvent::Mutation {
+                name,
+                payload,
+                confirmation,
+            } => {
+                let (tx_mutation, rx_mutation) = channel();
+                tx.send(SessionEvent::ExecuteMutation {
+                    tx: tx_mutation,
+                    mutation: deserialize_mutation(&name, payload)?,
+                    confirmation,
+                })?;
+                pending.push(Pending::Mutation(name, rx_mutation));
+            }
+            RecordedEvent::Snapshot => {
+                let (tx_snapshot, rx_snapshot) = channel();
+                tx.send(SessionEvent::ExecuteSnapshot { tx: tx_snapshot })?;
+                pending.push(Pending::Snapshot(rx_snapshot));
+            }
+        }
+    }
+    tx.send(SessionEvent::EndSession)?;
+
+    session.handle_events(&rx)?;
+
+    match rx_load.recv()?? {
+        messages::RepoConfig::Workspace { .. } => (),
+        _ => return Err(anyhow!("{} is not a workspace", workspace.display())),
+    }
+
+    pending
+        .into_iter()
+        .map(|pending| {
+            Ok(match pending {
+                Pending::Mutation(name, rx) => ReplayedEvent::Mutation {
+                    result: rx.recv().with_context(|| name.clone())?,
+                    name,
+                },
+                Pending::Snapshot(rx) => ReplayedEvent::Snapshot { status: rx.recv()? },
+            })
+        })
+        .collect()
+}
+
+macro_rules! replayable {
+    ($($name:ident),* $(,)?) => {
+        fn deserialize_mutation(
+            name: &str,
+            payload: serde_json::Value,
+        ) -> Result<Box<dyn Mutation + Send + Sync>> {
+            match name {
+                $(stringify!($name) => Ok(Box::new(
+                    serde_json::from_value::<messages::$name>(payload).context(stringify!($name))?,
+                )),)*
+                _ => Err(anyhow!("{name} can't be replayed")),
+            }
+        }
+    };
+}
+
+replayable!(
+    AbandonRevisions,
+    AddIgnorePattern,
+    AddParentToMerge,
+    BackoutRevisions,
+    CheckoutRevision,
+    CheckpointWorkingCopy,
+    CleanupBookmarks,
+    ColocateRepository,
+    CommitStaged,
+    CopyChanges,
+    CreateFile,
+    CreateMergeOf,
+    CreateRef,
+    CreateRevision,
+    DeletePaths,
+    DeleteRef,
+    DescribeRevision,
+    DescribeRevisions,
+    DuplicateRevisions,
+    FetchFromPath,
+    FixRevisions,
+    FollowWorkspace,
+    GitFetch,
+    GitPush,
+    GraftRevisions,
+    HideRevisions,
+    InsertRevision,
+    MergeRevisions,
+    MoveChanges,
+    MoveRef,
+    MoveRevision,
+    MoveSource,
+    ParallelizeRevisions,
+    RemoveParentFromMerge,
+    RemoveRevsetAlias,
+    RenameBranch,
+    RenamePath,
+    ResolveConflict,
+    ResolveWithMergeTool,
+    RewritePaths,
+    RunJjCommand,
+    RunRebasePlan,
+    SetAuthor,
+    SetDefaultQuery,
+    SetLabel,
+    SetRevsetAlias,
+    SetTrunk,
+    SetUserIdentity,
+    SplitRevision,
+    SwapParents,
+    TrackBranch,
+    UndoOperation,
+    UnhideRevisions,
+    UntrackBranch,
+);
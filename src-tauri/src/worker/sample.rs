@@ -0,0 +1,162 @@
+//! A small generated repo for new users to explore without risking their real work: a few
+//! bookmarks, a divergent pair of changes, a conflicted merge and an edited working copy.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use jj_lib::{
+    backend::TreeValue, commit::Commit, git, git_backend::GitBackend, merge::Merge,
+    merged_tree::MergedTreeBuilder, op_store::RefTarget, repo::Repo, repo_path::RepoPathBuf,
+    rewrite, settings::UserSettings, transaction::Transaction, workspace::Workspace,
+};
+use pollster::FutureExt;
+
+use super::WorkerSession;
+
+const SAMPLE_DIRECTORY: &str = "gg-sample";
+
+const README: &str = "# Sample Repository
+
+This repo was generated by gg so that you can try things out safely.
+Drag revisions onto each other, edit descriptions, resolve the conflict
+in \"Merge the translations\" - if anything goes wrong, just press Undo.
+";
+
+const NOTES: &str = "# Notes
+
+- Bookmarks are shown next to the revisions they point at.
+- The working copy (@) is a revision too; its changes are saved automatically.
+";
+
+/// creates a colocated repo in a new subdirectory of `parent`, returning its path
+pub fn create_sample_repository(session: &WorkerSession, parent: &Path) -> Result<PathBuf> {
+    let path = parent.join(SAMPLE_DIRECTORY);
+    if path.exists() {
+        return Err(anyhow!("{} already exists", path.display()));
+    }
+    fs::create_dir_all(&path)?;
+
+    let (settings, _) = session.read_settings(&path.join(".jj").join("repo"))?;
+    let (mut workspace, repo) = Workspace::init_colocated_git(&settings, &path)?;
+    fs::write(path.join(".jj").join(".gitignore"), "/*\n")?;
+
+    let mut tx = repo.start_transaction(&settings);
+    let root = tx.repo().store().root_commit();
+
+    let readme = write_commit(
+        &mut tx,
+        &settings,
+        vec![root],
+        &[("README.md", README)],
+        "Add a README",
+    )?;
+    let greeting = write_commit(
+        &mut tx,
+        &settings,
+        vec![readme],
+        &[("greeting.txt", "Hello, world!\n")],
+        "Add a greeting",
+    )?;
+    let french = write_commit(
+        &mut tx,
+        &settings,
+        vec![greeting.clone()],
+        &[("greeting.txt", "Bonjour, le monde !\n")],
+        "Greet in French",
+    )?;
+    let german = write_commit(
+        &mut tx,
+        &settings,
+        vec![greeting.clone()],
+        &[("greeting.txt", "Hallo, Welt!\n")],
+        "Greet in German",
+    )?;
+    write_commit(
+        &mut tx,
+        &settings,
+        vec![french.clone(), german.clone()],
+        &[],
+        "Merge the translations",
+    )?;
+    let notes = write_commit(
+        &mut tx,
+        &settings,
+        vec![greeting.clone()],
+        &[("NOTES.md", NOTES)],
+        "Add some notes",
+    )?;
+
+    for (name, commit) in [
+        ("main", &greeting),
+        ("french", &french),
+        ("german", &german),
+    ] {
+        tx.repo_mut()
+            .set_local_bookmark_target(name, RefTarget::normal(commit.id().clone()));
+    }
+
+    let wc = tx
+        .repo_mut()
+        .check_out(workspace.workspace_id().clone(), &settings, &notes)?;
+    tx.repo_mut().rebase_descendants(&settings)?; // the initial working copy is abandoned
+    let git_backend = tx
+        .repo()
+        .store()
+        .backend_impl()
+        .downcast_ref::<GitBackend>()
+        .ok_or(anyhow!("Sample repository is not backed by git"))?;
+    let git_repo = git_backend.open_git_repo()?;
+    git::reset_head(tx.repo_mut(), &git_repo, &wc)?;
+    git::export_refs(tx.repo_mut())?;
+    let repo = tx.commit("create sample repository");
+
+    workspace.check_out(repo.op_id().clone(), None, &wc)?;
+
+    // an unsnapshotted edit, so that the working copy has changes when it's opened
+    fs::write(
+        path.join("NOTES.md"),
+        format!("{NOTES}- Edits like this one are snapshotted when gg notices them.\n"),
+    )?;
+
+    Ok(path)
+}
+
+fn write_commit(
+    tx: &mut Transaction,
+    settings: &UserSettings,
+    parents: Vec<Commit>,
+    files: &[(&str, &str)],
+    description: &str,
+) -> Result<Commit> {
+    let store = tx.repo().store().clone();
+    let base_tree = rewrite::merge_commit_trees(tx.repo(), &parents)?;
+
+    let mut tree_builder = MergedTreeBuilder::new(base_tree.id());
+    for (path, content) in files {
+        let path = RepoPathBuf::from_internal_string(*path);
+        let id = store
+            .write_file(&path, &mut content.as_bytes())
+            .block_on()?;
+        tree_builder.set_or_remove(
+            path,
+            Merge::normal(TreeValue::File {
+                id,
+                executable: false,
+            }),
+        );
+    }
+    let tree_id = tree_builder.write_tree(&store)?;
+
+    Ok(tx
+        .repo_mut()
+        .new_commit(
+            settings,
+            parents.iter().map(|parent| parent.id().clone()).collect(),
+            tree_id,
+        )
+        .set_description(description)
+        .write()?)
+}
@@ -1,4 +1,8 @@
 use std::{
+    collections::hash_map::DefaultHasher,
+    fs::File,
+    hash::{Hash, Hasher},
+    io::BufWriter,
     panic::{catch_unwind, AssertUnwindSafe},
     path::PathBuf,
     sync::mpsc::{Receiver, Sender},
@@ -6,16 +10,20 @@ use std::{
 
 use anyhow::{anyhow, Context, Result};
 use jj_cli::config::{write_config_value_to_file, ConfigNamePathBuf, ConfigSource};
+use jj_lib::{object_id::ObjectId, repo::Repo};
 
 use super::{
+    bundles,
+    colocation::Colocation,
+    difftool,
+    drafts::DraftDescription,
     gui_util::WorkspaceSession,
+    journal::MutationJournal,
     queries::{self, QueryState},
-    Mutation, WorkerSession,
-};
-use crate::{
-    config::{read_config, GGSettings},
-    handler, messages,
+    readers::{self, ReadQuery},
+    sample, staging, Mutation, WorkerSession,
 };
+use crate::{config::GGSettings, handler, messages};
 
 /// implemented by states of the event loop
 pub trait Session {
@@ -32,27 +40,158 @@ pub enum SessionEvent {
         tx: Sender<Result<messages::RepoConfig>>,
         wd: Option<PathBuf>,
     },
+    /// takes effect when the workspace is next opened
+    SetSafeMode { enabled: bool },
     QueryRevision {
         tx: Sender<Result<messages::RevResult>>,
         id: messages::RevId,
+        aggregate_directories: bool,
+    },
+    QueryWorkingCopyDiff {
+        tx: Sender<Result<messages::WorkingCopyDiff>>,
+        base: String,
+        skip: usize,
+        snapshot: bool,
+    },
+    QueryRevisionPathsNextPage {
+        tx: Sender<Result<messages::RevChangesPage>>,
+        id: messages::RevId,
+        skip: usize,
+    },
+    QueryRewritePreview {
+        tx: Sender<Result<Vec<messages::RewriteMatch>>>,
+        id: messages::RevId,
+        paths: Vec<messages::TreePath>,
+        pattern: String,
+        replacement: String,
+    },
+    QuerySuggestedDescription {
+        tx: Sender<Result<String>>,
+        id: messages::RevId,
+    },
+    QueryChangeOperations {
+        tx: Sender<Result<messages::ChangeHistory>>,
+        id: messages::RevId,
+    },
+    QueryDescriptionCheck {
+        tx: Sender<Result<messages::DescriptionCheck>>,
+        text: String,
+    },
+    QueryRevsetAliases {
+        tx: Sender<Result<Vec<messages::RevsetAlias>>>,
+    },
+    QueryNotes {
+        tx: Sender<Result<Vec<messages::RevNote>>>,
+    },
+    QueryRevisionStats {
+        tx: Sender<Result<Vec<messages::RevStats>>>,
+        ids: Vec<messages::CommitId>,
+    },
+    QueryAmendTargets {
+        tx: Sender<Result<Vec<messages::AmendTarget>>>,
+        paths: Vec<messages::TreePath>,
+        limit: usize,
+    },
+    QueryMergeBase {
+        tx: Sender<Result<messages::MergeBase>>,
+        left: messages::CommitId,
+        right: messages::CommitId,
+    },
+    QueryOperationDiff {
+        tx: Sender<Result<messages::OperationDiff>>,
+        op: String,
+    },
+    QueryOperations {
+        tx: Sender<Result<Vec<messages::OperationSummary>>>,
+    },
+    QueryActivity {
+        tx: Sender<Result<Vec<messages::ActivityEntry>>>,
+        limit: usize,
+    },
+    QueryCheckpoints {
+        tx: Sender<Result<Vec<messages::Checkpoint>>>,
+    },
+    QueryVerifyRepository {
+        tx: Sender<Result<messages::RepoVerification>>,
+    },
+    QueryMergedBookmarks {
+        tx: Sender<Result<Vec<messages::MergedBookmark>>>,
+    },
+    CopyRevisions {
+        tx: Sender<Result<bundles::RevisionBundle>>,
+        ids: Vec<messages::RevId>,
+    },
+    SetViewOperation {
+        tx: Sender<Result<messages::RepoStatus>>,
+        op: Option<String>,
+    },
+    QueryDropTargets {
+        tx: Sender<Result<Vec<messages::DropTarget>>>,
+        dragged: Vec<messages::CommitId>,
+        visible: Vec<messages::CommitId>,
     },
     QueryRemotes {
-        tx: Sender<Result<Vec<String>>>,
+        tx: Sender<Result<Vec<messages::GitRemote>>>,
         tracking_branch: Option<String>,
     },
+    QueryRemoteConnection {
+        tx: Sender<Result<messages::RemoteConnection>>,
+        remote_name: String,
+    },
+    QueryResolvePath {
+        tx: Sender<Result<messages::ResolvedPath>>,
+        path: messages::TreePath,
+    },
+    LaunchDiffTool {
+        tx: Sender<Result<()>>,
+        id: messages::RevId,
+        path: Option<messages::TreePath>,
+    },
+    QueryConflict {
+        tx: Sender<Result<messages::FileConflict>>,
+        id: messages::RevId,
+        path: messages::TreePath,
+    },
     QueryLog {
         tx: Sender<Result<messages::LogPage>>,
         query: String,
+        first_parent: bool,
+        show_hidden: bool,
+        elide: Option<messages::LogElision>,
+        /// don't narrow the default query, even in a large repo
+        full: bool,
     },
     QueryLogNextPage {
         tx: Sender<Result<messages::LogPage>>,
     },
+    ExportLog {
+        tx: Sender<Result<usize>>,
+        query: String,
+        first_parent: bool,
+        show_hidden: bool,
+        format: messages::LogExportFormat,
+        path: PathBuf,
+    },
+    QueryTrace {
+        tx: Sender<Vec<messages::TraceEntry>>,
+    },
+    InitWorkspace {
+        tx: Sender<Result<()>>,
+        wd: PathBuf,
+        options: messages::InitWorkspace,
+    },
+    CreateSampleRepository {
+        tx: Sender<Result<PathBuf>>,
+        parent: PathBuf,
+    },
     ExecuteSnapshot {
         tx: Sender<Option<messages::RepoStatus>>,
     },
     ExecuteMutation {
         tx: Sender<messages::MutationResult>,
         mutation: Box<dyn Mutation + Send + Sync>,
+        /// token from an earlier ConfirmationRequired result
+        confirmation: Option<String>,
     },
     ReadConfigArray {
         tx: Sender<Result<Vec<String>>>,
@@ -63,12 +202,114 @@ pub enum SessionEvent {
         key: Vec<String>,
         values: Vec<String>,
     },
+    SetNote {
+        tx: Sender<Result<()>>,
+        change_hex: String,
+        text: String,
+    },
+    SaveDraft {
+        tx: Sender<Result<()>>,
+        change_hex: String,
+        text: String,
+    },
+    LoadDraft {
+        tx: Sender<Result<Option<String>>>,
+        change_hex: String,
+    },
+    DiscardDraft {
+        tx: Sender<Result<()>>,
+        change_hex: String,
+    },
+    StageChange {
+        tx: Sender<Result<messages::StagedChanges>>,
+        path: messages::TreePath,
+        hunk: Option<messages::HunkLocation>,
+        staged: bool,
+    },
+    QueryStaged {
+        tx: Sender<Result<messages::StagedChanges>>,
+    },
+    SaveWorkspaceState {
+        tx: Sender<()>,
+        scope: ConfigSource,
+        selection: Option<String>,
+    },
+}
+
+impl SessionEvent {
+    /// identifies the event in traces without including its payload
+    pub fn name(&self) -> String {
+        match self {
+            SessionEvent::EndSession => "EndSession".to_owned(),
+            SessionEvent::OpenWorkspace { .. } => "OpenWorkspace".to_owned(),
+            SessionEvent::SetSafeMode { .. } => "SetSafeMode".to_owned(),
+            SessionEvent::QueryRevision { .. } => "QueryRevision".to_owned(),
+            SessionEvent::QueryWorkingCopyDiff { .. } => "QueryWorkingCopyDiff".to_owned(),
+            SessionEvent::QueryRevisionPathsNextPage { .. } => {
+                "QueryRevisionPathsNextPage".to_owned()
+            }
+            SessionEvent::QueryRewritePreview { .. } => "QueryRewritePreview".to_owned(),
+            SessionEvent::QuerySuggestedDescription { .. } => {
+                "QuerySuggestedDescription".to_owned()
+            }
+            SessionEvent::QueryChangeOperations { .. } => "QueryChangeOperations".to_owned(),
+            SessionEvent::QueryDescriptionCheck { .. } => "QueryDescriptionCheck".to_owned(),
+            SessionEvent::QueryRevsetAliases { .. } => "QueryRevsetAliases".to_owned(),
+            SessionEvent::QueryNotes { .. } => "QueryNotes".to_owned(),
+            SessionEvent::QueryRevisionStats { .. } => "QueryRevisionStats".to_owned(),
+            SessionEvent::QueryAmendTargets { .. } => "QueryAmendTargets".to_owned(),
+            SessionEvent::QueryMergeBase { .. } => "QueryMergeBase".to_owned(),
+            SessionEvent::QueryDropTargets { .. } => "QueryDropTargets".to_owned(),
+            SessionEvent::QueryOperationDiff { .. } => "QueryOperationDiff".to_owned(),
+            SessionEvent::QueryVerifyRepository { .. } => "QueryVerifyRepository".to_owned(),
+            SessionEvent::QueryMergedBookmarks { .. } => "QueryMergedBookmarks".to_owned(),
+            SessionEvent::CopyRevisions { .. } => "CopyRevisions".to_owned(),
+            SessionEvent::QueryOperations { .. } => "QueryOperations".to_owned(),
+            SessionEvent::QueryActivity { .. } => "QueryActivity".to_owned(),
+            SessionEvent::QueryCheckpoints { .. } => "QueryCheckpoints".to_owned(),
+            SessionEvent::SetViewOperation { .. } => "SetViewOperation".to_owned(),
+            SessionEvent::QueryRemotes { .. } => "QueryRemotes".to_owned(),
+            SessionEvent::QueryRemoteConnection { .. } => "QueryRemoteConnection".to_owned(),
+            SessionEvent::QueryResolvePath { .. } => "QueryResolvePath".to_owned(),
+            SessionEvent::LaunchDiffTool { .. } => "LaunchDiffTool".to_owned(),
+            SessionEvent::QueryConflict { .. } => "QueryConflict".to_owned(),
+            SessionEvent::QueryLog { .. } => "QueryLog".to_owned(),
+            SessionEvent::QueryLogNextPage { .. } => "QueryLogNextPage".to_owned(),
+            SessionEvent::ExportLog { .. } => "ExportLog".to_owned(),
+            SessionEvent::QueryTrace { .. } => "QueryTrace".to_owned(),
+            SessionEvent::InitWorkspace { .. } => "InitWorkspace".to_owned(),
+            SessionEvent::CreateSampleRepository { .. } => "CreateSampleRepository".to_owned(),
+            SessionEvent::ExecuteSnapshot { .. } => "ExecuteSnapshot".to_owned(),
+            SessionEvent::ExecuteMutation { mutation, .. } => {
+                let name = mutation.describe();
+                format!(
+                    "ExecuteMutation({})",
+                    name.rsplit("::").next().unwrap_or(&name)
+                )
+            }
+            SessionEvent::ReadConfigArray { .. } => "ReadConfigArray".to_owned(),
+            SessionEvent::WriteConfigArray { .. } => "WriteConfigArray".to_owned(),
+            SessionEvent::SetNote { .. } => "SetNote".to_owned(),
+            SessionEvent::SaveDraft { .. } => "SaveDraft".to_owned(),
+            SessionEvent::LoadDraft { .. } => "LoadDraft".to_owned(),
+            SessionEvent::DiscardDraft { .. } => "DiscardDraft".to_owned(),
+            SessionEvent::StageChange { .. } => "StageChange".to_owned(),
+            SessionEvent::QueryStaged { .. } => "QueryStaged".to_owned(),
+            SessionEvent::SaveWorkspaceState { .. } => "SaveWorkspaceState".to_owned(),
+        }
+    }
 }
 
 /// transitions for a workspace session
 pub enum WorkspaceResult {
     Reopen(Sender<Result<messages::RepoConfig>>, Option<PathBuf>), // workspace -> workspace
+    Missing(PathBuf),                                              // workspace -> worker
     SessionComplete,                                               // workspace -> worker
+    Colocate(
+        Sender<messages::MutationResult>,
+        messages::MutationResult,
+        Colocation,
+    ), // workspace -> worker
 }
 
 /// transition for a query session
@@ -88,13 +329,25 @@ impl Session for WorkerSession {
         let mut latest_wd: Option<PathBuf> = None;
 
         loop {
+            self.trace.borrow_mut().end();
             let evt = rx.recv();
-            log::debug!("WorkerSession handling {evt:?}");
+            if let Ok(evt) = &evt {
+                self.trace.borrow_mut().begin("WorkerSession", evt);
+            }
             match evt {
                 Ok(SessionEvent::EndSession) => return Ok(()),
                 Ok(SessionEvent::ExecuteSnapshot { .. }) => (),
+                Ok(SessionEvent::SaveWorkspaceState { .. }) => (), // nothing to save
+                Ok(SessionEvent::SetSafeMode { enabled }) => self.safe_mode = enabled,
+                Ok(SessionEvent::QueryTrace { tx }) => tx.send(self.trace.borrow().entries())?,
+                Ok(SessionEvent::InitWorkspace { tx, wd, options }) => {
+                    tx.send(self.init_directory(&wd, &options))?
+                }
+                Ok(SessionEvent::CreateSampleRepository { tx, parent }) => {
+                    tx.send(sample::create_sample_repository(&self, &parent))?
+                }
                 Ok(SessionEvent::OpenWorkspace { mut tx, mut wd }) => loop {
-                    let resolved_wd = match wd.clone().or(latest_wd) {
+                    let resolved_wd = match wd.clone().or_else(|| latest_wd.clone()) {
                         Some(wd) => wd,
                         None => match self.get_cwd() {
                             Ok(wd) => wd,
@@ -121,15 +374,49 @@ impl Session for WorkerSession {
                         }
                     };
 
+                    if latest_wd.as_ref() != Some(&resolved_wd) {
+                        ws.session.followed_workspace = None;
+                        ws.restore_workspace_state();
+                    }
                     latest_wd = Some(resolved_wd);
 
+                    ws.session.interrupted_mutation =
+                        match MutationJournal::take_interrupted(ws.workspace.workspace_root()) {
+                            Ok(journal) => journal,
+                            Err(err) => {
+                                log::warn!("failed to read mutation journal: {err:#}");
+                                None
+                            }
+                        };
+
                     ws.import_and_snapshot(false)?;
 
                     tx.send(ws.format_config())?;
+                    ws.session.restored_selection = None;
+                    ws.session.interrupted_mutation = None;
 
                     match ws.handle_events(rx).context("WorkspaceSession")? {
                         WorkspaceResult::Reopen(new_tx, new_cwd) => (tx, wd) = (new_tx, new_cwd),
+                        WorkspaceResult::Missing(path) => {
+                            // keep latest_wd, so that reopening will retry the same path
+                            log::warn!("workspace missing: {}", path.display());
+                            self.callbacks.workspace_missing(&path);
+                            break;
+                        }
                         WorkspaceResult::SessionComplete => return Ok(()),
+                        WorkspaceResult::Colocate(tx, result, colocation) => {
+                            // keep latest_wd, so that the converted workspace can be reopened
+                            tx.send(match colocation.convert() {
+                                Ok(()) => result,
+                                Err(err) => {
+                                    log::error!("{err:?}");
+                                    messages::MutationResult::InternalError {
+                                        message: (&*format!("{err:?}")).into(),
+                                    }
+                                }
+                            })?;
+                            break;
+                        }
                     }
                 },
                 Ok(evt) => {
@@ -159,46 +446,234 @@ impl Session for WorkspaceSession<'_> {
             let next_event = if state.unhandled_event.is_some() {
                 state.unhandled_event.take().unwrap()
             } else {
-                let evt = rx.recv();
-                log::debug!("WorkspaceSession handling {evt:?}");
-                evt?
+                self.session.trace.borrow_mut().end();
+                let evt = rx.recv()?;
+                self.session
+                    .trace
+                    .borrow_mut()
+                    .begin("WorkspaceSession", &evt);
+                evt
             };
 
+            // if the workspace was deleted or moved, jj operations would fail and restart the worker
+            if !matches!(
+                next_event,
+                SessionEvent::EndSession
+                    | SessionEvent::OpenWorkspace { .. }
+                    | SessionEvent::SetSafeMode { .. }
+                    | SessionEvent::QueryTrace { .. }
+                    | SessionEvent::InitWorkspace { .. }
+                    | SessionEvent::CreateSampleRepository { .. }
+            ) && !self.is_present()
+            {
+                return Ok(WorkspaceResult::Missing(
+                    self.workspace.workspace_root().to_owned(),
+                ));
+            }
+
             match next_event {
-                SessionEvent::EndSession => return Ok(WorkspaceResult::SessionComplete),
+                SessionEvent::EndSession => {
+                    handler::optional!(self.save_cache());
+                    return Ok(WorkspaceResult::SessionComplete);
+                }
                 SessionEvent::OpenWorkspace { tx, wd: cwd } => {
+                    handler::optional!(self.save_cache());
                     return Ok(WorkspaceResult::Reopen(tx, cwd));
                 }
-                SessionEvent::QueryRevision { tx, id } => {
-                    tx.send(queries::query_revision(&self, id))?
+                SessionEvent::SetSafeMode { enabled } => self.session.safe_mode = enabled,
+                SessionEvent::QueryRevision {
+                    tx,
+                    id,
+                    aggregate_directories,
+                } => readers::dispatch(
+                    &self,
+                    ReadQuery::Revision {
+                        tx,
+                        id,
+                        aggregate_directories,
+                    },
+                )?,
+                SessionEvent::QueryRevisionPathsNextPage { tx, id, skip } => {
+                    readers::dispatch(&self, ReadQuery::RevisionPathsPage { tx, id, skip })?
+                }
+                SessionEvent::QueryWorkingCopyDiff {
+                    tx,
+                    base,
+                    skip,
+                    snapshot,
+                } => {
+                    // without a snapshot, the diff is of the working copy as it was last seen
+                    let snapshotted = if snapshot && !self.is_historical() {
+                        self.import_and_snapshot(true)
+                    } else {
+                        Ok(false)
+                    };
+                    tx.send(snapshotted.and_then(|snapshotted| {
+                        let mut diff = queries::query_working_copy_diff(&self, &base, skip)?;
+                        if snapshotted {
+                            diff.new_status = Some(self.format_status());
+                        }
+                        Ok(diff)
+                    }))?
+                }
+                SessionEvent::QueryRewritePreview {
+                    tx,
+                    id,
+                    paths,
+                    pattern,
+                    replacement,
+                } => tx.send(queries::query_rewrite_preview(
+                    &self,
+                    id,
+                    paths,
+                    pattern,
+                    replacement,
+                ))?,
+                SessionEvent::QuerySuggestedDescription { tx, id } => {
+                    tx.send(queries::query_suggested_description(&self, id))?
+                }
+                SessionEvent::QueryChangeOperations { tx, id } => {
+                    tx.send(queries::query_change_operations(&self, id))?
+                }
+                SessionEvent::QueryDescriptionCheck { tx, text } => {
+                    tx.send(queries::query_description_check(&self, &text))?
+                }
+                SessionEvent::QueryRevsetAliases { tx } => {
+                    tx.send(queries::query_revset_aliases(&self))?
+                }
+                SessionEvent::QueryNotes { tx } => tx.send(queries::query_notes(&self))?,
+                SessionEvent::QueryRevisionStats { tx, ids } => {
+                    tx.send(queries::query_revision_stats(&self, ids))?
+                }
+                SessionEvent::QueryAmendTargets { tx, paths, limit } => {
+                    tx.send(queries::query_amend_targets(&self, paths, limit))?
+                }
+                SessionEvent::QueryMergeBase { tx, left, right } => {
+                    tx.send(queries::query_merge_base(&self, left, right))?
+                }
+                SessionEvent::QueryOperationDiff { tx, op } => {
+                    tx.send(queries::query_operation_diff(&self, &op))?
                 }
+                SessionEvent::QueryOperations { tx } => {
+                    tx.send(queries::query_operations(&self))?
+                }
+                SessionEvent::QueryActivity { tx, limit } => {
+                    tx.send(queries::query_activity(&self, limit))?
+                }
+                SessionEvent::QueryCheckpoints { tx } => {
+                    tx.send(queries::query_checkpoints(&self))?
+                }
+                SessionEvent::QueryVerifyRepository { tx } => {
+                    tx.send(queries::query_verify_repository(&self))?
+                }
+                SessionEvent::QueryMergedBookmarks { tx } => {
+                    tx.send(queries::query_merged_bookmarks(&self))?
+                }
+                SessionEvent::CopyRevisions { tx, ids } => {
+                    tx.send(bundles::copy_revisions(&self, ids))?
+                }
+                SessionEvent::QueryDropTargets {
+                    tx,
+                    dragged,
+                    visible,
+                } => tx.send(queries::query_drop_targets(&self, dragged, visible))?,
                 SessionEvent::QueryRemotes {
                     tx,
                     tracking_branch,
                 } => tx.send(queries::query_remotes(&self, tracking_branch))?,
+                SessionEvent::QueryRemoteConnection { tx, remote_name } => {
+                    tx.send(queries::query_remote_connection(&self, remote_name))?
+                }
+                SessionEvent::QueryResolvePath { tx, path } => {
+                    tx.send(queries::query_resolve_path(&self, path))?
+                }
+                SessionEvent::LaunchDiffTool { tx, id, path } => {
+                    tx.send(difftool::launch(&self, id, path))?
+                }
+                SessionEvent::QueryConflict { tx, id, path } => {
+                    tx.send(queries::query_conflict(&self, id, path))?
+                }
                 SessionEvent::QueryLog {
                     tx,
                     query: revset_string,
+                    first_parent,
+                    show_hidden,
+                    elide,
+                    full,
                 } => {
                     let log_page_size = self
                         .session
                         .force_log_page_size
                         .unwrap_or(self.data.settings.query_log_page_size());
+                    let narrowed_query = if full {
+                        None
+                    } else {
+                        self.narrow_query(&revset_string)
+                    };
                     handle_query(
                         &mut state,
                         &self,
                         tx,
                         rx,
-                        Some(&revset_string),
-                        Some(QueryState::new(log_page_size)),
+                        Some(narrowed_query.as_deref().unwrap_or(&revset_string)),
+                        Some(
+                            QueryState::new(log_page_size, first_parent, show_hidden)
+                                .with_elision(elide)
+                                .with_narrowing(
+                                    narrowed_query.as_ref().map(|_| revset_string.clone()),
+                                ),
+                        ),
                     )?;
 
                     self.session.latest_query = Some(revset_string);
+                    self.session.latest_narrowed_query = narrowed_query;
                 }
                 SessionEvent::QueryLogNextPage { tx } => {
-                    let revset_string = self.session.latest_query.as_ref().map(|x| x.as_str());
+                    let revset_string = self
+                        .session
+                        .latest_narrowed_query
+                        .as_deref()
+                        .or(self.session.latest_query.as_deref());
                     handle_query(&mut state, &self, tx, rx, revset_string, None)?;
                 }
+                SessionEvent::ExportLog {
+                    tx,
+                    query,
+                    first_parent,
+                    show_hidden,
+                    format,
+                    path,
+                } => {
+                    // a separate query from the one being paged, so the log can keep scrolling afterwards
+                    let log_page_size = self
+                        .session
+                        .force_log_page_size
+                        .unwrap_or(self.data.settings.query_log_page_size());
+                    let result = File::create(&path)
+                        .with_context(|| format!("create {}", path.display()))
+                        .and_then(|file| {
+                            queries::export_log(
+                                &self,
+                                &query,
+                                first_parent,
+                                show_hidden,
+                                log_page_size,
+                                &format,
+                                &mut BufWriter::new(file),
+                            )
+                        });
+                    tx.send(result)?;
+                }
+                SessionEvent::QueryTrace { tx } => {
+                    tx.send(self.session.trace.borrow().entries())?
+                }
+                SessionEvent::InitWorkspace { tx, wd, options } => {
+                    tx.send(self.session.init_directory(&wd, &options))?
+                }
+                SessionEvent::CreateSampleRepository { tx, parent } => {
+                    tx.send(sample::create_sample_repository(self.session, &parent))?
+                }
+                SessionEvent::ExecuteSnapshot { tx } if self.is_historical() => tx.send(None)?,
                 SessionEvent::ExecuteSnapshot { tx } => {
                     let updated_head = self.load_at_head()?; // alternatively, this could be folded into snapshot so that it's done by all mutations
                     if self.import_and_snapshot(false)? || updated_head {
@@ -207,13 +682,54 @@ impl Session for WorkspaceSession<'_> {
                         tx.send(None)?;
                     }
                 }
-                SessionEvent::ExecuteMutation { tx, mutation } => {
+                SessionEvent::SetViewOperation { tx, op } => tx.send(
+                    self.load_at_operation(op.as_deref())
+                        .map(|_| self.format_status()),
+                )?,
+                SessionEvent::ExecuteMutation { tx, .. } if self.is_historical() => {
+                    tx.send(messages::MutationResult::PreconditionError {
+                        message:
+                            "Viewing an earlier operation; return to the present to make changes."
+                                .to_owned(),
+                    })?
+                }
+                SessionEvent::ExecuteMutation {
+                    tx,
+                    mutation,
+                    confirmation,
+                } => {
                     let name = mutation.as_ref().describe();
-                    match catch_unwind(AssertUnwindSafe(|| {
+                    if let Some(required) =
+                        confirmation_required(&self, mutation.as_ref(), &name, confirmation)
+                    {
+                        tx.send(required)?;
+                        continue;
+                    }
+
+                    self.mutation_name = name.rsplit("::").next().map(str::to_owned);
+
+                    // if gg is killed before the mutation returns, the journal will be found on next open
+                    let workspace_root = self.workspace.workspace_root().to_owned();
+                    let window_label = self.session.window_label.clone();
+                    if let Err(err) = MutationJournal::begin(
+                        &workspace_root,
+                        window_label.as_deref(),
+                        name.clone(),
+                        self.repo().op_id().hex(),
+                    ) {
+                        log::warn!("failed to write mutation journal: {err:#}");
+                    }
+                    let result = catch_unwind(AssertUnwindSafe(|| {
                         mutation.execute(&mut self).with_context(|| name.clone())
-                    })) {
+                    }));
+                    if let Err(err) = MutationJournal::end(&workspace_root, window_label.as_deref())
+                    {
+                        log::warn!("failed to clear mutation journal: {err:#}");
+                    }
+                    self.mutation_name = None;
+                    match result {
                         Ok(result) => {
-                            tx.send(match result {
+                            let result = match result {
                                 Ok(result) => result,
                                 Err(err) => {
                                     log::error!("{err:?}");
@@ -221,7 +737,14 @@ impl Session for WorkspaceSession<'_> {
                                         message: (&*format!("{err:?}")).into(),
                                     }
                                 }
-                            })?;
+                            };
+
+                            // the git backend has to be released before its files can be moved
+                            if let Some(colocation) = self.session.colocation.take() {
+                                handler::optional!(self.save_cache());
+                                return Ok(WorkspaceResult::Colocate(tx, result, colocation));
+                            }
+                            tx.send(result)?;
                         }
                         Err(panic) => {
                             let mut message = match panic.downcast::<&str>() {
@@ -256,16 +779,7 @@ impl Session for WorkspaceSession<'_> {
                 SessionEvent::WriteConfigArray { scope, key, values } => {
                     let name = key.iter().collect();
 
-                    let path = match scope {
-                        ConfigSource::User => jj_cli::config::new_config_path()
-                            .map_err(|err| anyhow!(err))
-                            .and_then(|path| {
-                                path.ok_or(anyhow!("No repo config path found to edit"))
-                            }),
-                        ConfigSource::Repo => Ok(self.workspace.repo_path().join("config.toml")),
-                        _ => Err(anyhow!("Can't get path for config source {scope:?}")),
-                    }
-                    .and_then(|path| {
+                    let path = self.config_path(&scope).and_then(|path| {
                         let toml_array = toml_edit::Value::Array(values.iter().collect());
                         write_config_value_to_file(&name, toml_array, &path)
                             .map_err(|err| anyhow!("{err:?}"))
@@ -273,29 +787,209 @@ impl Session for WorkspaceSession<'_> {
 
                     handler::optional!(path);
 
-                    (self.data.settings, self.data.aliases_map) =
-                        read_config(self.workspace.repo_path())?;
+                    self.reload_config()?;
+                }
+                SessionEvent::SetNote {
+                    tx,
+                    change_hex,
+                    text,
+                } => tx.send(
+                    self.update_annotations(|annotations| annotations.set_note(change_hex, text)),
+                )?,
+                SessionEvent::SaveDraft {
+                    tx,
+                    change_hex,
+                    text,
+                } => tx.send(DraftDescription::save(
+                    self.workspace.workspace_root(),
+                    &change_hex,
+                    &text,
+                ))?,
+                SessionEvent::LoadDraft { tx, change_hex } => tx.send(DraftDescription::load(
+                    self.workspace.workspace_root(),
+                    &change_hex,
+                ))?,
+                SessionEvent::DiscardDraft { tx, change_hex } => tx.send(
+                    DraftDescription::discard(self.workspace.workspace_root(), &change_hex),
+                )?,
+                SessionEvent::StageChange {
+                    tx,
+                    path,
+                    hunk,
+                    staged,
+                } => tx.send(staging::stage_change(&mut self, path, hunk, staged))?,
+                SessionEvent::QueryStaged { tx } => tx.send(staging::query_staged(&mut self))?,
+                SessionEvent::SaveWorkspaceState {
+                    tx,
+                    scope,
+                    selection,
+                } => {
+                    handler::optional!(self.save_workspace_state(&scope, selection));
+                    handler::optional!(self.save_cache());
+                    tx.send(())?;
                 }
             };
         }
     }
 }
 
+// destructive mutations are only executed when resubmitted with a token for the same summary at
+// the same operation, so a confirmation can't be replayed after the repo has changed
+fn confirmation_required(
+    ws: &WorkspaceSession,
+    mutation: &(dyn Mutation + Send + Sync),
+    name: &str,
+    confirmation: Option<String>,
+) -> Option<messages::MutationResult> {
+    let summary = match confirmation_summary(ws, mutation) {
+        Ok(summary) => summary?,
+        Err(err) => {
+            return Some(messages::MutationResult::InternalError {
+                message: (&*format!("{err:?}")).into(),
+            })
+        }
+    };
+
+    let mut hasher = DefaultHasher::new();
+    (name, &summary, ws.repo().op_id().hex()).hash(&mut hasher);
+    let token = format!("{:016x}", hasher.finish());
+
+    if confirmation.as_ref() == Some(&token) {
+        None
+    } else {
+        Some(messages::MutationResult::ConfirmationRequired { token, summary })
+    }
+}
+
+// large rewrites are confirmed even when destructive mutations aren't; one prompt covers both
+fn confirmation_summary(
+    ws: &WorkspaceSession,
+    mutation: &(dyn Mutation + Send + Sync),
+) -> Result<Option<String>> {
+    let large_rewrite = mutation
+        .rewrite_count(ws)?
+        .filter(|count| *count > ws.data.settings.operations_rewrite_limit());
+
+    let destructive = if ws.data.settings.ui_confirm_destructive() {
+        mutation.destructive_summary(ws)?
+    } else {
+        None
+    };
+
+    Ok(match (large_rewrite, destructive) {
+        (Some(count), Some(summary)) => {
+            Some(format!("This will rewrite {count} revisions. {summary}"))
+        }
+        (Some(count), None) => Some(format!("This will rewrite {count} revisions. Continue?")),
+        (None, summary) => summary,
+    })
+}
+
 impl Session for queries::QuerySession<'_, '_> {
     type Transition = QueryResult;
 
     fn handle_events(mut self, rx: &Receiver<SessionEvent>) -> Result<Self::Transition> {
         loop {
+            self.ws.session.trace.borrow_mut().end();
             let evt = rx.recv();
-            log::debug!("LogQuery handling {evt:?}");
+            if let Ok(evt) = &evt {
+                self.ws.session.trace.borrow_mut().begin("LogQuery", evt);
+            }
             match evt {
-                Ok(SessionEvent::QueryRevision { tx, id }) => {
-                    tx.send(queries::query_revision(&self.ws, id))?
+                Ok(SessionEvent::QueryTrace { tx }) => {
+                    tx.send(self.ws.session.trace.borrow().entries())?
+                }
+                // let the workspace session deal with a deleted or moved workspace
+                Ok(evt) if !self.ws.is_present() => return Ok(QueryResult(evt, self.state)),
+                Ok(SessionEvent::QueryRevision {
+                    tx,
+                    id,
+                    aggregate_directories,
+                }) => readers::dispatch(
+                    self.ws,
+                    ReadQuery::Revision {
+                        tx,
+                        id,
+                        aggregate_directories,
+                    },
+                )?,
+                Ok(SessionEvent::QueryRevisionPathsNextPage { tx, id, skip }) => {
+                    readers::dispatch(self.ws, ReadQuery::RevisionPathsPage { tx, id, skip })?
+                }
+                Ok(SessionEvent::QueryRewritePreview {
+                    tx,
+                    id,
+                    paths,
+                    pattern,
+                    replacement,
+                }) => tx.send(queries::query_rewrite_preview(
+                    self.ws,
+                    id,
+                    paths,
+                    pattern,
+                    replacement,
+                ))?,
+                Ok(SessionEvent::QuerySuggestedDescription { tx, id }) => {
+                    tx.send(queries::query_suggested_description(self.ws, id))?
+                }
+                Ok(SessionEvent::QueryChangeOperations { tx, id }) => {
+                    tx.send(queries::query_change_operations(self.ws, id))?
                 }
+                Ok(SessionEvent::QueryDescriptionCheck { tx, text }) => {
+                    tx.send(queries::query_description_check(self.ws, &text))?
+                }
+                Ok(SessionEvent::QueryRevsetAliases { tx }) => {
+                    tx.send(queries::query_revset_aliases(self.ws))?
+                }
+                Ok(SessionEvent::QueryNotes { tx }) => tx.send(queries::query_notes(self.ws))?,
+                Ok(SessionEvent::QueryRevisionStats { tx, ids }) => {
+                    tx.send(queries::query_revision_stats(self.ws, ids))?
+                }
+                Ok(SessionEvent::QueryAmendTargets { tx, paths, limit }) => {
+                    tx.send(queries::query_amend_targets(self.ws, paths, limit))?
+                }
+                Ok(SessionEvent::QueryMergeBase { tx, left, right }) => {
+                    tx.send(queries::query_merge_base(self.ws, left, right))?
+                }
+                Ok(SessionEvent::QueryOperationDiff { tx, op }) => {
+                    tx.send(queries::query_operation_diff(self.ws, &op))?
+                }
+                Ok(SessionEvent::QueryOperations { tx }) => {
+                    tx.send(queries::query_operations(self.ws))?
+                }
+                Ok(SessionEvent::QueryActivity { tx, limit }) => {
+                    tx.send(queries::query_activity(self.ws, limit))?
+                }
+                Ok(SessionEvent::QueryCheckpoints { tx }) => {
+                    tx.send(queries::query_checkpoints(self.ws))?
+                }
+                Ok(SessionEvent::QueryVerifyRepository { tx }) => {
+                    tx.send(queries::query_verify_repository(self.ws))?
+                }
+                Ok(SessionEvent::QueryMergedBookmarks { tx }) => {
+                    tx.send(queries::query_merged_bookmarks(self.ws))?
+                }
+                Ok(SessionEvent::QueryDropTargets {
+                    tx,
+                    dragged,
+                    visible,
+                }) => tx.send(queries::query_drop_targets(self.ws, dragged, visible))?,
                 Ok(SessionEvent::QueryRemotes {
                     tx,
                     tracking_branch,
                 }) => tx.send(queries::query_remotes(&self.ws, tracking_branch))?,
+                Ok(SessionEvent::QueryRemoteConnection { tx, remote_name }) => {
+                    tx.send(queries::query_remote_connection(self.ws, remote_name))?
+                }
+                Ok(SessionEvent::QueryResolvePath { tx, path }) => {
+                    tx.send(queries::query_resolve_path(self.ws, path))?
+                }
+                Ok(SessionEvent::LaunchDiffTool { tx, id, path }) => {
+                    tx.send(difftool::launch(self.ws, id, path))?
+                }
+                Ok(SessionEvent::QueryConflict { tx, id, path }) => {
+                    tx.send(queries::query_conflict(self.ws, id, path))?
+                }
                 Ok(SessionEvent::QueryLogNextPage { tx }) => tx.send(self.get_page())?,
                 Ok(unhandled) => return Ok(QueryResult(unhandled, self.state)),
                 Err(err) => return Err(anyhow!(err)),
@@ -337,13 +1031,35 @@ fn handle_query(
         }
     };
 
-    let revset = match ws
-        .evaluate_revset_str(revset_str)
-        .context("evaluate revset")
-    {
+    // a mutation or snapshot landed between pages
+    if query_state.is_stale(ws) {
+        tx.send(Ok(messages::LogPage {
+            rows: Vec::new(),
+            has_more: false,
+            invalidated: true,
+            hidden_match: None,
+            narrowed_from: None,
+        }))?;
+
+        state.unhandled_event = None;
+        state.unpaged_query = None;
+        return Ok(());
+    }
+
+    let revset = if query_state.show_hidden {
+        ws.evaluate_revset_str_with_hidden(revset_str)
+    } else {
+        ws.evaluate_revset_str(revset_str)
+    };
+
+    let revset = match revset {
         Ok(x) => x,
         Err(err) => {
-            tx.send(Err(err))?;
+            tx.send(match queries::query_hidden_match(ws, &err) {
+                Ok(Some(page)) => Ok(page),
+                Ok(None) => Err(anyhow::Error::from(err).context("evaluate revset")),
+                Err(hidden_err) => Err(hidden_err),
+            })?;
 
             state.unhandled_event = None;
             state.unpaged_query = None;
@@ -0,0 +1,320 @@
+//! A git-style index for the working copy: paths and hunks marked to be committed together by
+//! CommitStaged. Staging is held by the worker rather than the repo, so it belongs to one window.
+//! The same path and hunk selections are used by SplitRevision for any revision.
+
+use std::{collections::BTreeMap, io::Read, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use jj_lib::{
+    backend::{MergedTreeId, TreeValue},
+    merge::Merge,
+    merged_tree::{MergedTree, MergedTreeBuilder},
+    op_store::OperationId,
+    repo::Repo,
+    repo_path::{RepoPath, RepoPathBuf},
+    rewrite,
+};
+use pollster::FutureExt;
+
+use super::{gui_util::WorkspaceSession, queries};
+use crate::messages::{HunkLocation, StagedChanges, StagedPath, TreePath};
+
+pub struct Staging {
+    workspace_root: PathBuf,
+    /// the operation at which the staged hunks were last checked against the working copy
+    op_id: OperationId,
+    /// no hunks means every change to the path is staged
+    paths: BTreeMap<RepoPathBuf, Option<Vec<HunkLocation>>>,
+}
+
+/// contents of a text file which exists on both sides of the working copy's diff
+struct FileSides {
+    left: Vec<u8>,
+    right: Vec<u8>,
+    executable: bool,
+}
+
+pub fn query_staged(ws: &mut WorkspaceSession) -> Result<StagedChanges> {
+    let staging = take_current(ws)?;
+    let result = format_staging(ws, staging.as_ref());
+    ws.session.staging = staging;
+    result
+}
+
+/// adds or removes a path, or a single hunk within it
+pub fn stage_change(
+    ws: &mut WorkspaceSession,
+    path: TreePath,
+    hunk: Option<HunkLocation>,
+    staged: bool,
+) -> Result<StagedChanges> {
+    let mut staging = match take_current(ws)? {
+        Some(staging) => staging,
+        None => Staging {
+            workspace_root: ws.workspace.workspace_root().to_owned(),
+            op_id: ws.repo().op_id().clone(),
+            paths: BTreeMap::new(),
+        },
+    };
+
+    let result = update_staging(ws, &mut staging, path, hunk, staged)
+        .and_then(|_| format_staging(ws, Some(&staging)));
+    ws.session.staging = Some(staging).filter(|staging| !staging.paths.is_empty());
+    result
+}
+
+/// the parents' tree with the staged changes applied, or None if nothing is staged
+pub fn build_staged_tree(ws: &mut WorkspaceSession) -> Result<Option<MergedTreeId>> {
+    let staging = take_current(ws)?;
+    let result = match staging.as_ref() {
+        Some(staging) if !staging.paths.is_empty() => write_staged_tree(ws, staging).map(Some),
+        _ => Ok(None),
+    };
+    ws.session.staging = staging;
+    result
+}
+
+pub fn clear(ws: &mut WorkspaceSession) {
+    ws.session.staging = None;
+}
+
+// drops staged entries which no longer match the working copy, such as after a snapshot
+fn take_current(ws: &mut WorkspaceSession) -> Result<Option<Staging>> {
+    let Some(mut staging) = ws.session.staging.take() else {
+        return Ok(None);
+    };
+
+    if staging.workspace_root != ws.workspace.workspace_root() {
+        return Ok(None);
+    }
+
+    if staging.op_id != *ws.repo().op_id() {
+        let (parent_tree, wc_tree) = wc_trees(ws)?;
+        let mut paths = BTreeMap::new();
+        for (path, hunks) in staging.paths {
+            if parent_tree.path_value(&path)? == wc_tree.path_value(&path)? {
+                continue;
+            }
+
+            match hunks {
+                None => {
+                    paths.insert(path, None);
+                }
+                Some(hunks) => {
+                    let Some(sides) = read_file_sides(ws, &parent_tree, &wc_tree, &path)? else {
+                        continue;
+                    };
+                    let current = queries::diff_hunk_locations(&sides.left, &sides.right);
+                    let hunks: Vec<_> = hunks
+                        .into_iter()
+                        .filter(|hunk| current.contains(hunk))
+                        .collect();
+                    if !hunks.is_empty() {
+                        paths.insert(path, Some(hunks));
+                    }
+                }
+            }
+        }
+
+        staging.paths = paths;
+        staging.op_id = ws.repo().op_id().clone();
+    }
+
+    Ok(Some(staging))
+}
+
+fn update_staging(
+    ws: &WorkspaceSession,
+    staging: &mut Staging,
+    path: TreePath,
+    hunk: Option<HunkLocation>,
+    staged: bool,
+) -> Result<()> {
+    let repo_path = RepoPathBuf::from_internal_string(&path.repo_path);
+    let (parent_tree, wc_tree) = wc_trees(ws)?;
+    if parent_tree.path_value(&repo_path)? == wc_tree.path_value(&repo_path)? {
+        return Err(anyhow!(
+            "{} has no changes in the working copy",
+            path.relative_path.0
+        ));
+    }
+
+    let Some(hunk) = hunk else {
+        if staged {
+            staging.paths.insert(repo_path, None);
+        } else {
+            staging.paths.remove(&repo_path);
+        }
+        return Ok(());
+    };
+
+    let current = match read_file_sides(ws, &parent_tree, &wc_tree, &repo_path)? {
+        Some(sides) => queries::diff_hunk_locations(&sides.left, &sides.right),
+        None => {
+            return Err(anyhow!(
+                "{} can only be staged as a whole file",
+                path.relative_path.0
+            ))
+        }
+    };
+    if !current.contains(&hunk) {
+        return Err(anyhow!(
+            "The hunk in {} has changed; refresh and try again",
+            path.relative_path.0
+        ));
+    }
+
+    let hunks = match (staging.paths.remove(&repo_path), staged) {
+        (None, true) => vec![hunk],
+        (None, false) => return Ok(()),
+        (Some(None), true) => {
+            staging.paths.insert(repo_path, None);
+            return Ok(());
+        }
+        (Some(None), false) => current.into_iter().filter(|h| *h != hunk).collect(),
+        (Some(Some(mut hunks)), true) => {
+            if !hunks.contains(&hunk) {
+                hunks.push(hunk);
+            }
+            hunks
+        }
+        (Some(Some(hunks)), false) => hunks.into_iter().filter(|h| *h != hunk).collect(),
+    };
+
+    if !hunks.is_empty() {
+        staging.paths.insert(repo_path, Some(hunks));
+    }
+    Ok(())
+}
+
+fn format_staging(ws: &WorkspaceSession, staging: Option<&Staging>) -> Result<StagedChanges> {
+    let mut paths = Vec::new();
+    if let Some(staging) = staging {
+        for (path, hunks) in &staging.paths {
+            paths.push(StagedPath {
+                path: ws.format_path(path)?,
+                hunks: hunks.clone(),
+            });
+        }
+    }
+    Ok(StagedChanges { paths })
+}
+
+fn write_staged_tree(ws: &WorkspaceSession, staging: &Staging) -> Result<MergedTreeId> {
+    let (parent_tree, wc_tree) = wc_trees(ws)?;
+    write_selected_tree(ws, &parent_tree, &wc_tree, &staging.paths)
+}
+
+/// the left tree with some of the changes made by the right tree; no hunks means the whole path
+pub fn write_selected_tree(
+    ws: &WorkspaceSession,
+    left_tree: &MergedTree,
+    right_tree: &MergedTree,
+    selection: &BTreeMap<RepoPathBuf, Option<Vec<HunkLocation>>>,
+) -> Result<MergedTreeId> {
+    let store = ws.repo().store().clone();
+
+    let mut tree_builder = MergedTreeBuilder::new(left_tree.id().clone());
+    for (path, hunks) in selection {
+        let value = match hunks {
+            None => right_tree.path_value(path)?,
+            Some(hunks) => {
+                let sides = read_file_sides(ws, left_tree, right_tree, path)?.ok_or(anyhow!(
+                    "{} can only be selected as a whole file",
+                    path.as_internal_file_string()
+                ))?;
+                let current = queries::diff_hunk_locations(&sides.left, &sides.right);
+                if hunks.iter().any(|hunk| !current.contains(hunk)) {
+                    return Err(anyhow!(
+                        "The hunks in {} have changed; refresh and try again",
+                        path.as_internal_file_string()
+                    ));
+                }
+                let content = apply_hunks(&sides.left, &sides.right, hunks);
+                let id = store.write_file(path, &mut content.as_slice()).block_on()?;
+                Merge::normal(TreeValue::File {
+                    id,
+                    executable: sides.executable,
+                })
+            }
+        };
+        tree_builder.set_or_remove(path.clone(), value);
+    }
+
+    Ok(tree_builder.write_tree(&store)?)
+}
+
+// replaces each selected hunk's lines in the old contents with its lines from the new contents;
+// the line ranges are 1-based and include the same context on both sides
+fn apply_hunks(left: &[u8], right: &[u8], hunks: &[HunkLocation]) -> Vec<u8> {
+    let left_lines: Vec<&[u8]> = left.split_inclusive(|b| *b == b'\n').collect();
+    let right_lines: Vec<&[u8]> = right.split_inclusive(|b| *b == b'\n').collect();
+
+    let mut hunks = hunks.to_vec();
+    hunks.sort_by_key(|hunk| hunk.from_file.start);
+
+    let mut content = Vec::with_capacity(left.len().max(right.len()));
+    let mut next_left = 0;
+    for hunk in hunks {
+        let from_start = hunk.from_file.start.saturating_sub(1);
+        let to_start = hunk.to_file.start.saturating_sub(1);
+        for line in &left_lines[next_left.min(from_start)..from_start] {
+            content.extend_from_slice(line);
+        }
+        for line in &right_lines[to_start..to_start + hunk.to_file.len] {
+            content.extend_from_slice(line);
+        }
+        next_left = from_start + hunk.from_file.len;
+    }
+    for line in &left_lines[next_left.min(left_lines.len())..] {
+        content.extend_from_slice(line);
+    }
+
+    content
+}
+
+// the working copy's parents, merged, and its own tree
+fn wc_trees(ws: &WorkspaceSession) -> Result<(MergedTree, MergedTree)> {
+    let wc = ws.get_commit(ws.wc_id())?;
+    let parents: Result<Vec<_>, _> = wc.parents().collect();
+    let parent_tree = rewrite::merge_commit_trees(ws.repo(), &parents?)?;
+    Ok((parent_tree, wc.tree()?))
+}
+
+// hunks are only selected in UTF-8 files, whose diff lines are the same as the file's
+fn read_file_sides(
+    ws: &WorkspaceSession,
+    parent_tree: &MergedTree,
+    wc_tree: &MergedTree,
+    path: &RepoPath,
+) -> Result<Option<FileSides>> {
+    let (
+        Some(Some(TreeValue::File { id: left_id, .. })),
+        Some(Some(TreeValue::File {
+            id: right_id,
+            executable,
+        })),
+    ) = (
+        parent_tree.path_value(path)?.as_resolved().cloned(),
+        wc_tree.path_value(path)?.as_resolved().cloned(),
+    )
+    else {
+        return Ok(None);
+    };
+
+    let store = ws.repo().store();
+    let mut left = Vec::new();
+    store.read_file(path, &left_id)?.read_to_end(&mut left)?;
+    let mut right = Vec::new();
+    store.read_file(path, &right_id)?.read_to_end(&mut right)?;
+
+    if std::str::from_utf8(&left).is_err() || std::str::from_utf8(&right).is_err() {
+        return Ok(None);
+    }
+
+    Ok(Some(FileSides {
+        left,
+        right,
+        executable,
+    }))
+}
@@ -1,15 +1,31 @@
 use super::{mkrepo, revs};
 use crate::{
     messages::{
-        AbandonRevisions, CheckoutRevision, CopyChanges, CreateRevision, DescribeRevision,
-        DuplicateRevisions, InsertRevision, MoveChanges, MoveSource, MutationResult, RevResult,
-        TreePath,
+        AbandonRevisions, AddIgnorePattern, AddParentToMerge, BackoutRevisions, ChangeKind,
+        CheckoutPolicy, CheckoutRevision, CheckpointWorkingCopy, CleanupBookmarks,
+        ColocateRepository, CommitStaged, ConfigScope, CopyChanges, CreateFile, CreateRevision,
+        DeletePaths, DeleteRef, DescribeRevision, DescribeRevisions, DescriptionEdit,
+        DuplicateRevisions, FetchFromPath, FixRevisions, FollowWorkspace, GitHead, GraftRevisions,
+        HideRevisions, InsertRevision, LabelColor, MergeRevisions, MoveChanges, MoveRef,
+        MoveSource, MutationResult, ParallelizeRevisions, RebaseAction, RebaseStep,
+        RemoveParentFromMerge, RemoveRevsetAlias, RenamePath, RepoConfig, ResolveConflict,
+        ResolveWithMergeTool, RestoredChange, RevLabel, RevResult, RewritePaths, RunJjCommand,
+        RunRebasePlan, SetAuthor, SetDefaultQuery, SetLabel, SetRevsetAlias, SetTrunk,
+        SetUserIdentity, SplitPath, SplitRevision, StoreRef, SwapParents, TreePath, UndoOperation,
+        UnhideRevisions,
+    },
+    worker::{
+        bundles, queries, staging, FixedClock, Mutation, PasteBundle, WorkerSession,
+        WorkspaceSession,
     },
-    worker::{queries, Mutation, WorkerSession},
 };
 use anyhow::Result;
 use assert_matches::assert_matches;
-use std::fs;
+use jj_lib::{
+    backend::TreeValue, object_id::ObjectId, op_store::WorkspaceId, repo::Repo,
+    repo_path::RepoPathBuf,
+};
+use std::{fs, io::Read, sync::Arc};
 
 #[test]
 fn abandon_revisions() -> Result<()> {
@@ -32,6 +48,106 @@ fn abandon_revisions() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn hide_and_unhide_revisions() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let result = HideRevisions { ids: vec![] }.execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let result = HideRevisions {
+        ids: vec![revs::main_bookmark().commit],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let hide = HideRevisions {
+        ids: vec![revs::resolve_conflict().commit],
+    };
+    assert_eq!(
+        Some(format!(
+            "Hide revision {}?",
+            revs::resolve_conflict().commit.prefix
+        )),
+        hide.destructive_summary(&ws)?
+    );
+    let result = hide.execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let page = queries::query_log(&ws, "all()", 100)?;
+    assert_eq!(11, page.rows.len());
+
+    let page = queries::query_log_with(&ws, "all()", 100, false, true)?;
+    assert!(page.rows.iter().any(|row| row.revision.is_hidden
+        && row.revision.id.commit.hex == revs::resolve_conflict().commit.hex));
+
+    let result = UnhideRevisions {
+        ids: vec![revs::resolve_conflict().commit],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let page = queries::query_log(&ws, "all()", 100)?;
+    assert_eq!(12, page.rows.len());
+    assert!(page.rows.iter().all(|row| !row.revision.is_hidden));
+
+    Ok(())
+}
+
+#[test]
+fn backout_revisions_disjoint() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let result = BackoutRevisions {
+        ids: vec![revs::main_bookmark(), revs::resolve_conflict()],
+        new_parent_id: None,
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let rev = queries::query_revision(&ws, revs::working_copy(), false)?;
+    assert_matches!(rev, RevResult::Detail { changes, .. } if changes
+        .iter()
+        .any(|change| matches!(change.kind, ChangeKind::Added) && change.path.repo_path == "c.txt")
+        && changes
+            .iter()
+            .any(|change| matches!(change.kind, ChangeKind::Deleted) && change.path.repo_path == "d.txt")
+        && changes.iter().any(|change| change.path.repo_path == "b.txt"));
+
+    Ok(())
+}
+
+#[test]
+fn backout_revisions_into_new_commit() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let result = BackoutRevisions {
+        ids: vec![revs::main_bookmark()],
+        new_parent_id: Some(revs::working_copy().commit),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::UpdatedSelection { .. });
+
+    // the working copy is untouched
+    let rev = queries::query_revision(&ws, revs::working_copy(), false)?;
+    assert_matches!(rev, RevResult::Detail { changes, .. } if changes.is_empty());
+
+    let page = queries::query_log(&ws, "children(@)", 100)?;
+    assert_eq!(1, page.rows.len());
+    assert!(page.rows[0].revision.description.lines[0].starts_with("Back out"));
+
+    Ok(())
+}
+
 #[test]
 fn checkout_revision() -> Result<()> {
     let repo = mkrepo();
@@ -39,25 +155,141 @@ fn checkout_revision() -> Result<()> {
     let mut session = WorkerSession::default();
     let mut ws = session.load_directory(repo.path())?;
 
-    let head_rev = queries::query_revision(&ws, revs::working_copy())?;
-    let conflict_rev = queries::query_revision(&ws, revs::conflict_bookmark())?;
+    let head_rev = queries::query_revision(&ws, revs::working_copy(), false)?;
+    let conflict_rev = queries::query_revision(&ws, revs::conflict_bookmark(), false)?;
     assert_matches!(head_rev, RevResult::Detail { header, .. } if header.is_working_copy);
     assert_matches!(conflict_rev, RevResult::Detail { header, .. } if !header.is_working_copy);
 
     let result = CheckoutRevision {
         id: revs::conflict_bookmark(),
+        policy: None,
     }
     .execute_unboxed(&mut ws)?;
     assert_matches!(result, MutationResult::UpdatedSelection { .. });
 
-    let head_rev = queries::query_revision(&ws, revs::working_copy())?;
-    let conflict_rev = queries::query_revision(&ws, revs::conflict_bookmark())?;
+    let head_rev = queries::query_revision(&ws, revs::working_copy(), false)?;
+    let conflict_rev = queries::query_revision(&ws, revs::conflict_bookmark(), false)?;
     assert_matches!(head_rev, RevResult::NotFound { .. });
     assert_matches!(conflict_rev, RevResult::Detail { header, .. } if header.is_working_copy);
 
     Ok(())
 }
 
+#[test]
+fn checkout_revision_bookmarks_undescribed() -> Result<()> {
+    let repo = mkrepo();
+    fs::write(repo.path().join("wip.txt"), "unfinished")?;
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let result = CheckoutRevision {
+        id: revs::conflict_bookmark(),
+        policy: Some(CheckoutPolicy::Bookmark),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::UpdatedSelection { .. });
+
+    let page = queries::query_log(&ws, "bookmarks(glob:\"wip/*\")", 10)?;
+    assert_eq!(1, page.rows.len());
+    assert!(page.rows[0]
+        .revision
+        .id
+        .change
+        .hex
+        .starts_with(&revs::working_copy().change.hex));
+    assert!(!page.rows[0].revision.is_working_copy);
+
+    Ok(())
+}
+
+#[test]
+fn checkout_revision_bookmark_taken() -> Result<()> {
+    let repo = mkrepo();
+    fs::write(repo.path().join("wip.txt"), "unfinished")?;
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    // an unrelated bookmark which happens to have the name that would be chosen
+    let wc = ws.get_commit(ws.wc_id())?;
+    let name = format!("wip/{}", &wc.change_id().reverse_hex()[..12]);
+    let result = CreateRef {
+        id: revs::main_bookmark(),
+        r#ref: StoreRef::LocalBookmark {
+            branch_name: name.clone(),
+            has_conflict: false,
+            is_synced: false,
+            tracking_remotes: vec![],
+            available_remotes: 0,
+            potential_remotes: 0,
+        },
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let result = CheckoutRevision {
+        id: revs::conflict_bookmark(),
+        policy: Some(CheckoutPolicy::Bookmark),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::UpdatedSelection { .. });
+
+    let main_id = jj_lib::backend::CommitId::try_from_hex(&revs::main_bookmark().commit.hex)?;
+    assert_eq!(
+        Some(&main_id),
+        ws.view().get_local_bookmark(&name).as_normal()
+    );
+    assert!(ws
+        .view()
+        .get_local_bookmark(&format!("{name}-2"))
+        .is_present());
+
+    Ok(())
+}
+
+#[test]
+fn checkout_revision_new_child() -> Result<()> {
+    let repo = mkrepo();
+    fs::write(repo.path().join("wip.txt"), "unfinished")?;
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let result = CheckoutRevision {
+        id: revs::conflict_bookmark(),
+        policy: Some(CheckoutPolicy::NewChild),
+    }
+    .execute_unboxed(&mut ws)?;
+    let MutationResult::UpdatedSelection { new_selection, .. } = result else {
+        panic!("CheckoutRevision failed: {result:?}");
+    };
+    assert!(new_selection.is_working_copy);
+    assert_ne!(
+        revs::conflict_bookmark().change.hex,
+        new_selection.id.change.hex
+    );
+    assert_eq!(
+        revs::conflict_bookmark().commit.hex,
+        new_selection.parent_ids[0].hex
+    );
+
+    // both the undescribed work and the revision are left as they were
+    let page = queries::query_log(&ws, "all()", 100)?;
+    assert!(page
+        .rows
+        .iter()
+        .any(|row| row.revision.id.commit.hex == revs::conflict_bookmark().commit.hex));
+    assert!(page.rows.iter().any(|row| row
+        .revision
+        .id
+        .change
+        .hex
+        .starts_with(&revs::working_copy().change.hex)));
+
+    Ok(())
+}
+
 #[test]
 fn copy_changes() -> Result<()> {
     let repo = mkrepo();
@@ -65,8 +297,8 @@ fn copy_changes() -> Result<()> {
     let mut session = WorkerSession::default();
     let mut ws = session.load_directory(repo.path())?;
 
-    let from_rev = queries::query_revision(&ws, revs::resolve_conflict())?;
-    let to_rev = queries::query_revision(&ws, revs::working_copy())?;
+    let from_rev = queries::query_revision(&ws, revs::resolve_conflict(), false)?;
+    let to_rev = queries::query_revision(&ws, revs::working_copy(), false)?;
     assert_matches!(from_rev, RevResult::Detail { changes, .. } if changes.len() == 1);
     assert_matches!(to_rev, RevResult::Detail { changes, .. } if changes.len() == 0);
 
@@ -81,8 +313,8 @@ fn copy_changes() -> Result<()> {
     .execute_unboxed(&mut ws)?;
     assert_matches!(result, MutationResult::Updated { .. });
 
-    let from_rev = queries::query_revision(&ws, revs::resolve_conflict())?;
-    let to_rev = queries::query_revision(&ws, revs::working_copy())?;
+    let from_rev = queries::query_revision(&ws, revs::resolve_conflict(), false)?;
+    let to_rev = queries::query_revision(&ws, revs::working_copy(), false)?;
     assert_matches!(from_rev, RevResult::Detail { changes, .. } if changes.len() == 1);
     assert_matches!(to_rev, RevResult::Detail { changes, .. } if changes.len() == 1);
 
@@ -96,7 +328,7 @@ fn create_revision_single_parent() -> Result<()> {
     let mut session = WorkerSession::default();
     let mut ws = session.load_directory(repo.path())?;
 
-    let parent_rev = queries::query_revision(&ws, revs::working_copy())?;
+    let parent_rev = queries::query_revision(&ws, revs::working_copy(), false)?;
     assert_matches!(parent_rev, RevResult::Detail { header, .. } if header.is_working_copy);
 
     let result = CreateRevision {
@@ -106,8 +338,8 @@ fn create_revision_single_parent() -> Result<()> {
 
     match result {
         MutationResult::UpdatedSelection { new_selection, .. } => {
-            let parent_rev = queries::query_revision(&ws, revs::working_copy())?;
-            let child_rev = queries::query_revision(&ws, new_selection.id)?;
+            let parent_rev = queries::query_revision(&ws, revs::working_copy(), false)?;
+            let child_rev = queries::query_revision(&ws, new_selection.id, false)?;
             assert!(
                 matches!(parent_rev, RevResult::Detail { header, .. } if !header.is_working_copy)
             );
@@ -121,6 +353,69 @@ fn create_revision_single_parent() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn parallelize_revisions() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    // main <- a <- b <- c
+    let mut stack = vec![revs::main_bookmark()];
+    for _ in 0..3 {
+        let MutationResult::UpdatedSelection { new_selection, .. } = CreateRevision {
+            parent_ids: vec![stack.last().unwrap().clone()],
+        }
+        .execute_unboxed(&mut ws)?
+        else {
+            panic!("CreateRevision failed");
+        };
+        stack.push(new_selection.id);
+    }
+
+    let result = ParallelizeRevisions {
+        ids: vec![stack[1].clone()],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let result = ParallelizeRevisions {
+        ids: vec![stack[1].clone(), stack[2].clone()],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    // a and b are siblings, and c merges them
+    let b_rev = queries::query_revision(&ws, stack[2].clone(), false)?;
+    assert_matches!(b_rev, RevResult::Detail { parents, .. }
+        if matches!(parents.as_slice(), [parent] if parent.id.change.hex == revs::main_bookmark().change.hex));
+    let c_rev = queries::query_revision(&ws, stack[3].clone(), false)?;
+    assert_matches!(c_rev, RevResult::Detail { parents, .. } if parents.len() == 2);
+
+    Ok(())
+}
+
+#[test]
+fn create_revision_description_template() -> Result<()> {
+    let repo = mkrepo();
+    fs::write(
+        repo.path().join(".jj/repo/config.toml"),
+        "gg.ui.new-commit-description = \"wip({ticket}) on {bookmark}\"\ngg.ui.new-commit-ticket-pattern = \"ma(in)\"\n",
+    )?;
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let result = CreateRevision {
+        parent_ids: vec![revs::main_bookmark()],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::UpdatedSelection { new_selection, .. }
+        if new_selection.description.lines == vec!["wip(in) on main"]);
+
+    Ok(())
+}
+
 #[test]
 fn create_revision_multi_parent() -> Result<()> {
     let repo: tempfile::TempDir = mkrepo();
@@ -128,7 +423,7 @@ fn create_revision_multi_parent() -> Result<()> {
     let mut session = WorkerSession::default();
     let mut ws = session.load_directory(repo.path())?;
 
-    let parent_rev = queries::query_revision(&ws, revs::working_copy())?;
+    let parent_rev = queries::query_revision(&ws, revs::working_copy(), false)?;
     assert_matches!(parent_rev, RevResult::Detail { header, .. } if header.is_working_copy);
 
     let result = CreateRevision {
@@ -138,7 +433,7 @@ fn create_revision_multi_parent() -> Result<()> {
 
     match result {
         MutationResult::UpdatedSelection { new_selection, .. } => {
-            let child_rev = queries::query_revision(&ws, new_selection.id)?;
+            let child_rev = queries::query_revision(&ws, new_selection.id, false)?;
             assert_matches!(child_rev, RevResult::Detail { parents, .. } if parents.len() == 2);
         }
         _ => assert!(false, "CreateRevision failed"),
@@ -147,6 +442,39 @@ fn create_revision_multi_parent() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn create_revision_fixed_clock() -> Result<()> {
+    let mut created = Vec::new();
+    for _ in 0..2 {
+        let repo = mkrepo();
+
+        let mut session = WorkerSession {
+            clock: Some(FixedClock::default()),
+            ..Default::default()
+        };
+        let mut ws = session.load_directory(repo.path())?;
+
+        let MutationResult::UpdatedSelection { new_selection, .. } = CreateRevision {
+            parent_ids: vec![revs::main_bookmark()],
+        }
+        .execute_unboxed(&mut ws)?
+        else {
+            panic!("CreateRevision failed");
+        };
+
+        created.push((
+            new_selection.id.commit.hex,
+            new_selection.id.change.hex,
+            ws.repo().op_id().hex(),
+        ));
+    }
+
+    // separate repos, same ids
+    assert_eq!(created[0], created[1]);
+
+    Ok(())
+}
+
 #[test]
 fn describe_revision() -> Result<()> {
     let repo = mkrepo();
@@ -154,7 +482,7 @@ fn describe_revision() -> Result<()> {
     let mut session = WorkerSession::default();
     let mut ws = session.load_directory(repo.path())?;
 
-    let rev = queries::query_revision(&ws, revs::working_copy())?;
+    let rev = queries::query_revision(&ws, revs::working_copy(), false)?;
     assert_matches!(rev, RevResult::Detail { header, .. } if header.description.lines[0] == "");
 
     let result = DescribeRevision {
@@ -165,7 +493,7 @@ fn describe_revision() -> Result<()> {
     .execute_unboxed(&mut ws)?;
     assert_matches!(result, MutationResult::Updated { .. });
 
-    let rev = queries::query_revision(&ws, revs::working_copy())?;
+    let rev = queries::query_revision(&ws, revs::working_copy(), false)?;
     assert!(
         matches!(rev, RevResult::Detail { header, .. } if header.description.lines[0] == "wip")
     );
@@ -180,7 +508,7 @@ fn describe_revision_with_snapshot() -> Result<()> {
     let mut session = WorkerSession::default();
     let mut ws = session.load_directory(repo.path())?;
 
-    let rev = queries::query_revision(&ws, revs::working_copy())?;
+    let rev = queries::query_revision(&ws, revs::working_copy(), false)?;
     assert!(
         matches!(rev, RevResult::Detail { header, changes, .. } if header.description.lines[0] == "" && changes.len() == 0)
     );
@@ -194,7 +522,7 @@ fn describe_revision_with_snapshot() -> Result<()> {
     }
     .execute_unboxed(&mut ws)?;
 
-    let rev = queries::query_revision(&ws, revs::working_copy())?;
+    let rev = queries::query_revision(&ws, revs::working_copy(), false)?;
     assert!(
         matches!(rev, RevResult::Detail { header, changes, .. } if header.description.lines[0] == "wip" && changes.len() != 0)
     );
@@ -202,6 +530,52 @@ fn describe_revision_with_snapshot() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn set_author() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let RevResult::Detail { header, .. } =
+        queries::query_revision(&ws, revs::working_copy(), false)?
+    else {
+        panic!("working copy not found");
+    };
+    let old_author = header.author;
+
+    let result = SetAuthor {
+        ids: vec![revs::working_copy()],
+        name: None,
+        email: Some("fixed@example.com".to_owned()),
+        timestamp: Some(chrono::DateTime::parse_from_rfc3339(
+            "2024-01-31T09:00:00+10:00",
+        )?),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let RevResult::Detail { header, .. } =
+        queries::query_revision(&ws, revs::working_copy(), false)?
+    else {
+        panic!("working copy not found");
+    };
+    assert_eq!(old_author.name, header.author.name);
+    assert_eq!("fixed@example.com", header.author.email);
+    assert_eq!(1706655600, header.author.timestamp.timestamp());
+
+    let result = SetAuthor {
+        ids: vec![revs::working_copy()],
+        name: None,
+        email: None,
+        timestamp: None,
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Unchanged);
+
+    Ok(())
+}
+
 #[test]
 fn duplicate_revisions() -> Result<()> {
     let repo = mkrepo();
@@ -209,7 +583,7 @@ fn duplicate_revisions() -> Result<()> {
     let mut session = WorkerSession::default();
     let mut ws = session.load_directory(repo.path())?;
 
-    let rev = queries::query_revision(&ws, revs::working_copy())?;
+    let rev = queries::query_revision(&ws, revs::working_copy(), false)?;
     assert_matches!(rev, RevResult::Detail { header, .. } if header.description.lines[0] == "");
 
     let result = DuplicateRevisions {
@@ -225,105 +599,1941 @@ fn duplicate_revisions() -> Result<()> {
 }
 
 #[test]
-fn insert_revision() -> Result<()> {
+fn describe_revisions() -> Result<()> {
     let repo = mkrepo();
 
     let mut session = WorkerSession::default();
     let mut ws = session.load_directory(repo.path())?;
 
-    let page = queries::query_log(&ws, "main::@", 4)?;
-    assert_eq!(2, page.rows.len());
-
-    InsertRevision {
-        after_id: revs::main_bookmark(),
-        before_id: revs::working_copy(),
-        id: revs::resolve_conflict(),
+    let ids = vec![revs::working_copy(), revs::resolve_conflict()];
+    let description = |ws: &WorkspaceSession, id| -> Result<Vec<String>> {
+        let RevResult::Detail { header, .. } = queries::query_revision(ws, id, false)? else {
+            panic!("revision not found");
+        };
+        Ok(header.description.lines)
+    };
+
+    let result = DescribeRevisions {
+        ids: ids.clone(),
+        edit: DescriptionEdit::Set {
+            text: "bulk\n".to_owned(),
+        },
     }
     .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+    assert_eq!(vec!["bulk", ""], description(&ws, revs::working_copy())?);
+    assert_eq!(
+        vec!["bulk", ""],
+        description(&ws, revs::resolve_conflict())?
+    );
 
-    let page = queries::query_log(&ws, "main::@", 4)?;
-    assert_eq!(3, page.rows.len());
+    let append = || DescribeRevisions {
+        ids: ids.clone(),
+        edit: DescriptionEdit::AppendTrailer {
+            trailer: "Reviewed-by: Someone".to_owned(),
+        },
+    };
+    let result = append().execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+    let result = append().execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Unchanged);
+    assert_eq!(
+        vec!["bulk", "", "Reviewed-by: Someone", ""],
+        description(&ws, revs::working_copy())?
+    );
+
+    let result = DescribeRevisions {
+        ids: ids.clone(),
+        edit: DescriptionEdit::Replace {
+            find: "bulk".to_owned(),
+            replace: "done".to_owned(),
+        },
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+    assert_eq!("done", description(&ws, revs::resolve_conflict())?[0]);
+
+    let result = DescribeRevisions {
+        ids,
+        edit: DescriptionEdit::Replace {
+            find: "".to_owned(),
+            replace: "x".to_owned(),
+        },
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
 
     Ok(())
 }
 
 #[test]
-fn move_changes_all_paths() -> Result<()> {
+fn graft_revisions() -> Result<()> {
     let repo = mkrepo();
 
     let mut session = WorkerSession::default();
     let mut ws = session.load_directory(repo.path())?;
 
-    let parent_rev = queries::query_revision(&ws, revs::conflict_bookmark())?;
-    assert_matches!(parent_rev, RevResult::Detail { header, .. } if header.has_conflict);
+    let pull_request = queries::query_log(&ws, "pull-request", 1)?.rows[0]
+        .revision
+        .id
+        .clone();
 
-    let result = MoveChanges {
-        from_id: revs::resolve_conflict(),
-        to_id: revs::conflict_bookmark().commit,
-        paths: vec![],
+    let result = GraftRevisions {
+        ids: vec![revs::main_bookmark()],
+        parent_ids: vec![pull_request.clone()],
     }
     .execute_unboxed(&mut ws)?;
-    assert_matches!(result, MutationResult::Updated { .. });
+    assert_matches!(result, MutationResult::UpdatedSelection { new_selection, .. }
+        if matches!(new_selection.parent_ids.as_slice(), [parent_id] if parent_id.hex == pull_request.commit.hex)
+            && new_selection.id.change.hex != revs::main_bookmark().change.hex);
 
-    let parent_rev = queries::query_revision(&ws, revs::conflict_bookmark())?;
-    assert_matches!(parent_rev, RevResult::Detail { header, .. } if !header.has_conflict);
+    let page = queries::query_log(&ws, "children(pull-request)", 3)?;
+    assert_eq!(1, page.rows.len());
+    let rev = queries::query_revision(&ws, page.rows[0].revision.id.clone(), false)?;
+    assert_matches!(rev, RevResult::Detail { header, changes, .. }
+        if !header.has_conflict && changes.len() == 2);
 
     Ok(())
 }
 
 #[test]
-fn move_changes_single_path() -> Result<()> {
+fn graft_revisions_keeps_outside_parents() -> Result<()> {
     let repo = mkrepo();
 
     let mut session = WorkerSession::default();
     let mut ws = session.load_directory(repo.path())?;
 
-    let from_rev = queries::query_revision(&ws, revs::main_bookmark())?;
-    let to_rev = queries::query_revision(&ws, revs::working_copy())?;
-    assert_matches!(from_rev, RevResult::Detail { changes, .. } if changes.len() == 2);
-    assert_matches!(to_rev, RevResult::Detail { changes, .. } if changes.len() == 0);
+    let pull_request = queries::query_log(&ws, "pull-request", 1)?.rows[0]
+        .revision
+        .id
+        .clone();
 
-    let result = MoveChanges {
-        from_id: revs::main_bookmark(),
-        to_id: revs::working_copy().commit,
-        paths: vec![TreePath {
-            repo_path: "c.txt".to_owned(),
-            relative_path: "".into(),
-        }],
+    // a <- m -> conflict
+    let MutationResult::UpdatedSelection {
+        new_selection: a, ..
+    } = CreateRevision {
+        parent_ids: vec![revs::main_bookmark()],
+    }
+    .execute_unboxed(&mut ws)?
+    else {
+        panic!("CreateRevision failed");
+    };
+    let MutationResult::UpdatedSelection {
+        new_selection: m, ..
+    } = CreateRevision {
+        parent_ids: vec![a.id.clone(), revs::conflict_bookmark()],
+    }
+    .execute_unboxed(&mut ws)?
+    else {
+        panic!("CreateRevision failed");
+    };
+
+    let result = GraftRevisions {
+        ids: vec![a.id, m.id],
+        parent_ids: vec![pull_request],
     }
     .execute_unboxed(&mut ws)?;
-    assert_matches!(result, MutationResult::Updated { .. });
+    assert_matches!(result, MutationResult::UpdatedSelection { new_selection, .. }
+        if new_selection.parent_ids.len() == 2
+            && new_selection.parent_ids[1].hex == revs::conflict_bookmark().commit.hex);
 
-    let from_rev = queries::query_revision(&ws, revs::main_bookmark())?;
-    let to_rev = queries::query_revision(&ws, revs::working_copy())?;
-    assert_matches!(from_rev, RevResult::Detail { changes, .. } if changes.len() == 1);
-    assert_matches!(to_rev, RevResult::Detail { changes, .. } if changes.len() == 1);
+    Ok(())
+}
+
+#[test]
+fn paste_revisions_from_another_repo() -> Result<()> {
+    let source_repo = mkrepo();
+    let target_repo = mkrepo();
+
+    let mut source_session = WorkerSession::default();
+    let source_ws = source_session.load_directory(source_repo.path())?;
+    let bundle = bundles::copy_revisions(&source_ws, vec![revs::main_bookmark()])?;
+    let RevResult::Detail {
+        header: source_header,
+        ..
+    } = queries::query_revision(&source_ws, revs::main_bookmark(), false)?
+    else {
+        panic!("source revision not found");
+    };
+
+    let mut target_session = WorkerSession::default();
+    let mut target_ws = target_session.load_directory(target_repo.path())?;
+    let result = PasteBundle {
+        bundle: Arc::new(bundle),
+        parent_ids: vec![revs::working_copy()],
+    }
+    .execute_unboxed(&mut target_ws)?;
+    let MutationResult::UpdatedSelection { new_selection, .. } = result else {
+        panic!("paste failed: {result:?}");
+    };
+    assert_matches!(new_selection.parent_ids.as_slice(), [parent_id]
+        if parent_id.hex == revs::working_copy().commit.hex);
+    assert_ne!(
+        revs::main_bookmark().change.hex,
+        new_selection.id.change.hex
+    );
+    assert_eq!(
+        source_header.description.lines,
+        new_selection.description.lines
+    );
+    assert_eq!(source_header.author.email, new_selection.author.email);
 
     Ok(())
 }
 
 #[test]
-fn move_source() -> Result<()> {
+fn paste_revisions_conflicting_with_target() -> Result<()> {
     let repo = mkrepo();
 
     let mut session = WorkerSession::default();
     let mut ws = session.load_directory(repo.path())?;
 
-    let page = queries::query_log(&ws, "@+", 1)?;
-    assert_eq!(0, page.rows.len());
-
-    MoveSource {
-        id: revs::resolve_conflict(),
-        parent_ids: vec![revs::working_copy().commit],
+    let MutationResult::UpdatedSelection {
+        new_selection: source,
+        ..
+    } = CreateRevision {
+        parent_ids: vec![revs::main_bookmark()],
+    }
+    .execute_unboxed(&mut ws)?
+    else {
+        panic!("CreateRevision failed");
+    };
+    fs::write(repo.path().join("a.txt"), "source\n")?;
+    ws.import_and_snapshot(true)?;
+    let bundle = bundles::copy_revisions(&ws, vec![source.id])?;
+
+    let MutationResult::UpdatedSelection {
+        new_selection: target,
+        ..
+    } = CreateRevision {
+        parent_ids: vec![revs::main_bookmark()],
+    }
+    .execute_unboxed(&mut ws)?
+    else {
+        panic!("CreateRevision failed");
+    };
+    fs::write(repo.path().join("a.txt"), "target\n")?;
+    ws.import_and_snapshot(true)?;
+
+    // the target's edit isn't overwritten
+    let result = PasteBundle {
+        bundle: Arc::new(bundle),
+        parent_ids: vec![target.id],
     }
     .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::UpdatedSelection { new_selection, .. }
+        if new_selection.has_conflict);
 
-    let page = queries::query_log(&ws, "@+", 2)?;
-    assert_eq!(1, page.rows.len());
+    Ok(())
+}
+
+#[test]
+fn paste_revisions_keeps_outside_parents() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    // a <- m -> conflict
+    let MutationResult::UpdatedSelection {
+        new_selection: a, ..
+    } = CreateRevision {
+        parent_ids: vec![revs::main_bookmark()],
+    }
+    .execute_unboxed(&mut ws)?
+    else {
+        panic!("CreateRevision failed");
+    };
+    let MutationResult::UpdatedSelection {
+        new_selection: m, ..
+    } = CreateRevision {
+        parent_ids: vec![a.id.clone(), revs::conflict_bookmark()],
+    }
+    .execute_unboxed(&mut ws)?
+    else {
+        panic!("CreateRevision failed");
+    };
+    let bundle = bundles::copy_revisions(&ws, vec![a.id, m.id])?;
+
+    let result = PasteBundle {
+        bundle: Arc::new(bundle),
+        parent_ids: vec![revs::main_bookmark()],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::UpdatedSelection { new_selection, .. }
+        if new_selection.parent_ids.len() == 2
+            && new_selection.parent_ids[1].hex == revs::conflict_bookmark().commit.hex);
 
     Ok(())
 }
 
-// XXX missing tests for:
-// - branch/ref mutations
-// - git interop
+#[test]
+fn insert_revision() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let page = queries::query_log(&ws, "main::@", 4)?;
+    assert_eq!(2, page.rows.len());
+
+    InsertRevision {
+        after_id: revs::main_bookmark(),
+        before_id: revs::working_copy(),
+        id: revs::resolve_conflict(),
+    }
+    .execute_unboxed(&mut ws)?;
+
+    let page = queries::query_log(&ws, "main::@", 4)?;
+    assert_eq!(3, page.rows.len());
+
+    Ok(())
+}
+
+#[test]
+fn merge_revisions() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let result = MergeRevisions {
+        ids: vec![revs::working_copy(), revs::main_bookmark()],
+        checkout: true,
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let result = MergeRevisions {
+        ids: vec![revs::main_bookmark(), revs::conflict_bookmark()],
+        checkout: true,
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::UpdatedSelection { new_selection, .. } if new_selection.is_working_copy);
+
+    let page = queries::query_log(&ws, "@", 1)?;
+    let parent_ids = &page.rows[0].revision.parent_ids;
+    assert_eq!(2, parent_ids.len());
+    assert_eq!(revs::main_bookmark().commit.hex, parent_ids[0].hex);
+    assert_eq!(revs::conflict_bookmark().commit.hex, parent_ids[1].hex);
+
+    Ok(())
+}
+
+#[test]
+fn edit_merge_parents() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let wc_parents = |ws: &WorkspaceSession| -> Result<Vec<String>> {
+        let page = queries::query_log(ws, "@", 1)?;
+        Ok(page.rows[0]
+            .revision
+            .parent_ids
+            .iter()
+            .map(|id| id.hex.clone())
+            .collect())
+    };
+
+    let result = AddParentToMerge {
+        id: revs::working_copy(),
+        parent_id: revs::main_bookmark(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let result = SwapParents {
+        id: revs::working_copy(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    AddParentToMerge {
+        id: revs::working_copy(),
+        parent_id: revs::conflict_bookmark(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_eq!(
+        vec![
+            revs::main_bookmark().commit.hex,
+            revs::conflict_bookmark().commit.hex
+        ],
+        wc_parents(&ws)?
+    );
+
+    // a descendant of an existing parent
+    let result = AddParentToMerge {
+        id: revs::working_copy(),
+        parent_id: revs::resolve_conflict(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    SwapParents {
+        id: revs::working_copy(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_eq!(
+        vec![
+            revs::conflict_bookmark().commit.hex,
+            revs::main_bookmark().commit.hex
+        ],
+        wc_parents(&ws)?
+    );
+
+    RemoveParentFromMerge {
+        id: revs::working_copy(),
+        parent_id: revs::conflict_bookmark().commit,
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_eq!(vec![revs::main_bookmark().commit.hex], wc_parents(&ws)?);
+
+    let result = RemoveParentFromMerge {
+        id: revs::working_copy(),
+        parent_id: revs::main_bookmark().commit,
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    Ok(())
+}
+
+#[test]
+fn move_changes_all_paths() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let parent_rev = queries::query_revision(&ws, revs::conflict_bookmark(), false)?;
+    assert_matches!(parent_rev, RevResult::Detail { header, .. } if header.has_conflict);
+
+    let result = MoveChanges {
+        from_id: revs::resolve_conflict(),
+        to_id: revs::conflict_bookmark().commit,
+        paths: vec![],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let parent_rev = queries::query_revision(&ws, revs::conflict_bookmark(), false)?;
+    assert_matches!(parent_rev, RevResult::Detail { header, .. } if !header.has_conflict);
+
+    Ok(())
+}
+
+#[test]
+fn move_changes_single_path() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let from_rev = queries::query_revision(&ws, revs::main_bookmark(), false)?;
+    let to_rev = queries::query_revision(&ws, revs::working_copy(), false)?;
+    assert_matches!(from_rev, RevResult::Detail { changes, .. } if changes.len() == 2);
+    assert_matches!(to_rev, RevResult::Detail { changes, .. } if changes.len() == 0);
+
+    let result = MoveChanges {
+        from_id: revs::main_bookmark(),
+        to_id: revs::working_copy().commit,
+        paths: vec![TreePath {
+            repo_path: "c.txt".to_owned(),
+            relative_path: "".into(),
+        }],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let from_rev = queries::query_revision(&ws, revs::main_bookmark(), false)?;
+    let to_rev = queries::query_revision(&ws, revs::working_copy(), false)?;
+    assert_matches!(from_rev, RevResult::Detail { changes, .. } if changes.len() == 1);
+    assert_matches!(to_rev, RevResult::Detail { changes, .. } if changes.len() == 1);
+
+    Ok(())
+}
+
+#[test]
+fn move_source() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let page = queries::query_log(&ws, "@+", 1)?;
+    assert_eq!(0, page.rows.len());
+
+    MoveSource {
+        id: revs::resolve_conflict(),
+        parent_ids: vec![revs::working_copy().commit],
+    }
+    .execute_unboxed(&mut ws)?;
+
+    let page = queries::query_log(&ws, "@+", 2)?;
+    assert_eq!(1, page.rows.len());
+
+    Ok(())
+}
+
+#[test]
+fn protected_bookmarks() -> Result<()> {
+    let repo = mkrepo();
+    fs::write(
+        repo.path().join(".jj/repo/config.toml"),
+        "gg.git.protected-bookmarks = [\"main\"]\n",
+    )?;
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let main_ref = StoreRef::LocalBookmark {
+        branch_name: "main".to_owned(),
+        has_conflict: false,
+        is_synced: true,
+        tracking_remotes: vec!["origin".to_owned()],
+        available_remotes: 2,
+        potential_remotes: 1,
+    };
+
+    let result = DeleteRef {
+        r#ref: main_ref.clone(),
+        allow_protected: false,
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::OverrideRequired { .. });
+
+    let result = MoveRef {
+        r#ref: main_ref.clone(),
+        to_id: revs::conflict_bookmark(),
+        allow_protected: false,
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::OverrideRequired { .. });
+
+    let result = MoveRef {
+        r#ref: main_ref.clone(),
+        to_id: revs::working_copy(),
+        allow_protected: false,
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let result = DeleteRef {
+        r#ref: main_ref,
+        allow_protected: true,
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    Ok(())
+}
+
+#[test]
+fn cleanup_bookmarks() -> Result<()> {
+    let repo = mkrepo();
+    fs::write(
+        repo.path().join(".jj/repo/config.toml"),
+        "gg.git.protected-bookmarks = [\"main\"]\n",
+    )?;
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let result = CleanupBookmarks {
+        names: vec!["main".to_owned()],
+        delete_remotes: false,
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let cleanup = CleanupBookmarks {
+        names: vec!["pull-request".to_owned(), "conflicted-merge".to_owned()],
+        delete_remotes: false,
+    };
+    assert_eq!(
+        Some("Delete bookmarks pull-request, conflicted-merge?".to_owned()),
+        cleanup.destructive_summary(&ws)?
+    );
+    let result = cleanup.execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    assert!(ws.view().get_local_bookmark("pull-request").is_absent());
+    assert!(ws.view().get_local_bookmark("conflicted-merge").is_absent());
+    assert!(!ws
+        .view()
+        .get_remote_bookmark("pull-request", "second")
+        .is_tracking());
+
+    Ok(())
+}
+
+#[test]
+fn cleanup_bookmarks_delete_remotes() -> Result<()> {
+    let repo = mkrepo();
+
+    // stand in for the "second" remote, which has pull-request where jj last saw it
+    let remote_dir = tempfile::tempdir()?;
+    let remote_repo = git2::Repository::init_bare(remote_dir.path())?;
+    let git_repo = git2::Repository::open(repo.path().join(".jj/repo/store/git"))?;
+    let remote_url = remote_dir.path().to_str().unwrap();
+    git_repo.remote_anonymous(remote_url)?.push(
+        &["refs/remotes/second/pull-request:refs/heads/pull-request"],
+        None,
+    )?;
+    git_repo.remote_set_url("second", remote_url)?;
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let result = CleanupBookmarks {
+        names: vec!["pull-request".to_owned()],
+        delete_remotes: true,
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    assert!(ws.view().get_local_bookmark("pull-request").is_absent());
+    assert!(ws
+        .view()
+        .get_remote_bookmark("pull-request", "second")
+        .is_absent());
+    assert!(remote_repo
+        .find_reference("refs/heads/pull-request")
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn resolve_conflict() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let path = TreePath {
+        repo_path: "b.txt".to_owned(),
+        relative_path: "b.txt".into(),
+    };
+
+    let result = ResolveConflict {
+        id: revs::conflict_bookmark(),
+        path: path.clone(),
+        choices: vec![],
+        content: None,
+        has_trailing_newline: false,
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let result = ResolveConflict {
+        id: revs::conflict_bookmark(),
+        path,
+        choices: vec![1],
+        content: None,
+        has_trailing_newline: false,
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    // the change is found by its id, now that its commit has been rewritten
+    let rev = queries::query_revision(&ws, revs::conflict_bookmark(), false)?;
+    assert_matches!(
+        rev,
+        RevResult::Detail { header, conflicted_paths, .. }
+            if !header.has_conflict && conflicted_paths.is_empty()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn resolve_conflict_content() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let path = TreePath {
+        repo_path: "b.txt".to_owned(),
+        relative_path: "b.txt".into(),
+    };
+
+    let result = ResolveConflict {
+        id: revs::conflict_bookmark(),
+        path: path.clone(),
+        choices: vec![],
+        content: Some("merged".into()),
+        has_trailing_newline: false,
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let rev = queries::query_revision(&ws, revs::conflict_bookmark(), false)?;
+    assert_matches!(rev, RevResult::Detail { header, .. } if !header.has_conflict);
+
+    let commit = ws.resolve_single_change(&revs::conflict_bookmark())?;
+    let value = commit
+        .tree()?
+        .path_value(&RepoPathBuf::from_internal_string(&path.repo_path))?;
+    let Some(Some(TreeValue::File { id, .. })) = value.as_resolved() else {
+        panic!("b.txt is not a resolved file");
+    };
+    let mut content = String::new();
+    ws.repo()
+        .store()
+        .read_file(&RepoPathBuf::from_internal_string(&path.repo_path), id)?
+        .read_to_string(&mut content)?;
+    assert_eq!("merged", content);
+
+    Ok(())
+}
+
+#[test]
+fn resolve_with_merge_tool_unconfigured() -> Result<()> {
+    let repo = mkrepo();
+    fs::write(
+        repo.path().join(".jj/repo/config.toml"),
+        "ui.merge-editor = \":builtin\"\n",
+    )?;
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    assert_matches!(
+        ws.format_config()?,
+        RepoConfig::Workspace {
+            has_external_merge_tool: false,
+            ..
+        }
+    );
+
+    let result = ResolveWithMergeTool {
+        id: revs::conflict_bookmark(),
+        path: TreePath {
+            repo_path: "b.txt".to_owned(),
+            relative_path: "b.txt".into(),
+        },
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn resolve_with_merge_tool() -> Result<()> {
+    let repo = mkrepo();
+    fs::write(
+        repo.path().join(".jj/repo/config.toml"),
+        r#"ui.merge-editor = "fake"
+merge-tools.fake.program = "sh"
+merge-tools.fake.merge-args = ["-c", "echo resolved > \"$0\"", "$output"]
+"#,
+    )?;
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    assert_matches!(
+        ws.format_config()?,
+        RepoConfig::Workspace {
+            has_external_merge_tool: true,
+            ..
+        }
+    );
+
+    let result = ResolveWithMergeTool {
+        id: revs::conflict_bookmark(),
+        path: TreePath {
+            repo_path: "b.txt".to_owned(),
+            relative_path: "b.txt".into(),
+        },
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let rev = queries::query_revision(&ws, revs::conflict_bookmark(), false)?;
+    assert_matches!(
+        rev,
+        RevResult::Detail { header, conflicted_paths, .. }
+            if !header.has_conflict && conflicted_paths.is_empty()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rewrite_paths() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    fs::write(repo.path().join("x.txt"), "hello world\nfoo\nworld peace\n")?;
+    ws.import_and_snapshot(true)?;
+
+    let paths = vec![TreePath {
+        repo_path: "x.txt".to_owned(),
+        relative_path: "x.txt".into(),
+    }];
+
+    let preview = queries::query_rewrite_preview(
+        &ws,
+        revs::working_copy(),
+        paths.clone(),
+        "wor(ld)".to_owned(),
+        "W${1}".to_owned(),
+    )?;
+    assert_eq!(2, preview.len());
+    assert_eq!(1, preview[0].line);
+    assert_eq!("hello Wld", preview[0].after);
+    assert_eq!(3, preview[1].line);
+
+    let result = RewritePaths {
+        id: revs::working_copy(),
+        paths: paths.clone(),
+        pattern: "wor(ld)".to_owned(),
+        replacement: "W${1}".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+    assert_eq!(
+        "hello Wld\nfoo\nWld peace\n",
+        fs::read_to_string(repo.path().join("x.txt"))?
+    );
+
+    let result = RewritePaths {
+        id: revs::working_copy(),
+        paths,
+        pattern: "(".to_owned(),
+        replacement: "".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    Ok(())
+}
+
+#[test]
+fn rewrite_paths_exact_bytes() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    // trailing whitespace, CRLF and a missing final newline must all survive untouched lines
+    fs::write(repo.path().join("x.txt"), "keep  \r\nfoo\t\r\nlast line")?;
+    ws.import_and_snapshot(true)?;
+
+    let result = RewritePaths {
+        id: revs::working_copy(),
+        paths: vec![TreePath {
+            repo_path: "x.txt".to_owned(),
+            relative_path: "x.txt".into(),
+        }],
+        pattern: "foo".to_owned(),
+        replacement: "bar".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+    assert_eq!(
+        b"keep  \r\nbar\t\r\nlast line".as_slice(),
+        fs::read(repo.path().join("x.txt"))?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn fix_revisions() -> Result<()> {
+    let repo = mkrepo();
+    fs::write(
+        repo.path().join(".jj/repo/config.toml"),
+        "[fix.tools.upper]\ncommand = [\"tr\", \"a-z\", \"A-Z\"]\npatterns = [\"glob:*.txt\"]\n",
+    )?;
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    fs::write(repo.path().join("x.txt"), "shout\n")?;
+    fs::write(repo.path().join("x.md"), "whisper\n")?;
+    ws.import_and_snapshot(true)?;
+
+    let result = FixRevisions {
+        ids: vec![ws.format_commit_id(ws.wc_id())],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+    assert_eq!("SHOUT\n", fs::read_to_string(repo.path().join("x.txt"))?);
+    assert_eq!("whisper\n", fs::read_to_string(repo.path().join("x.md"))?);
+
+    // already fixed
+    let result = FixRevisions {
+        ids: vec![ws.format_commit_id(ws.wc_id())],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Unchanged);
+
+    Ok(())
+}
+
+#[test]
+fn safe_mode() -> Result<()> {
+    let repo = mkrepo();
+    fs::write(
+        repo.path().join(".jj/repo/config.toml"),
+        r#"ui.merge-editor = "fake"
+merge-tools.fake.program = "sh"
+[fix.tools.upper]
+command = ["tr", "a-z", "A-Z"]
+patterns = ["glob:*.txt"]
+"#,
+    )?;
+
+    let mut session = WorkerSession {
+        safe_mode: true,
+        ..Default::default()
+    };
+    let mut ws = session.load_directory(repo.path())?;
+
+    assert_matches!(
+        ws.format_config()?,
+        RepoConfig::Workspace {
+            safe_mode: true,
+            has_external_merge_tool: false,
+            ..
+        }
+    );
+
+    fs::write(repo.path().join("x.txt"), "shout\n")?;
+    ws.import_and_snapshot(true)?;
+
+    let result = FixRevisions {
+        ids: vec![ws.format_commit_id(ws.wc_id())],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+    assert_eq!("shout\n", fs::read_to_string(repo.path().join("x.txt"))?);
+
+    Ok(())
+}
+
+#[test]
+fn safe_mode_jj_command() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession {
+        safe_mode: true,
+        ..Default::default()
+    };
+    let mut ws = session.load_directory(repo.path())?;
+    let op_id = ws.repo().op_id().clone();
+
+    let result = RunJjCommand {
+        args: vec!["new".to_owned()],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    ws.load_at_operation(None)?;
+    assert_eq!(&op_id, ws.repo().op_id());
+
+    Ok(())
+}
+
+#[test]
+fn follow_workspace() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    // another workspace, which has no files on disk
+    let main = ws.resolve_single_change(&revs::main_bookmark())?;
+    let mut tx = ws.start_transaction()?;
+    tx.repo_mut().check_out(
+        WorkspaceId::new("other".to_owned()),
+        &ws.data.settings,
+        &main,
+    )?;
+    ws.finish_transaction(tx, "add workspace")?;
+
+    let other_wc = ws
+        .repo()
+        .view()
+        .get_wc_commit_id(&WorkspaceId::new("other".to_owned()))
+        .expect("other workspace")
+        .clone();
+    let header = ws.format_header(&ws.repo().store().get_commit(&other_wc)?, None)?;
+    assert!(!header.is_working_copy);
+    assert_eq!(vec!["other".to_owned()], header.working_copy_of);
+
+    assert_matches!(
+        ws.format_config()?,
+        RepoConfig::Workspace { workspace_name, workspaces, .. }
+            if workspace_name == "default" && workspaces.len() == 2
+    );
+
+    let result = FollowWorkspace {
+        name: Some("missing".to_owned()),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let result = FollowWorkspace {
+        name: Some("other".to_owned()),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+    assert_matches!(
+        ws.format_config()?,
+        RepoConfig::Workspace { workspace_name, .. } if workspace_name == "other"
+    );
+
+    let page = queries::query_log(&ws, "@", 1)?;
+    assert_eq!(other_wc.hex(), page.rows[0].revision.id.commit.hex);
+
+    let result = FollowWorkspace { name: None }.execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+    let result = FollowWorkspace { name: None }.execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Unchanged);
+
+    Ok(())
+}
+
+#[test]
+fn commit_staged() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let lines = (1..=20).map(|n| format!("line {n}\n")).collect::<String>();
+    fs::write(repo.path().join("x.txt"), &lines)?;
+    fs::write(repo.path().join("y.txt"), "unstaged\n")?;
+    ws.import_and_snapshot(true)?;
+
+    let x_path = TreePath {
+        repo_path: "x.txt".to_owned(),
+        relative_path: "x.txt".into(),
+    };
+    let result = CommitStaged {
+        description: "nothing".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    staging::stage_change(&mut ws, x_path.clone(), None, true)?;
+    let result = CommitStaged {
+        description: "add x".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+    assert_eq!(lines, read_parent_file(&ws, "x.txt")?);
+    assert!(read_parent_file(&ws, "y.txt").is_err());
+    assert!(staging::query_staged(&mut ws)?.paths.is_empty());
+
+    // change both ends of the file, but only stage the first hunk
+    let edited = lines
+        .replace("line 1\n", "first\n")
+        .replace("line 20\n", "last\n");
+    fs::write(repo.path().join("x.txt"), &edited)?;
+    ws.import_and_snapshot(true)?;
+
+    let hunks = queries::diff_hunk_locations(lines.as_bytes(), edited.as_bytes());
+    assert_eq!(2, hunks.len());
+    let staged = staging::stage_change(&mut ws, x_path.clone(), Some(hunks[0].clone()), true)?;
+    assert_eq!(Some(vec![hunks[0].clone()]), staged.paths[0].hunks);
+
+    let result = CommitStaged {
+        description: "edit x".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+    assert_eq!(
+        lines.replace("line 1\n", "first\n"),
+        read_parent_file(&ws, "x.txt")?
+    );
+    assert_eq!(edited, fs::read_to_string(repo.path().join("x.txt"))?);
+
+    Ok(())
+}
+
+#[test]
+fn split_revision() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let lines = (1..=20).map(|n| format!("line {n}\n")).collect::<String>();
+    fs::write(repo.path().join("x.txt"), &lines)?;
+    fs::write(repo.path().join("y.txt"), "remainder\n")?;
+    ws.import_and_snapshot(true)?;
+
+    let x_path = TreePath {
+        repo_path: "x.txt".to_owned(),
+        relative_path: "x.txt".into(),
+    };
+    let result = SplitRevision {
+        id: revs::working_copy(),
+        paths: vec![],
+        description: "nothing".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    // the selected path stays in the original change and the working copy moves to the remainder
+    let result = SplitRevision {
+        id: revs::working_copy(),
+        paths: vec![SplitPath {
+            path: x_path.clone(),
+            hunks: None,
+        }],
+        description: "add x".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::UpdatedSelection { new_selection, .. }
+        if new_selection.id.change.hex == revs::working_copy().change.hex);
+    assert_eq!(lines, read_parent_file(&ws, "x.txt")?);
+    assert!(read_parent_file(&ws, "y.txt").is_err());
+    assert_eq!(
+        "remainder\n",
+        fs::read_to_string(repo.path().join("y.txt"))?
+    );
+
+    // split off only the first of two hunks
+    let edited = lines
+        .replace("line 1\n", "first\n")
+        .replace("line 20\n", "last\n");
+    fs::write(repo.path().join("x.txt"), &edited)?;
+    ws.import_and_snapshot(true)?;
+
+    let hunks = queries::diff_hunk_locations(lines.as_bytes(), edited.as_bytes());
+    assert_eq!(2, hunks.len());
+    let wc_id = ws.format_id(&ws.get_commit(ws.wc_id())?);
+    let result = SplitRevision {
+        id: wc_id,
+        paths: vec![SplitPath {
+            path: x_path,
+            hunks: Some(vec![hunks[0].clone()]),
+        }],
+        description: "edit x".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::UpdatedSelection { .. });
+    assert_eq!(
+        lines.replace("line 1\n", "first\n"),
+        read_parent_file(&ws, "x.txt")?
+    );
+    assert_eq!(edited, fs::read_to_string(repo.path().join("x.txt"))?);
+
+    Ok(())
+}
+
+#[test]
+fn create_file() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    fs::write(repo.path().join(".gitignore"), "*.log\n")?;
+
+    let result = CreateFile {
+        path: "../outside.md".to_owned(),
+        content: String::new(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    for path in [".git/hooks/post-commit", ".jj/repo/config.toml"] {
+        let result = CreateFile {
+            path: path.to_owned(),
+            content: String::new(),
+        }
+        .execute_unboxed(&mut ws)?;
+        assert_matches!(result, MutationResult::PreconditionError { .. });
+    }
+    assert!(!repo.path().join(".git/hooks/post-commit").exists());
+
+    let result = CreateFile {
+        path: "notes/build.log".to_owned(),
+        content: String::new(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let result = CreateFile {
+        path: "notes/TODO.md".to_owned(),
+        content: "- [ ] write more tests\n".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    let MutationResult::UpdatedSelection { new_selection, .. } = result else {
+        panic!("CreateFile failed: {result:?}");
+    };
+    assert!(new_selection.is_working_copy);
+    assert_eq!(
+        "- [ ] write more tests\n",
+        fs::read_to_string(repo.path().join("notes").join("TODO.md"))?
+    );
+
+    let rev = queries::query_revision(&ws, new_selection.id, false)?;
+    assert_matches!(rev, RevResult::Detail { changes, .. }
+        if changes.iter().any(|change| change.path.repo_path == "notes/TODO.md"));
+
+    let result = CreateFile {
+        path: "notes/TODO.md".to_owned(),
+        content: String::new(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    Ok(())
+}
+
+#[test]
+fn rename_and_delete_paths() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    fs::write(repo.path().join("x.txt"), "contents\n")?;
+    let x_path = TreePath {
+        repo_path: "x.txt".to_owned(),
+        relative_path: "x.txt".into(),
+    };
+
+    let result = RenamePath {
+        path: x_path.clone(),
+        new_path: "../outside.txt".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let result = RenamePath {
+        path: x_path.clone(),
+        new_path: ".git/hooks/post-commit".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    fs::write(repo.path().join(".jj/notes.txt"), "metadata\n")?;
+    let result = RenamePath {
+        path: TreePath {
+            repo_path: ".jj/notes.txt".to_owned(),
+            relative_path: ".jj/notes.txt".into(),
+        },
+        new_path: "notes.txt".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+    assert!(repo.path().join(".jj/notes.txt").exists());
+
+    let result = RenamePath {
+        path: x_path,
+        new_path: "dir/y.txt".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+    assert!(!repo.path().join("x.txt").exists());
+    assert_eq!(
+        "contents\n",
+        fs::read_to_string(repo.path().join("dir").join("y.txt"))?
+    );
+
+    let wc_id = ws.format_id(&ws.get_commit(ws.wc_id())?);
+    let rev = queries::query_revision(&ws, wc_id, false)?;
+    assert_matches!(rev, RevResult::Detail { changes, .. }
+        if changes.iter().any(|change| change.path.repo_path == "dir/y.txt")
+            && !changes.iter().any(|change| change.path.repo_path == "x.txt"));
+
+    let result = DeletePaths {
+        paths: vec![TreePath {
+            repo_path: "dir/y.txt".to_owned(),
+            relative_path: "dir/y.txt".into(),
+        }],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+    assert!(!repo.path().join("dir").join("y.txt").exists());
+
+    let wc_id = ws.format_id(&ws.get_commit(ws.wc_id())?);
+    let rev = queries::query_revision(&ws, wc_id, false)?;
+    assert_matches!(rev, RevResult::Detail { changes, .. }
+        if !changes.iter().any(|change| change.path.repo_path == "dir/y.txt"));
+
+    Ok(())
+}
+
+fn read_parent_file(ws: &WorkspaceSession, path: &str) -> Result<String> {
+    let wc = ws.get_commit(ws.wc_id())?;
+    let parent = wc.parents().next().expect("working copy has a parent")?;
+    let path = RepoPathBuf::from_internal_string(path);
+    let value = parent.tree()?.path_value(&path)?;
+    match value.as_normal() {
+        Some(TreeValue::File { id, .. }) => {
+            let mut content = String::new();
+            ws.repo()
+                .store()
+                .read_file(&path, id)?
+                .read_to_string(&mut content)?;
+            Ok(content)
+        }
+        _ => Err(anyhow::anyhow!(
+            "{} is not a file",
+            path.as_internal_file_string()
+        )),
+    }
+}
+
+#[test]
+fn add_ignore_pattern() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    fs::write(repo.path().join("x.log"), "noise\n")?;
+    ws.import_and_snapshot(true)?;
+
+    let rev = queries::query_revision(&ws, revs::working_copy(), false)?;
+    assert_matches!(rev, RevResult::Detail { changes, .. } if changes.iter().any(|change| change.path.repo_path == "x.log"));
+
+    let result = AddIgnorePattern {
+        pattern: "*.log".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let rev = queries::query_revision(&ws, revs::working_copy(), false)?;
+    assert_matches!(rev, RevResult::Detail { changes, .. } if !changes.iter().any(|change| change.path.repo_path == "x.log"));
+    assert!(repo.path().join("x.log").exists());
+    assert!(fs::read_to_string(repo.path().join(".gitignore"))?.contains("*.log"));
+
+    // the file stays untracked after further snapshots
+    ws.import_and_snapshot(true)?;
+    let rev = queries::query_revision(&ws, revs::working_copy(), false)?;
+    assert_matches!(rev, RevResult::Detail { changes, .. } if !changes.iter().any(|change| change.path.repo_path == "x.log"));
+
+    Ok(())
+}
+
+#[test]
+fn add_ignore_pattern_keeps_ancestor_files() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    // tracked.log is committed in @-, and new.log is added in @
+    fs::write(repo.path().join("tracked.log"), "kept\n")?;
+    ws.import_and_snapshot(true)?;
+    CreateRevision {
+        parent_ids: vec![revs::working_copy()],
+    }
+    .execute_unboxed(&mut ws)?;
+    fs::write(repo.path().join("new.log"), "noise\n")?;
+    ws.import_and_snapshot(true)?;
+
+    let result = AddIgnorePattern {
+        pattern: "*.log".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let wc_tree = ws.get_commit(ws.wc_id())?.tree()?;
+    assert!(wc_tree
+        .path_value(&RepoPathBuf::from_internal_string("tracked.log"))?
+        .is_present());
+    assert!(wc_tree
+        .path_value(&RepoPathBuf::from_internal_string("new.log"))?
+        .is_absent());
+
+    Ok(())
+}
+
+#[test]
+fn colocate_repository() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    {
+        let mut ws = session.load_directory(repo.path())?;
+        assert!(!ws.is_colocated());
+
+        let result = ColocateRepository.execute_unboxed(&mut ws)?;
+        assert_matches!(result, MutationResult::Updated { .. });
+    }
+
+    // nothing moves until the workspace has been released
+    assert!(!repo.path().join(".git").exists());
+    session.colocation.take().unwrap().convert()?;
+
+    let store_path = repo.path().join(".jj/repo/store");
+    assert!(repo.path().join(".git").is_dir());
+    assert!(!store_path.join("git").exists());
+    assert_eq!(
+        "git",
+        fs::read_to_string(store_path.join("git_target.bak"))?
+    );
+
+    let git_repo = git2::Repository::open(repo.path())?;
+    assert!(!git_repo.is_bare());
+    assert_eq!(
+        revs::main_bookmark().commit.hex,
+        git_repo.head()?.peel_to_commit()?.id().to_string()
+    );
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+    assert!(ws.is_colocated());
+    assert_eq!(12, queries::query_log(&ws, "all()", 100)?.rows.len());
+    // colocation moved HEAD behind jj's back, until the next import
+    assert_matches!(
+        ws.format_status().git_head,
+        Some(GitHead::Detached { commit, is_synced: false }) if commit.hex == revs::main_bookmark().commit.hex
+    );
+    ws.import_and_snapshot(true)?;
+    assert_matches!(
+        ws.format_status().git_head,
+        Some(GitHead::Detached {
+            is_synced: true,
+            ..
+        })
+    );
+
+    let result = ColocateRepository.execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    Ok(())
+}
+
+#[test]
+fn colocate_repository_failure() -> Result<()> {
+    let repo = mkrepo();
+    let store_path = repo.path().join(".jj/repo/store");
+    let old_target = fs::read_to_string(store_path.join("git_target"))?;
+
+    let mut session = WorkerSession::default();
+    {
+        let mut ws = session.load_directory(repo.path())?;
+        let result = ColocateRepository.execute_unboxed(&mut ws)?;
+        assert_matches!(result, MutationResult::Updated { .. });
+    }
+
+    // fails after the move, when checking out a commit which isn't there
+    let mut colocation = session.colocation.take().unwrap();
+    colocation.head = Some(git2::Oid::from_str(&"0".repeat(40))?);
+    assert!(colocation.convert().is_err());
+
+    assert!(!repo.path().join(".git").exists());
+    assert!(store_path.join("git").is_dir());
+    assert_eq!(
+        old_target,
+        fs::read_to_string(store_path.join("git_target"))?
+    );
+    assert!(git2::Repository::open(store_path.join("git"))?.is_bare());
+
+    let mut session = WorkerSession::default();
+    let ws = session.load_directory(repo.path())?;
+    assert!(!ws.is_colocated());
+    assert_eq!(12, queries::query_log(&ws, "all()", 100)?.rows.len());
+
+    Ok(())
+}
+
+#[test]
+fn fetch_from_path() -> Result<()> {
+    let repo = mkrepo();
+    let source = mkrepo();
+
+    // simulate work done in another checkout: a new commit on a branch
+    let source_git = git2::Repository::open(source.path().join(".jj/repo/store/git"))?;
+    let parent = source_git.find_commit(git2::Oid::from_str(&revs::main_bookmark().commit.hex)?)?;
+    let signature = git2::Signature::now("Test", "test@example.com")?;
+    source_git.commit(
+        Some("refs/heads/shared"),
+        &signature,
+        &signature,
+        "from elsewhere",
+        &parent.tree()?,
+        &[&parent],
+    )?;
+
+    // git creates this, but the test archive doesn't preserve empty directories
+    fs::create_dir_all(repo.path().join(".jj/repo/store/git/objects/pack"))?;
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let result = FetchFromPath {
+        path: repo
+            .path()
+            .join("not-a-repo")
+            .to_string_lossy()
+            .into_owned(),
+        remote_name: "elsewhere".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let result = FetchFromPath {
+        path: source.path().to_string_lossy().into_owned(),
+        remote_name: "git".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let result = FetchFromPath {
+        path: source.path().to_string_lossy().into_owned(),
+        remote_name: "elsewhere".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let page = queries::query_log(&ws, "remote_bookmarks(remote=exact:elsewhere)", 100)?;
+    assert_eq!(1, page.rows.len());
+    assert_eq!("from elsewhere", page.rows[0].revision.description.lines[0]);
+    assert_matches!(
+        &page.rows[0].revision.refs[..],
+        [StoreRef::RemoteBookmark { branch_name, remote_name, .. }] if branch_name == "shared" && remote_name == "elsewhere"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn revset_aliases() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let result = SetRevsetAlias {
+        scope: ConfigScope::Repo,
+        name: "marked".to_owned(),
+        definition: "bookmarks()".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let aliases = queries::query_revset_aliases(&ws)?;
+    assert!(aliases.iter().any(|alias| alias.name == "marked"
+        && alias.definition == "bookmarks()"
+        && alias.scope == ConfigScope::Repo));
+    assert_eq!(3, queries::query_log(&ws, "marked", 100)?.rows.len());
+
+    let result = RemoveRevsetAlias {
+        scope: ConfigScope::Repo,
+        name: "marked".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+    assert!(!queries::query_revset_aliases(&ws)?
+        .iter()
+        .any(|alias| alias.name == "marked"));
+
+    let result = RemoveRevsetAlias {
+        scope: ConfigScope::Repo,
+        name: "marked".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let result = SetRevsetAlias {
+        scope: ConfigScope::Default,
+        name: "marked".to_owned(),
+        definition: "bookmarks()".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    Ok(())
+}
+
+#[test]
+fn set_user_identity_requires_both() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    // the successful case writes to the real user config, so only preconditions are tested
+    let result = SetUserIdentity {
+        name: "Someone".to_owned(),
+        email: "  ".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    Ok(())
+}
+
+#[test]
+fn set_trunk() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    // the default trunk() finds the origin bookmark
+    let page = queries::query_log(&ws, "main@origin", 1)?;
+    let remote_main = page.rows[0].revision.id.commit.hex.clone();
+    let trunk = ws.resolve_trunk()?.expect("trunk");
+    assert_eq!(remote_main, trunk.id().hex());
+
+    let RevResult::Detail { header, .. } =
+        queries::query_revision(&ws, revs::conflict_bookmark(), false)?
+    else {
+        panic!("revision not found");
+    };
+    let result = SetTrunk {
+        r#ref: Some(header.refs[0].clone()),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let trunk = ws.resolve_trunk()?.expect("trunk");
+    assert_eq!(revs::conflict_bookmark().commit.hex, trunk.id().hex());
+
+    let result = SetTrunk { r#ref: None }.execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let trunk = ws.resolve_trunk()?.expect("trunk");
+    assert_eq!(remote_main, trunk.id().hex());
+
+    let result = SetTrunk { r#ref: None }.execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Unchanged);
+
+    Ok(())
+}
+
+#[test]
+fn checkpoint_working_copy() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let result = CheckpointWorkingCopy {
+        label: " ".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let result = CheckpointWorkingCopy {
+        label: "before refactor".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    fs::write(repo.path().join("new.txt"), []).unwrap();
+
+    let result = CheckpointWorkingCopy {
+        label: "after refactor".to_owned(),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { new_status } if new_status.operation_description == "checkpoint: after refactor");
+
+    let checkpoints = queries::query_checkpoints(&ws)?;
+    assert_eq!(2, checkpoints.len());
+    assert_eq!("after refactor", checkpoints[0].label);
+    assert_eq!("before refactor", checkpoints[1].label);
+    assert_eq!(
+        revs::working_copy().commit.hex,
+        checkpoints[1].working_copy.hex
+    );
+    assert_ne!(
+        checkpoints[0].working_copy.hex,
+        checkpoints[1].working_copy.hex
+    );
+
+    Ok(())
+}
+
+#[test]
+fn run_jj_command_preconditions() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    for args in [
+        vec![],
+        vec!["describe", "-m", "not allowed"],
+        vec!["new", "-R", "/somewhere/else"],
+        vec!["new", "--at-op=@-"],
+    ] {
+        let result = RunJjCommand {
+            args: args.into_iter().map(str::to_owned).collect(),
+        }
+        .execute_unboxed(&mut ws)?;
+        assert_matches!(result, MutationResult::PreconditionError { .. });
+    }
+
+    Ok(())
+}
+
+#[test]
+fn set_default_query() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let default_query = |ws: &WorkspaceSession| match ws.format_config() {
+        Ok(RepoConfig::Workspace { default_query, .. }) => default_query,
+        _ => panic!("workspace not loaded"),
+    };
+    let log_revset = default_query(&ws);
+
+    let result = SetDefaultQuery {
+        query: Some("((".to_owned()),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let result = SetDefaultQuery {
+        query: Some("bookmarks()".to_owned()),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+    assert_eq!("bookmarks()", default_query(&ws));
+
+    let result = SetDefaultQuery { query: None }.execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+    assert_eq!(log_revset, default_query(&ws));
+
+    Ok(())
+}
+
+#[test]
+fn set_label() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let label = RevLabel {
+        text: "important".to_owned(),
+        color: LabelColor::Mauve,
+    };
+
+    let result = SetLabel {
+        id: revs::main_bookmark(),
+        label: Some(RevLabel {
+            text: " important ".to_owned(),
+            color: LabelColor::Mauve,
+        }),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let rev = queries::query_revision(&ws, revs::main_bookmark(), false)?;
+    assert_matches!(rev, RevResult::Detail { header, .. } if header.label.as_ref() == Some(&label));
+
+    // labels are keyed by change id, so they follow a change through rewrites
+    DescribeRevision {
+        id: revs::main_bookmark(),
+        new_description: "renamed".to_owned(),
+        reset_author: false,
+    }
+    .execute_unboxed(&mut ws)?;
+    let page = queries::query_log(&ws, "description(renamed)", 1)?;
+    assert_eq!(Some(&label), page.rows[0].revision.label.as_ref());
+
+    let result = SetLabel {
+        id: page.rows[0].revision.id.clone(),
+        label: Some(label.clone()),
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Unchanged);
+
+    // a new session rereads the stored labels
+    drop(ws);
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+    let page = queries::query_log(&ws, "description(renamed)", 1)?;
+    assert_eq!(Some(&label), page.rows[0].revision.label.as_ref());
+
+    SetLabel {
+        id: page.rows[0].revision.id.clone(),
+        label: None,
+    }
+    .execute_unboxed(&mut ws)?;
+    let page = queries::query_log(&ws, "description(renamed)", 1)?;
+    assert_eq!(None, page.rows[0].revision.label);
+
+    Ok(())
+}
+
+#[test]
+fn run_rebase_plan() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let result = RunRebasePlan {
+        steps: vec![RebaseStep {
+            id: queries::query_log(&ws, "ummxkyyk", 1)?.rows[0]
+                .revision
+                .id
+                .clone(),
+            action: RebaseAction::Keep,
+        }],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let result = RunRebasePlan {
+        steps: vec![
+            RebaseStep {
+                id: revs::main_bookmark(),
+                action: RebaseAction::Reword {
+                    new_description: "reworded".to_owned(),
+                },
+            },
+            RebaseStep {
+                id: revs::working_copy(),
+                action: RebaseAction::Squash,
+            },
+        ],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let page = queries::query_log(&ws, "all()", 100)?;
+    assert_eq!(11, page.rows.len());
+
+    let page = queries::query_log(&ws, "main", 1)?;
+    let rev = queries::query_revision(&ws, page.rows[0].revision.id.clone(), false)?;
+    assert_matches!(rev, RevResult::Detail { header, changes, .. }
+        if header.description.lines[0] == "reworded" && changes.len() == 2);
+
+    Ok(())
+}
+
+#[test]
+fn run_rebase_plan_drop() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let result = RunRebasePlan {
+        steps: vec![
+            RebaseStep {
+                id: revs::main_bookmark(),
+                action: RebaseAction::Drop,
+            },
+            RebaseStep {
+                id: revs::working_copy(),
+                action: RebaseAction::Keep,
+            },
+        ],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Updated { .. });
+
+    let page = queries::query_log(&ws, "@-", 1)?;
+    assert!(page.rows[0].revision.id.commit.hex.starts_with("bfdaff78"));
+
+    Ok(())
+}
+
+#[test]
+fn run_rebase_plan_squash_after_drop() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    let MutationResult::UpdatedSelection { new_selection, .. } = CreateRevision {
+        parent_ids: vec![revs::working_copy()],
+    }
+    .execute_unboxed(&mut ws)?
+    else {
+        panic!("CreateRevision failed");
+    };
+
+    let result = RunRebasePlan {
+        steps: vec![
+            RebaseStep {
+                id: revs::main_bookmark(),
+                action: RebaseAction::Keep,
+            },
+            RebaseStep {
+                id: revs::working_copy(),
+                action: RebaseAction::Drop,
+            },
+            RebaseStep {
+                id: new_selection.id,
+                action: RebaseAction::Squash,
+            },
+        ],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::PreconditionError { .. });
+
+    let page = queries::query_log(&ws, "main", 1)?;
+    assert_eq!(
+        revs::main_bookmark().commit.hex,
+        page.rows[0].revision.id.commit.hex
+    );
+
+    Ok(())
+}
+
+// XXX missing tests for:
+// - branch/ref mutations
+// - git interop
+
+#[test]
+fn undo_restores_selection() -> Result<()> {
+    let repo = mkrepo();
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(repo.path())?;
+
+    DescribeRevision {
+        id: revs::working_copy(),
+        new_description: "undone".to_owned(),
+        reset_author: false,
+    }
+    .execute_unboxed(&mut ws)?;
+    let result = UndoOperation {
+        selected: vec![revs::working_copy().change],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Restored { new_selection, restored, .. }
+        if new_selection.id.commit.hex == revs::working_copy().commit.hex
+            && matches!(restored.as_slice(), [RestoredChange::Visible { header }]
+                if header.id.commit.hex == revs::working_copy().commit.hex));
+
+    let MutationResult::UpdatedSelection {
+        new_selection: created,
+        ..
+    } = CreateRevision {
+        parent_ids: vec![revs::main_bookmark()],
+    }
+    .execute_unboxed(&mut ws)?
+    else {
+        panic!("revision not created");
+    };
+    let result = UndoOperation {
+        selected: vec![created.id.change],
+    }
+    .execute_unboxed(&mut ws)?;
+    assert_matches!(result, MutationResult::Restored { new_selection, restored, .. }
+        if new_selection.is_working_copy
+            && matches!(restored.as_slice(), [RestoredChange::Hidden { .. }]));
+
+    Ok(())
+}
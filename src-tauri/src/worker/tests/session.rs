@@ -1,11 +1,22 @@
 use super::{mkid, mkrepo, revs};
 use crate::{
-    messages::{LogPage, RepoConfig, RevResult},
-    worker::{Session, SessionEvent, WorkerSession},
+    messages::{
+        AbandonRevisions, CreateRevision, DescribeRevision, DuplicateRevisions, InitWorkspace,
+        LogPage, MutationResult, RepoConfig, RepoStatus, RepoVerification, RevNote, RevResult,
+        TraceEntry, TreePath,
+    },
+    worker::{
+        load_recording, queries, replay, FixedClock, ReaderPool, RecordedEvent, ReplayedEvent,
+        Session, SessionEvent, SessionRecorder, WorkerSession,
+    },
 };
 use anyhow::Result;
 use jj_cli::config::ConfigSource;
-use std::{path::PathBuf, sync::mpsc::channel};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{mpsc::channel, Arc},
+};
 
 #[test]
 fn start_and_stop() -> Result<()> {
@@ -90,6 +101,10 @@ fn reload_with_default_query() -> Result<()> {
     tx.send(SessionEvent::QueryLog {
         tx: tx_query,
         query: "none()".to_owned(),
+        first_parent: false,
+        show_hidden: false,
+        elide: None,
+        full: false,
     })?;
     tx.send(SessionEvent::OpenWorkspace {
         tx: tx_reload,
@@ -124,6 +139,10 @@ fn query_log_single() -> Result<()> {
     tx.send(SessionEvent::QueryLog {
         tx: tx_query,
         query: "@".to_owned(),
+        first_parent: false,
+        show_hidden: false,
+        elide: None,
+        full: false,
     })?;
     tx.send(SessionEvent::EndSession)?;
 
@@ -137,6 +156,67 @@ fn query_log_single() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn query_log_large_repo() -> Result<()> {
+    let repo = mkrepo();
+    fs::write(
+        repo.path().join(".jj/repo/config.toml"),
+        "[gg.queries]\ndefault = \"all()\"\nlarge-repo-heuristic = 1\nlarge-repo-narrowing = \"@ | @-\"\n",
+    )?;
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_page1, rx_page1) = channel::<Result<LogPage>>();
+    let (tx_page2, rx_page2) = channel::<Result<LogPage>>();
+    let (tx_full, rx_full) = channel::<Result<LogPage>>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::QueryLog {
+        tx: tx_page1,
+        query: "all()".to_owned(),
+        first_parent: false,
+        show_hidden: false,
+        elide: None,
+        full: false,
+    })?;
+    tx.send(SessionEvent::QueryLogNextPage { tx: tx_page2 })?;
+    tx.send(SessionEvent::QueryLog {
+        tx: tx_full,
+        query: "all()".to_owned(),
+        first_parent: false,
+        show_hidden: false,
+        elide: None,
+        full: true,
+    })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession {
+        force_log_page_size: Some(1),
+        ..Default::default()
+    }
+    .handle_events(&rx)?;
+
+    _ = rx_load.recv()??;
+
+    // later pages continue the narrowed query
+    let page1 = rx_page1.recv()??;
+    assert_eq!(Some("all()"), page1.narrowed_from.as_deref());
+    assert_eq!(1, page1.rows.len());
+    assert!(page1.has_more);
+    let page2 = rx_page2.recv()??;
+    assert_eq!(1, page2.rows.len());
+    assert!(!page2.has_more);
+
+    let full = rx_full.recv()??;
+    assert_eq!(None, full.narrowed_from);
+    assert!(full.has_more);
+
+    Ok(())
+}
+
 #[test]
 fn query_log_multi() -> Result<()> {
     let repo = mkrepo();
@@ -152,6 +232,10 @@ fn query_log_multi() -> Result<()> {
     tx.send(SessionEvent::QueryLog {
         tx: tx_page1,
         query: "all()".to_owned(),
+        first_parent: false,
+        show_hidden: false,
+        elide: None,
+        full: false,
     })?;
     tx.send(SessionEvent::QueryLogNextPage { tx: tx_page2 })?;
     tx.send(SessionEvent::EndSession)?;
@@ -191,10 +275,18 @@ fn query_log_multi_restart() -> Result<()> {
     tx.send(SessionEvent::QueryLog {
         tx: tx_page1,
         query: "all()".to_owned(),
+        first_parent: false,
+        show_hidden: false,
+        elide: None,
+        full: false,
     })?;
     tx.send(SessionEvent::QueryLog {
         tx: tx_page1b,
         query: "all()".to_owned(),
+        first_parent: false,
+        show_hidden: false,
+        elide: None,
+        full: false,
     })?;
     tx.send(SessionEvent::QueryLogNextPage { tx: tx_page2 })?;
     tx.send(SessionEvent::EndSession)?;
@@ -238,10 +330,15 @@ fn query_log_multi_interrupt() -> Result<()> {
     tx.send(SessionEvent::QueryLog {
         tx: tx_page1,
         query: "all()".to_owned(),
+        first_parent: false,
+        show_hidden: false,
+        elide: None,
+        full: false,
     })?;
     tx.send(SessionEvent::QueryRevision {
         tx: tx_rev,
         id: revs::working_copy(),
+        aggregate_directories: false,
     })?;
     tx.send(SessionEvent::QueryLogNextPage { tx: tx_page2 })?;
     tx.send(SessionEvent::EndSession)?;
@@ -268,6 +365,62 @@ fn query_log_multi_interrupt() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn query_log_multi_mutation() -> Result<()> {
+    let repo = mkrepo();
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_page1, rx_page1) = channel::<Result<LogPage>>();
+    let (tx_mutate, rx_mutate) = channel::<MutationResult>();
+    let (tx_page2, rx_page2) = channel::<Result<LogPage>>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::QueryLog {
+        tx: tx_page1,
+        query: "all()".to_owned(),
+        first_parent: false,
+        show_hidden: false,
+        elide: None,
+        full: false,
+    })?;
+    tx.send(SessionEvent::ExecuteMutation {
+        tx: tx_mutate,
+        mutation: Box::new(DescribeRevision {
+            id: revs::working_copy(),
+            new_description: "wip".to_owned(),
+            reset_author: false,
+        }),
+        confirmation: None,
+    })?;
+    tx.send(SessionEvent::QueryLogNextPage { tx: tx_page2 })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession {
+        force_log_page_size: Some(7),
+        ..Default::default()
+    }
+    .handle_events(&rx)?;
+
+    rx_load.recv()??;
+
+    let page1 = rx_page1.recv()??;
+    assert_eq!(7, page1.rows.len());
+    assert!(!page1.invalidated);
+
+    assert!(matches!(rx_mutate.recv()?, MutationResult::Updated { .. }));
+
+    // continuing would mix rows from two operations
+    let page2 = rx_page2.recv()??;
+    assert!(page2.invalidated);
+    assert_eq!(0, page2.rows.len());
+    assert!(!page2.has_more);
+
+    Ok(())
+}
+
 #[test]
 fn query_check_immutable() -> Result<()> {
     let repo = mkrepo();
@@ -282,6 +435,10 @@ fn query_check_immutable() -> Result<()> {
     tx.send(SessionEvent::QueryLog {
         tx: tx_page,
         query: "@|main@origin".to_owned(),
+        first_parent: false,
+        show_hidden: false,
+        elide: None,
+        full: false,
     })?;
     tx.send(SessionEvent::EndSession)?;
 
@@ -316,6 +473,7 @@ fn query_rev_not_found() -> Result<()> {
     tx.send(SessionEvent::QueryRevision {
         tx: tx_query,
         id: mkid("abcdefghijklmnopqrstuvwxyz", "00000000"),
+        aggregate_directories: false,
     })?;
     tx.send(SessionEvent::EndSession)?;
 
@@ -331,6 +489,163 @@ fn query_rev_not_found() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn query_rev_parallel() -> Result<()> {
+    let repo = mkrepo();
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_mutate, rx_mutate) = channel::<MutationResult>();
+    let (tx_query, rx_query) = channel::<Result<RevResult>>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::ExecuteMutation {
+        tx: tx_mutate,
+        mutation: Box::new(DescribeRevision {
+            id: revs::working_copy(),
+            new_description: "parallel".to_owned(),
+            reset_author: false,
+        }),
+        confirmation: None,
+    })?;
+    tx.send(SessionEvent::QueryRevision {
+        tx: tx_query,
+        id: revs::working_copy(),
+        aggregate_directories: false,
+    })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession {
+        readers: Some(Arc::new(ReaderPool::new(1))),
+        ..Default::default()
+    }
+    .handle_events(&rx)?;
+
+    _ = rx_load.recv()??;
+    _ = rx_mutate.recv()?;
+
+    // answered by a reader, which must see the operation the worker was at
+    let result = rx_query.recv()??;
+    assert!(
+        matches!(result, RevResult::Detail { header, .. } if header.description.lines[0] == "parallel")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn query_rev_parallel_prefixes() -> Result<()> {
+    let repo = mkrepo();
+    let hex = revs::main_bookmark().commit.hex;
+
+    // a length kept from an earlier operation, longer than a fresh computation would give
+    let cache_dir = repo.path().join(".jj/gg-cache");
+    fs::create_dir_all(&cache_dir)?;
+    fs::write(
+        cache_dir.join("prefixes.json"),
+        format!(r#"{{"op_id":"","commits":{{"{hex}":12}},"changes":{{}}}}"#),
+    )?;
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_log, rx_log) = channel::<Result<LogPage>>();
+    let (tx_query, rx_query) = channel::<Result<RevResult>>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::QueryLog {
+        tx: tx_log,
+        query: hex.clone(),
+        first_parent: false,
+        show_hidden: false,
+        elide: None,
+        full: false,
+    })?;
+
+    let worker = std::thread::spawn(move || {
+        WorkerSession {
+            readers: Some(Arc::new(ReaderPool::new(1))),
+            ..Default::default()
+        }
+        .handle_events(&rx)
+    });
+
+    _ = rx_load.recv()??;
+    let page = rx_log.recv()??;
+    let log_prefix = page.rows[0].revision.id.commit.prefix.clone();
+    assert_eq!(12, log_prefix.len());
+
+    // a reader must use the session's lengths, not whatever is on disk
+    fs::remove_dir_all(&cache_dir)?;
+    tx.send(SessionEvent::QueryRevision {
+        tx: tx_query,
+        id: revs::main_bookmark(),
+        aggregate_directories: false,
+    })?;
+    let result = rx_query.recv()??;
+    assert!(
+        matches!(result, RevResult::Detail { header, .. } if header.id.commit.prefix == log_prefix)
+    );
+
+    tx.send(SessionEvent::EndSession)?;
+    worker.join().unwrap()?;
+
+    Ok(())
+}
+
+#[test]
+fn mutation_annotates_operation() -> Result<()> {
+    let repo = mkrepo();
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_mutate, rx_mutate) = channel::<MutationResult>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::ExecuteMutation {
+        tx: tx_mutate,
+        mutation: Box::new(DescribeRevision {
+            id: revs::working_copy(),
+            new_description: "wip".to_owned(),
+            reset_author: false,
+        }),
+        confirmation: None,
+    })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession {
+        window_label: Some("main".to_owned()),
+        ..Default::default()
+    }
+    .handle_events(&rx)?;
+
+    _ = rx_load.recv()??;
+    let result = rx_mutate.recv()?;
+    assert!(matches!(result, MutationResult::Updated { .. }));
+
+    let mut session = WorkerSession::default();
+    let ws = session.load_directory(repo.path())?;
+    let tags = &ws.repo().operation().metadata().tags;
+    assert!(tags
+        .get("gg.tool")
+        .is_some_and(|tool| tool.starts_with("gg ")));
+    assert_eq!(
+        Some("DescribeRevision"),
+        tags.get("gg.mutation").map(String::as_str)
+    );
+    assert_eq!(Some("main"), tags.get("gg.window").map(String::as_str));
+
+    Ok(())
+}
+
 #[test]
 fn config_read() -> Result<()> {
     let repo = mkrepo();
@@ -391,3 +706,841 @@ fn config_write() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn trace_events() -> Result<()> {
+    let repo = mkrepo();
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_log, rx_log) = channel::<Result<LogPage>>();
+    let (tx_rev, rx_rev) = channel::<Result<RevResult>>();
+    let (tx_trace, rx_trace) = channel::<Vec<TraceEntry>>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::QueryLog {
+        tx: tx_log,
+        query: "all()".to_owned(),
+        first_parent: false,
+        show_hidden: false,
+        elide: None,
+        full: false,
+    })?;
+    tx.send(SessionEvent::QueryRevision {
+        tx: tx_rev,
+        id: revs::working_copy(),
+        aggregate_directories: false,
+    })?;
+    tx.send(SessionEvent::QueryTrace { tx: tx_trace })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession::default().handle_events(&rx)?;
+
+    _ = rx_load.recv()??;
+    _ = rx_log.recv()??;
+    _ = rx_rev.recv()??;
+    let trace = rx_trace.recv()?;
+
+    // the trace request itself is still in progress
+    let events: Vec<_> = trace.iter().map(|entry| entry.event.as_str()).collect();
+    assert_eq!(vec!["OpenWorkspace", "QueryLog", "QueryRevision"], events);
+    assert!(trace.windows(2).all(|pair| pair[0].id < pair[1].id));
+    assert_eq!("LogQuery", trace[2].state);
+
+    Ok(())
+}
+
+#[test]
+fn workspace_moved() -> Result<()> {
+    let repo = mkrepo();
+    let moved_path = repo.path().with_extension("moved");
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_snapshot, rx_snapshot) = channel::<Option<RepoStatus>>();
+    let (tx_missing, rx_missing) = channel::<Result<RepoConfig>>();
+    let (tx_restored, rx_restored) = channel::<Result<RepoConfig>>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+
+    let worker = std::thread::spawn(move || WorkerSession::default().handle_events(&rx));
+
+    _ = rx_load.recv()??;
+    std::fs::rename(repo.path(), &moved_path)?;
+
+    // the event which found the workspace missing is dropped, rather than restarting the worker
+    tx.send(SessionEvent::ExecuteSnapshot { tx: tx_snapshot })?;
+    assert!(rx_snapshot.recv().is_err());
+
+    // reopening retries the same path
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_missing,
+        wd: None,
+    })?;
+    let config = rx_missing.recv()??;
+    assert!(matches!(config, RepoConfig::LoadError { .. }));
+
+    std::fs::rename(&moved_path, repo.path())?;
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_restored,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    let config = rx_restored.recv()??;
+    assert!(matches!(config, RepoConfig::Workspace { .. }));
+
+    tx.send(SessionEvent::EndSession)?;
+    worker.join().unwrap()?;
+
+    Ok(())
+}
+
+#[test]
+fn confirm_destructive() -> Result<()> {
+    let repo = mkrepo();
+    fs::write(
+        repo.path().join(".jj/repo/config.toml"),
+        "[gg.ui]\nconfirm-destructive = true\n",
+    )?;
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_first, rx_first) = channel::<MutationResult>();
+    let (tx_forged, rx_forged) = channel::<MutationResult>();
+    let (tx_confirmed, rx_confirmed) = channel::<MutationResult>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+
+    let worker = std::thread::spawn(move || WorkerSession::default().handle_events(&rx));
+    _ = rx_load.recv()??;
+
+    let abandon = || {
+        Box::new(AbandonRevisions {
+            ids: vec![revs::resolve_conflict().commit],
+        })
+    };
+
+    tx.send(SessionEvent::ExecuteMutation {
+        tx: tx_first,
+        mutation: abandon(),
+        confirmation: None,
+    })?;
+    let MutationResult::ConfirmationRequired { token, summary } = rx_first.recv()? else {
+        panic!("destructive mutation executed without confirmation");
+    };
+    assert!(summary.starts_with("Abandon revision"));
+
+    tx.send(SessionEvent::ExecuteMutation {
+        tx: tx_forged,
+        mutation: abandon(),
+        confirmation: Some("0000000000000000".to_owned()),
+    })?;
+    assert!(matches!(
+        rx_forged.recv()?,
+        MutationResult::ConfirmationRequired { .. }
+    ));
+
+    tx.send(SessionEvent::ExecuteMutation {
+        tx: tx_confirmed,
+        mutation: abandon(),
+        confirmation: Some(token),
+    })?;
+    assert!(matches!(
+        rx_confirmed.recv()?,
+        MutationResult::Updated { .. }
+    ));
+
+    tx.send(SessionEvent::EndSession)?;
+    worker.join().unwrap()?;
+
+    Ok(())
+}
+
+#[test]
+fn confirm_large_rewrite() -> Result<()> {
+    let repo = mkrepo();
+    fs::write(
+        repo.path().join(".jj/repo/config.toml"),
+        "[gg.operations]\nrewrite-limit = 1\n",
+    )?;
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_first, rx_first) = channel::<MutationResult>();
+    let (tx_confirmed, rx_confirmed) = channel::<MutationResult>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+
+    let worker = std::thread::spawn(move || WorkerSession::default().handle_events(&rx));
+    _ = rx_load.recv()??;
+
+    let abandon = || {
+        Box::new(AbandonRevisions {
+            ids: vec![revs::resolve_conflict().commit, revs::working_copy().commit],
+        })
+    };
+
+    tx.send(SessionEvent::ExecuteMutation {
+        tx: tx_first,
+        mutation: abandon(),
+        confirmation: None,
+    })?;
+    let MutationResult::ConfirmationRequired { token, summary } = rx_first.recv()? else {
+        panic!("large rewrite executed without confirmation");
+    };
+    assert!(summary.starts_with("This will rewrite"));
+
+    tx.send(SessionEvent::ExecuteMutation {
+        tx: tx_confirmed,
+        mutation: abandon(),
+        confirmation: Some(token),
+    })?;
+    assert!(matches!(
+        rx_confirmed.recv()?,
+        MutationResult::Updated { .. }
+    ));
+
+    tx.send(SessionEvent::EndSession)?;
+    worker.join().unwrap()?;
+
+    Ok(())
+}
+
+#[test]
+fn confirm_large_destructive_rewrite() -> Result<()> {
+    let repo = mkrepo();
+    fs::write(
+        repo.path().join(".jj/repo/config.toml"),
+        "[gg.operations]\nrewrite-limit = 1\n[gg.ui]\nconfirm-destructive = true\n",
+    )?;
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_mutate, rx_mutate) = channel::<MutationResult>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::ExecuteMutation {
+        tx: tx_mutate,
+        mutation: Box::new(AbandonRevisions {
+            ids: vec![revs::resolve_conflict().commit, revs::working_copy().commit],
+        }),
+        confirmation: None,
+    })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession::default().handle_events(&rx)?;
+    _ = rx_load.recv()??;
+
+    // one prompt mentions both the size of the rewrite and what will be lost
+    let MutationResult::ConfirmationRequired { summary, .. } = rx_mutate.recv()? else {
+        panic!("destructive rewrite executed without confirmation");
+    };
+    assert!(summary.starts_with("This will rewrite"));
+    assert!(summary.contains("Abandon 2 revisions?"));
+
+    Ok(())
+}
+
+#[test]
+fn confirm_large_duplicate() -> Result<()> {
+    let repo = mkrepo();
+    fs::write(
+        repo.path().join(".jj/repo/config.toml"),
+        "[gg.operations]\nrewrite-limit = 1\n",
+    )?;
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_mutate, rx_mutate) = channel::<MutationResult>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::ExecuteMutation {
+        tx: tx_mutate,
+        mutation: Box::new(DuplicateRevisions {
+            ids: vec![revs::main_bookmark(), revs::resolve_conflict()],
+        }),
+        confirmation: None,
+    })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession::default().handle_events(&rx)?;
+
+    _ = rx_load.recv()??;
+    assert!(matches!(
+        rx_mutate.recv()?,
+        MutationResult::ConfirmationRequired { .. }
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn replay_recording() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("session.jsonl");
+
+    let mut recorder = SessionRecorder::create(&path)?;
+    recorder.record(&RecordedEvent::Snapshot)?;
+    recorder.record(&RecordedEvent::mutation(
+        &DescribeRevision {
+            id: revs::working_copy(),
+            new_description: "recorded".to_owned(),
+            reset_author: false,
+        },
+        None,
+    )?)?;
+    recorder.record(&RecordedEvent::mutation(
+        &CreateRevision {
+            parent_ids: vec![revs::main_bookmark()],
+        },
+        None,
+    )?)?;
+    drop(recorder);
+
+    let mut selections = Vec::new();
+    for _ in 0..2 {
+        let repo = mkrepo();
+        let session = WorkerSession {
+            clock: Some(FixedClock::default()),
+            ..Default::default()
+        };
+
+        let replayed = replay(session, repo.path(), load_recording(&path)?)?;
+        assert_eq!(3, replayed.len());
+        assert!(matches!(
+            &replayed[1],
+            ReplayedEvent::Mutation { name, result: MutationResult::Updated { .. } }
+                if name == "DescribeRevision"
+        ));
+        let ReplayedEvent::Mutation {
+            result: MutationResult::UpdatedSelection { new_selection, .. },
+            ..
+        } = &replayed[2]
+        else {
+            panic!("CreateRevision failed: {:?}", replayed[2]);
+        };
+        selections.push(new_selection.id.commit.hex.clone());
+    }
+
+    // a fixed clock makes replays reproducible
+    assert_eq!(selections[0], selections[1]);
+
+    Ok(())
+}
+
+#[test]
+fn restore_workspace_state() -> Result<()> {
+    let repo = mkrepo();
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_query, rx_query) = channel::<Result<LogPage>>();
+    let (tx_save, rx_save) = channel::<()>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::QueryLog {
+        tx: tx_query,
+        query: "bookmarks()".to_owned(),
+        first_parent: false,
+        show_hidden: false,
+        elide: None,
+        full: false,
+    })?;
+    tx.send(SessionEvent::SaveWorkspaceState {
+        tx: tx_save,
+        scope: ConfigSource::Repo,
+        selection: Some(revs::main_bookmark().change.hex),
+    })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession::default().handle_events(&rx)?;
+
+    _ = rx_load.recv()??;
+    _ = rx_query.recv()??;
+    rx_save.recv()?;
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_reload, rx_reload) = channel::<Result<RepoConfig>>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_reload,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession::default().handle_events(&rx)?;
+
+    let config = rx_reload.recv()??;
+    let RepoConfig::Workspace {
+        latest_query,
+        latest_selection,
+        ..
+    } = config
+    else {
+        panic!("workspace not loaded");
+    };
+    assert_eq!("bookmarks()", latest_query);
+    assert_eq!(
+        revs::main_bookmark().commit.hex,
+        latest_selection
+            .expect("selection not restored")
+            .id
+            .commit
+            .hex
+    );
+
+    Ok(())
+}
+
+#[test]
+fn notes_persist() -> Result<()> {
+    let repo = mkrepo();
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_set, rx_set) = channel::<Result<()>>();
+    let (tx_clear, rx_clear) = channel::<Result<()>>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::SetNote {
+        tx: tx_set,
+        change_hex: revs::main_bookmark().change.hex,
+        text: "needs tests".to_owned(),
+    })?;
+    tx.send(SessionEvent::SetNote {
+        tx: tx_clear,
+        change_hex: revs::working_copy().change.hex,
+        text: "".to_owned(),
+    })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession::default().handle_events(&rx)?;
+
+    _ = rx_load.recv()??;
+    rx_set.recv()??;
+    rx_clear.recv()??;
+
+    // a new session sees the note written by the previous one
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_notes, rx_notes) = channel::<Result<Vec<RevNote>>>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::QueryNotes { tx: tx_notes })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession::default().handle_events(&rx)?;
+
+    _ = rx_load.recv()??;
+    let notes = rx_notes.recv()??;
+    assert_eq!(1, notes.len());
+    assert_eq!(revs::main_bookmark().change.hex, notes[0].change_hex);
+    assert_eq!("needs tests", notes[0].text);
+
+    Ok(())
+}
+
+#[test]
+fn drafts_persist() -> Result<()> {
+    let repo = mkrepo();
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_save, rx_save) = channel::<Result<()>>();
+    let (tx_invalid, rx_invalid) = channel::<Result<()>>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::SaveDraft {
+        tx: tx_save,
+        change_hex: revs::main_bookmark().change.hex,
+        text: "half-written".to_owned(),
+    })?;
+    tx.send(SessionEvent::SaveDraft {
+        tx: tx_invalid,
+        change_hex: "../escape".to_owned(),
+        text: "".to_owned(),
+    })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession::default().handle_events(&rx)?;
+
+    _ = rx_load.recv()??;
+    rx_save.recv()??;
+    assert!(rx_invalid.recv()?.is_err());
+
+    // a new session can restore the draft, then discard it
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_restore, rx_restore) = channel::<Result<Option<String>>>();
+    let (tx_discard, rx_discard) = channel::<Result<()>>();
+    let (tx_restore_again, rx_restore_again) = channel::<Result<Option<String>>>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::LoadDraft {
+        tx: tx_restore,
+        change_hex: revs::main_bookmark().change.hex,
+    })?;
+    tx.send(SessionEvent::DiscardDraft {
+        tx: tx_discard,
+        change_hex: revs::main_bookmark().change.hex,
+    })?;
+    tx.send(SessionEvent::LoadDraft {
+        tx: tx_restore_again,
+        change_hex: revs::main_bookmark().change.hex,
+    })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession::default().handle_events(&rx)?;
+
+    _ = rx_load.recv()??;
+    assert_eq!(Some("half-written".to_owned()), rx_restore.recv()??);
+    rx_discard.recv()??;
+    assert_eq!(None, rx_restore_again.recv()??);
+
+    Ok(())
+}
+
+#[test]
+fn interrupted_mutation() -> Result<()> {
+    let repo = mkrepo();
+    let journal_dir = repo.path().join(".jj").join("gg-journals");
+    let is_empty = |dir: &PathBuf| {
+        dir.read_dir()
+            .map_or(true, |mut entries| entries.next().is_none())
+    };
+
+    // a mutation which returns leaves no journal behind
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_mutate, rx_mutate) = channel::<MutationResult>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::ExecuteMutation {
+        tx: tx_mutate,
+        mutation: Box::new(DescribeRevision {
+            id: revs::working_copy(),
+            new_description: "wip".to_owned(),
+            reset_author: false,
+        }),
+        confirmation: None,
+    })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession::default().handle_events(&rx)?;
+
+    _ = rx_load.recv()??;
+    assert!(matches!(rx_mutate.recv()?, MutationResult::Updated { .. }));
+    assert!(is_empty(&journal_dir));
+
+    // one left by a killed process is reported once, and the repo can then be checked
+    std::fs::create_dir_all(&journal_dir)?;
+    std::fs::write(
+        journal_dir.join("2147483647-main.json"),
+        r#"{"mutation": "gg::messages::mutations::DescribeRevision", "op_id": "abc123", "process_id": 2147483647}"#,
+    )?;
+
+    // one belonging to another running process is left alone
+    #[cfg(unix)]
+    let running_path = {
+        let parent_id = std::os::unix::process::parent_id();
+        let path = journal_dir.join(format!("{parent_id}-main.json"));
+        std::fs::write(
+            &path,
+            format!(
+                r#"{{"mutation": "gg::messages::mutations::AbandonRevisions", "op_id": "def456", "process_id": {parent_id}}}"#
+            ),
+        )?;
+        path
+    };
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_verify, rx_verify) = channel::<Result<RepoVerification>>();
+    let (tx_reload, rx_reload) = channel::<Result<RepoConfig>>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::QueryVerifyRepository { tx: tx_verify })?;
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_reload,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession::default().handle_events(&rx)?;
+
+    let RepoConfig::Workspace {
+        interrupted_mutation: Some(interrupted),
+        ..
+    } = rx_load.recv()??
+    else {
+        panic!("interrupted mutation not reported");
+    };
+    assert_eq!("DescribeRevision", interrupted.mutation);
+    assert_eq!("abc123", interrupted.op_id);
+
+    let verification = rx_verify.recv()??;
+    assert!(verification.commits_checked > 0);
+    assert_eq!(Vec::<String>::new(), verification.problems);
+
+    assert!(matches!(
+        rx_reload.recv()??,
+        RepoConfig::Workspace {
+            interrupted_mutation: None,
+            ..
+        }
+    ));
+    #[cfg(unix)]
+    {
+        assert!(running_path.exists());
+        std::fs::remove_file(running_path)?;
+    }
+    assert!(is_empty(&journal_dir));
+
+    Ok(())
+}
+
+#[test]
+fn view_operation() -> Result<()> {
+    let repo = mkrepo();
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+    let (tx_describe, rx_describe) = channel::<MutationResult>();
+    let (tx_past, rx_past) = channel::<Result<RepoStatus>>();
+    let (tx_blocked, rx_blocked) = channel::<MutationResult>();
+    let (tx_present, rx_present) = channel::<Result<RepoStatus>>();
+
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(repo.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::ExecuteMutation {
+        tx: tx_describe,
+        mutation: Box::new(DescribeRevision {
+            id: revs::working_copy(),
+            new_description: "wip".to_owned(),
+            reset_author: false,
+        }),
+        confirmation: None,
+    })?;
+    tx.send(SessionEvent::SetViewOperation {
+        tx: tx_past,
+        op: Some("@-".to_owned()),
+    })?;
+    tx.send(SessionEvent::ExecuteMutation {
+        tx: tx_blocked,
+        mutation: Box::new(DescribeRevision {
+            id: revs::working_copy(),
+            new_description: "rewritten history".to_owned(),
+            reset_author: false,
+        }),
+        confirmation: None,
+    })?;
+    tx.send(SessionEvent::SetViewOperation {
+        tx: tx_present,
+        op: None,
+    })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession::default().handle_events(&rx)?;
+
+    _ = rx_load.recv()??;
+    assert!(matches!(
+        rx_describe.recv()?,
+        MutationResult::Updated { .. }
+    ));
+
+    // the original, undescribed working copy is visible again
+    let past = rx_past.recv()??;
+    assert!(past.historical_operation.is_some());
+    assert_eq!(revs::working_copy().commit.hex, past.working_copy.hex);
+
+    assert!(matches!(
+        rx_blocked.recv()?,
+        MutationResult::PreconditionError { .. }
+    ));
+
+    let present = rx_present.recv()??;
+    assert!(present.historical_operation.is_none());
+    assert_ne!(revs::working_copy().commit.hex, present.working_copy.hex);
+
+    Ok(())
+}
+
+#[test]
+fn init_workspace() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_init, rx_init) = channel::<Result<()>>();
+    let (tx_reinit, rx_reinit) = channel::<Result<()>>();
+    let (tx_load, rx_load) = channel::<Result<RepoConfig>>();
+
+    tx.send(SessionEvent::InitWorkspace {
+        tx: tx_init,
+        wd: dir.path().to_owned(),
+        options: InitWorkspace {
+            gitignore_templates: vec!["rust".to_owned(), "macOS".to_owned()],
+            description: Some("initial commit".to_owned()),
+            bookmark: Some("main".to_owned()),
+        },
+    })?;
+    tx.send(SessionEvent::InitWorkspace {
+        tx: tx_reinit,
+        wd: dir.path().to_owned(),
+        options: InitWorkspace::default(),
+    })?;
+    tx.send(SessionEvent::OpenWorkspace {
+        tx: tx_load,
+        wd: Some(dir.path().to_owned()),
+    })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession::default().handle_events(&rx)?;
+
+    rx_init.recv()??;
+    assert!(rx_reinit.recv()?.is_err());
+    assert!(matches!(rx_load.recv()??, RepoConfig::Workspace { .. }));
+
+    assert_eq!(
+        "/target\n.DS_Store\n",
+        std::fs::read_to_string(dir.path().join(".gitignore"))?
+    );
+    assert!(dir.path().join(".git").is_dir());
+
+    let mut session = WorkerSession::default();
+    let ws = session.load_directory(dir.path())?;
+    let page = queries::query_log(&ws, "bookmarks(main)", 10)?;
+    assert_eq!(1, page.rows.len());
+    assert!(page.rows[0].revision.is_working_copy);
+    assert_eq!("initial commit", page.rows[0].revision.description.lines[0]);
+
+    Ok(())
+}
+
+#[test]
+fn create_sample_repository() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+
+    let (tx, rx) = channel::<SessionEvent>();
+    let (tx_create, rx_create) = channel::<Result<PathBuf>>();
+    let (tx_recreate, rx_recreate) = channel::<Result<PathBuf>>();
+
+    tx.send(SessionEvent::CreateSampleRepository {
+        tx: tx_create,
+        parent: dir.path().to_owned(),
+    })?;
+    tx.send(SessionEvent::CreateSampleRepository {
+        tx: tx_recreate,
+        parent: dir.path().to_owned(),
+    })?;
+    tx.send(SessionEvent::EndSession)?;
+
+    WorkerSession::default().handle_events(&rx)?;
+
+    let path = rx_create.recv()??;
+    assert!(rx_recreate.recv()?.is_err());
+
+    let mut session = WorkerSession::default();
+    let mut ws = session.load_directory(&path)?;
+    assert!(ws.import_and_snapshot(false)?); // picks up the edit to NOTES.md
+
+    let page = queries::query_log(&ws, "bookmarks()", 10)?;
+    assert_eq!(3, page.rows.len());
+
+    let page = queries::query_log(&ws, "conflicts()", 10)?;
+    assert_eq!(1, page.rows.len());
+    assert_eq!(
+        "Merge the translations",
+        page.rows[0].revision.description.lines[0]
+    );
+
+    let page = queries::query_log(&ws, "@-", 1)?;
+    assert_eq!("Add some notes", page.rows[0].revision.description.lines[0]);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn launch_diff_tool() -> Result<()> {
+    let repo = mkrepo();
+
+    let launch = |path: Option<TreePath>| -> Result<Result<()>> {
+        let (tx, rx) = channel::<SessionEvent>();
+        let (tx_open, rx_open) = channel();
+        let (tx_launch, rx_launch) = channel();
+
+        tx.send(SessionEvent::OpenWorkspace {
+            tx: tx_open,
+            wd: Some(repo.path().to_owned()),
+        })?;
+        tx.send(SessionEvent::LaunchDiffTool {
+            tx: tx_launch,
+            id: revs::conflict_bookmark(),
+            path,
+        })?;
+        tx.send(SessionEvent::EndSession)?;
+
+        WorkerSession::default().handle_events(&rx)?;
+
+        rx_open.recv()??;
+        Ok(rx_launch.recv()?)
+    };
+
+    fs::write(
+        repo.path().join(".jj/repo/config.toml"),
+        "ui.diff-editor = \":builtin\"\n",
+    )?;
+    assert!(launch(None)?.is_err());
+
+    fs::write(
+        repo.path().join(".jj/repo/config.toml"),
+        "ui.diff-editor = [\"true\", \"$left\", \"$right\"]\n",
+    )?;
+    launch(None)??;
+    launch(Some(TreePath {
+        repo_path: "b.txt".to_owned(),
+        relative_path: "b.txt".into(),
+    }))??;
+
+    Ok(())
+}
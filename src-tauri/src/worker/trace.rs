@@ -0,0 +1,98 @@
+//! Correlates worker events with their handling time, for diagnosing slow or stuck requests
+
+use std::{collections::VecDeque, time::Instant};
+
+use chrono::{DateTime, Local};
+
+use super::SessionEvent;
+use crate::messages;
+
+const DEFAULT_CAPACITY: usize = 100;
+
+struct PendingEvent {
+    id: usize,
+    state: &'static str,
+    event: String,
+    received_at: DateTime<Local>,
+    started: Instant,
+}
+
+/// numbers each received event and keeps the most recently completed ones
+pub struct EventTrace {
+    next_id: usize,
+    capacity: usize,
+    pending: Option<PendingEvent>,
+    completed: VecDeque<messages::TraceEntry>,
+}
+
+impl Default for EventTrace {
+    fn default() -> Self {
+        EventTrace {
+            next_id: 1,
+            capacity: DEFAULT_CAPACITY,
+            pending: None,
+            completed: VecDeque::new(),
+        }
+    }
+}
+
+impl EventTrace {
+    /// a capacity of 0 disables the buffer, but events are still logged
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.completed.len() > capacity {
+            self.completed.pop_front();
+        }
+    }
+
+    /// records the start of an event's handling, completing the previous one if necessary
+    pub fn begin(&mut self, state: &'static str, evt: &SessionEvent) {
+        self.end();
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        log::debug!("[#{id}] {state} handling {evt:?}");
+
+        self.pending = Some(PendingEvent {
+            id,
+            state,
+            event: evt.name(),
+            received_at: Local::now(),
+            started: Instant::now(),
+        });
+    }
+
+    /// records that the current event has been handled; called before waiting for another
+    pub fn end(&mut self) {
+        let Some(pending) = self.pending.take() else {
+            return;
+        };
+
+        let duration_ms = pending.started.elapsed().as_secs_f64() * 1000.0;
+        log::debug!(
+            "[#{}] {} completed in {duration_ms:.1}ms",
+            pending.id,
+            pending.event
+        );
+
+        if self.capacity == 0 {
+            return;
+        }
+        if self.completed.len() == self.capacity {
+            self.completed.pop_front();
+        }
+        self.completed.push_back(messages::TraceEntry {
+            id: pending.id,
+            state: pending.state.to_owned(),
+            event: pending.event,
+            received_at: pending.received_at.to_rfc3339(),
+            duration_ms,
+        });
+    }
+
+    /// completed events, oldest first
+    pub fn entries(&self) -> Vec<messages::TraceEntry> {
+        self.completed.iter().cloned().collect()
+    }
+}
@@ -1,9 +1,10 @@
 import { invoke, type InvokeArgs } from "@tauri-apps/api/core";
 import { emit, listen, type EventCallback } from "@tauri-apps/api/event";
 import type { Readable, Subscriber, Unsubscriber } from "svelte/store";
+import type { Event } from "./messages/Event";
 import type { MutationResult } from "./messages/MutationResult";
-import { currentInput, currentMutation, repoStatusEvent, revisionSelectEvent } from "./stores";
-import { onMount } from "svelte";
+import { currentInput, currentMutation, repoStatusEvent, revisionMultiSelect, revisionSelectEvent } from "./stores";
+import { onMount, tick } from "svelte";
 import { resolve } from "@tauri-apps/api/path";
 
 export type Query<T> = { type: "wait" } | { type: "data", value: T } | { type: "error", message: string };
@@ -12,9 +13,17 @@ export interface Settable<T> extends Readable<T> {
     set: (value: T) => void;
 }
 
+/**
+ * payload of an event sent by the backend, which must be listened for by its exact name
+ */
+export type EventPayload<N extends Event["name"]> = Extract<Event, { name: N }>["payload"];
+
 /**
  * multiplexes tauri events into a svelte store; never actually unsubscribes because the store protocol isn't async
  */
+export async function event<N extends Event["name"]>(name: N, initialValue: EventPayload<N>): Promise<Settable<EventPayload<N>>>;
+export async function event<N extends Event["name"]>(name: N, initialValue: undefined): Promise<Settable<EventPayload<N> | undefined>>;
+export async function event<T>(name: string, initialValue: T): Promise<Settable<T>>;
 export async function event<T>(name: string, initialValue: T): Promise<Settable<T>> {
     const subscribers = new Set<Subscriber<T>>();
     let lastValue: T = initialValue;
@@ -48,6 +57,8 @@ export async function event<T>(name: string, initialValue: T): Promise<Settable<
 /**
  * subscribes to tauri events for a component's lifetime
  */
+export function onEvent<N extends Event["name"]>(name: N, callback: (payload: EventPayload<N>) => void): void;
+export function onEvent<T>(name: string, callback: (payload: T) => void): void;
 export function onEvent<T>(name: string, callback: (payload: T) => void) {
     onMount(() => {
         let promise = listen<T>(name, e => callback(e.payload));
@@ -102,24 +113,44 @@ export function trigger(command: string, request?: InvokeArgs) {
 /**
  * call an IPC which, if successful, modifies the repo
  */
-export async function mutate<T>(command: string, mutation: T): Promise<boolean> {
+export async function mutate<T>(command: string, mutation: T, confirmation?: string): Promise<boolean> {
     try {
         // set a wait state then the data state, unless the data comes in hella fast
-        let fetch = invoke<MutationResult>(command, { mutation });
+        let fetch = invoke<MutationResult>(command, { mutation, confirmation });
         let result = await Promise.race([fetch.then(r => Promise.resolve<Query<MutationResult>>({ type: "data", value: r })), delay<MutationResult>()]);
         currentMutation.set(result);
         let value = await fetch;
 
         // succeeded; dismiss modals
-        if (value.type == "Updated" || value.type == "UpdatedSelection" || value.type == "Unchanged") {
+        if (value.type == "Updated" || value.type == "UpdatedSelection" || value.type == "Restored" || value.type == "Unchanged") {
             if (value.type != "Unchanged") {
                 repoStatusEvent.set(value.new_status);
-                if (value.type == "UpdatedSelection") {
+                if (value.type == "UpdatedSelection" || value.type == "Restored") {
                     revisionSelectEvent.set(value.new_selection);
                 }
+                if (value.type == "Restored") {
+                    // a new status clears the multi-selection, so put it back afterwards
+                    let headers = value.restored.flatMap((change) => (change.type == "Visible" ? [change.header] : []));
+                    if (headers.length > 1) {
+                        await tick();
+                        revisionMultiSelect.set(headers);
+                    }
+                }
             }
             currentMutation.set(null);
 
+            // destructive; ask, then resubmit with the backend's token
+        } else if (value.type == "ConfirmationRequired") {
+            currentMutation.set(null);
+            let confirmed = await getInput("Confirm", value.summary, []);
+            return confirmed ? await mutate(command, mutation, value.token) : false;
+
+            // protected bookmark; ask, then resubmit with the protection overridden
+        } else if (value.type == "OverrideRequired") {
+            currentMutation.set(null);
+            let confirmed = await getInput("Protected Bookmark", value.summary, []);
+            return confirmed ? await mutate(command, { ...mutation, allow_protected: true }, confirmation) : false;
+
             // failed; transition from overlay or delay to error
         } else {
             currentMutation.set({ type: "data", value });
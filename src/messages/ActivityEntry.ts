@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ActivityKind } from "./ActivityKind";
+import type { BookmarkChange } from "./BookmarkChange";
+import type { CommitId } from "./CommitId";
+
+export interface ActivityEntry { op_id: string, kind: ActivityKind, description: string, time: string, bookmarks: Array<BookmarkChange>, revisions: Array<CommitId>, }
\ No newline at end of file
@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CommitId } from "./CommitId";
 import type { RevId } from "./RevId";
 
-export interface BackoutRevisions { ids: Array<RevId>, }
\ No newline at end of file
+export interface BackoutRevisions { ids: Array<RevId>, new_parent_id: CommitId | null, }
\ No newline at end of file
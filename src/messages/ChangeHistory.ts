@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChangeOperation } from "./ChangeOperation";
+
+export interface ChangeHistory { operations: Array<ChangeOperation>, truncated: boolean, }
\ No newline at end of file
@@ -1,5 +1,6 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HunkKind } from "./HunkKind";
 import type { HunkLocation } from "./HunkLocation";
 import type { MultilineString } from "./MultilineString";
 
-export interface ChangeHunk { location: HunkLocation, lines: MultilineString, }
\ No newline at end of file
+export interface ChangeHunk { location: HunkLocation, kind: HunkKind, lines: MultilineString, }
\ No newline at end of file
@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type ChangeKind = "None" | "Added" | "Deleted" | "Modified";
\ No newline at end of file
+export type ChangeKind = "None" | "Added" | "Deleted" | "Modified" | "CaseRenamed";
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChangeOperationKind } from "./ChangeOperationKind";
+import type { CommitId } from "./CommitId";
+
+export interface ChangeOperation { id: string, description: string, time: string, kind: ChangeOperationKind, commits: Array<CommitId>, }
\ No newline at end of file
@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CheckoutPolicy } from "./CheckoutPolicy";
 import type { RevId } from "./RevId";
 
-export interface CheckoutRevision { id: RevId, }
\ No newline at end of file
+export interface CheckoutRevision { id: RevId, policy: CheckoutPolicy | null, }
\ No newline at end of file
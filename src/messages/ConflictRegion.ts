@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ConflictSide } from "./ConflictSide";
+
+export interface ConflictRegion { index: number, bases: Array<ConflictSide>, sides: Array<ConflictSide>, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MultilineString } from "./MultilineString";
+
+export interface ConflictSide { label: string, lines: MultilineString, }
\ No newline at end of file
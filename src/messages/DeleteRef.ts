@@ -1,4 +1,4 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { StoreRef } from "./StoreRef";
 
-export interface DeleteRef { ref: StoreRef, }
\ No newline at end of file
+export interface DeleteRef { ref: StoreRef, allow_protected: boolean, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DescriptionEdit } from "./DescriptionEdit";
+import type { RevId } from "./RevId";
+
+export interface DescribeRevisions { ids: Array<RevId>, edit: DescriptionEdit, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DescriptionViolation } from "./DescriptionViolation";
+
+export interface DescriptionCheck { lines: number, words: number, characters: number, violations: Array<DescriptionViolation>, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type DescriptionEdit = { "type": "Set", text: string, } | { "type": "Prepend", text: string, } | { "type": "AppendTrailer", trailer: string, } | { "type": "Replace", find: string, replace: string, };
\ No newline at end of file
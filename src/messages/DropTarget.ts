@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CommitId } from "./CommitId";
+
+export interface DropTarget { id: CommitId, can_move: boolean, can_insert: boolean, can_merge: boolean, }
\ No newline at end of file
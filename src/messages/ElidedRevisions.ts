@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CommitId } from "./CommitId";
+
+export interface ElidedRevisions { count: number, last: CommitId, }
\ No newline at end of file
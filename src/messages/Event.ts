@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { InputRequest } from "./InputRequest";
+import type { ProgressEvent } from "./ProgressEvent";
+import type { RepoConfig } from "./RepoConfig";
+import type { RepoStatus } from "./RepoStatus";
+import type { UpdateAvailable } from "./UpdateAvailable";
+
+export type Event = { "name": "gg://repo/config", "payload": RepoConfig } | { "name": "gg://repo/status", "payload": RepoStatus } | { "name": "gg://worker/busy", "payload": string | null } | { "name": "gg://progress", "payload": ProgressEvent } | { "name": "gg://input", "payload": InputRequest } | { "name": "gg://update", "payload": UpdateAvailable };
\ No newline at end of file
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ConflictRegion } from "./ConflictRegion";
+import type { ConflictSide } from "./ConflictSide";
+import type { TreePath } from "./TreePath";
+
+export interface FileConflict { path: TreePath, bases: Array<ConflictSide>, sides: Array<ConflictSide>, regions: Array<ConflictRegion>, has_trailing_newline: boolean, }
\ No newline at end of file
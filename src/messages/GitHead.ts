@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CommitId } from "./CommitId";
+
+export type GitHead = { "type": "Branch", name: string, commit: CommitId, is_synced: boolean, } | { "type": "Detached", commit: CommitId, is_synced: boolean, } | { "type": "Unborn", name: string, };
\ No newline at end of file
@@ -1,4 +1,4 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { StoreRef } from "./StoreRef";
 
-export type GitPush = { "type": "AllBookmarks", remote_name: string, } | { "type": "AllRemotes", branch_ref: StoreRef, } | { "type": "RemoteBookmark", remote_name: string, branch_ref: StoreRef, };
\ No newline at end of file
+export type GitPush = { "type": "AllBookmarks", remote_name: string, allow_protected: boolean, } | { "type": "AllRemotes", branch_ref: StoreRef, allow_protected: boolean, } | { "type": "RemoteBookmark", remote_name: string, branch_ref: StoreRef, allow_protected: boolean, };
\ No newline at end of file
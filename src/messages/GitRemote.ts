@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface GitRemote { name: string, url: string | null, push_url: string | null, fetch_refspecs: Array<string>, push_refspecs: Array<string>, is_default_fetch: boolean, is_default_push: boolean, last_fetched: string | null, }
\ No newline at end of file
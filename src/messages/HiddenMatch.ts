@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RevHeader } from "./RevHeader";
+
+export interface HiddenMatch { symbol: string, revisions: Array<RevHeader>, }
\ No newline at end of file
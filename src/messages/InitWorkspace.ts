@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface InitWorkspace { gitignore_templates: Array<string>, description: string | null, bookmark: string | null, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CommitId } from "./CommitId";
+
+export interface LogElision { min_run: number, keep: Array<CommitId>, expanded: Array<CommitId>, }
\ No newline at end of file
@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HiddenMatch } from "./HiddenMatch";
 import type { LogRow } from "./LogRow";
 
-export interface LogPage { rows: Array<LogRow>, has_more: boolean, }
\ No newline at end of file
+export interface LogPage { rows: Array<LogRow>, has_more: boolean, invalidated: boolean, hidden_match: HiddenMatch | null, narrowed_from: string | null, }
\ No newline at end of file
@@ -1,6 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ElidedRevisions } from "./ElidedRevisions";
 import type { LogCoordinates } from "./LogCoordinates";
 import type { LogLine } from "./LogLine";
 import type { RevHeader } from "./RevHeader";
 
-export interface LogRow { revision: RevHeader, location: LogCoordinates, padding: number, lines: Array<LogLine>, }
\ No newline at end of file
+export interface LogRow { revision: RevHeader, location: LogCoordinates, padding: number, lines: Array<LogLine>, push_bookmarks: Array<string>, elided: ElidedRevisions | null, accessible_description: string, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RevHeader } from "./RevHeader";
+
+export interface MergeBase { bases: Array<RevHeader>, ahead: number, behind: number, }
\ No newline at end of file
@@ -2,4 +2,4 @@
 import type { RevId } from "./RevId";
 import type { StoreRef } from "./StoreRef";
 
-export interface MoveRef { ref: StoreRef, to_id: RevId, }
\ No newline at end of file
+export interface MoveRef { ref: StoreRef, to_id: RevId, allow_protected: boolean, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BookmarkChange } from "./BookmarkChange";
+import type { RevHeader } from "./RevHeader";
+
+export interface OperationDiff { id: string, description: string, added: Array<RevHeader>, removed: Array<RevHeader>, rewritten: Array<RevHeader>, bookmarks: Array<BookmarkChange>, truncated: boolean, }
\ No newline at end of file
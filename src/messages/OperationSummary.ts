@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface OperationSummary { id: string, description: string, time: string, duration_ms: number, mutation: string | null, is_gg: boolean, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DisplayPath } from "./DisplayPath";
+
+export type PathInfo = { "type": "Workspace", root: DisplayPath, } | { "type": "GitRepository", root: DisplayPath, } | { "type": "Unversioned", path: DisplayPath, } | { "type": "Missing", path: DisplayPath, };
\ No newline at end of file
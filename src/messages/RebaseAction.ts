@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type RebaseAction = { "type": "Keep" } | { "type": "Squash" } | { "type": "Drop" } | { "type": "Reword", new_description: string, };
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RebaseAction } from "./RebaseAction";
+import type { RevId } from "./RevId";
+
+export interface RebaseStep { id: RevId, action: RebaseAction, }
\ No newline at end of file
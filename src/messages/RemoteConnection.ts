@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface RemoteConnection { remote_name: string, url: string | null, proxy: string | null, reachable: boolean, message: string, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CommitId } from "./CommitId";
+import type { RevId } from "./RevId";
+
+export interface RemoveParentFromMerge { id: RevId, parent_id: CommitId, }
\ No newline at end of file
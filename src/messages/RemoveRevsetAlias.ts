@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ConfigScope } from "./ConfigScope";
+
+export interface RemoveRevsetAlias { scope: ConfigScope, name: string, }
\ No newline at end of file
@@ -1,5 +1,11 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ConfigDiagnostic } from "./ConfigDiagnostic";
 import type { DisplayPath } from "./DisplayPath";
+import type { InterruptedMutation } from "./InterruptedMutation";
+import type { QueryChoice } from "./QueryChoice";
+import type { RepoBackend } from "./RepoBackend";
 import type { RepoStatus } from "./RepoStatus";
+import type { RevHeader } from "./RevHeader";
+import type { WorkspaceHead } from "./WorkspaceHead";
 
-export type RepoConfig = { "type": "Initial" } | { "type": "Workspace", absolute_path: DisplayPath, git_remotes: Array<string>, default_query: string, latest_query: string, status: RepoStatus, theme_override: string | null, mark_unpushed_branches: boolean, } | { "type": "TimeoutError" } | { "type": "LoadError", absolute_path: DisplayPath, message: string, } | { "type": "WorkerError", message: string, };
\ No newline at end of file
+export type RepoConfig = { "type": "Initial" } | { "type": "Workspace", absolute_path: DisplayPath, git_remotes: Array<string>, default_query: string, latest_query: string, query_choices: Array<QueryChoice>, status: RepoStatus, theme_override: string | null, mark_unpushed_branches: boolean, latest_selection: RevHeader | null, trunk: RevHeader | null, config_diagnostics: Array<ConfigDiagnostic>, missing_identity: boolean, check_updates: boolean, has_external_merge_tool: boolean, safe_mode: boolean, workspace_name: string, workspaces: Array<WorkspaceHead>, interrupted_mutation: InterruptedMutation | null, backend: RepoBackend, schema_version: number, } | { "type": "TimeoutError" } | { "type": "SchemaMismatch", frontend_version: number, backend_version: number, } | { "type": "LoadError", absolute_path: DisplayPath, message: string, } | { "type": "WorkspaceMissing", absolute_path: DisplayPath, } | { "type": "WorkerError", message: string, };
\ No newline at end of file
@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { CommitId } from "./CommitId";
+import type { GitHead } from "./GitHead";
 
-export interface RepoStatus { operation_description: string, working_copy: CommitId, }
\ No newline at end of file
+export interface RepoStatus { operation_description: string, working_copy: CommitId, historical_operation: string | null, git_head: GitHead | null, }
\ No newline at end of file
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MultilineString } from "./MultilineString";
+import type { RevId } from "./RevId";
+import type { TreePath } from "./TreePath";
+
+export interface ResolveConflict { id: RevId, path: TreePath, choices: Array<number>, content: MultilineString | null, has_trailing_newline: boolean, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DisplayPath } from "./DisplayPath";
+
+export interface ResolvedPath { absolute_path: DisplayPath, exists: boolean, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChangeId } from "./ChangeId";
+import type { RevHeader } from "./RevHeader";
+
+export type RestoredChange = { "type": "Visible", header: RevHeader, } | { "type": "Hidden", change_id: ChangeId, };
\ No newline at end of file
@@ -1,6 +1,8 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { ChangeHunk } from "./ChangeHunk";
 import type { ChangeKind } from "./ChangeKind";
+import type { ConflictRegion } from "./ConflictRegion";
+import type { TextFormat } from "./TextFormat";
 import type { TreePath } from "./TreePath";
 
-export interface RevChange { kind: ChangeKind, path: TreePath, has_conflict: boolean, hunks: Array<ChangeHunk>, }
\ No newline at end of file
+export interface RevChange { kind: ChangeKind, path: TreePath, old_path: TreePath | null, has_conflict: boolean, lines_added: number, lines_removed: number, is_binary: boolean, format: TextFormat, hunks: Array<ChangeHunk>, conflict_regions: Array<ConflictRegion>, }
\ No newline at end of file
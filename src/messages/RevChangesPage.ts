@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RevChange } from "./RevChange";
+
+export interface RevChangesPage { changes: Array<RevChange>, has_more: boolean, }
\ No newline at end of file
@@ -1,5 +1,6 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { ChangeHunk } from "./ChangeHunk";
+import type { ConflictRegion } from "./ConflictRegion";
 import type { TreePath } from "./TreePath";
 
-export interface RevConflict { path: TreePath, hunk: ChangeHunk, }
\ No newline at end of file
+export interface RevConflict { path: TreePath, hunk: ChangeHunk, regions: Array<ConflictRegion>, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TreePath } from "./TreePath";
+
+export interface RevDirectory { path: TreePath, files_changed: number, lines_added: number, lines_removed: number, }
\ No newline at end of file
@@ -1,8 +1,10 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { CommitId } from "./CommitId";
 import type { MultilineString } from "./MultilineString";
+import type { RevAge } from "./RevAge";
 import type { RevAuthor } from "./RevAuthor";
 import type { RevId } from "./RevId";
+import type { RevLabel } from "./RevLabel";
 import type { StoreRef } from "./StoreRef";
 
-export interface RevHeader { id: RevId, description: MultilineString, author: RevAuthor, has_conflict: boolean, is_working_copy: boolean, is_immutable: boolean, refs: Array<StoreRef>, parent_ids: Array<CommitId>, }
\ No newline at end of file
+export interface RevHeader { id: RevId, description: MultilineString, author: RevAuthor, has_conflict: boolean, is_working_copy: boolean, working_copy_of: Array<string>, is_immutable: boolean, is_signed: boolean, is_hidden: boolean, refs: Array<StoreRef>, parent_ids: Array<CommitId>, label: RevLabel | null, age: RevAge, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LabelColor } from "./LabelColor";
+
+export interface RevLabel { text: string, color: LabelColor, }
\ No newline at end of file
@@ -1,7 +1,11 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { RevChange } from "./RevChange";
 import type { RevConflict } from "./RevConflict";
+import type { RevConflictPath } from "./RevConflictPath";
+import type { RevDirectory } from "./RevDirectory";
 import type { RevHeader } from "./RevHeader";
 import type { RevId } from "./RevId";
+import type { RevSignature } from "./RevSignature";
+import type { RevStats } from "./RevStats";
 
-export type RevResult = { "type": "NotFound", id: RevId, } | { "type": "Detail", header: RevHeader, parents: Array<RevHeader>, changes: Array<RevChange>, conflicts: Array<RevConflict>, };
\ No newline at end of file
+export type RevResult = { "type": "NotFound", id: RevId, } | { "type": "Detail", header: RevHeader, parents: Array<RevHeader>, changes: Array<RevChange>, has_more_changes: boolean, conflicts: Array<RevConflict>, conflicted_paths: Array<RevConflictPath>, stats: RevStats, directories: Array<RevDirectory> | null, signature: RevSignature, };
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type RevSignature = { "type": "Unsigned" } | { "type": "Good", key: string | null, signer: string | null, } | { "type": "Bad", key: string | null, signer: string | null, } | { "type": "UnknownKey", key: string | null, signer: string | null, };
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ConfigScope } from "./ConfigScope";
+
+export interface RevsetAlias { name: string, definition: string, scope: ConfigScope, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TreePath } from "./TreePath";
+
+export interface RewriteMatch { path: TreePath, line: number, before: string, after: string, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RevId } from "./RevId";
+
+export interface SetAuthor { ids: Array<RevId>, name: string | null, email: string | null, timestamp: string | null, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HunkLocation } from "./HunkLocation";
+import type { TreePath } from "./TreePath";
+
+export interface SplitPath { path: TreePath, hunks: Array<HunkLocation> | null, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Indentation } from "./Indentation";
+import type { LineEndings } from "./LineEndings";
+
+export interface TextFormat { line_endings: LineEndings, indentation: Indentation, encoding: string | null, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface TraceEntry { id: number, state: string, event: string, received_at: string, duration_ms: number, }
\ No newline at end of file
@@ -1,3 +1,4 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChangeId } from "./ChangeId";
 
-export type UndoOperation = null;
\ No newline at end of file
+export interface UndoOperation { selected: Array<ChangeId>, }
\ No newline at end of file
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RepoStatus } from "./RepoStatus";
+import type { RevChange } from "./RevChange";
+import type { RevHeader } from "./RevHeader";
+
+export interface WorkingCopyDiff { base: RevHeader, changes: Array<RevChange>, has_more: boolean, new_status: RepoStatus | null, }
\ No newline at end of file
@@ -4,13 +4,19 @@ import type { MoveChanges } from "../messages/MoveChanges";
 import type { MoveRef } from "../messages/MoveRef";
 import type { InsertRevision } from "../messages/InsertRevision";
 import type { MoveRevision } from "../messages/MoveRevision";
-import type { MoveSource } from "../messages/MoveSource";
+import type { AddParentToMerge } from "../messages/AddParentToMerge";
+import type { RemoveParentFromMerge } from "../messages/RemoveParentFromMerge";
+import type { CreateMergeOf } from "../messages/CreateMergeOf";
+import type { GraftRevisions } from "../messages/GraftRevisions";
 import type { ChangeId } from "../messages/ChangeId";
 import type { CommitId } from "../messages/CommitId";
 import RevisionMutator from "./RevisionMutator";
 import ChangeMutator from "./ChangeMutator";
 import RefMutator from "./RefMutator";
 import type { StoreRef } from "../messages/StoreRef";
+import type { DropTarget } from "../messages/DropTarget";
+import { get } from "svelte/store";
+import { dropTargets } from "../stores";
 
 export type RichHint = (string | ChangeId | CommitId | Extract<StoreRef, { type: "LocalBookmark" } | { type: "RemoteBookmark" }>)[];
 export type Eligibility = { type: "yes", hint: RichHint } | { type: "maybe", hint: string } | { type: "no" };
@@ -18,10 +24,13 @@ export type Eligibility = { type: "yes", hint: RichHint } | { type: "maybe", hin
 export default class BinaryMutator {
     #from: Operand;
     #to: Operand;
+    #copy: boolean;
 
-    constructor(from: Operand, to: Operand) {
+    // copy is set when a modifier key is held during the drag
+    constructor(from: Operand, to: Operand, copy: boolean = false) {
         this.#from = from;
         this.#to = to;
+        this.#copy = copy;
     }
 
     static canDrag(from: Operand): Eligibility {
@@ -51,7 +60,30 @@ export default class BinaryMutator {
         return { type: "no" };
     }
 
+    // backend rules for dropping the dragged revision; permissive until they've loaded
+    static #permits(id: CommitId, operation: keyof Omit<DropTarget, "id">): boolean {
+        return get(dropTargets)[id.hex]?.[operation] ?? true;
+    }
+
     canDrop(): Eligibility {
+        // grafting copies the source, so it needn't be mutable
+        if (this.#copy && this.#from.type == "Revision" && this.#to.type == "Revision") {
+            if (this.#to.header.id.change.hex == this.#from.header.id.change.hex) {
+                return { type: "no" };
+            } else {
+                return { type: "yes", hint: ["Grafting revision ", this.#from.header.id.change, " onto ", this.#to.header.id.change] };
+            }
+        }
+
+        // a new merge leaves both revisions as they are
+        if (this.#copy && this.#from.type == "Revision" && this.#to.type == "Merge") {
+            if (this.#to.header.id.change.hex == this.#from.header.id.change.hex) {
+                return { type: "no" };
+            } else {
+                return { type: "yes", hint: ["Creating a merge of ", this.#to.header.id.change, " and ", this.#from.header.id.change] };
+            }
+        }
+
         // generic prohibitions - don't drop undroppables, don't drop on yourself
         if (BinaryMutator.canDrag(this.#from).type != "yes" && !(this.#from.type == "Revision" && this.#to.type == "Merge")) {
             return { type: "no" };
@@ -61,20 +93,27 @@ export default class BinaryMutator {
 
         if (this.#from.type == "Revision") {
             if (this.#to.type == "Revision") {
+                if (!BinaryMutator.#permits(this.#to.header.id.commit, "can_move")) {
+                    return { type: "maybe", hint: "(can't rebase onto this revision)" };
+                }
                 return { type: "yes", hint: ["Rebasing revision ", this.#from.header.id.change, " onto ", this.#to.header.id.change] };
             } else if (this.#to.type == "Parent") {
-                if (this.#to.child == this.#from.header) {
+                if (this.#to.child == this.#from.header || this.#to.header.id.commit.hex == this.#from.header.id.commit.hex) {
                     return { type: "no" };
                 } else if (this.#to.child.is_immutable) {
                     return { type: "maybe", hint: "(can't insert before an immutable revision)" };
+                } else if (!BinaryMutator.#permits(this.#to.child.id.commit, "can_insert")) {
+                    return { type: "maybe", hint: "(can't insert before this revision)" };
                 } else {
                     return { type: "yes", hint: ["Inserting revision ", this.#from.header.id.change, " before ", this.#to.child.id.change] };
                 }
             } else if (this.#to.type == "Merge") {
                 if (this.#to.header.id.change.hex == this.#from.header.id.change.hex) {
                     return { type: "no" };
+                } else if (!BinaryMutator.#permits(this.#to.header.id.commit, "can_merge")) {
+                    return { type: "maybe", hint: "(can't add as a parent of this revision)" };
                 } else {
-                    return { type: "yes", hint: ["Adding parent to revision ", this.#to.header.id.change] };
+                    return { type: "yes", hint: ["Adding parent ", this.#from.header.id.change, " to revision ", this.#to.header.id.change] };
                 }
             } else if (this.#to.type == "Repository") {
                 return { type: "yes", hint: ["Abandoning commit ", this.#from.header.id.commit] };
@@ -141,6 +180,18 @@ export default class BinaryMutator {
     }
 
     doDrop() {
+        if (this.#copy && this.#from.type == "Revision" && this.#to.type == "Revision") {
+            // copy rev onto target
+            mutate<GraftRevisions>("graft_revisions", { ids: [this.#from.header.id], parent_ids: [this.#to.header.id] });
+            return;
+        }
+
+        if (this.#copy && this.#from.type == "Revision" && this.#to.type == "Merge") {
+            // new merge of target and rev
+            mutate<CreateMergeOf>("create_merge_of", { ids: [this.#to.header.id, this.#from.header.id] });
+            return;
+        }
+
         if (this.#from.type == "Revision") {
             if (this.#to.type == "Revision") {
                 // rebase rev onto single target
@@ -151,9 +202,8 @@ export default class BinaryMutator {
                 mutate<InsertRevision>("insert_revision", { id: this.#from.header.id, after_id: this.#to.header.id, before_id: this.#to.child.id });
                 return;
             } else if (this.#to.type == "Merge") {
-                // rebase subtree onto additional targets
-                let newParents = [...this.#to.header.parent_ids, this.#from.header.id.commit];
-                mutate<MoveSource>("move_source", { id: this.#to.header.id, parent_ids: newParents });
+                // rebase subtree onto an additional target
+                mutate<AddParentToMerge>("add_parent_to_merge", { id: this.#to.header.id, parent_id: this.#from.header.id });
                 return;
             } else if (this.#to.type == "Repository") {
                 // abandon source
@@ -164,10 +214,8 @@ export default class BinaryMutator {
 
         if (this.#from.type == "Parent") {
             if (this.#to.type == "Repository") {
-                // rebase subtree onto fewer targets 
-                let removeCommit = this.#from.header.id.commit;
-                let newParents = this.#from.child.parent_ids.filter(id => id.hex != removeCommit.hex);
-                mutate<MoveSource>("move_source", { id: this.#from.child.id, parent_ids: newParents });
+                // rebase subtree onto fewer targets
+                mutate<RemoveParentFromMerge>("remove_parent_from_merge", { id: this.#from.child.id, parent_id: this.#from.header.id.commit });
                 return;
             }
         }
@@ -187,7 +235,7 @@ export default class BinaryMutator {
         if (this.#from.type == "Ref") {
             if (this.#to.type == "Revision") {
                 // point ref to revision
-                mutate<MoveRef>("move_ref", { to_id: this.#to.header.id, ref: this.#from.ref });
+                mutate<MoveRef>("move_ref", { to_id: this.#to.header.id, ref: this.#from.ref, allow_protected: false });
                 return;
             } else if (this.#to.type == "Ref" && this.#from.ref.type == "RemoteBookmark") {
                 // track remote bookmark with existing local
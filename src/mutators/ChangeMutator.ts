@@ -1,8 +1,14 @@
 import type { RevHeader } from "../messages/RevHeader";
 import type { CopyChanges } from "../messages/CopyChanges";
 import type { MoveChanges } from "../messages/MoveChanges";
+import type { AddIgnorePattern } from "../messages/AddIgnorePattern";
+import type { DeletePaths } from "../messages/DeletePaths";
+import type { RenamePath } from "../messages/RenamePath";
+import type { MultilineString } from "../messages/MultilineString";
+import type { ResolveConflict } from "../messages/ResolveConflict";
+import type { ResolveWithMergeTool } from "../messages/ResolveWithMergeTool";
 import type { TreePath } from "../messages/TreePath";
-import { mutate } from "../ipc";
+import { getInput, mutate, trigger } from "../ipc";
 
 export default class ChangeMutator {
     #revision: RevHeader;
@@ -25,6 +31,24 @@ export default class ChangeMutator {
             case "restore":
                 this.onRestore();
                 break;
+            case "ignore_file":
+                this.onIgnore(`/${this.#path.repo_path}`);
+                break;
+            case "ignore_extension":
+                this.onIgnore(`*.${this.#path.repo_path.split("/").pop()!.split(".").pop()}`);
+                break;
+            case "ignore_directory":
+                this.onIgnore(`/${this.#path.repo_path.substring(0, this.#path.repo_path.lastIndexOf("/"))}/`);
+                break;
+            case "rename":
+                this.onRename();
+                break;
+            case "delete":
+                this.onDelete();
+                break;
+            case "diff_tool":
+                this.onDiffTool();
+                break;
             default:
                 console.log(`unimplemented mutation '${event}'`, this);
         }
@@ -38,6 +62,14 @@ export default class ChangeMutator {
         });
     };
 
+    onAmendInto = (target: RevHeader) => {
+        mutate<MoveChanges>("move_changes", {
+            from_id: this.#revision.id,
+            to_id: target.id.commit,
+            paths: [this.#path]
+        });
+    };
+
     onRestore = () => {
         mutate<CopyChanges>("copy_changes", {
             from_id: this.#revision.parent_ids[0],
@@ -45,4 +77,54 @@ export default class ChangeMutator {
             paths: [this.#path]
         });
     };
+
+    // one side index per conflict region, in order
+    onResolve = (choices: number[]) => {
+        mutate<ResolveConflict>("resolve_conflict", {
+            id: this.#revision.id,
+            path: this.#path,
+            choices,
+            content: null,
+            has_trailing_newline: false
+        });
+    };
+
+    // the whole merged file, e.g. from an editor showing the full FileConflict, whose newline flag is passed back
+    onResolveContent = (content: MultilineString, has_trailing_newline: boolean) => {
+        mutate<ResolveConflict>("resolve_conflict", {
+            id: this.#revision.id,
+            path: this.#path,
+            choices: [],
+            content,
+            has_trailing_newline
+        });
+    };
+
+    // the worker waits for the tool to exit before anything else runs
+    onMergeTool = () => {
+        mutate<ResolveWithMergeTool>("resolve_with_merge_tool", {
+            id: this.#revision.id,
+            path: this.#path
+        });
+    };
+
+    onIgnore = (pattern: string) => {
+        mutate<AddIgnorePattern>("add_ignore_pattern", { pattern });
+    };
+
+    // the new path is relative to the workspace root, like the old one
+    onRename = async () => {
+        let response = await getInput("Rename File", `Move ${this.#path.repo_path} to a new path.`, ["New path"]);
+        if (response) {
+            mutate<RenamePath>("rename_path", { path: this.#path, new_path: response["New path"] });
+        }
+    };
+
+    onDelete = () => {
+        mutate<DeletePaths>("delete_paths", { paths: [this.#path] });
+    };
+
+    onDiffTool = () => {
+        trigger("launch_diff_tool", { id: this.#revision.id, path: this.#path });
+    };
 }
@@ -5,6 +5,8 @@ import type { RenameBranch } from "../messages/RenameBranch";
 import type { GitPush } from "../messages/GitPush";
 import type { GitFetch } from "../messages/GitFetch";
 import type { DeleteRef } from "../messages/DeleteRef";
+import type { SetTrunk } from "../messages/SetTrunk";
+import type { GitRemote } from "../messages/GitRemote";
 import { getInput, mutate, query } from "../ipc";
 
 export default class RefMutator {
@@ -52,6 +54,10 @@ export default class RefMutator {
                 this.onDelete();
                 break;
 
+            case "trunk":
+                this.onSetTrunk();
+                break;
+
             default:
                 console.log(`unimplemented mutation '${event}'`, this);
         }
@@ -59,13 +65,15 @@ export default class RefMutator {
 
     onTrack = () => {
         mutate<TrackBranch>("track_branch", {
-            ref: this.#ref
+            ref: this.#ref,
+            allow_protected: false
         });
     };
 
     onUntrack = () => {
         mutate<UntrackBranch>("untrack_branch", {
-            ref: this.#ref
+            ref: this.#ref,
+            allow_protected: false
         });
     };
 
@@ -82,6 +90,13 @@ export default class RefMutator {
 
     onDelete = () => {
         mutate<DeleteRef>("delete_ref", {
+            ref: this.#ref,
+            allow_protected: false
+        });
+    };
+
+    onSetTrunk = () => {
+        mutate<SetTrunk>("set_trunk", {
             ref: this.#ref
         });
     };
@@ -96,14 +111,16 @@ export default class RefMutator {
                 mutate<GitPush>("git_push", {
                     type: "RemoteBookmark",
                     remote_name: this.#ref.remote_name,
-                    branch_ref: this.#ref
+                    branch_ref: this.#ref,
+                    allow_protected: false
                 });
                 break;
 
             case "LocalBookmark":
                 mutate<GitPush>("git_push", {
                     type: "AllRemotes",
-                    branch_ref: this.#ref
+                    branch_ref: this.#ref,
+                    allow_protected: false
                 });
                 break;
         }
@@ -120,19 +137,21 @@ export default class RefMutator {
                 break;
 
             case "LocalBookmark":
-                let allRemotes = await query<string[]>("query_remotes", { tracking_branch: null });
+                let allRemotes = await query<GitRemote[]>("query_remotes", { tracking_branch: null });
                 if (allRemotes.type == "error") {
                     console.log("error loading remotes: " + allRemotes.message);
                     return;
                 }
 
-                let response = await getInput("Select Remote", "", [{ label: "Remote Name", choices: allRemotes.value }]);
+                let pushRemotes = sortDefaultFirst(allRemotes.value, (remote) => remote.is_default_push);
+                let response = await getInput("Select Remote", describeRemotes(pushRemotes, true), [{ label: "Remote Name", choices: pushRemotes.map((remote) => remote.name) }]);
                 if (response) {
                     let remote_name = response["Remote Name"];
                     mutate<GitPush>("git_push", {
                         type: "RemoteBookmark",
                         remote_name,
-                        branch_ref: this.#ref
+                        branch_ref: this.#ref,
+                        allow_protected: false
                     })
                 }
                 break;
@@ -148,14 +167,16 @@ export default class RefMutator {
             case "RemoteBookmark":
                 mutate<GitFetch>("git_fetch", {
                     type: "AllRemotes",
-                    branch_ref: this.#ref
+                    branch_ref: this.#ref,
+                    allow_protected: false
                 });
                 break;
 
             case "LocalBookmark":
                 mutate<GitFetch>("git_fetch", {
                     type: "AllRemotes",
-                    branch_ref: this.#ref
+                    branch_ref: this.#ref,
+                    allow_protected: false
                 });
                 break;
         }
@@ -172,22 +193,37 @@ export default class RefMutator {
                 break;
 
             case "LocalBookmark":
-                let trackedRemotes = await query<string[]>("query_remotes", { tracking_branch: this.#ref.branch_name });
+                let trackedRemotes = await query<GitRemote[]>("query_remotes", { tracking_branch: this.#ref.branch_name });
                 if (trackedRemotes.type == "error") {
                     console.log("error loading remotes: " + trackedRemotes.message);
                     return;
                 }
 
-                let response = await getInput("Select Remote", "", [{ label: "Remote Name", choices: trackedRemotes.value }]);
+                let fetchRemotes = sortDefaultFirst(trackedRemotes.value, (remote) => remote.is_default_fetch);
+                let response = await getInput("Select Remote", describeRemotes(fetchRemotes, false), [{ label: "Remote Name", choices: fetchRemotes.map((remote) => remote.name) }]);
                 if (response) {
                     let remote_name = response["Remote Name"];
                     mutate<GitFetch>("git_fetch", {
                         type: "RemoteBookmark",
                         remote_name,
-                        branch_ref: this.#ref
+                        branch_ref: this.#ref,
+                        allow_protected: false
                     })
                 }
                 break;
         }
     };
 }
+
+// the first choice is preselected, so put jj's default remote there
+function sortDefaultFirst(remotes: GitRemote[], isDefault: (remote: GitRemote) => boolean): GitRemote[] {
+    return [...remotes.filter(isDefault), ...remotes.filter((remote) => !isDefault(remote))];
+}
+
+function describeRemotes(remotes: GitRemote[], push: boolean): string {
+    return remotes.map((remote) => {
+        let url = (push ? remote.push_url : null) ?? remote.url ?? "no url";
+        let fetched = remote.last_fetched ? `fetched ${new Date(remote.last_fetched).toLocaleString()}` : "never fetched by gg";
+        return `${remote.name}: ${url} (${fetched})`;
+    }).join("\n");
+}
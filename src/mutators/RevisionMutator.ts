@@ -6,10 +6,25 @@ import type { CopyChanges } from "../messages/CopyChanges";
 import type { CreateRevision } from "../messages/CreateRevision";
 import type { DescribeRevision } from "../messages/DescribeRevision";
 import type { DuplicateRevisions } from "../messages/DuplicateRevisions";
+import type { HideRevisions } from "../messages/HideRevisions";
+import type { UnhideRevisions } from "../messages/UnhideRevisions";
 import type { MoveChanges } from "../messages/MoveChanges";
 import type { CreateRef } from "../messages/CreateRef";
-import { getInput, mutate } from "../ipc";
+import type { RewritePaths } from "../messages/RewritePaths";
+import type { FixRevisions } from "../messages/FixRevisions";
+import type { RewriteMatch } from "../messages/RewriteMatch";
+import type { RevLabel } from "../messages/RevLabel";
+import type { SetLabel } from "../messages/SetLabel";
+import type { CommitStaged } from "../messages/CommitStaged";
+import type { PasteRevisions } from "../messages/PasteRevisions";
+import type { SwapParents } from "../messages/SwapParents";
+import type { CreateFile } from "../messages/CreateFile";
+import type { FollowWorkspace } from "../messages/FollowWorkspace";
+import { getInput, mutate, query, trigger } from "../ipc";
+import { commandOutput, repoStatusEvent } from "../stores";
+import { get } from "svelte/store";
 import type { StoreRef } from "../messages/StoreRef";
+import { COPIED_BUNDLE_KEY, copyRevisions, setAuthor } from "./RevisionsMutator";
 
 export default class RevisionMutator {
     #revision: RevHeader;
@@ -36,14 +51,33 @@ export default class RevisionMutator {
             case "backout":
                 this.onBackout();
                 break;
+            case "backout_new":
+                this.onBackoutNew();
+                break;
             case "duplicate":
                 this.onDuplicate();
                 break;
+            case "copy":
+                copyRevisions([this.#revision.id]);
+                break;
+            case "paste":
+                this.onPaste();
+                break;
             case "abandon":
                 if (!this.#revision.is_immutable) {
                     this.onAbandon();
                 }
                 break;
+            case "hide":
+                if (!this.#revision.is_immutable && !this.#revision.is_hidden) {
+                    this.onHide();
+                }
+                break;
+            case "unhide":
+                if (this.#revision.is_hidden) {
+                    this.onUnhide();
+                }
+                break;
             case "squash":
                 if (!this.#revision.is_immutable && this.#revision.parent_ids.length == 1) {
                     this.onSquash();
@@ -54,6 +88,39 @@ export default class RevisionMutator {
                     this.onRestore();
                 }
                 break;
+            case "swap_parents":
+                if (!this.#revision.is_immutable && this.#revision.parent_ids.length == 2) {
+                    this.onSwapParents();
+                }
+                break;
+            case "rewrite":
+                if (!this.#revision.is_immutable) {
+                    this.onRewrite();
+                }
+                break;
+            case "fix":
+                if (!this.#revision.is_immutable) {
+                    this.onFix();
+                }
+                break;
+            case "set_author":
+                if (!this.#revision.is_immutable) {
+                    setAuthor([this.#revision.id]);
+                }
+                break;
+            case "new_file":
+                if (this.#revision.is_working_copy) {
+                    this.onNewFile();
+                }
+                break;
+            case "diff_tool":
+                this.onDiffTool();
+                break;
+            case "follow_workspace":
+                if (this.#revision.working_copy_of.length > 0) {
+                    this.onFollowWorkspace();
+                }
+                break;
             case "branch":
                 this.onBranch();
                 break;
@@ -80,6 +147,7 @@ export default class RevisionMutator {
         } else {
             mutate<CheckoutRevision>("checkout_revision", {
                 id: this.#revision.id,
+                policy: null,
             });
         }
     };
@@ -87,6 +155,19 @@ export default class RevisionMutator {
     onBackout = () => {
         mutate<BackoutRevisions>("backout_revisions", {
             ids: [this.#revision.id],
+            new_parent_id: null
+        });
+    };
+
+    onBackoutNew = () => {
+        let status = get(repoStatusEvent);
+        if (!status) {
+            return;
+        }
+
+        mutate<BackoutRevisions>("backout_revisions", {
+            ids: [this.#revision.id],
+            new_parent_id: status.working_copy
         });
     };
 
@@ -96,12 +177,42 @@ export default class RevisionMutator {
         });
     };
 
+    onPaste = () => {
+        let token = localStorage.getItem(COPIED_BUNDLE_KEY);
+        if (token) {
+            mutate<PasteRevisions>("paste_revisions", {
+                token,
+                parent_ids: [this.#revision.id],
+            });
+        }
+    };
+
     onAbandon = () => {
         mutate<AbandonRevisions>("abandon_revisions", {
             ids: [this.#revision.id.commit],
         });
     };
 
+    onHide = () => {
+        mutate<HideRevisions>("hide_revisions", {
+            ids: [this.#revision.id.commit],
+        });
+    };
+
+    onUnhide = () => {
+        mutate<UnhideRevisions>("unhide_revisions", {
+            ids: [this.#revision.id.commit],
+        });
+    };
+
+    // the staged changes become a new parent of the working copy
+    onCommitStaged = async () => {
+        let response = await getInput("Commit Staged", "Describe the new revision for the staged changes.", ["Description"]);
+        if (response) {
+            mutate<CommitStaged>("commit_staged", { description: response["Description"] });
+        }
+    };
+
     onDescribe = (new_description: string, reset_author: boolean) => {
         mutate<DescribeRevision>("describe_revision", {
             id: this.#revision.id,
@@ -110,6 +221,13 @@ export default class RevisionMutator {
         });
     };
 
+    onSetLabel = (label: RevLabel | null) => {
+        mutate<SetLabel>("set_label", {
+            id: this.#revision.id,
+            label,
+        });
+    };
+
     onSquash = () => {
         mutate<MoveChanges>("move_changes", {
             from_id: this.#revision.id,
@@ -126,6 +244,57 @@ export default class RevisionMutator {
         });
     };
 
+    onSwapParents = () => {
+        mutate<SwapParents>("swap_parents", {
+            id: this.#revision.id,
+        });
+    };
+
+    onRewrite = async () => {
+        let request = await getInput("Search and Replace", "Regex replacement, applied to each line of every file.", ["Pattern", "Replacement"]);
+        if (!request) {
+            return;
+        }
+
+        let args = { id: this.#revision.id, paths: [], pattern: request["Pattern"], replacement: request["Replacement"] };
+        let preview = await query<RewriteMatch[]>("query_rewrite_preview", args);
+        if (preview.type != "data") {
+            return;
+        }
+
+        let numFiles = new Set(preview.value.map(m => m.path.repo_path)).size;
+        let confirm = await getInput("Search and Replace", `${preview.value.length} line(s) in ${numFiles} file(s) will be changed.`, []);
+        if (confirm) {
+            mutate<RewritePaths>("rewrite_paths", args);
+        }
+    };
+
+    // each file is reported as it's fixed, along with any tool errors
+    onFix = () => {
+        commandOutput.set([]);
+        mutate<FixRevisions>("fix_revisions", {
+            ids: [this.#revision.id.commit],
+        });
+    };
+
+    onNewFile = async () => {
+        let response = await getInput("New File", "Create a file in the working copy.", ["Path", "Contents"]);
+        if (response) {
+            mutate<CreateFile>("create_file", { path: response["Path"], content: response["Contents"] });
+        }
+    };
+
+    onDiffTool = () => {
+        trigger("launch_diff_tool", { id: this.#revision.id, path: null });
+    };
+
+    // following the open workspace's own working copy goes back to it
+    onFollowWorkspace = () => {
+        mutate<FollowWorkspace>("follow_workspace", {
+            name: this.#revision.is_working_copy ? null : this.#revision.working_copy_of[0],
+        });
+    };
+
     onBranch = async () => {
         let response = await getInput("Create Bookmark", "", ["Bookmark Name"]);
         if (response) {
@@ -0,0 +1,193 @@
+import type { RevHeader } from "../messages/RevHeader";
+import type { AbandonRevisions } from "../messages/AbandonRevisions";
+import type { BackoutRevisions } from "../messages/BackoutRevisions";
+import type { DuplicateRevisions } from "../messages/DuplicateRevisions";
+import type { MergeRevisions } from "../messages/MergeRevisions";
+import type { ParallelizeRevisions } from "../messages/ParallelizeRevisions";
+import type { GitPush } from "../messages/GitPush";
+import type { FixRevisions } from "../messages/FixRevisions";
+import type { RevId } from "../messages/RevId";
+import type { SetAuthor } from "../messages/SetAuthor";
+import type { DescribeRevisions } from "../messages/DescribeRevisions";
+import type { DescriptionEdit } from "../messages/DescriptionEdit";
+import { getInput, mutate, query } from "../ipc";
+import { commandOutput, currentMutation } from "../stores";
+
+// localStorage is shared by every window, so a bundle copied in one can be pasted in another
+export const COPIED_BUNDLE_KEY = "gg.copied-bundle";
+
+export async function copyRevisions(ids: RevId[]) {
+    let token = await query<string>("copy_revisions", { ids });
+    if (token.type == "data") {
+        localStorage.setItem(COPIED_BUNDLE_KEY, token.value);
+    } else {
+        currentMutation.set(token);
+    }
+}
+
+// blank fields keep each revision's current value
+export async function setAuthor(ids: RevId[]) {
+    let response = await getInput(
+        "Set Author",
+        "Leave a field blank to keep its current value. Timestamps are RFC 3339, such as 2024-01-31T09:00:00+10:00.",
+        ["Name", "Email", "Timestamp"],
+    );
+    if (response) {
+        mutate<SetAuthor>("set_author", {
+            ids,
+            name: response["Name"].trim() || null,
+            email: response["Email"].trim() || null,
+            timestamp: response["Timestamp"].trim() || null,
+        });
+    }
+}
+
+/**
+ * Bulk actions on a multi-selection of revisions.
+ */
+export default class RevisionsMutator {
+    #revisions: RevHeader[];
+
+    constructor(revs: RevHeader[]) {
+        this.#revisions = revs;
+    }
+
+    handle(event: string | undefined) {
+        if (!event) {
+            return;
+        }
+
+        switch (event) {
+            case "backout":
+                this.onBackout();
+                break;
+            case "duplicate":
+                this.onDuplicate();
+                break;
+            case "copy":
+                this.onCopy();
+                break;
+            case "merge":
+                this.onMerge();
+                break;
+            case "parallelize":
+                this.onParallelize();
+                break;
+            case "abandon":
+                this.onAbandon();
+                break;
+            case "fix":
+                this.onFix();
+                break;
+            case "set_author":
+                setAuthor(this.#revisions.map((rev) => rev.id));
+                break;
+            case "describe":
+                this.onDescribe();
+                break;
+            case "push":
+                this.onPush();
+                break;
+            default:
+                console.log(`unimplemented mutation '${event}'`, this);
+        }
+    }
+
+    onBackout = () => {
+        mutate<BackoutRevisions>("backout_revisions", {
+            ids: this.#revisions.map((rev) => rev.id),
+            new_parent_id: null
+        });
+    };
+
+    onDuplicate = () => {
+        mutate<DuplicateRevisions>("duplicate_revisions", {
+            ids: this.#revisions.map((rev) => rev.id),
+        });
+    };
+
+    onCopy = () => {
+        copyRevisions(this.#revisions.map((rev) => rev.id));
+    };
+
+    onMerge = () => {
+        mutate<MergeRevisions>("merge_revisions", {
+            ids: this.#revisions.map((rev) => rev.id),
+            checkout: true,
+        });
+    };
+
+    onParallelize = () => {
+        mutate<ParallelizeRevisions>("parallelize_revisions", {
+            ids: this.#revisions.map((rev) => rev.id),
+        });
+    };
+
+    onAbandon = () => {
+        mutate<AbandonRevisions>("abandon_revisions", {
+            ids: this.#revisions.map((rev) => rev.id.commit),
+        });
+    };
+
+    onFix = () => {
+        commandOutput.set([]);
+        mutate<FixRevisions>("fix_revisions", {
+            ids: this.#revisions.map((rev) => rev.id.commit),
+        });
+    };
+
+    onDescribe = async () => {
+        let response = await getInput(
+            "Edit Descriptions",
+            "Applies one edit to every selected revision. Find and Replace substitutes the Replacement for the Text.",
+            [
+                { label: "Edit", choices: ["Set", "Prepend", "Append Trailer", "Find and Replace"] },
+                { label: "Text", choices: [] },
+                { label: "Replacement", choices: [] },
+            ],
+        );
+        if (!response) {
+            return;
+        }
+
+        let text = response["Text"];
+        let edit: DescriptionEdit;
+        switch (response["Edit"]) {
+            case "Set":
+                edit = { type: "Set", text: text.trim() ? `${text.trim()}\n` : "" };
+                break;
+            case "Prepend":
+                edit = { type: "Prepend", text };
+                break;
+            case "Append Trailer":
+                edit = { type: "AppendTrailer", trailer: text };
+                break;
+            default:
+                edit = { type: "Replace", find: text, replace: response["Replacement"] };
+                break;
+        }
+
+        mutate<DescribeRevisions>("describe_revisions", {
+            ids: this.#revisions.map((rev) => rev.id),
+            edit,
+        });
+    };
+
+    // GitPush targets a single bookmark, so push them one at a time
+    onPush = async () => {
+        for (let rev of this.#revisions) {
+            for (let ref of rev.refs) {
+                if (ref.type == "LocalBookmark") {
+                    let pushed = await mutate<GitPush>("git_push", {
+                        type: "AllRemotes",
+                        branch_ref: ref,
+                        allow_protected: false
+                    });
+                    if (!pushed) {
+                        return;
+                    }
+                }
+            }
+        }
+    };
+}
@@ -1,6 +1,4 @@
 import type { MutationResult } from "./messages/MutationResult";
-import type { RepoConfig } from "./messages/RepoConfig";
-import type { RepoStatus } from "./messages/RepoStatus";
 import type { RevHeader } from "./messages/RevHeader";
 import type { Operand } from "./messages/Operand";
 import { writable } from "svelte/store";
@@ -8,16 +6,28 @@ import { event, type Query } from "./ipc";
 import type { InputRequest } from "./messages/InputRequest";
 import type { InputResponse } from "./messages/InputResponse";
 import type { RevChange } from "./messages/RevChange";
+import type { CommitId } from "./messages/CommitId";
+import type { DropTarget } from "./messages/DropTarget";
+import type { ChangeHistory } from "./messages/ChangeHistory";
 
-export const repoConfigEvent = await event<RepoConfig>("gg://repo/config", { type: "Initial" });
-export const repoStatusEvent = await event<RepoStatus | undefined>("gg://repo/status", undefined);
+export const repoConfigEvent = await event("gg://repo/config", { type: "Initial" });
+export const repoStatusEvent = await event("gg://repo/status", undefined);
 export const revisionSelectEvent = await event<RevHeader | undefined>("gg://revision/select", undefined);
 export const changeSelectEvent = await event<RevChange | undefined>("gg://change/select", undefined);
+export const workerBusyEvent = await event("gg://worker/busy", null); // name of the running mutation
+
+export const revisionMultiSelect = writable<RevHeader[]>([]);
+export const revisionNotes = writable<Record<string, string>>({}); // change id -> private note
 
 export const currentMutation = writable<Query<MutationResult> | null>(null);
 export const currentContext = writable<Operand | null>();
 export const currentSource = writable<Operand | null>();
 export const currentTarget = writable<Operand | null>();
+export const currentCopy = writable<boolean>(false);
+export const visibleRevisions = writable<CommitId[]>([]);
+export const dropTargets = writable<Record<string, DropTarget>>({}); // commit id -> drops permitted by the backend
+export const commandOutput = writable<string[] | null>(null); // lines from a running or finished jj command
+export const changeHistory = writable<{ header: RevHeader; history: Query<ChangeHistory> } | null>(null); // operations which touched a change
 export const currentInput = writable<InputRequest & { callback: (response: InputResponse) => void } | null>();
 
 export const hasModal = writable<boolean>(false);